@@ -0,0 +1,242 @@
+// JARVIS2026 - Account suspension and bans
+// Admins can suspend an account (for a while or until reinstated) or ban it.
+// Suspended accounts can still sign in and browse but can't upload or move
+// tokens; banned accounts lose their sessions and are refused everywhere
+// (see the `CurrentUser` extractor).
+
+use actix_web::{put, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::AdminUser;
+use crate::sessions;
+use crate::AppState;
+
+const MAX_REASON_CHARS: usize = 500;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountStatus {
+    Active,
+    Suspended,
+    Banned,
+}
+
+/// Why an account may not upload or move tokens right now.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Restriction {
+    status: String,
+    /// End of a temporary suspension
+    suspended_until: Option<chrono::DateTime<chrono::Utc>>,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetStatusRequest {
+    status: AccountStatus,
+    reason: Option<String>,
+    /// Only for suspensions; open-ended when omitted
+    until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'active'
+        CHECK (status IN ('active', 'suspended', 'banned'))"#,
+    )
+    .execute(pool)
+    .await?;
+
+    for column in ["suspended_until TIMESTAMPTZ", "status_reason TEXT"] {
+        sqlx::query(&format!(
+            "ALTER TABLE users ADD COLUMN IF NOT EXISTS {}",
+            column
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// ENFORCEMENT
+// ============================================================================
+
+impl AccountStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Suspended => "suspended",
+            AccountStatus::Banned => "banned",
+        }
+    }
+}
+
+/// The account's restriction, if any. A suspension past its end date no
+/// longer counts.
+pub async fn restriction(pool: &PgPool, user_id: Uuid) -> Result<Option<Restriction>, sqlx::Error> {
+    sqlx::query_as::<_, Restriction>(
+        r#"SELECT status, suspended_until, status_reason AS reason FROM users
+        WHERE id = $1 AND status <> 'active'
+          AND NOT (status = 'suspended' AND suspended_until IS NOT NULL AND suspended_until <= NOW())"#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub fn restricted_response(restriction: &Restriction) -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": format!("This account is {}", restriction.status),
+        "status": restriction.status,
+        "suspended_until": restriction.suspended_until,
+        "reason": restriction.reason
+    }))
+}
+
+/// Guard for upload and token handlers: `Err` carries the response to return.
+pub async fn ensure_active(pool: &PgPool, user_id: Uuid) -> Result<(), HttpResponse> {
+    match restriction(pool, user_id).await {
+        Ok(None) => Ok(()),
+        Ok(Some(restriction)) => Err(restricted_response(&restriction)),
+        Err(e) => {
+            error!("Failed to check account status of {}: {}", user_id, e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to check account status"
+            })))
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[put("/api/admin/users/{id}/status")]
+pub async fn set_account_status(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    req: web::Json<SetStatusRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let status = req.status;
+
+    if user_id == admin.id && status != AccountStatus::Active {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Admins cannot suspend or ban themselves"
+        }));
+    }
+    let reason = req
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > MAX_REASON_CHARS) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("reason must be at most {} characters", MAX_REASON_CHARS)
+        }));
+    }
+    let until = match (status, req.until) {
+        (AccountStatus::Suspended, Some(until)) if until <= chrono::Utc::now() => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "until must be in the future"
+            }))
+        }
+        (AccountStatus::Suspended, until) => until,
+        (_, Some(_)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "until only applies to suspensions"
+            }))
+        }
+        (_, None) => None,
+    };
+
+    let result: Result<Option<String>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let Some(previous) =
+            sqlx::query_scalar::<_, String>("SELECT status FROM users WHERE id = $1 FOR UPDATE")
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            r#"UPDATE users SET status = $2, suspended_until = $3, status_reason = $4
+            WHERE id = $1"#,
+        )
+        .bind(user_id)
+        .bind(status.as_str())
+        .bind(until)
+        .bind(if status == AccountStatus::Active {
+            None
+        } else {
+            reason
+        })
+        .execute(&mut *tx)
+        .await?;
+
+        if status == AccountStatus::Banned {
+            sessions::revoke_all(&mut *tx, user_id).await?;
+        }
+
+        audit::record(
+            &mut tx,
+            admin.id,
+            "user.set_status",
+            "user",
+            user_id,
+            serde_json::json!({
+                "from": previous,
+                "to": status,
+                "until": until,
+                "reason": reason
+            }),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(Some(previous))
+    }
+    .await;
+
+    match result {
+        Ok(Some(previous)) => {
+            info!(
+                "User {} status changed from {} to {} by {}",
+                user_id,
+                previous,
+                status.as_str(),
+                admin.id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "user_id": user_id,
+                "status": status,
+                "suspended_until": until,
+                "reason": reason
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        })),
+        Err(e) => {
+            error!("Failed to set status of {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update account status"
+            }))
+        }
+    }
+}
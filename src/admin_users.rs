@@ -0,0 +1,338 @@
+// JARVIS2026 - Admin user directory
+// Support staff look accounts up by name, email or wallet, narrow the list by
+// role, verification and balance, and open a single account to see its
+// listings, uploads and token history.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::account_status::AccountStatus;
+use crate::auth::{AdminUser, Role};
+use crate::email_verification::VERIFIED_USER_CONDITION;
+use crate::filters;
+use crate::AppState;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+/// Most recent rows of each kind shown on the drill-down.
+const DETAIL_ITEMS: i64 = 50;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum UserSort {
+    #[default]
+    CreatedAt,
+    TokenBalance,
+    Username,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Deserialize)]
+pub struct DirectoryQuery {
+    /// Matched against username, email and wallet address
+    q: Option<String>,
+    role: Option<Role>,
+    status: Option<AccountStatus>,
+    verified: Option<bool>,
+    min_balance: Option<i64>,
+    max_balance: Option<i64>,
+    joined_after: Option<chrono::DateTime<chrono::Utc>>,
+    joined_before: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    sort: UserSort,
+    #[serde(default)]
+    order: SortOrder,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct DirectoryEntry {
+    id: Uuid,
+    username: String,
+    email: Option<String>,
+    wallet_address: Option<String>,
+    role: String,
+    status: String,
+    suspended_until: Option<chrono::DateTime<chrono::Utc>>,
+    token_balance: i64,
+    verified: bool,
+    email_verified_at: Option<chrono::DateTime<chrono::Utc>>,
+    wallet_verified_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip)]
+    total: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct UserListing {
+    id: Uuid,
+    title: String,
+    location: String,
+    price: f64,
+    moderation_status: String,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct UserUpload {
+    id: Uuid,
+    property_id: Option<Uuid>,
+    file_type: String,
+    file_size: i64,
+    is_original: Option<bool>,
+    tokens_earned: Option<i64>,
+    moderation_status: String,
+    uploaded_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct UserTransaction {
+    id: Uuid,
+    media_id: Option<Uuid>,
+    amount: i64,
+    transaction_type: String,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize)]
+struct UserDetail {
+    #[serde(flatten)]
+    user: DirectoryEntry,
+    listing_count: i64,
+    upload_count: i64,
+    listings: Vec<UserListing>,
+    uploads: Vec<UserUpload>,
+    transactions: Vec<UserTransaction>,
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+impl UserSort {
+    fn column(self) -> &'static str {
+        match self {
+            UserSort::CreatedAt => "u.created_at",
+            UserSort::TokenBalance => "u.token_balance",
+            UserSort::Username => "LOWER(u.username)",
+        }
+    }
+}
+
+impl SortOrder {
+    fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+fn directory_select() -> String {
+    format!(
+        r#"SELECT u.id, u.username, c.email, u.wallet_address, u.role, u.status,
+               u.suspended_until, u.token_balance, {} AS verified, u.email_verified_at, u.wallet_verified_at, u.created_at,
+               COUNT(*) OVER () AS total
+        FROM users u
+        LEFT JOIN credentials c ON c.user_id = u.id
+        WHERE TRUE"#,
+        VERIFIED_USER_CONDITION
+    )
+}
+
+fn push_filters(qb: &mut QueryBuilder<'_, Postgres>, query: &DirectoryQuery) {
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", filters::escape_like(&q.to_lowercase()));
+        qb.push(" AND (LOWER(u.username) LIKE ");
+        qb.push_bind(pattern.clone());
+        qb.push(" OR LOWER(c.email) LIKE ");
+        qb.push_bind(pattern.clone());
+        qb.push(" OR LOWER(u.wallet_address) LIKE ");
+        qb.push_bind(pattern);
+        qb.push(")");
+    }
+    if let Some(role) = query.role {
+        qb.push(" AND u.role = ");
+        qb.push_bind(role.as_str());
+    }
+    if let Some(status) = query.status {
+        qb.push(" AND u.status = ");
+        qb.push_bind(status.as_str());
+    }
+    if let Some(verified) = query.verified {
+        qb.push(if verified { " AND " } else { " AND NOT " });
+        qb.push(VERIFIED_USER_CONDITION);
+    }
+    if let Some(min) = query.min_balance {
+        qb.push(" AND u.token_balance >= ");
+        qb.push_bind(min);
+    }
+    if let Some(max) = query.max_balance {
+        qb.push(" AND u.token_balance <= ");
+        qb.push_bind(max);
+    }
+    if let Some(after) = query.joined_after {
+        qb.push(" AND u.created_at >= ");
+        qb.push_bind(after);
+    }
+    if let Some(before) = query.joined_before {
+        qb.push(" AND u.created_at < ");
+        qb.push_bind(before);
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/admin/users")]
+pub async fn list_users(
+    _admin: AdminUser,
+    query: web::Query<DirectoryQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let query = query.into_inner();
+    if let (Some(min), Some(max)) = (query.min_balance, query.max_balance) {
+        if min > max {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "min_balance must not exceed max_balance"
+            }));
+        }
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let mut qb = QueryBuilder::<Postgres>::new(directory_select());
+    push_filters(&mut qb, &query);
+    qb.push(" ORDER BY ");
+    qb.push(query.sort.column());
+    qb.push(" ");
+    qb.push(query.order.sql());
+    // Stable pages when many rows share the sort key
+    qb.push(", u.id LIMIT ");
+    qb.push_bind(limit);
+    qb.push(" OFFSET ");
+    qb.push_bind(offset);
+
+    match qb
+        .build_query_as::<DirectoryEntry>()
+        .fetch_all(&state.db)
+        .await
+    {
+        Ok(users) => {
+            let total = users.first().map_or(0, |u| u.total);
+            HttpResponse::Ok().json(serde_json::json!({
+                "total": total,
+                "limit": limit,
+                "offset": offset,
+                "users": users
+            }))
+        }
+        Err(e) => {
+            error!("Failed to list users: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list users"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/users/{id}")]
+pub async fn user_detail(
+    _admin: AdminUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+
+    let detail: Result<Option<UserDetail>, sqlx::Error> = async {
+        let Some(user) =
+            sqlx::query_as::<_, DirectoryEntry>(&format!("{} AND u.id = $1", directory_select()))
+                .bind(user_id)
+                .fetch_optional(&state.db)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let (listing_count, upload_count) = sqlx::query_as::<_, (i64, i64)>(
+            r#"SELECT (SELECT COUNT(*) FROM properties WHERE user_id = $1),
+                      (SELECT COUNT(*) FROM media_uploads WHERE user_id = $1)"#,
+        )
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+
+        let listings = sqlx::query_as::<_, UserListing>(
+            r#"SELECT id, title, location, price, moderation_status, created_at
+            FROM properties WHERE user_id = $1
+            ORDER BY created_at DESC NULLS LAST LIMIT $2"#,
+        )
+        .bind(user_id)
+        .bind(DETAIL_ITEMS)
+        .fetch_all(&state.db)
+        .await?;
+
+        let uploads = sqlx::query_as::<_, UserUpload>(
+            r#"SELECT id, property_id, file_type, file_size, is_original, tokens_earned,
+                      moderation_status, uploaded_at
+            FROM media_uploads WHERE user_id = $1
+            ORDER BY uploaded_at DESC NULLS LAST LIMIT $2"#,
+        )
+        .bind(user_id)
+        .bind(DETAIL_ITEMS)
+        .fetch_all(&state.db)
+        .await?;
+
+        let transactions = sqlx::query_as::<_, UserTransaction>(
+            r#"SELECT id, media_id, amount, transaction_type, created_at
+            FROM token_transactions WHERE user_id = $1
+            ORDER BY created_at DESC NULLS LAST LIMIT $2"#,
+        )
+        .bind(user_id)
+        .bind(DETAIL_ITEMS)
+        .fetch_all(&state.db)
+        .await?;
+
+        Ok(Some(UserDetail {
+            user,
+            listing_count,
+            upload_count,
+            listings,
+            uploads,
+            transactions,
+        }))
+    }
+    .await;
+
+    match detail {
+        Ok(Some(detail)) => HttpResponse::Ok().json(detail),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        })),
+        Err(e) => {
+            error!("Failed to load user {} for admin: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load user"
+            }))
+        }
+    }
+}
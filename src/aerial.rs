@@ -0,0 +1,157 @@
+// JARVIS2026 - Drone footage
+// `drone_video` uploads must come with the flight's GPS track. The track has
+// to be centred on the listing before the footage counts as verified aerial
+// coverage, which earns the listing a badge and the uploader a higher reward.
+
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::geo::haversine_km;
+
+pub const DRONE_FILE_TYPE: &str = "drone_video";
+/// Reward for original, verified drone footage (regular uploads earn 100).
+pub const DRONE_UPLOAD_TOKENS: i64 = 250;
+const MIN_TRACK_POINTS: usize = 5;
+const MAX_TRACK_POINTS: usize = 20_000;
+/// How far the track's centre may sit from the listing's coordinates.
+const MAX_CENTER_OFFSET_KM: f64 = 0.5;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct TrackPoint {
+    lat: f64,
+    #[serde(alias = "lon")]
+    lng: f64,
+}
+
+/// A flight track that passed validation against the listing.
+#[derive(Debug)]
+pub struct VerifiedFlight {
+    points: usize,
+    center: (f64, f64),
+    offset_km: f64,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS drone_flights (
+            media_id UUID PRIMARY KEY REFERENCES media_uploads(id) ON DELETE CASCADE,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            track_points INTEGER NOT NULL,
+            center_lat DOUBLE PRECISION NOT NULL,
+            center_lng DOUBLE PRECISION NOT NULL,
+            center_offset_m DOUBLE PRECISION NOT NULL,
+            verified_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// TRACK VALIDATION
+// ============================================================================
+
+pub fn is_video_file(filename: &str) -> bool {
+    let lower = filename.to_lowercase();
+    lower.ends_with(".mp4") || lower.ends_with(".mov")
+}
+
+/// Tracks are a JSON array of `{"lat": .., "lng": ..}` points or a GPX file.
+fn parse_track(raw: &str) -> Result<Vec<TrackPoint>, String> {
+    let raw = raw.trim();
+    if raw.starts_with('<') {
+        let doc = roxmltree::Document::parse(raw).map_err(|_| "invalid GPX track".to_string())?;
+        doc.descendants()
+            .filter(|n| n.has_tag_name("trkpt"))
+            .map(|n| {
+                let attr = |name| n.attribute(name).and_then(|v: &str| v.parse::<f64>().ok());
+                match (attr("lat"), attr("lon")) {
+                    (Some(lat), Some(lng)) => Ok(TrackPoint { lat, lng }),
+                    _ => Err("GPX trkpt without lat/lon".to_string()),
+                }
+            })
+            .collect()
+    } else {
+        serde_json::from_str(raw)
+            .map_err(|_| "flight_track must be a JSON array of {lat, lng} or GPX".to_string())
+    }
+}
+
+/// Checks a flight track is plausible and centred on the listing.
+pub fn verify_track(raw: &str, property: Option<(f64, f64)>) -> Result<VerifiedFlight, String> {
+    let Some(property) = property else {
+        return Err("drone footage needs the listing's latitude and longitude".to_string());
+    };
+    let points = parse_track(raw)?;
+    if points.len() < MIN_TRACK_POINTS || points.len() > MAX_TRACK_POINTS {
+        return Err(format!(
+            "flight_track must have {}-{} points",
+            MIN_TRACK_POINTS, MAX_TRACK_POINTS
+        ));
+    }
+    if points
+        .iter()
+        .any(|p| !(-90.0..=90.0).contains(&p.lat) || !(-180.0..=180.0).contains(&p.lng))
+    {
+        return Err("flight_track has out-of-range coordinates".to_string());
+    }
+
+    let n = points.len() as f64;
+    let center = (
+        points.iter().map(|p| p.lat).sum::<f64>() / n,
+        points.iter().map(|p| p.lng).sum::<f64>() / n,
+    );
+    let offset_km = haversine_km(center, property);
+    if offset_km > MAX_CENTER_OFFSET_KM {
+        return Err(format!(
+            "flight track is centred {:.1} km from the listing (max {} km)",
+            offset_km, MAX_CENTER_OFFSET_KM
+        ));
+    }
+
+    Ok(VerifiedFlight {
+        points: points.len(),
+        center,
+        offset_km,
+    })
+}
+
+/// Stores the flight and marks the listing as having verified aerial footage.
+pub async fn record_flight(
+    pool: &PgPool,
+    media_id: Uuid,
+    property_id: Uuid,
+    flight: &VerifiedFlight,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO drone_flights
+        (media_id, property_id, track_points, center_lat, center_lng, center_offset_m)
+        VALUES ($1, $2, $3, $4, $5, $6)"#,
+    )
+    .bind(media_id)
+    .bind(property_id)
+    .bind(flight.points as i32)
+    .bind(flight.center.0)
+    .bind(flight.center.1)
+    .bind(flight.offset_km * 1000.0)
+    .execute(pool)
+    .await?;
+
+    sqlx::query("UPDATE properties SET verified_aerial = true WHERE id = $1")
+        .bind(property_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
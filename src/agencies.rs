@@ -0,0 +1,506 @@
+// JARVIS2026 - Agencies and their branding
+// Agents can belong to an agency. Each agency keeps its own branding (logo,
+// colors, contact footer), which is applied to the share images and QR codes
+// of its members' listings and to the emails those members receive.
+// Admins create agencies and assign members; members edit the branding.
+
+use actix_files::NamedFile;
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use image::{imageops, ImageFormat, Rgba};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::io::Cursor;
+use tokio::fs as async_fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::{AdminUser, AgentUser, Role};
+use crate::AppState;
+
+const BRANDING_DIR: &str = "branding";
+/// Logos are stored re-encoded and no larger than this on either side.
+const LOGO_MAX_DIMENSION: u32 = 512;
+const MAX_NAME_CHARS: usize = 120;
+const MAX_FOOTER_CHARS: usize = 500;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Agency {
+    id: Uuid,
+    name: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateAgencyRequest {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetAgencyRequest {
+    /// `null` removes the user from their agency
+    agency_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Branding {
+    pub agency_id: Uuid,
+    pub agency_name: String,
+    /// `#rrggbb`
+    pub primary_color: Option<String>,
+    /// `#rrggbb`
+    pub accent_color: Option<String>,
+    pub contact_footer: Option<String>,
+    #[serde(skip)]
+    pub logo_path: Option<String>,
+    pub has_logo: bool,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateBrandingRequest {
+    primary_color: Option<String>,
+    accent_color: Option<String>,
+    contact_footer: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS agencies (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "ALTER TABLE users ADD COLUMN IF NOT EXISTS agency_id UUID REFERENCES agencies(id) ON DELETE SET NULL",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS agency_branding (
+            agency_id UUID PRIMARY KEY REFERENCES agencies(id) ON DELETE CASCADE,
+            primary_color TEXT,
+            accent_color TEXT,
+            contact_footer TEXT,
+            logo_path TEXT,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// BRANDING LOOKUP
+// ============================================================================
+
+const BRANDING_SELECT: &str = r#"SELECT a.id AS agency_id, a.name AS agency_name,
+           b.primary_color, b.accent_color, b.contact_footer, b.logo_path,
+           b.logo_path IS NOT NULL AS has_logo, b.updated_at
+    FROM agencies a LEFT JOIN agency_branding b ON b.agency_id = a.id"#;
+
+async fn for_agency(pool: &PgPool, agency_id: Uuid) -> Result<Option<Branding>, sqlx::Error> {
+    sqlx::query_as::<_, Branding>(&format!("{} WHERE a.id = $1", BRANDING_SELECT))
+        .bind(agency_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Branding of the agency the user belongs to, if any.
+pub async fn for_user(pool: &PgPool, user_id: Uuid) -> Result<Option<Branding>, sqlx::Error> {
+    sqlx::query_as::<_, Branding>(&format!(
+        "{} JOIN users u ON u.agency_id = a.id WHERE u.id = $1",
+        BRANDING_SELECT
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Branding of the agency whose member owns the listing, if any.
+pub async fn for_property(
+    pool: &PgPool,
+    property_id: Uuid,
+) -> Result<Option<Branding>, sqlx::Error> {
+    sqlx::query_as::<_, Branding>(&format!(
+        r#"{} JOIN users u ON u.agency_id = a.id
+        JOIN properties p ON p.user_id = u.id
+        WHERE p.id = $1"#,
+        BRANDING_SELECT
+    ))
+    .bind(property_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Appends the recipient's agency contact footer to an email body.
+pub async fn with_footer(pool: &PgPool, user_id: Uuid, body: String) -> String {
+    match for_user(pool, user_id).await {
+        Ok(Some(Branding {
+            contact_footer: Some(footer),
+            ..
+        })) => format!("{}\n\n--\n{}", body, footer),
+        Ok(_) => body,
+        Err(e) => {
+            warn!("Failed to load email branding for {}: {}", user_id, e);
+            body
+        }
+    }
+}
+
+/// `#rrggbb` as an opaque pixel.
+pub fn parse_color(raw: &str) -> Option<Rgba<u8>> {
+    let hex = raw.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+fn normalize_color(raw: Option<&str>, field: &str) -> Result<Option<String>, String> {
+    match raw.map(str::trim).filter(|c| !c.is_empty()) {
+        None => Ok(None),
+        Some(color) if parse_color(color).is_some() => Ok(Some(color.to_lowercase())),
+        Some(_) => Err(format!("{} must be a hex color like #1a2b3c", field)),
+    }
+}
+
+/// Decodes an uploaded logo, shrinks it to fit and re-encodes it as PNG.
+fn normalize_logo(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let logo = image::load_from_memory(bytes)?;
+    let logo = if logo.width() > LOGO_MAX_DIMENSION || logo.height() > LOGO_MAX_DIMENSION {
+        logo.resize(
+            LOGO_MAX_DIMENSION,
+            LOGO_MAX_DIMENSION,
+            imageops::FilterType::Lanczos3,
+        )
+    } else {
+        logo
+    };
+    let mut png = Vec::new();
+    logo.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Admins manage every agency; agents only the one they belong to.
+async fn may_edit(pool: &PgPool, agent: &AgentUser, agency_id: Uuid) -> Result<bool, sqlx::Error> {
+    if agent.role == Role::Admin {
+        return Ok(true);
+    }
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND agency_id = $2)",
+    )
+    .bind(agent.id)
+    .bind(agency_id)
+    .fetch_one(pool)
+    .await
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Agency not found"
+    }))
+}
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Only members of this agency can change its branding"
+    }))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/admin/agencies")]
+pub async fn create_agency(
+    _admin: AdminUser,
+    req: web::Json<CreateAgencyRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("name must be 1-{} characters", MAX_NAME_CHARS)
+        }));
+    }
+
+    match sqlx::query_as::<_, Agency>("INSERT INTO agencies (name) VALUES ($1) RETURNING *")
+        .bind(name)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(agency) => {
+            info!("Agency {} created: {}", agency.id, agency.name);
+            HttpResponse::Ok().json(agency)
+        }
+        Err(e) => {
+            error!("Failed to create agency: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create agency"
+            }))
+        }
+    }
+}
+
+#[put("/api/admin/users/{user_id}/agency")]
+pub async fn set_user_agency(
+    _admin: AdminUser,
+    path: web::Path<Uuid>,
+    req: web::Json<SetAgencyRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+
+    match sqlx::query("UPDATE users SET agency_id = $1 WHERE id = $2")
+        .bind(req.agency_id)
+        .bind(user_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            }))
+        }
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "user_id": user_id,
+            "agency_id": req.agency_id
+        })),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => not_found(),
+        Err(e) => {
+            error!("Failed to set agency of {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update agency membership"
+            }))
+        }
+    }
+}
+
+#[get("/api/agencies/{agency_id}/branding")]
+pub async fn get_branding(path: web::Path<Uuid>, state: web::Data<AppState>) -> impl Responder {
+    let agency_id = path.into_inner();
+
+    match for_agency(&state.db, agency_id).await {
+        Ok(Some(branding)) => HttpResponse::Ok().json(branding),
+        Ok(None) => not_found(),
+        Err(e) => {
+            error!("Failed to load branding of {}: {}", agency_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load branding"
+            }))
+        }
+    }
+}
+
+#[put("/api/agencies/{agency_id}/branding")]
+pub async fn update_branding(
+    agent: AgentUser,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateBrandingRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let agency_id = path.into_inner();
+
+    let colors =
+        normalize_color(req.primary_color.as_deref(), "primary_color").and_then(|primary| {
+            normalize_color(req.accent_color.as_deref(), "accent_color")
+                .map(|accent| (primary, accent))
+        });
+    let (primary_color, accent_color) = match colors {
+        Ok(colors) => colors,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+    let contact_footer = req
+        .contact_footer
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty());
+    if contact_footer.is_some_and(|f| f.chars().count() > MAX_FOOTER_CHARS) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("contact_footer must be at most {} characters", MAX_FOOTER_CHARS)
+        }));
+    }
+
+    match may_edit(&state.db, &agent, agency_id).await {
+        Ok(true) => {}
+        Ok(false) => return forbidden(),
+        Err(e) => {
+            error!("Failed to check agency membership of {}: {}", agent.id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update branding"
+            }));
+        }
+    }
+
+    let result = sqlx::query(
+        r#"INSERT INTO agency_branding (agency_id, primary_color, accent_color, contact_footer)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (agency_id) DO UPDATE
+        SET primary_color = EXCLUDED.primary_color, accent_color = EXCLUDED.accent_color,
+            contact_footer = EXCLUDED.contact_footer, updated_at = NOW()"#,
+    )
+    .bind(agency_id)
+    .bind(&primary_color)
+    .bind(&accent_color)
+    .bind(contact_footer)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(_) => match for_agency(&state.db, agency_id).await {
+            Ok(Some(branding)) => HttpResponse::Ok().json(branding),
+            _ => HttpResponse::Ok().json(serde_json::json!({ "agency_id": agency_id })),
+        },
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => not_found(),
+        Err(e) => {
+            error!("Failed to update branding of {}: {}", agency_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update branding"
+            }))
+        }
+    }
+}
+
+/// The logo is sent as the raw request body (PNG, JPEG or WebP), within the
+/// default 256 KiB payload limit.
+#[put("/api/agencies/{agency_id}/branding/logo")]
+pub async fn upload_logo(
+    agent: AgentUser,
+    path: web::Path<Uuid>,
+    body: web::Bytes,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let agency_id = path.into_inner();
+
+    match may_edit(&state.db, &agent, agency_id).await {
+        Ok(true) => {}
+        Ok(false) => return forbidden(),
+        Err(e) => {
+            error!("Failed to check agency membership of {}: {}", agent.id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store logo"
+            }));
+        }
+    }
+
+    let png = match web::block(move || normalize_logo(&body)).await {
+        Ok(Ok(png)) => png,
+        Ok(Err(e)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Unreadable logo image: {}", e)
+            }))
+        }
+        Err(e) => {
+            error!("Logo processing task failed: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store logo"
+            }));
+        }
+    };
+
+    // A new name per upload, so cached renders keyed on the path go stale
+    let digest = hex::encode(Sha256::digest(&png));
+    let logo_path = format!("{}/{}-{}.png", BRANDING_DIR, agency_id, &digest[..12]);
+    async_fs::create_dir_all(BRANDING_DIR).await.ok();
+    if let Err(e) = async_fs::write(&logo_path, &png).await {
+        error!("Failed to write logo {}: {}", logo_path, e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to store logo"
+        }));
+    }
+
+    let previous = sqlx::query_scalar::<_, Option<String>>(
+        r#"WITH old AS (SELECT logo_path FROM agency_branding WHERE agency_id = $1)
+        INSERT INTO agency_branding (agency_id, logo_path) VALUES ($1, $2)
+        ON CONFLICT (agency_id) DO UPDATE SET logo_path = EXCLUDED.logo_path, updated_at = NOW()
+        RETURNING (SELECT logo_path FROM old)"#,
+    )
+    .bind(agency_id)
+    .bind(&logo_path)
+    .fetch_one(&state.db)
+    .await;
+
+    match previous {
+        Ok(previous) => {
+            if let Some(old) = previous.filter(|old| *old != logo_path) {
+                if let Err(e) = async_fs::remove_file(&old).await {
+                    warn!("Failed to remove old logo {}: {}", old, e);
+                }
+            }
+            info!("Logo updated for agency {}", agency_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "agency_id": agency_id,
+                "has_logo": true
+            }))
+        }
+        Err(e) => {
+            async_fs::remove_file(&logo_path).await.ok();
+            if matches!(&e, sqlx::Error::Database(db) if db.is_foreign_key_violation()) {
+                return not_found();
+            }
+            error!("Failed to save logo of {}: {}", agency_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store logo"
+            }))
+        }
+    }
+}
+
+#[get("/api/agencies/{agency_id}/branding/logo.png")]
+pub async fn get_logo(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let agency_id = path.into_inner();
+
+    let logo_path = match sqlx::query_scalar::<_, Option<String>>(
+        "SELECT logo_path FROM agency_branding WHERE agency_id = $1",
+    )
+    .bind(agency_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(Some(path))) => path,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "No logo uploaded"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up logo of {}: {}", agency_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load logo"
+            }));
+        }
+    };
+
+    match NamedFile::open_async(&logo_path).await {
+        Ok(file) => file.disable_content_disposition().into_response(&req),
+        Err(e) => {
+            error!("Logo {} unreadable: {}", logo_path, e);
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "No logo uploaded"
+            }))
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// JARVIS2026 - Agent performance
+// Per-agent summaries for managers: listing output, buyer attention, how
+// quickly inquiries are answered, and how leads convert into viewings and
+// closed (sold or rented) listings.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::{AgentUser, Role};
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+const DEFAULT_PERIOD_DAYS: i32 = 30;
+const MAX_PERIOD_DAYS: i32 = 365;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct AnalyticsQuery {
+    days: Option<i32>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct AgentActivity {
+    active_listings: i64,
+    listings_published: i64,
+    listings_sold: i64,
+    listings_rented: i64,
+    views: i64,
+    unique_viewers: i64,
+    contact_reveals: i64,
+    inquiries: i64,
+    inquiries_answered: i64,
+    median_response_hours: Option<f64>,
+    viewings_booked: i64,
+    viewings_attended: i64,
+}
+
+#[derive(Serialize)]
+struct Conversion {
+    /// Inquiries per view
+    view_to_inquiry: f64,
+    /// Attended viewings per inquiry
+    inquiry_to_viewing: f64,
+    /// Listings sold or rented per listing published
+    listing_to_close: f64,
+}
+
+#[derive(Serialize)]
+struct AgentAnalytics {
+    agent_id: Uuid,
+    period_days: i32,
+    #[serde(flatten)]
+    activity: AgentActivity,
+    conversion: Conversion,
+}
+
+fn ratio(numerator: i64, denominator: i64) -> f64 {
+    if denominator > 0 {
+        numerator as f64 / denominator as f64
+    } else {
+        0.0
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Visible to agents for their own figures and to admins for anyone's.
+#[get("/api/agents/{id}/analytics")]
+pub async fn agent_analytics(
+    path: web::Path<Uuid>,
+    query: web::Query<AnalyticsQuery>,
+    user: AgentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let agent_id = path.into_inner();
+    if agent_id != user.id && user.role != Role::Admin {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Not allowed to view this agent's analytics"
+        }));
+    }
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_PERIOD_DAYS)
+        .clamp(1, MAX_PERIOD_DAYS);
+
+    let sql = format!(
+        r#"WITH listings AS (
+            SELECT id, created_at, status, status_changed_at, {public} AS is_public
+            FROM properties WHERE user_id = $1
+        ),
+        since AS (SELECT NOW() - make_interval(days => $2) AS ts)
+        SELECT
+            (SELECT COUNT(*) FROM listings WHERE is_public AND {active}) AS active_listings,
+            (SELECT COUNT(*) FROM listings, since
+             WHERE is_public AND created_at >= since.ts) AS listings_published,
+            (SELECT COUNT(*) FROM sold_records s, since
+             WHERE s.seller_user_id = $1 AND s.sold_at >= since.ts) AS listings_sold,
+            (SELECT COUNT(*) FROM listings, since
+             WHERE status = 'rented' AND status_changed_at >= since.ts) AS listings_rented,
+            (SELECT COUNT(*) FROM property_views v JOIN listings l ON l.id = v.property_id, since
+             WHERE v.viewed_at >= since.ts) AS views,
+            (SELECT COUNT(DISTINCT COALESCE(v.user_id::text, v.visitor_id))
+             FROM property_views v JOIN listings l ON l.id = v.property_id, since
+             WHERE v.viewed_at >= since.ts) AS unique_viewers,
+            (SELECT COUNT(*) FROM contact_reveals r, since
+             WHERE r.owner_user_id = $1 AND r.revealed_at >= since.ts) AS contact_reveals,
+            (SELECT COUNT(*) FROM inquiries i, since
+             WHERE i.owner_user_id = $1 AND i.created_at >= since.ts) AS inquiries,
+            (SELECT COUNT(first_response_at) FROM inquiries i, since
+             WHERE i.owner_user_id = $1 AND i.created_at >= since.ts) AS inquiries_answered,
+            (SELECT percentile_cont(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM first_response_at - i.created_at)::float8 / 3600.0)
+             FROM inquiries i, since
+             WHERE i.owner_user_id = $1 AND i.created_at >= since.ts
+               AND first_response_at IS NOT NULL) AS median_response_hours,
+            (SELECT COUNT(*) FROM viewings w, since
+             WHERE w.agent_user_id = $1 AND w.scheduled_at >= since.ts) AS viewings_booked,
+            (SELECT COUNT(*) FROM viewings w, since
+             WHERE w.agent_user_id = $1 AND w.scheduled_at >= since.ts
+               AND w.status = 'attended') AS viewings_attended"#,
+        public = PUBLIC_LISTING_CONDITION,
+        active = ACTIVE_LISTING_CONDITION
+    );
+
+    match sqlx::query_as::<_, AgentActivity>(&sql)
+        .bind(agent_id)
+        .bind(days)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(activity) => {
+            let conversion = Conversion {
+                view_to_inquiry: ratio(activity.inquiries, activity.views),
+                inquiry_to_viewing: ratio(activity.viewings_attended, activity.inquiries),
+                listing_to_close: ratio(
+                    activity.listings_sold + activity.listings_rented,
+                    activity.listings_published,
+                ),
+            };
+            HttpResponse::Ok().json(AgentAnalytics {
+                agent_id,
+                period_days: days,
+                activity,
+                conversion,
+            })
+        }
+        Err(e) => {
+            error!("Failed to load analytics for agent {}: {}", agent_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load agent analytics"
+            }))
+        }
+    }
+}
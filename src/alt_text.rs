@@ -0,0 +1,350 @@
+// JARVIS2026 - Image alt text
+// Every photo and 3D model can carry alt text for screen readers, returned
+// wherever the media itself is. Owners see a suggestion for each image that
+// lacks one, written from the tags the image was given and what the listing
+// is, and can take them all in one go. Completeness holds back its last
+// points until every public photo is described.
+
+use actix_web::{get, post, put, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::completeness;
+use crate::media_tags;
+use crate::AppState;
+
+pub const MAX_ALT_TEXT_CHARS: usize = 250;
+/// Tags named in a suggestion
+const SUGGESTION_TAGS: usize = 3;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct AltTextRequest {
+    /// Empty or `null` clears it
+    alt_text: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ImageRow {
+    id: Uuid,
+    alt_text: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ImageAltText {
+    media_id: Uuid,
+    alt_text: Option<String>,
+    /// Only for images without alt text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<String>,
+    tags: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct MediaOwner {
+    property_id: Uuid,
+    owner_user_id: Option<Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ListingOwner {
+    owner_user_id: Option<Uuid>,
+    location: String,
+    property_type: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("ALTER TABLE media_uploads ADD COLUMN IF NOT EXISTS alt_text TEXT")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// SUGGESTIONS
+// ============================================================================
+
+/// "Kitchen, dining area and pool in a villa in Ubud"; without tags, just
+/// what and where the listing is.
+pub fn suggest(tags: &[String], property_type: Option<&str>, location: &str) -> String {
+    let property_type = property_type
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or("property");
+    let place = format!("{} in {}", property_type, location.trim());
+    let labels: Vec<String> = tags
+        .iter()
+        .take(SUGGESTION_TAGS)
+        .map(|tag| tag.replace('_', " "))
+        .collect();
+    let suggestion = match labels.as_slice() {
+        [] => format!("Photo of a {}", place),
+        [only] => format!("{} in a {}", only, place),
+        [rest @ .., last] => format!("{} and {} in a {}", rest.join(", "), last, place),
+    };
+    let mut chars = suggestion.chars();
+    let capitalized: String = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    capitalized.chars().take(MAX_ALT_TEXT_CHARS).collect()
+}
+
+/// Trimmed alt text, `None` for blank; an error when too long.
+fn normalize(alt_text: Option<&str>) -> Result<Option<String>, String> {
+    let Some(text) = alt_text.map(str::trim).filter(|t| !t.is_empty()) else {
+        return Ok(None);
+    };
+    if text.chars().count() > MAX_ALT_TEXT_CHARS {
+        return Err(format!(
+            "alt_text must be at most {} characters",
+            MAX_ALT_TEXT_CHARS
+        ));
+    }
+    Ok(Some(text.to_string()))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+async fn load_listing(
+    pool: &PgPool,
+    property_id: Uuid,
+    user: &CurrentUser,
+) -> Result<ListingOwner, HttpResponse> {
+    match sqlx::query_as::<_, ListingOwner>(
+        "SELECT user_id AS owner_user_id, location, property_type FROM properties WHERE id = $1",
+    )
+    .bind(property_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(listing)) if listing.owner_user_id == Some(user.id) || user.is_admin() => {
+            Ok(listing)
+        }
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the owner can manage alt text"
+        }))),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        }))),
+        Err(e) => {
+            error!("Failed to load property {}: {}", property_id, e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load property"
+            })))
+        }
+    }
+}
+
+/// The listing's photos with their alt text and suggestions for the rest.
+#[get("/api/properties/{id}/alt-text")]
+pub async fn property_alt_text(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let listing = match load_listing(&state.db, property_id, &user).await {
+        Ok(listing) => listing,
+        Err(response) => return response,
+    };
+
+    let result: Result<_, sqlx::Error> = async {
+        let images = sqlx::query_as::<_, ImageRow>(
+            r#"SELECT id, alt_text FROM media_uploads
+            WHERE property_id = $1 AND file_type = 'image'
+            ORDER BY uploaded_at, id"#,
+        )
+        .bind(property_id)
+        .fetch_all(&state.db)
+        .await?;
+        let tags = media_tags::by_media(&state.db, property_id).await?;
+        Ok((images, tags))
+    }
+    .await;
+
+    match result {
+        Ok((images, mut tags)) => {
+            let images: Vec<ImageAltText> = images
+                .into_iter()
+                .map(|image| {
+                    let tags = tags.remove(&image.id).unwrap_or_default();
+                    ImageAltText {
+                        media_id: image.id,
+                        suggestion: image.alt_text.is_none().then(|| {
+                            suggest(&tags, listing.property_type.as_deref(), &listing.location)
+                        }),
+                        alt_text: image.alt_text,
+                        tags,
+                    }
+                })
+                .collect();
+            HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "missing": images.iter().filter(|i| i.alt_text.is_none()).count(),
+                "images": images
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load alt text for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load alt text"
+            }))
+        }
+    }
+}
+
+/// Gives every photo still without alt text its suggestion.
+#[post("/api/properties/{id}/alt-text/apply-suggestions")]
+pub async fn apply_suggestions(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let listing = match load_listing(&state.db, property_id, &user).await {
+        Ok(listing) => listing,
+        Err(response) => return response,
+    };
+
+    let result: Result<usize, sqlx::Error> = async {
+        let tags = media_tags::by_media(&state.db, property_id).await?;
+        let mut tx = state.db.begin().await?;
+        let missing: Vec<Uuid> = sqlx::query_scalar(
+            r#"SELECT id FROM media_uploads
+            WHERE property_id = $1 AND file_type = 'image' AND alt_text IS NULL
+            FOR UPDATE"#,
+        )
+        .bind(property_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for media_id in &missing {
+            let text = suggest(
+                tags.get(media_id).map_or(&[][..], Vec::as_slice),
+                listing.property_type.as_deref(),
+                &listing.location,
+            );
+            sqlx::query("UPDATE media_uploads SET alt_text = $2 WHERE id = $1")
+                .bind(media_id)
+                .bind(text)
+                .execute(&mut *tx)
+                .await?;
+        }
+        completeness::refresh(&mut *tx, property_id).await?;
+        tx.commit().await?;
+        Ok(missing.len())
+    }
+    .await;
+
+    match result {
+        Ok(applied) => {
+            state.property_cache.invalidate(property_id).await;
+            info!(
+                "Applied {} alt text suggestions to {} for {}",
+                applied, property_id, user.id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "applied": applied
+            }))
+        }
+        Err(e) => {
+            error!(
+                "Failed to apply alt text suggestions to {}: {}",
+                property_id, e
+            );
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to apply alt text suggestions"
+            }))
+        }
+    }
+}
+
+#[put("/api/media/{id}/alt-text")]
+pub async fn set_alt_text(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<AltTextRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let media_id = path.into_inner();
+    let alt_text = match normalize(req.alt_text.as_deref()) {
+        Ok(alt_text) => alt_text,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": message,
+                "field": "alt_text"
+            }))
+        }
+    };
+
+    let owner = match sqlx::query_as::<_, MediaOwner>(
+        r#"SELECT m.property_id, p.user_id AS owner_user_id
+        FROM media_uploads m JOIN properties p ON p.id = m.property_id
+        WHERE m.id = $1"#,
+    )
+    .bind(media_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(owner)) => owner,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Media not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load media {}: {}", media_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update alt text"
+            }));
+        }
+    };
+    if owner.owner_user_id != Some(user.id) && !user.is_admin() {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the owner can manage alt text"
+        }));
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("UPDATE media_uploads SET alt_text = $2 WHERE id = $1")
+            .bind(media_id)
+            .bind(&alt_text)
+            .execute(&mut *tx)
+            .await?;
+        completeness::refresh(&mut *tx, owner.property_id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            state.property_cache.invalidate(owner.property_id).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "media_id": media_id,
+                "alt_text": alt_text
+            }))
+        }
+        Err(e) => {
+            error!("Failed to update alt text of {}: {}", media_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update alt text"
+            }))
+        }
+    }
+}
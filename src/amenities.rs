@@ -0,0 +1,447 @@
+// JARVIS2026 - Amenities
+// A fixed catalog of amenities (pool, garage, ...) that owners attach to
+// their listings, so search can filter on them reliably instead of guessing
+// from descriptions. Listing and search take `?amenities=pool,garage` and the
+// query language `amenity:pool` (see `filters`); admins extend the catalog.
+
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres};
+use std::collections::{BTreeSet, HashMap};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::{AdminUser, CurrentUser};
+use crate::completeness;
+use crate::listing_status::PUBLISHED_LISTING_CONDITION;
+use crate::media_tags;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+const MAX_NAME_CHARS: usize = 100;
+
+/// Seeded on startup; admins can add more through the API.
+const DEFAULT_AMENITIES: &[(&str, &str, &str)] = &[
+    ("pool", "Swimming pool", "outdoor"),
+    ("garden", "Garden", "outdoor"),
+    ("garage", "Garage", "parking"),
+    ("carport", "Carport", "parking"),
+    ("air_conditioning", "Air conditioning", "comfort"),
+    ("furnished", "Furnished", "comfort"),
+    ("water_heater", "Water heater", "comfort"),
+    ("wifi", "Wi-Fi", "utilities"),
+    ("backup_power", "Backup generator", "utilities"),
+    ("security", "24-hour security", "security"),
+    ("cctv", "CCTV", "security"),
+    ("gym", "Gym", "facilities"),
+    ("elevator", "Elevator", "facilities"),
+    ("ocean_view", "Ocean view", "views"),
+    ("rice_field_view", "Rice field view", "views"),
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Amenity {
+    slug: String,
+    name: String,
+    category: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateAmenityRequest {
+    slug: String,
+    name: String,
+    category: Option<String>,
+}
+
+enum Access {
+    Allowed,
+    NotFound,
+    Forbidden,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS amenities (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            slug TEXT UNIQUE NOT NULL,
+            name TEXT NOT NULL,
+            category TEXT NOT NULL DEFAULT 'general',
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS property_amenities (
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            amenity_id UUID NOT NULL REFERENCES amenities(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (property_id, amenity_id)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Search filters go from the amenity to the listings that have it
+    sqlx::query(
+        r#"CREATE INDEX IF NOT EXISTS idx_property_amenities_amenity
+        ON property_amenities(amenity_id, property_id)"#,
+    )
+    .execute(pool)
+    .await?;
+
+    for (slug, name, category) in DEFAULT_AMENITIES {
+        sqlx::query(
+            "INSERT INTO amenities (slug, name, category) VALUES ($1, $2, $3) \
+             ON CONFLICT (slug) DO NOTHING",
+        )
+        .bind(slug)
+        .bind(name)
+        .bind(category)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// LOOKUPS
+// ============================================================================
+
+/// Amenity slugs have the same shape as photo tags, so `has:pool` and
+/// `amenity:pool` name the same thing.
+pub fn normalize_slug(raw: &str) -> Option<String> {
+    media_tags::normalize_tag(raw)
+}
+
+async fn for_property(pool: &PgPool, property_id: Uuid) -> Result<Vec<Amenity>, sqlx::Error> {
+    sqlx::query_as::<_, Amenity>(
+        r#"SELECT a.slug, a.name, a.category
+        FROM property_amenities pa
+        JOIN amenities a ON a.id = pa.amenity_id
+        WHERE pa.property_id = $1
+        ORDER BY a.category, a.name"#,
+    )
+    .bind(property_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Listing id to the slugs of its amenities, for several listings at once.
+pub async fn slugs_for<'c, E>(
+    executor: E,
+    property_ids: &[Uuid],
+) -> Result<HashMap<Uuid, BTreeSet<String>>, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        r#"SELECT pa.property_id, a.slug
+        FROM property_amenities pa
+        JOIN amenities a ON a.id = pa.amenity_id
+        WHERE pa.property_id = ANY($1)"#,
+    )
+    .bind(property_ids)
+    .fetch_all(executor)
+    .await?;
+
+    let mut amenities: HashMap<Uuid, BTreeSet<String>> = HashMap::new();
+    for (property_id, slug) in rows {
+        amenities.entry(property_id).or_default().insert(slug);
+    }
+    Ok(amenities)
+}
+
+async fn check_owner(
+    pool: &PgPool,
+    property_id: Uuid,
+    user: &CurrentUser,
+) -> Result<Access, sqlx::Error> {
+    let owner =
+        sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM properties WHERE id = $1")
+            .bind(property_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(match owner {
+        None => Access::NotFound,
+        Some(owner) if owner == Some(user.id) || user.is_admin() => Access::Allowed,
+        Some(_) => Access::Forbidden,
+    })
+}
+
+/// Whether the listing exists and `caller` may see it: anyone for public,
+/// published listings, otherwise only the owner and admins.
+async fn visible_to(
+    pool: &PgPool,
+    property_id: Uuid,
+    caller: Option<&CurrentUser>,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query_as::<_, (Option<Uuid>, bool)>(&format!(
+        "SELECT user_id, ({}) AND ({}) FROM properties WHERE id = $1",
+        PUBLIC_LISTING_CONDITION, PUBLISHED_LISTING_CONDITION
+    ))
+    .bind(property_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some_and(|(owner, is_public)| {
+        is_public || caller.is_some_and(|c| c.is_admin() || owner == Some(c.id))
+    }))
+}
+
+/// Adds or removes one amenity; `None` when the slug isn't in the catalog.
+async fn set_amenity(
+    pool: &PgPool,
+    actor_id: Uuid,
+    property_id: Uuid,
+    slug: &str,
+    attached: bool,
+) -> Result<Option<bool>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some(amenity_id) =
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM amenities WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&mut *tx)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let changed = if attached {
+        sqlx::query(
+            "INSERT INTO property_amenities (property_id, amenity_id) VALUES ($1, $2) \
+             ON CONFLICT DO NOTHING",
+        )
+    } else {
+        sqlx::query("DELETE FROM property_amenities WHERE property_id = $1 AND amenity_id = $2")
+    }
+    .bind(property_id)
+    .bind(amenity_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if changed {
+        audit::record(
+            &mut tx,
+            actor_id,
+            if attached {
+                "property.amenity_add"
+            } else {
+                "property.amenity_remove"
+            },
+            "property",
+            property_id,
+            serde_json::json!({ "amenity": slug }),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(Some(changed))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/amenities")]
+pub async fn list_amenities(state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, Amenity>(
+        "SELECT slug, name, category FROM amenities ORDER BY category, name",
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(amenities) => HttpResponse::Ok().json(amenities),
+        Err(e) => {
+            error!("Failed to list amenities: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list amenities"
+            }))
+        }
+    }
+}
+
+#[post("/api/admin/amenities")]
+pub async fn create_amenity(
+    admin: AdminUser,
+    req: web::Json<CreateAmenityRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let Some(slug) = normalize_slug(&req.slug) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "slug is required"
+        }));
+    };
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("name must be between 1 and {} characters", MAX_NAME_CHARS)
+        }));
+    }
+    let category = req
+        .category
+        .as_deref()
+        .and_then(normalize_slug)
+        .unwrap_or_else(|| "general".to_string());
+
+    match sqlx::query_as::<_, Amenity>(
+        r#"INSERT INTO amenities (slug, name, category) VALUES ($1, $2, $3)
+        ON CONFLICT (slug) DO NOTHING
+        RETURNING slug, name, category"#,
+    )
+    .bind(&slug)
+    .bind(name)
+    .bind(&category)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(amenity)) => {
+            info!("Amenity '{}' added by {}", slug, admin.id);
+            HttpResponse::Created().json(amenity)
+        }
+        Ok(None) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Amenity '{}' already exists", slug)
+        })),
+        Err(e) => {
+            error!("Failed to create amenity '{}': {}", slug, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create amenity"
+            }))
+        }
+    }
+}
+
+#[get("/api/properties/{id}/amenities")]
+pub async fn get_property_amenities(
+    path: web::Path<Uuid>,
+    caller: Option<CurrentUser>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    match visible_to(&state.db, property_id, caller.as_ref()).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load property"
+            }));
+        }
+    }
+    match for_property(&state.db, property_id).await {
+        Ok(amenities) => HttpResponse::Ok().json(amenities),
+        Err(e) => {
+            error!("Failed to load amenities of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load amenities"
+            }))
+        }
+    }
+}
+
+async fn update_property_amenity(
+    property_id: Uuid,
+    slug: &str,
+    attached: bool,
+    user: CurrentUser,
+    state: &AppState,
+) -> HttpResponse {
+    let Some(slug) = normalize_slug(slug) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Amenity is required"
+        }));
+    };
+
+    match check_owner(&state.db, property_id, &user).await {
+        Ok(Access::Allowed) => {}
+        Ok(Access::NotFound) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Ok(Access::Forbidden) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only the owner can change listing amenities"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load property"
+            }));
+        }
+    }
+
+    let changed = match set_amenity(&state.db, user.id, property_id, &slug, attached).await {
+        Ok(Some(changed)) => changed,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("Unknown amenity '{}'", slug)
+            }))
+        }
+        Err(e) => {
+            error!(
+                "Failed to update amenity '{}' on {}: {}",
+                slug, property_id, e
+            );
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update amenities"
+            }));
+        }
+    };
+    if changed {
+        if let Err(e) = completeness::refresh(&state.db, property_id).await {
+            error!("Failed to score completeness of {}: {}", property_id, e);
+        }
+        state.property_cache.invalidate(property_id).await;
+    }
+
+    match for_property(&state.db, property_id).await {
+        Ok(amenities) => HttpResponse::Ok().json(amenities),
+        Err(e) => {
+            error!("Failed to load amenities of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load amenities"
+            }))
+        }
+    }
+}
+
+/// Attaches an amenity; attaching one the listing already has is a no-op.
+#[put("/api/properties/{id}/amenities/{slug}")]
+pub async fn attach_amenity(
+    path: web::Path<(Uuid, String)>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (property_id, slug) = path.into_inner();
+    update_property_amenity(property_id, &slug, true, user, &state).await
+}
+
+#[delete("/api/properties/{id}/amenities/{slug}")]
+pub async fn detach_amenity(
+    path: web::Path<(Uuid, String)>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (property_id, slug) = path.into_inner();
+    update_property_amenity(property_id, &slug, false, user, &state).await
+}
@@ -0,0 +1,487 @@
+// JARVIS2026 - Market analytics endpoints
+// Aggregates are computed server-side so map layers never need the full listing set.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::search::MIN_LOCATION_QUERY_LEN;
+use crate::AppState;
+
+const DEFAULT_HEATMAP_CELLS: u32 = 32;
+const MAX_HEATMAP_CELLS: u32 = 256;
+
+const TRENDING_WINDOWS: &[i32] = &[7, 30];
+const TRENDING_CACHE_TTL: Duration = Duration::from_secs(3600);
+const TRENDING_LIMIT: usize = 20;
+/// Locations need this much activity in the current window to rank at all.
+const TRENDING_MIN_VOLUME: i64 = 5;
+/// Added to the previous-window volume so a jump from 0 to 2 doesn't top the list.
+const TRENDING_SMOOTHING: f64 = 10.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+struct HeatmapQuery {
+    /// `min_lng,min_lat,max_lng,max_lat`
+    bbox: String,
+    metric: Option<String>,
+    /// `grid` (default) or `hex`
+    bin: Option<String>,
+    /// Number of cells across the wider side of the bbox
+    cells: Option<u32>,
+}
+
+#[derive(Clone, Copy)]
+struct BoundingBox {
+    min_lng: f64,
+    min_lat: f64,
+    max_lng: f64,
+    max_lat: f64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum HeatmapMetric {
+    PricePerSqm,
+    Price,
+    Count,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BinShape {
+    Grid,
+    Hex,
+}
+
+#[derive(sqlx::FromRow)]
+struct GeoPriceRow {
+    latitude: f64,
+    longitude: f64,
+    price: f64,
+    area_sqm: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct HeatmapBin {
+    lat: f64,
+    lng: f64,
+    count: usize,
+    mean: f64,
+    median: f64,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Serialize)]
+struct HeatmapResponse {
+    metric: &'static str,
+    bin: &'static str,
+    cell_size_deg: f64,
+    total: usize,
+    bins: Vec<HeatmapBin>,
+}
+
+#[derive(Deserialize)]
+struct TrendingQuery {
+    days: Option<i32>,
+}
+
+#[derive(sqlx::FromRow)]
+struct LocationActivity {
+    location: String,
+    searches: i64,
+    previous_searches: i64,
+    views: i64,
+    previous_views: i64,
+}
+
+#[derive(Clone, Serialize)]
+struct TrendingLocation {
+    location: String,
+    searches: i64,
+    views: i64,
+    previous_volume: i64,
+    /// Relative change in combined search and view volume versus the previous window.
+    growth: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct TrendingResponse {
+    days: i32,
+    generated_at: chrono::DateTime<chrono::Utc>,
+    locations: Vec<TrendingLocation>,
+}
+
+/// Trending results per window, recomputed at most once an hour.
+pub struct TrendingCache {
+    entries: Mutex<HashMap<i32, (Instant, TrendingResponse)>>,
+}
+
+impl BoundingBox {
+    fn parse(raw: &str) -> Option<Self> {
+        let parts: Vec<f64> = raw
+            .split(',')
+            .map(|p| p.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .ok()?;
+        if parts.len() != 4 {
+            return None;
+        }
+        let bbox = BoundingBox {
+            min_lng: parts[0],
+            min_lat: parts[1],
+            max_lng: parts[2],
+            max_lat: parts[3],
+        };
+        let valid = (-180.0..=180.0).contains(&bbox.min_lng)
+            && (-180.0..=180.0).contains(&bbox.max_lng)
+            && (-90.0..=90.0).contains(&bbox.min_lat)
+            && (-90.0..=90.0).contains(&bbox.max_lat)
+            && bbox.min_lng < bbox.max_lng
+            && bbox.min_lat < bbox.max_lat;
+        valid.then_some(bbox)
+    }
+}
+
+impl HeatmapMetric {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        match raw.unwrap_or("price_per_sqm") {
+            "price_per_sqm" => Some(HeatmapMetric::PricePerSqm),
+            "price" => Some(HeatmapMetric::Price),
+            "count" => Some(HeatmapMetric::Count),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            HeatmapMetric::PricePerSqm => "price_per_sqm",
+            HeatmapMetric::Price => "price",
+            HeatmapMetric::Count => "count",
+        }
+    }
+
+    fn value(self, row: &GeoPriceRow) -> Option<f64> {
+        match self {
+            HeatmapMetric::PricePerSqm => match row.area_sqm {
+                Some(area) if area > 0.0 => Some(row.price / area),
+                _ => None,
+            },
+            HeatmapMetric::Price => Some(row.price),
+            HeatmapMetric::Count => Some(1.0),
+        }
+    }
+}
+
+// ============================================================================
+// BINNING
+// ============================================================================
+
+type CellKey = (i64, i64);
+type LatLng = (f64, f64);
+
+/// Returns the cell key and the cell center (lat, lng) for a point.
+fn grid_cell(bbox: &BoundingBox, cell: f64, lat: f64, lng: f64) -> (CellKey, LatLng) {
+    let ix = ((lng - bbox.min_lng) / cell).floor() as i64;
+    let iy = ((lat - bbox.min_lat) / cell).floor() as i64;
+    let center_lng = bbox.min_lng + (ix as f64 + 0.5) * cell;
+    let center_lat = bbox.min_lat + (iy as f64 + 0.5) * cell;
+    ((ix, iy), (center_lat, center_lng))
+}
+
+/// Pointy-top hexagons in axial coordinates, `size` being the center-to-corner distance.
+fn hex_cell(bbox: &BoundingBox, size: f64, lat: f64, lng: f64) -> (CellKey, LatLng) {
+    let sqrt3 = 3f64.sqrt();
+    let x = lng - bbox.min_lng;
+    let y = lat - bbox.min_lat;
+
+    let q = (sqrt3 / 3.0 * x - y / 3.0) / size;
+    let r = (2.0 / 3.0 * y) / size;
+
+    // Cube rounding keeps points on the correct side of hex edges
+    let (cx, cz) = (q, r);
+    let cy = -cx - cz;
+    let (mut rx, ry, mut rz) = (cx.round(), cy.round(), cz.round());
+    let (dx, dy, dz) = ((rx - cx).abs(), (ry - cy).abs(), (rz - cz).abs());
+    if dx > dy && dx > dz {
+        rx = -ry - rz;
+    } else if dy <= dz {
+        rz = -rx - ry;
+    }
+
+    let center_lng = bbox.min_lng + size * (sqrt3 * rx + sqrt3 / 2.0 * rz);
+    let center_lat = bbox.min_lat + size * (1.5 * rz);
+    ((rx as i64, rz as i64), (center_lat, center_lng))
+}
+
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n == 0 {
+        return 0.0;
+    }
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+fn build_heatmap(
+    rows: &[GeoPriceRow],
+    bbox: &BoundingBox,
+    metric: HeatmapMetric,
+    shape: BinShape,
+    cells: u32,
+) -> (f64, Vec<HeatmapBin>) {
+    let span = (bbox.max_lng - bbox.min_lng).max(bbox.max_lat - bbox.min_lat);
+    let cell = span / cells as f64;
+
+    let mut buckets: HashMap<CellKey, (LatLng, Vec<f64>)> = HashMap::new();
+    for row in rows {
+        let Some(value) = metric.value(row) else {
+            continue;
+        };
+        let (key, center) = match shape {
+            BinShape::Grid => grid_cell(bbox, cell, row.latitude, row.longitude),
+            BinShape::Hex => hex_cell(bbox, cell / 2.0, row.latitude, row.longitude),
+        };
+        buckets
+            .entry(key)
+            .or_insert_with(|| (center, Vec::new()))
+            .1
+            .push(value);
+    }
+
+    let mut bins: Vec<HeatmapBin> = buckets
+        .into_values()
+        .map(|((lat, lng), mut values)| {
+            values.sort_by(|a, b| a.total_cmp(b));
+            let count = values.len();
+            let sum: f64 = values.iter().sum();
+            HeatmapBin {
+                lat,
+                lng,
+                count,
+                mean: if metric == HeatmapMetric::Count {
+                    count as f64
+                } else {
+                    sum / count as f64
+                },
+                median: median(&values),
+                min: values[0],
+                max: values[count - 1],
+            }
+        })
+        .collect();
+
+    bins.sort_by(|a, b| a.lat.total_cmp(&b.lat).then(a.lng.total_cmp(&b.lng)));
+    (cell, bins)
+}
+
+// ============================================================================
+// TRENDING LOCATIONS
+// ============================================================================
+
+impl TrendingCache {
+    pub fn new() -> Self {
+        TrendingCache {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, days: i32) -> Option<TrendingResponse> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&days)
+            .filter(|(stored_at, _)| stored_at.elapsed() < TRENDING_CACHE_TTL)
+            .map(|(_, response)| response.clone())
+    }
+
+    fn insert(&self, days: i32, response: TrendingResponse) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(days, (Instant::now(), response));
+    }
+}
+
+/// Search and view counts per location for the last `days` and the `days` before that.
+async fn location_activity(
+    pool: &sqlx::PgPool,
+    days: i32,
+) -> Result<Vec<LocationActivity>, sqlx::Error> {
+    sqlx::query_as::<_, LocationActivity>(&format!(
+        r#"WITH events AS (
+            SELECT l.location, 'search' AS kind, s.searched_at AS at
+            FROM search_queries s
+            JOIN (SELECT DISTINCT location FROM properties WHERE {public} AND {active}) l
+              ON LOWER(l.location) LIKE '%' || s.normalized || '%'
+            WHERE s.searched_at >= NOW() - make_interval(days => $1 * 2)
+              AND LENGTH(s.normalized) >= $2
+            UNION ALL
+            SELECT p.location, 'view' AS kind, v.viewed_at AS at
+            FROM property_views v
+            JOIN properties p ON p.id = v.property_id
+            WHERE v.viewed_at >= NOW() - make_interval(days => $1 * 2)
+              AND p.{public} AND p.{active}
+        )
+        SELECT location,
+            COUNT(*) FILTER (WHERE kind = 'search' AND at >= NOW() - make_interval(days => $1)) AS searches,
+            COUNT(*) FILTER (WHERE kind = 'search' AND at < NOW() - make_interval(days => $1)) AS previous_searches,
+            COUNT(*) FILTER (WHERE kind = 'view' AND at >= NOW() - make_interval(days => $1)) AS views,
+            COUNT(*) FILTER (WHERE kind = 'view' AND at < NOW() - make_interval(days => $1)) AS previous_views
+        FROM events
+        GROUP BY location"#,
+        public = PUBLIC_LISTING_CONDITION,
+        active = ACTIVE_LISTING_CONDITION
+    ))
+    .bind(days)
+    .bind(MIN_LOCATION_QUERY_LEN)
+    .fetch_all(pool)
+    .await
+}
+
+fn rank_trending(activity: Vec<LocationActivity>) -> Vec<TrendingLocation> {
+    let mut trending: Vec<TrendingLocation> = activity
+        .into_iter()
+        .filter_map(|a| {
+            let current = a.searches + a.views;
+            let previous = a.previous_searches + a.previous_views;
+            if current < TRENDING_MIN_VOLUME || current <= previous {
+                return None;
+            }
+            Some(TrendingLocation {
+                location: a.location,
+                searches: a.searches,
+                views: a.views,
+                previous_volume: previous,
+                growth: (current - previous) as f64 / (previous as f64 + TRENDING_SMOOTHING),
+            })
+        })
+        .collect();
+
+    trending.sort_by(|a, b| {
+        b.growth
+            .total_cmp(&a.growth)
+            .then_with(|| a.location.cmp(&b.location))
+    });
+    trending.truncate(TRENDING_LIMIT);
+    trending
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/analytics/heatmap")]
+pub async fn price_heatmap(
+    query: web::Query<HeatmapQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let Some(bbox) = BoundingBox::parse(&query.bbox) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "bbox must be min_lng,min_lat,max_lng,max_lat"
+        }));
+    };
+
+    let Some(metric) = HeatmapMetric::parse(query.metric.as_deref()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "metric must be one of price_per_sqm, price, count"
+        }));
+    };
+
+    let shape = match query.bin.as_deref().unwrap_or("grid") {
+        "grid" => BinShape::Grid,
+        "hex" => BinShape::Hex,
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "bin must be grid or hex"
+            }))
+        }
+    };
+
+    let cells = query
+        .cells
+        .unwrap_or(DEFAULT_HEATMAP_CELLS)
+        .clamp(1, MAX_HEATMAP_CELLS);
+
+    let rows = match sqlx::query_as::<_, GeoPriceRow>(&format!(
+        "SELECT latitude, longitude, price, area_sqm FROM properties
+         WHERE latitude IS NOT NULL AND longitude IS NOT NULL
+         AND longitude BETWEEN $1 AND $3
+         AND latitude BETWEEN $2 AND $4
+         AND {} AND {}",
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(bbox.min_lng)
+    .bind(bbox.min_lat)
+    .bind(bbox.max_lng)
+    .bind(bbox.max_lat)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!("Failed to load heatmap data: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to compute heatmap"
+            }));
+        }
+    };
+
+    let (cell_size_deg, bins) = build_heatmap(&rows, &bbox, metric, shape, cells);
+    let total = bins.iter().map(|b| b.count).sum();
+
+    HttpResponse::Ok().json(HeatmapResponse {
+        metric: metric.name(),
+        bin: match shape {
+            BinShape::Grid => "grid",
+            BinShape::Hex => "hex",
+        },
+        cell_size_deg,
+        total,
+        bins,
+    })
+}
+
+#[get("/api/analytics/trending-locations")]
+pub async fn trending_locations(
+    query: web::Query<TrendingQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let days = query.days.unwrap_or(TRENDING_WINDOWS[0]);
+    if !TRENDING_WINDOWS.contains(&days) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "days must be 7 or 30"
+        }));
+    }
+
+    if let Some(cached) = state.trending_cache.get(days) {
+        return HttpResponse::Ok().json(cached);
+    }
+
+    match location_activity(&state.db, days).await {
+        Ok(activity) => {
+            let response = TrendingResponse {
+                days,
+                generated_at: chrono::Utc::now(),
+                locations: rank_trending(activity),
+            };
+            state.trending_cache.insert(days, response.clone());
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            error!("Failed to compute trending locations: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to compute trending locations"
+            }))
+        }
+    }
+}
@@ -0,0 +1,462 @@
+// JARVIS2026 - Partner API keys
+// Server-to-server clients (partner portals) authenticate with an
+// `X-Api-Key` header. Admins issue keys with a set of scopes; the middleware
+// rejects unknown or revoked keys, only lets a key reach routes its scopes
+// cover, and counts usage per key and day. Requests without the header pass
+// through untouched. Only a SHA-256 of each key is stored.
+//
+// Keys with `write:properties` act as the account they were issued for: the
+// `CurrentUser` extractor resolves to that account. Read-only keys have no
+// account, so they can never reach user routes.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::ResponseError,
+    get,
+    http::{Method, StatusCode},
+    middleware::Next,
+    post, web, Error, HttpMessage, HttpRequest, HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::fmt;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::AppState;
+
+const API_KEY_HEADER: &str = "X-Api-Key";
+const KEY_PREFIX: &str = "jv_";
+/// Characters of the key kept in clear so admins can tell keys apart.
+const DISPLAY_PREFIX_LEN: usize = 11;
+const DEFAULT_USAGE_DAYS: i32 = 30;
+const MAX_USAGE_DAYS: i32 = 365;
+
+pub const SCOPE_READ_PROPERTIES: &str = "read:properties";
+pub const SCOPE_WRITE_PROPERTIES: &str = "write:properties";
+pub const SCOPE_READ_ANALYTICS: &str = "read:analytics";
+const KNOWN_SCOPES: &[&str] = &[
+    SCOPE_READ_PROPERTIES,
+    SCOPE_WRITE_PROPERTIES,
+    SCOPE_READ_ANALYTICS,
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug)]
+pub enum ApiKeyError {
+    Invalid,
+    OutOfScope(&'static str),
+    NotAvailable,
+    Internal,
+}
+
+/// The key a request authenticated with, stored in the request extensions.
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub key_id: Uuid,
+    /// Account the key acts as; only write-scoped keys have one
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateKeyRequest {
+    name: String,
+    scopes: Vec<String>,
+    /// Required with `write:properties`
+    user_id: Option<Uuid>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct ApiKeyInfo {
+    id: Uuid,
+    name: String,
+    key_prefix: String,
+    scopes: Vec<String>,
+    user_id: Option<Uuid>,
+    request_count: i64,
+    last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_by: Option<Uuid>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize)]
+struct CreatedKey {
+    /// Shown once; only its hash is kept
+    key: String,
+    #[serde(flatten)]
+    info: ApiKeyInfo,
+}
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    days: Option<i32>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct DailyUsage {
+    day: chrono::NaiveDate,
+    requests: i64,
+}
+
+impl fmt::Display for ApiKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiKeyError::Invalid => write!(f, "Invalid or revoked API key"),
+            ApiKeyError::OutOfScope(scope) => write!(f, "API key lacks the '{}' scope", scope),
+            ApiKeyError::NotAvailable => write!(f, "This endpoint is not available to API keys"),
+            ApiKeyError::Internal => write!(f, "API key authentication failed"),
+        }
+    }
+}
+
+impl ResponseError for ApiKeyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiKeyError::Invalid => StatusCode::UNAUTHORIZED,
+            ApiKeyError::OutOfScope(_) | ApiKeyError::NotAvailable => StatusCode::FORBIDDEN,
+            ApiKeyError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "error": self.to_string()
+        }))
+    }
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS api_keys (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name TEXT NOT NULL,
+            key_prefix TEXT NOT NULL,
+            key_hash TEXT UNIQUE NOT NULL,
+            scopes TEXT[] NOT NULL,
+            request_count BIGINT NOT NULL DEFAULT 0,
+            last_used_at TIMESTAMPTZ,
+            created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            revoked_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS api_key_usage (
+            key_id UUID NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+            day DATE NOT NULL,
+            requests BIGINT NOT NULL DEFAULT 0,
+            PRIMARY KEY (key_id, day)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS user_id UUID REFERENCES users(id) ON DELETE CASCADE",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// MIDDLEWARE
+// ============================================================================
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Listing writes a partner may make: creating listings, and changing or
+/// closing one of its own (`/api/properties/{id}`, `.../sold` and `.../status`).
+fn is_listing_write(method: &Method, path: &str) -> bool {
+    if *method == Method::GET {
+        return false;
+    }
+    if *method == Method::POST && path == "/api/upload-property" {
+        return true;
+    }
+    let segments: Vec<&str> = path
+        .trim_start_matches("/api/properties/")
+        .split('/')
+        .collect();
+    path.starts_with("/api/properties/")
+        && Uuid::parse_str(segments[0]).is_ok()
+        && matches!(segments[1..], [] | ["sold"] | ["status"])
+}
+
+/// The scope a key needs to call a route; `None` for routes keys can't use.
+fn required_scope(method: &Method, path: &str) -> Option<&'static str> {
+    let is_listing_read = (method == Method::GET
+        && (path == "/api/properties"
+            || path.starts_with("/api/properties/")
+            || path == "/api/search/suggest"
+            || path == "/api/search/nearby"
+            || path == "/api/search/semantic"))
+        || (method == Method::POST
+            && (path == "/api/search"
+                || path == "/api/search/parse"
+                || path == "/api/estimate-price"));
+    if is_listing_read {
+        return Some(SCOPE_READ_PROPERTIES);
+    }
+    if is_listing_write(method, path) {
+        return Some(SCOPE_WRITE_PROPERTIES);
+    }
+    (method == Method::GET && path.starts_with("/api/analytics/")).then_some(SCOPE_READ_ANALYTICS)
+}
+
+/// The API key the request came with, if any.
+pub fn context(req: &HttpRequest) -> Option<ApiKeyContext> {
+    req.extensions().get::<ApiKeyContext>().cloned()
+}
+
+fn record_usage(pool: &PgPool, key_id: Uuid) {
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let result: Result<(), sqlx::Error> = async {
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "UPDATE api_keys SET request_count = request_count + 1, last_used_at = NOW() WHERE id = $1",
+            )
+            .bind(key_id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                r#"INSERT INTO api_key_usage (key_id, day, requests) VALUES ($1, CURRENT_DATE, 1)
+                ON CONFLICT (key_id, day) DO UPDATE SET requests = api_key_usage.requests + 1"#,
+            )
+            .bind(key_id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await
+        }
+        .await;
+        if let Err(e) = result {
+            warn!("Failed to record usage for API key {}: {}", key_id, e);
+        }
+    });
+}
+
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(key) = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+    else {
+        return next.call(req).await;
+    };
+    let state = req
+        .app_data::<web::Data<AppState>>()
+        .cloned()
+        .ok_or(ApiKeyError::Internal)?;
+
+    let found = sqlx::query_as::<_, (Uuid, Vec<String>, Option<Uuid>)>(
+        "SELECT id, scopes, user_id FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+    )
+    .bind(hash_key(&key))
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        error!("Failed to look up API key: {}", e);
+        ApiKeyError::Internal
+    })?;
+    let (key_id, scopes, user_id) = found.ok_or(ApiKeyError::Invalid)?;
+
+    let scope = required_scope(req.method(), req.path()).ok_or(ApiKeyError::NotAvailable)?;
+    if !scopes.iter().any(|s| s == scope) {
+        return Err(ApiKeyError::OutOfScope(scope).into());
+    }
+
+    record_usage(&state.db, key_id);
+    req.extensions_mut()
+        .insert(ApiKeyContext { key_id, user_id });
+    next.call(req).await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/admin/api-keys")]
+pub async fn create_api_key(
+    admin: AdminUser,
+    req: web::Json<CreateKeyRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "name is required"
+        }));
+    }
+    if req.scopes.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Provide at least one scope: {}", KNOWN_SCOPES.join(", "))
+        }));
+    }
+    if let Some(unknown) = req
+        .scopes
+        .iter()
+        .find(|s| !KNOWN_SCOPES.contains(&s.as_str()))
+    {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unknown scope '{}'", unknown)
+        }));
+    }
+
+    let key = format!(
+        "{}{}{}",
+        KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let mut scopes = req.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    // Writes need an account to own them; read-only keys must not carry one
+    let writes = scopes.iter().any(|s| s == SCOPE_WRITE_PROPERTIES);
+    if writes != req.user_id.is_some() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("user_id is required with, and only allowed with, the '{}' scope", SCOPE_WRITE_PROPERTIES)
+        }));
+    }
+
+    match sqlx::query_as::<_, ApiKeyInfo>(
+        r#"INSERT INTO api_keys (name, key_prefix, key_hash, scopes, user_id, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, name, key_prefix, scopes, user_id, request_count, last_used_at,
+                  created_by, created_at, revoked_at"#,
+    )
+    .bind(name)
+    .bind(&key[..DISPLAY_PREFIX_LEN])
+    .bind(hash_key(&key))
+    .bind(&scopes)
+    .bind(req.user_id)
+    .bind(admin.id)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(info) => {
+            info!(
+                "API key '{}' ({}) issued by {}",
+                info.name, info.id, admin.id
+            );
+            HttpResponse::Ok().json(CreatedKey { key, info })
+        }
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => HttpResponse::BadRequest()
+            .json(serde_json::json!({
+                "error": "user_id does not match a user"
+            })),
+        Err(e) => {
+            error!("Failed to create API key: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create API key"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/api-keys")]
+pub async fn list_api_keys(_admin: AdminUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, ApiKeyInfo>(
+        r#"SELECT id, name, key_prefix, scopes, user_id, request_count, last_used_at,
+                  created_by, created_at, revoked_at
+        FROM api_keys ORDER BY created_at DESC"#,
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(keys) => HttpResponse::Ok().json(keys),
+        Err(e) => {
+            error!("Failed to list API keys: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list API keys"
+            }))
+        }
+    }
+}
+
+#[post("/api/admin/api-keys/{id}/revoke")]
+pub async fn revoke_api_key(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let key_id = path.into_inner();
+
+    match sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(key_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "API key not found or already revoked"
+            }))
+        }
+        Ok(_) => {
+            info!("API key {} revoked by {}", key_id, admin.id);
+            HttpResponse::Ok().json(serde_json::json!({ "revoked": key_id }))
+        }
+        Err(e) => {
+            error!("Failed to revoke API key {}: {}", key_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to revoke API key"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/api-keys/{id}/usage")]
+pub async fn api_key_usage(
+    _admin: AdminUser,
+    path: web::Path<Uuid>,
+    query: web::Query<UsageQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let key_id = path.into_inner();
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_USAGE_DAYS)
+        .clamp(1, MAX_USAGE_DAYS);
+
+    match sqlx::query_as::<_, DailyUsage>(
+        r#"SELECT day, requests FROM api_key_usage
+        WHERE key_id = $1 AND day > CURRENT_DATE - $2
+        ORDER BY day"#,
+    )
+    .bind(key_id)
+    .bind(days)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(usage) => HttpResponse::Ok().json(usage),
+        Err(e) => {
+            error!("Failed to load usage for API key {}: {}", key_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load API key usage"
+            }))
+        }
+    }
+}
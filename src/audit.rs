@@ -0,0 +1,61 @@
+// JARVIS2026 - Audit log
+// Append-only record of privileged actions. Entries are written inside the
+// caller's transaction so an action and its audit trail commit together.
+
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS audit_log (
+            id BIGSERIAL PRIMARY KEY,
+            actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            action TEXT NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id UUID,
+            details JSONB NOT NULL DEFAULT '{}'::jsonb,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log(target_type, target_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// RECORDING
+// ============================================================================
+
+pub async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    actor_id: Uuid,
+    action: &str,
+    target_type: &str,
+    target_id: Uuid,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO audit_log (actor_id, action, target_type, target_id, details)
+        VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(actor_id)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(sqlx::types::Json(details))
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
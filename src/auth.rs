@@ -0,0 +1,422 @@
+// JARVIS2026 - Request authentication and roles
+// Resolves the calling user for `/me` style endpoints. Callers authenticate
+// with an API key (see `api_keys`) or `Authorization: Bearer <session token>`
+// (see `sessions`); a bare user id proves nothing, since ids are public in
+// listing JSON. Every user has a role (user / agent / admin); `AgentUser`
+// and `AdminUser` enforce them per route. `ADMIN_USER_IDS` only bootstraps
+// the first admins at startup.
+
+use actix_web::{
+    dev::Payload,
+    error::ResponseError,
+    http::{header, StatusCode},
+    put, web, FromRequest, HttpRequest, HttpResponse, Responder,
+};
+use futures_util::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::fmt;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::api_keys;
+use crate::audit;
+use crate::sessions;
+use crate::AppState;
+
+pub mod oauth;
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// Ordered by privilege: each role can do everything the ones before it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Agent,
+    Admin,
+}
+
+/// The authenticated caller. Extracting it fails with 401 when no valid user is given.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser {
+    pub id: Uuid,
+    pub role: Role,
+}
+
+/// A caller signed in with a session token; API keys don't qualify. For
+/// account-level actions such as data export and deletion.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionUser {
+    pub id: Uuid,
+    pub role: Role,
+    /// When the user last proved who they are; refreshing a session keeps it
+    pub signed_in_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A caller with the agent role or higher; fails with 403 for everyone else.
+#[derive(Debug, Clone, Copy)]
+pub struct AgentUser {
+    pub id: Uuid,
+    pub role: Role,
+}
+
+/// A caller with the admin role; fails with 403 for everyone else.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminUser {
+    pub id: Uuid,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    Missing,
+    UnknownUser,
+    Forbidden(Role),
+    Banned,
+    /// An API key without an account tried to act as a user
+    ReadOnlyKey,
+    /// The route needs a signed-in session rather than an API key
+    SessionRequired,
+    Internal,
+}
+
+#[derive(Deserialize)]
+pub struct SetRoleRequest {
+    role: Role,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Agent => "agent",
+            Role::Admin => "admin",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Role> {
+        match raw {
+            "user" => Some(Role::User),
+            "agent" => Some(Role::Agent),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl CurrentUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+}
+
+impl SessionUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    /// Whether the user signed in within the last `minutes`, for actions
+    /// that ask for a fresh sign-in.
+    pub fn signed_in_within(&self, minutes: i64) -> bool {
+        chrono::Utc::now() - self.signed_in_at < chrono::Duration::minutes(minutes)
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Missing => write!(f, "Authentication required"),
+            AuthError::UnknownUser => write!(f, "Unknown user"),
+            AuthError::Forbidden(Role::Admin) => write!(f, "Admin access required"),
+            AuthError::Forbidden(Role::Agent) => write!(f, "Agent access required"),
+            AuthError::Forbidden(Role::User) => write!(f, "Access denied"),
+            AuthError::Banned => write!(f, "Account banned"),
+            AuthError::ReadOnlyKey => write!(f, "This API key is read-only"),
+            AuthError::SessionRequired => write!(f, "Sign in to do this"),
+            AuthError::Internal => write!(f, "Authentication failed"),
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Missing | AuthError::UnknownUser | AuthError::SessionRequired => {
+                StatusCode::UNAUTHORIZED
+            }
+            AuthError::Forbidden(_) | AuthError::Banned | AuthError::ReadOnlyKey => {
+                StatusCode::FORBIDDEN
+            }
+            AuthError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "error": self.to_string()
+        }))
+    }
+}
+
+/// Parses a comma-separated list of user ids, skipping anything malformed.
+pub fn parse_admin_ids(raw: &str) -> HashSet<Uuid> {
+    raw.split(',')
+        .filter_map(|id| Uuid::parse_str(id.trim()).ok())
+        .collect()
+}
+
+/// The raw token from an `Authorization: Bearer` header.
+pub fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix(BEARER_PREFIX))
+        .map(|token| token.trim().to_string())
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"ALTER TABLE users ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'user'
+        CHECK (role IN ('user', 'agent', 'admin'))"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Grants the admin role to the users in `ADMIN_USER_IDS`, so a fresh
+/// deployment has someone who can hand out roles.
+pub async fn bootstrap_admins(pool: &PgPool, ids: &HashSet<Uuid>) -> Result<u64, sqlx::Error> {
+    let ids: Vec<Uuid> = ids.iter().copied().collect();
+    sqlx::query("UPDATE users SET role = 'admin' WHERE id = ANY($1) AND role <> 'admin'")
+        .bind(&ids)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+// ============================================================================
+// EXTRACTORS
+// ============================================================================
+
+/// The role of an authenticated user, refusing banned accounts.
+async fn load_role(pool: &PgPool, user_id: Uuid) -> Result<Role, AuthError> {
+    let (role, status) =
+        sqlx::query_as::<_, (String, String)>("SELECT role, status FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("Failed to authenticate user {}: {}", user_id, e);
+                AuthError::Internal
+            })?
+            .ok_or(AuthError::UnknownUser)?;
+
+    // Suspensions are enforced where they apply; a ban shuts everything
+    if status == "banned" {
+        return Err(AuthError::Banned);
+    }
+    Ok(Role::parse(&role).unwrap_or(Role::User))
+}
+
+impl FromRequest for CurrentUser {
+    type Error = AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let bearer = bearer_token(req);
+        let api_key = api_keys::context(req);
+        let state = req.app_data::<web::Data<AppState>>().cloned();
+
+        Box::pin(async move {
+            // An API key acts as its own account, whatever else is sent; a
+            // session token comes next
+            let user_id = match (api_key, bearer) {
+                (Some(key), _) => key.user_id.ok_or(AuthError::ReadOnlyKey)?,
+                (None, Some(token)) => {
+                    let pool = &state.as_ref().ok_or(AuthError::Internal)?.db;
+                    sessions::user_for_token(pool, &token)
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to resolve session: {}", e);
+                            AuthError::Internal
+                        })?
+                        .ok_or(AuthError::UnknownUser)?
+                }
+                (None, None) => return Err(AuthError::Missing),
+            };
+            let state = state.ok_or(AuthError::Internal)?;
+
+            Ok(CurrentUser {
+                id: user_id,
+                role: load_role(&state.db, user_id).await?,
+            })
+        })
+    }
+}
+
+impl FromRequest for SessionUser {
+    type Error = AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let bearer = bearer_token(req).filter(|_| api_keys::context(req).is_none());
+        let state = req.app_data::<web::Data<AppState>>().cloned();
+
+        Box::pin(async move {
+            let state = state.ok_or(AuthError::Internal)?;
+            let token = bearer.ok_or(AuthError::SessionRequired)?;
+            let (user_id, signed_in_at) = sessions::sign_in_for_token(&state.db, &token)
+                .await
+                .map_err(|e| {
+                    error!("Failed to resolve session: {}", e);
+                    AuthError::Internal
+                })?
+                .ok_or(AuthError::UnknownUser)?;
+
+            Ok(SessionUser {
+                id: user_id,
+                role: load_role(&state.db, user_id).await?,
+                signed_in_at,
+            })
+        })
+    }
+}
+
+impl FromRequest for AgentUser {
+    type Error = AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = CurrentUser::from_request(req, payload);
+
+        Box::pin(async move {
+            let user = user.await?;
+            if user.role < Role::Agent {
+                return Err(AuthError::Forbidden(Role::Agent));
+            }
+            Ok(AgentUser {
+                id: user.id,
+                role: user.role,
+            })
+        })
+    }
+}
+
+impl FromRequest for AdminUser {
+    type Error = AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = CurrentUser::from_request(req, payload);
+
+        Box::pin(async move {
+            let user = user.await?;
+            if !user.is_admin() {
+                return Err(AuthError::Forbidden(Role::Admin));
+            }
+            Ok(AdminUser { id: user.id })
+        })
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[put("/api/admin/users/{id}/role")]
+pub async fn set_user_role(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    req: web::Json<SetRoleRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let role = req.role;
+
+    // Keeps at least one admin around to undo mistakes
+    if user_id == admin.id && role != Role::Admin {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Admins cannot remove their own admin role"
+        }));
+    }
+
+    let result: Result<Option<String>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let previous =
+            sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = $1 FOR UPDATE")
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        if let Some(previous) = &previous {
+            sqlx::query("UPDATE users SET role = $1 WHERE id = $2")
+                .bind(role.as_str())
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            audit::record(
+                &mut tx,
+                admin.id,
+                "user.set_role",
+                "user",
+                user_id,
+                serde_json::json!({ "from": previous, "to": role }),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(previous)
+    }
+    .await;
+
+    match result {
+        Ok(Some(previous)) => {
+            info!(
+                "User {} role changed from {} to {} by {}",
+                user_id,
+                previous,
+                role.as_str(),
+                admin.id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "user_id": user_id,
+                "role": role
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        })),
+        Err(e) => {
+            error!("Failed to set role for {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update role"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[actix_web::test]
+    async fn a_bare_user_id_header_does_not_authenticate() {
+        let req = TestRequest::default()
+            .insert_header(("X-User-Id", Uuid::new_v4().to_string()))
+            .to_http_request();
+        let result = CurrentUser::extract(&req).await;
+        assert!(matches!(result, Err(AuthError::Missing)));
+    }
+}
@@ -0,0 +1,458 @@
+// JARVIS2026 - OAuth2 sign-in
+// Authorization-code flow with PKCE against external identity providers
+// (Google for now). `/start` redirects the browser to the provider; the
+// provider sends it back to `/callback`, where the code is exchanged, the
+// external identity is mapped to a local user through `user_identities`,
+// and a session starts. The browser then lands on the frontend with the
+// session tokens in the URL fragment, which never reaches a server log.
+
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse, Responder};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::fraud;
+use crate::secrets;
+use crate::sessions;
+use crate::AppState;
+
+const STATE_TTL_MINUTES: i32 = 10;
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const GOOGLE_AUTHORIZE_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const GOOGLE_USERINFO_URL: &str = "https://openidconnect.googleapis.com/v1/userinfo";
+const GOOGLE_SCOPES: &str = "openid email profile";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Google,
+}
+
+struct ProviderConfig {
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+}
+
+/// Configured providers plus the HTTP client used to talk to them.
+pub struct OAuthClients {
+    http: reqwest::Client,
+    google: Option<ProviderConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    /// Set by the provider when the user declined or the request was bad
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// OpenID Connect userinfo claims we use.
+#[derive(Deserialize)]
+struct UserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS oauth_states (
+            state TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            code_verifier TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS user_identities (
+            provider TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            email TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_login_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (provider, subject)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_user_identities_user ON user_identities(user_id)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// PROVIDERS
+// ============================================================================
+
+impl Provider {
+    fn name(self) -> &'static str {
+        match self {
+            Provider::Google => "google",
+        }
+    }
+
+    fn authorize_url(self) -> &'static str {
+        match self {
+            Provider::Google => GOOGLE_AUTHORIZE_URL,
+        }
+    }
+
+    fn token_url(self) -> &'static str {
+        match self {
+            Provider::Google => GOOGLE_TOKEN_URL,
+        }
+    }
+
+    fn userinfo_url(self) -> &'static str {
+        match self {
+            Provider::Google => GOOGLE_USERINFO_URL,
+        }
+    }
+
+    fn scopes(self) -> &'static str {
+        match self {
+            Provider::Google => GOOGLE_SCOPES,
+        }
+    }
+}
+
+impl OAuthClients {
+    fn config(&self, provider: Provider) -> Option<&ProviderConfig> {
+        match provider {
+            Provider::Google => self.google.as_ref(),
+        }
+    }
+
+    /// Swaps an authorization code for the signed-in account's claims.
+    async fn fetch_identity(
+        &self,
+        provider: Provider,
+        config: &ProviderConfig,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<UserInfo, String> {
+        let token: TokenResponse = self
+            .http
+            .post(provider.token_url())
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("client_id", &config.client_id),
+                ("client_secret", &config.client_secret),
+                ("redirect_uri", &config.redirect_uri),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Token exchange failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid token response: {}", e))?;
+
+        self.http
+            .get(provider.userinfo_url())
+            .bearer_auth(&token.access_token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Userinfo request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid userinfo response: {}", e))
+    }
+}
+
+/// Google is enabled by `GOOGLE_CLIENT_ID` and `GOOGLE_CLIENT_SECRET`. The
+/// callback defaults to `<PUBLIC_BASE_URL>/api/auth/oauth/google/callback`
+/// and can be overridden with `GOOGLE_REDIRECT_URI`.
+pub fn clients_from_env(public_base_url: &str) -> OAuthClients {
+    let google = match (
+        std::env::var("GOOGLE_CLIENT_ID"),
+        secrets::var("GOOGLE_CLIENT_SECRET"),
+    ) {
+        (Ok(client_id), Ok(client_secret)) => {
+            info!("Google sign-in enabled");
+            Some(ProviderConfig {
+                client_id,
+                client_secret,
+                redirect_uri: std::env::var("GOOGLE_REDIRECT_URI").unwrap_or_else(|_| {
+                    format!("{}/api/auth/oauth/google/callback", public_base_url)
+                }),
+            })
+        }
+        _ => None,
+    };
+
+    let http = reqwest::Client::builder()
+        .timeout(PROVIDER_TIMEOUT)
+        .build()
+        .unwrap_or_else(|e| {
+            warn!("Failed to build OAuth client: {}; using defaults", e);
+            reqwest::Client::new()
+        });
+
+    OAuthClients { http, google }
+}
+
+// ============================================================================
+// IDENTITY MAPPING
+// ============================================================================
+
+/// Finds the local user behind an external identity. A first sign-in links
+/// to an existing account when both sides have verified the same email, and
+/// creates a new account otherwise. Returns the user and whether it is new.
+async fn user_for_identity(
+    pool: &PgPool,
+    provider: Provider,
+    info: &UserInfo,
+) -> Result<(Uuid, bool), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let existing = sqlx::query_scalar::<_, Uuid>(
+        r#"UPDATE user_identities SET last_login_at = NOW(), email = $3
+        WHERE provider = $1 AND subject = $2
+        RETURNING user_id"#,
+    )
+    .bind(provider.name())
+    .bind(&info.sub)
+    .bind(&info.email)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(user_id) = existing {
+        tx.commit().await?;
+        return Ok((user_id, false));
+    }
+
+    let verified_email = info.email.as_deref().filter(|_| info.email_verified);
+    let linked = match verified_email {
+        Some(email) => {
+            sqlx::query_scalar::<_, Uuid>(
+                r#"SELECT u.id FROM users u JOIN credentials c ON c.user_id = u.id
+                WHERE LOWER(c.email) = LOWER($1) AND u.email_verified_at IS NOT NULL"#,
+            )
+            .bind(email)
+            .fetch_optional(&mut *tx)
+            .await?
+        }
+        None => None,
+    };
+
+    let (user_id, new_user) = match linked {
+        Some(user_id) => (user_id, false),
+        None => {
+            // The provider's subject is stable and unique, so it doubles as
+            // the username, the same way wallet sign-in uses the address
+            let user_id = sqlx::query_scalar::<_, Uuid>(
+                r#"INSERT INTO users (username, email_verified_at)
+                VALUES ($1, CASE WHEN $2 THEN NOW() END)
+                RETURNING id"#,
+            )
+            .bind(format!("{}:{}", provider.name(), info.sub))
+            .bind(verified_email.is_some())
+            .fetch_one(&mut *tx)
+            .await?;
+            (user_id, true)
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO user_identities (provider, subject, user_id, email) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(provider.name())
+    .bind(&info.sub)
+    .bind(user_id)
+    .bind(&info.email)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok((user_id, new_user))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+fn redirect(location: String) -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+/// Sends the browser back to the frontend with an error code it can show.
+fn fail(state: &AppState, code: &str) -> HttpResponse {
+    redirect(format!("{}/#oauth_error={}", state.public_base_url, code))
+}
+
+#[get("/api/auth/oauth/{provider}/start")]
+pub async fn start(path: web::Path<Provider>, state: web::Data<AppState>) -> impl Responder {
+    let provider = path.into_inner();
+    let Some(config) = state.oauth.config(provider) else {
+        return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "error": format!("{} sign-in is not configured", provider.name())
+        }));
+    };
+
+    let csrf_state = sessions::new_token();
+    let code_verifier = sessions::new_token();
+    let code_challenge = Base64UrlUnpadded::encode_string(&Sha256::digest(&code_verifier));
+
+    let stored: Result<(), sqlx::Error> = async {
+        sqlx::query("DELETE FROM oauth_states WHERE expires_at <= NOW()")
+            .execute(&state.db)
+            .await?;
+        sqlx::query(
+            r#"INSERT INTO oauth_states (state, provider, code_verifier, expires_at)
+            VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))"#,
+        )
+        .bind(&csrf_state)
+        .bind(provider.name())
+        .bind(&code_verifier)
+        .bind(STATE_TTL_MINUTES)
+        .execute(&state.db)
+        .await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = stored {
+        error!("Failed to start {} sign-in: {}", provider.name(), e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to start sign-in"
+        }));
+    }
+
+    let mut url = match reqwest::Url::parse(provider.authorize_url()) {
+        Ok(url) => url,
+        Err(e) => {
+            error!("Invalid {} authorize URL: {}", provider.name(), e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to start sign-in"
+            }));
+        }
+    };
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &config.redirect_uri)
+        .append_pair("scope", provider.scopes())
+        .append_pair("state", &csrf_state)
+        .append_pair("code_challenge", &code_challenge)
+        .append_pair("code_challenge_method", "S256");
+
+    redirect(url.to_string())
+}
+
+#[get("/api/auth/oauth/{provider}/callback")]
+pub async fn callback(
+    http_req: HttpRequest,
+    path: web::Path<Provider>,
+    query: web::Query<CallbackQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let provider = path.into_inner();
+    let Some(config) = state.oauth.config(provider) else {
+        return fail(&state, "not_configured");
+    };
+    if let Some(error) = &query.error {
+        info!("{} sign-in declined: {}", provider.name(), error);
+        return fail(&state, "access_denied");
+    }
+    let (Some(code), Some(csrf_state)) = (&query.code, &query.state) else {
+        return fail(&state, "invalid_request");
+    };
+
+    // One use per state; it also carries the PKCE verifier for this attempt
+    let code_verifier = match sqlx::query_scalar::<_, String>(
+        r#"DELETE FROM oauth_states
+        WHERE state = $1 AND provider = $2 AND expires_at > NOW()
+        RETURNING code_verifier"#,
+    )
+    .bind(csrf_state)
+    .bind(provider.name())
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(verifier)) => verifier,
+        Ok(None) => return fail(&state, "expired"),
+        Err(e) => {
+            error!("Failed to load {} sign-in state: {}", provider.name(), e);
+            return fail(&state, "server_error");
+        }
+    };
+
+    let identity = match state
+        .oauth
+        .fetch_identity(provider, config, code, &code_verifier)
+        .await
+    {
+        Ok(identity) => identity,
+        Err(e) => {
+            warn!("{} sign-in failed: {}", provider.name(), e);
+            return fail(&state, "provider_error");
+        }
+    };
+
+    let (user_id, new_user) = match user_for_identity(&state.db, provider, &identity).await {
+        Ok(found) => found,
+        Err(e) => {
+            error!(
+                "Failed to resolve user for {} identity {}: {}",
+                provider.name(),
+                identity.sub,
+                e
+            );
+            return fail(&state, "server_error");
+        }
+    };
+
+    match sessions::issue(&state.db, user_id, provider.name()).await {
+        Ok(session) => {
+            info!("{} sign-in as user {}", provider.name(), user_id);
+            fraud::record_request(&state.db, user_id, &http_req).await;
+            redirect(format!(
+                "{}/#user_id={}&new_user={}&access_token={}&refresh_token={}&expires_at={}",
+                state.public_base_url,
+                user_id,
+                new_user,
+                session.token,
+                session.refresh_token,
+                session.expires_at.timestamp()
+            ))
+        }
+        Err(e) => {
+            error!("Failed to start session for {}: {}", user_id, e);
+            fail(&state, "server_error")
+        }
+    }
+}
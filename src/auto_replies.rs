@@ -0,0 +1,283 @@
+// JARVIS2026 - Inquiry auto-responder
+// Agents can set a templated reply that goes out the moment an inquiry
+// arrives outside their working hours, so buyers aren't left wondering
+// overnight. Working hours are evaluated in the agent's own timezone.
+// Automated replies are flagged on the message and don't count as the
+// agent's first response.
+
+use actix_web::{get, put, web, HttpResponse, Responder};
+use chrono::{Datelike, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::AgentUser;
+use crate::sharing;
+use crate::timezones;
+use crate::AppState;
+
+const MAX_TEMPLATE_LEN: usize = 2000;
+/// Placeholders a template may use, written as `{{name}}`.
+const TEMPLATE_VARIABLES: &[&str] = &[
+    "property_title",
+    "listing_link",
+    "viewing_link",
+    "agent_name",
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AutoReplySettings {
+    enabled: bool,
+    template: String,
+    /// ISO weekdays the agent works, Monday = 1 … Sunday = 7
+    working_days: Vec<i16>,
+    work_start: NaiveTime,
+    work_end: NaiveTime,
+}
+
+impl Default for AutoReplySettings {
+    fn default() -> Self {
+        AutoReplySettings {
+            enabled: false,
+            template: "Thanks for your interest in {{property_title}}! I'm away right now and \
+                       will reply during working hours. Meanwhile you can book a viewing here: \
+                       {{viewing_link}}"
+                .to_string(),
+            working_days: vec![1, 2, 3, 4, 5],
+            work_start: NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default(),
+            work_end: NaiveTime::from_hms_opt(17, 0, 0).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AwayOwner {
+    username: String,
+    timezone: String,
+    #[sqlx(flatten)]
+    settings: AutoReplySettings,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS auto_reply_settings (
+            user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            enabled BOOLEAN NOT NULL DEFAULT false,
+            template TEXT NOT NULL,
+            working_days SMALLINT[] NOT NULL,
+            work_start TIME NOT NULL,
+            work_end TIME NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "ALTER TABLE inquiry_messages ADD COLUMN IF NOT EXISTS automated BOOLEAN NOT NULL DEFAULT false",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// TEMPLATES AND WORKING HOURS
+// ============================================================================
+
+impl AutoReplySettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.template.trim().is_empty() || self.template.chars().count() > MAX_TEMPLATE_LEN {
+            return Err(format!(
+                "template must be 1-{} characters",
+                MAX_TEMPLATE_LEN
+            ));
+        }
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                return Err("template has an unclosed {{".to_string());
+            };
+            let name = rest[start + 2..start + 2 + len].trim();
+            if !TEMPLATE_VARIABLES.contains(&name) {
+                return Err(format!(
+                    "Unknown template variable '{}'; use one of: {}",
+                    name,
+                    TEMPLATE_VARIABLES.join(", ")
+                ));
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+        if self.working_days.iter().any(|d| !(1..=7).contains(d)) {
+            return Err("working_days must be ISO weekdays 1 (Monday) to 7 (Sunday)".to_string());
+        }
+        if self.work_start >= self.work_end {
+            return Err("work_start must be before work_end".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether `now`, read in the agent's timezone, falls inside working hours.
+    fn is_working_time(&self, now: chrono::DateTime<Utc>, timezone: &str) -> bool {
+        let tz = timezones::parse(timezone).unwrap_or(timezones::DEFAULT_TIMEZONE);
+        let local = now.with_timezone(&tz);
+        let weekday = local.weekday().number_from_monday() as i16;
+        let time = local.time();
+        self.working_days.contains(&weekday) && time >= self.work_start && time < self.work_end
+    }
+}
+
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = values
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map_or("", |(_, value)| *value);
+        rendered.push_str(value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Posts the owner's auto-reply into a freshly opened inquiry when it
+/// arrives outside their working hours. Returns whether a reply was sent.
+pub async fn reply_if_away(
+    tx: &mut Transaction<'_, Postgres>,
+    base_url: &str,
+    inquiry_id: Uuid,
+    owner_user_id: Uuid,
+    property_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let Some(owner) = sqlx::query_as::<_, AwayOwner>(
+        r#"SELECT u.username, u.timezone, s.enabled, s.template, s.working_days,
+                  s.work_start, s.work_end
+        FROM auto_reply_settings s JOIN users u ON u.id = s.user_id
+        WHERE s.user_id = $1 AND s.enabled"#,
+    )
+    .bind(owner_user_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    else {
+        return Ok(false);
+    };
+    if owner.settings.is_working_time(Utc::now(), &owner.timezone) {
+        return Ok(false);
+    }
+
+    let title = sqlx::query_scalar::<_, String>("SELECT title FROM properties WHERE id = $1")
+        .bind(property_id)
+        .fetch_one(&mut **tx)
+        .await?;
+    let listing_link = sharing::listing_url(base_url, property_id);
+    let viewing_link = format!("{}#book-viewing", listing_link);
+    let body = render(
+        &owner.settings.template,
+        &[
+            ("property_title", &title),
+            ("listing_link", &listing_link),
+            ("viewing_link", &viewing_link),
+            ("agent_name", &owner.username),
+        ],
+    );
+
+    sqlx::query(
+        // clock_timestamp() keeps the reply after the inquiry's first
+        // message, which shares this transaction's NOW()
+        r#"INSERT INTO inquiry_messages (inquiry_id, sender_user_id, body, automated, created_at)
+        VALUES ($1, $2, $3, true, clock_timestamp())"#,
+    )
+    .bind(inquiry_id)
+    .bind(owner_user_id)
+    .bind(body)
+    .execute(&mut **tx)
+    .await?;
+
+    info!(
+        "Auto-replied to inquiry {} for {}",
+        inquiry_id, owner_user_id
+    );
+    Ok(true)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/users/me/auto-reply")]
+pub async fn get_auto_reply(agent: AgentUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, AutoReplySettings>(
+        r#"SELECT enabled, template, working_days, work_start, work_end
+        FROM auto_reply_settings WHERE user_id = $1"#,
+    )
+    .bind(agent.id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(settings) => HttpResponse::Ok().json(settings.unwrap_or_default()),
+        Err(e) => {
+            error!("Failed to load auto-reply for {}: {}", agent.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load auto-reply settings"
+            }))
+        }
+    }
+}
+
+#[put("/api/users/me/auto-reply")]
+pub async fn update_auto_reply(
+    agent: AgentUser,
+    req: web::Json<AutoReplySettings>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let mut settings = req.into_inner();
+    settings.working_days.sort_unstable();
+    settings.working_days.dedup();
+    if let Err(message) = settings.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }));
+    }
+
+    match sqlx::query(
+        r#"INSERT INTO auto_reply_settings
+            (user_id, enabled, template, working_days, work_start, work_end)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) DO UPDATE
+        SET enabled = EXCLUDED.enabled, template = EXCLUDED.template,
+            working_days = EXCLUDED.working_days, work_start = EXCLUDED.work_start,
+            work_end = EXCLUDED.work_end, updated_at = NOW()"#,
+    )
+    .bind(agent.id)
+    .bind(settings.enabled)
+    .bind(&settings.template)
+    .bind(&settings.working_days)
+    .bind(settings.work_start)
+    .bind(settings.work_end)
+    .execute(&state.db)
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(settings),
+        Err(e) => {
+            error!("Failed to save auto-reply for {}: {}", agent.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to save auto-reply settings"
+            }))
+        }
+    }
+}
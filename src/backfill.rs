@@ -0,0 +1,644 @@
+// JARVIS2026 - Background backfills
+// Long-running passes over existing listings after a schema or feature
+// change: populating slugs, generating missing thumbnails, embedding
+// listings for semantic search, recomputing completeness scores. Admins start a run per job; a worker walks the
+// matching listings in id order at a capped rate, checkpointing after every
+// batch so a paused, failed or interrupted run picks up where it stopped.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::AdminUser;
+use crate::completeness;
+use crate::embeddings;
+use crate::images;
+use crate::AppState;
+
+const DEFAULT_RATE_PER_SEC: i32 = 20;
+const MAX_RATE_PER_SEC: i32 = 500;
+const DEFAULT_BATCH_SIZE: i32 = 100;
+const MAX_BATCH_SIZE: i32 = 1000;
+const RECENT_RUNS: i64 = 50;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Job {
+    Completeness,
+    Slugs,
+    Thumbnails,
+    Embeddings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunAction {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+#[derive(Deserialize)]
+pub struct StartBackfillRequest {
+    job: Job,
+    rate_per_sec: Option<i32>,
+    batch_size: Option<i32>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct BackfillRun {
+    id: Uuid,
+    job: String,
+    status: String,
+    /// Listings the run set out to cover
+    total: i64,
+    processed: i64,
+    failed: i64,
+    /// Last listing id handled
+    checkpoint: Option<Uuid>,
+    rate_per_sec: i32,
+    batch_size: i32,
+    last_error: Option<String>,
+    started_by: Option<Uuid>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize)]
+struct RunProgress {
+    #[serde(flatten)]
+    run: BackfillRun,
+    percent: f64,
+    /// At the configured rate; `None` once the run is no longer running
+    eta_seconds: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct JobInfo {
+    job: Job,
+    description: &'static str,
+}
+
+enum Transition {
+    Changed(BackfillRun),
+    NotFound,
+    NotAllowed(String),
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS backfill_runs (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            job TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running'
+                CHECK (status IN ('running', 'paused', 'completed', 'failed', 'cancelled')),
+            total BIGINT NOT NULL DEFAULT 0,
+            processed BIGINT NOT NULL DEFAULT 0,
+            failed BIGINT NOT NULL DEFAULT 0,
+            checkpoint UUID,
+            rate_per_sec INTEGER NOT NULL,
+            batch_size INTEGER NOT NULL,
+            last_error TEXT,
+            worker UUID,
+            started_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            finished_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // One unfinished run per job
+    sqlx::query(
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS idx_backfill_runs_active
+        ON backfill_runs(job) WHERE status IN ('running', 'paused')"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// JOBS
+// ============================================================================
+
+impl Job {
+    const ALL: [Job; 4] = [
+        Job::Completeness,
+        Job::Slugs,
+        Job::Thumbnails,
+        Job::Embeddings,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Job::Completeness => "completeness",
+            Job::Slugs => "slugs",
+            Job::Thumbnails => "thumbnails",
+            Job::Embeddings => "embeddings",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        Job::ALL.into_iter().find(|job| job.as_str() == raw)
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Job::Completeness => "Recompute the completeness score of every listing",
+            Job::Slugs => "Give listings created before slugs existed a URL slug",
+            Job::Thumbnails => "Render card and hero images for listings that have photos but none",
+            Job::Embeddings => "Embed listings that have no vector for semantic search",
+        }
+    }
+
+    /// SQL condition on `properties` selecting the listings still to do.
+    fn condition(self) -> &'static str {
+        match self {
+            Job::Completeness => "TRUE",
+            Job::Slugs => "slug IS NULL",
+            Job::Embeddings => "embedding IS NULL",
+            Job::Thumbnails => {
+                r#"image_thumb_webp IS NULL AND EXISTS (
+                    SELECT 1 FROM media_uploads m
+                    WHERE m.property_id = properties.id AND m.file_type = 'image'
+                      AND m.moderation_status NOT IN ('rejected', 'unpublished')
+                )"#
+            }
+        }
+    }
+
+    async fn count(self, pool: &PgPool) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM properties WHERE {}",
+            self.condition()
+        ))
+        .fetch_one(pool)
+        .await
+    }
+
+    async fn next_batch(
+        self,
+        pool: &PgPool,
+        after: Option<Uuid>,
+        limit: i32,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(&format!(
+            r#"SELECT id FROM properties
+            WHERE ({}) AND ($1::uuid IS NULL OR id > $1)
+            ORDER BY id
+            LIMIT $2"#,
+            self.condition()
+        ))
+        .bind(after)
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn process(self, state: &AppState, property_id: Uuid) -> Result<(), String> {
+        match self {
+            Job::Completeness => {
+                completeness::refresh(&state.db, property_id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            Job::Slugs => {
+                let title =
+                    sqlx::query_scalar::<_, String>("SELECT title FROM properties WHERE id = $1")
+                        .bind(property_id)
+                        .fetch_one(&state.db)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                sqlx::query("UPDATE properties SET slug = $2 WHERE id = $1 AND slug IS NULL")
+                    .bind(property_id)
+                    .bind(crate::listing_slug(&title, property_id))
+                    .execute(&state.db)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                state.property_cache.invalidate(property_id).await;
+            }
+            Job::Thumbnails => {
+                images::generate_listing_images(&state.db, property_id).await?;
+                state.property_cache.invalidate(property_id).await;
+            }
+            Job::Embeddings => embeddings::refresh(state, property_id).await?,
+        }
+        Ok(())
+    }
+}
+
+impl RunAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            RunAction::Pause => "pause",
+            RunAction::Resume => "resume",
+            RunAction::Cancel => "cancel",
+        }
+    }
+}
+
+// ============================================================================
+// RUNNER
+// ============================================================================
+
+impl BackfillRun {
+    fn progress(self) -> RunProgress {
+        let done = self.processed + self.failed;
+        let percent = if self.total > 0 {
+            ((done as f64 / self.total as f64) * 1000.0)
+                .round()
+                .min(1000.0)
+                / 10.0
+        } else {
+            100.0
+        };
+        let eta_seconds = (self.status == "running")
+            .then(|| (self.total - done).max(0) / self.rate_per_sec.max(1) as i64);
+        RunProgress {
+            run: self,
+            percent,
+            eta_seconds,
+        }
+    }
+}
+
+async fn fetch_run(pool: &PgPool, run_id: Uuid) -> Result<Option<BackfillRun>, sqlx::Error> {
+    sqlx::query_as::<_, BackfillRun>("SELECT * FROM backfill_runs WHERE id = $1")
+        .bind(run_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Claims a running run for a new worker and starts it. Any older worker of
+/// the same run notices at its next checkpoint and stops.
+async fn spawn_worker(state: web::Data<AppState>, run_id: Uuid) -> Result<(), sqlx::Error> {
+    let worker = Uuid::new_v4();
+    let claimed =
+        sqlx::query("UPDATE backfill_runs SET worker = $2 WHERE id = $1 AND status = 'running'")
+            .bind(run_id)
+            .bind(worker)
+            .execute(&state.db)
+            .await?
+            .rows_affected();
+    if claimed > 0 {
+        tokio::spawn(async move {
+            if let Err(e) = run_worker(&state, run_id, worker).await {
+                error!("Backfill run {} failed: {}", run_id, e);
+                let _ = sqlx::query(
+                    r#"UPDATE backfill_runs
+                    SET status = 'failed', last_error = $3, updated_at = NOW(), finished_at = NOW()
+                    WHERE id = $1 AND worker = $2 AND status = 'running'"#,
+                )
+                .bind(run_id)
+                .bind(worker)
+                .bind(e.to_string())
+                .execute(&state.db)
+                .await;
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn run_worker(state: &AppState, run_id: Uuid, worker: Uuid) -> Result<(), sqlx::Error> {
+    let Some(run) = fetch_run(&state.db, run_id).await? else {
+        return Ok(());
+    };
+    let Some(job) = Job::parse(&run.job) else {
+        warn!("Backfill run {} has unknown job {}", run_id, run.job);
+        return Ok(());
+    };
+
+    let mut pace = tokio::time::interval(Duration::from_secs_f64(
+        1.0 / run.rate_per_sec.max(1) as f64,
+    ));
+    pace.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut checkpoint = run.checkpoint;
+
+    loop {
+        let ids = job
+            .next_batch(&state.db, checkpoint, run.batch_size)
+            .await?;
+        if ids.is_empty() {
+            sqlx::query(
+                r#"UPDATE backfill_runs
+                SET status = 'completed', updated_at = NOW(), finished_at = NOW()
+                WHERE id = $1 AND worker = $2 AND status = 'running'"#,
+            )
+            .bind(run_id)
+            .bind(worker)
+            .execute(&state.db)
+            .await?;
+            info!("Backfill run {} ({}) completed", run_id, job.as_str());
+            return Ok(());
+        }
+
+        let (mut processed, mut failed, mut last_error) = (0i64, 0i64, None);
+        for &property_id in &ids {
+            pace.tick().await;
+            match job.process(state, property_id).await {
+                Ok(()) => processed += 1,
+                Err(e) => {
+                    warn!(
+                        "Backfill {} failed for listing {}: {}",
+                        job.as_str(),
+                        property_id,
+                        e
+                    );
+                    failed += 1;
+                    last_error = Some(format!("{}: {}", property_id, e));
+                }
+            }
+        }
+        checkpoint = ids.last().copied();
+
+        // Progress is kept even when the run was paused or cancelled mid-batch
+        let status = sqlx::query_scalar::<_, String>(
+            r#"UPDATE backfill_runs
+            SET checkpoint = $3, processed = processed + $4, failed = failed + $5,
+                last_error = COALESCE($6, last_error), updated_at = NOW()
+            WHERE id = $1 AND worker = $2
+            RETURNING status"#,
+        )
+        .bind(run_id)
+        .bind(worker)
+        .bind(checkpoint)
+        .bind(processed)
+        .bind(failed)
+        .bind(last_error)
+        .fetch_optional(&state.db)
+        .await?;
+        if status.as_deref() != Some("running") {
+            info!("Backfill run {} stopped at {:?}", run_id, checkpoint);
+            return Ok(());
+        }
+    }
+}
+
+/// Restarts runs a previous process left running.
+pub async fn resume_interrupted(state: web::Data<AppState>) {
+    let run_ids = match sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM backfill_runs WHERE status = 'running'",
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(run_ids) => run_ids,
+        Err(e) => {
+            error!("Failed to look up interrupted backfills: {}", e);
+            return;
+        }
+    };
+    for run_id in run_ids {
+        info!("Resuming backfill run {}", run_id);
+        if let Err(e) = spawn_worker(state.clone(), run_id).await {
+            error!("Failed to resume backfill run {}: {}", run_id, e);
+        }
+    }
+}
+
+async fn transition(
+    pool: &PgPool,
+    admin: AdminUser,
+    run_id: Uuid,
+    action: RunAction,
+) -> Result<Transition, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some(status) = sqlx::query_scalar::<_, String>(
+        "SELECT status FROM backfill_runs WHERE id = $1 FOR UPDATE",
+    )
+    .bind(run_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(Transition::NotFound);
+    };
+
+    let next = match (action, status.as_str()) {
+        (RunAction::Pause, "running") => "paused",
+        (RunAction::Resume, "paused" | "failed") => "running",
+        (RunAction::Cancel, "running" | "paused") => "cancelled",
+        _ => return Ok(Transition::NotAllowed(status)),
+    };
+
+    let result = sqlx::query(
+        r#"UPDATE backfill_runs
+        SET status = $2, updated_at = NOW(),
+            finished_at = CASE WHEN $2 = 'cancelled' THEN NOW() END
+        WHERE id = $1"#,
+    )
+    .bind(run_id)
+    .bind(next)
+    .execute(&mut *tx)
+    .await;
+    match result {
+        // Resuming a failed run while a newer one of the same job is open
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(Transition::NotAllowed(status));
+        }
+        other => {
+            other?;
+        }
+    }
+
+    audit::record(
+        &mut tx,
+        admin.id,
+        "backfill.status",
+        "backfill_run",
+        run_id,
+        serde_json::json!({ "from": status, "to": next }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(fetch_run(pool, run_id)
+        .await?
+        .map_or(Transition::NotFound, Transition::Changed))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/admin/backfills")]
+pub async fn list_backfills(_admin: AdminUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, BackfillRun>(
+        "SELECT * FROM backfill_runs ORDER BY created_at DESC LIMIT $1",
+    )
+    .bind(RECENT_RUNS)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(runs) => HttpResponse::Ok().json(serde_json::json!({
+            "jobs": Job::ALL
+                .into_iter()
+                .map(|job| JobInfo {
+                    job,
+                    description: job.description(),
+                })
+                .collect::<Vec<_>>(),
+            "runs": runs.into_iter().map(BackfillRun::progress).collect::<Vec<_>>()
+        })),
+        Err(e) => {
+            error!("Failed to list backfills: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list backfills"
+            }))
+        }
+    }
+}
+
+#[post("/api/admin/backfills")]
+pub async fn start_backfill(
+    admin: AdminUser,
+    req: web::Json<StartBackfillRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let rate_per_sec = req.rate_per_sec.unwrap_or(DEFAULT_RATE_PER_SEC);
+    if !(1..=MAX_RATE_PER_SEC).contains(&rate_per_sec) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("rate_per_sec must be between 1 and {}", MAX_RATE_PER_SEC)
+        }));
+    }
+    let batch_size = req.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+    if !(1..=MAX_BATCH_SIZE).contains(&batch_size) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("batch_size must be between 1 and {}", MAX_BATCH_SIZE)
+        }));
+    }
+
+    let result: Result<BackfillRun, sqlx::Error> = async {
+        let total = req.job.count(&state.db).await?;
+        let mut tx = state.db.begin().await?;
+        let run = sqlx::query_as::<_, BackfillRun>(
+            r#"INSERT INTO backfill_runs (job, total, rate_per_sec, batch_size, started_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *"#,
+        )
+        .bind(req.job.as_str())
+        .bind(total)
+        .bind(rate_per_sec)
+        .bind(batch_size)
+        .bind(admin.id)
+        .fetch_one(&mut *tx)
+        .await?;
+        audit::record(
+            &mut tx,
+            admin.id,
+            "backfill.start",
+            "backfill_run",
+            run.id,
+            serde_json::json!({
+                "job": req.job,
+                "total": total,
+                "rate_per_sec": rate_per_sec,
+                "batch_size": batch_size
+            }),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(run)
+    }
+    .await;
+
+    match result {
+        Ok(run) => {
+            if let Err(e) = spawn_worker(state.clone(), run.id).await {
+                error!("Failed to start backfill run {}: {}", run.id, e);
+            }
+            info!(
+                "Backfill {} started by {} over {} listings",
+                req.job.as_str(),
+                admin.id,
+                run.total
+            );
+            HttpResponse::Accepted().json(run.progress())
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("A {} backfill is already running or paused", req.job.as_str())
+            }))
+        }
+        Err(e) => {
+            error!("Failed to start backfill {}: {}", req.job.as_str(), e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to start backfill"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/backfills/{id}")]
+pub async fn backfill_progress(
+    path: web::Path<Uuid>,
+    _admin: AdminUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let run_id = path.into_inner();
+    match fetch_run(&state.db, run_id).await {
+        Ok(Some(run)) => HttpResponse::Ok().json(run.progress()),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Backfill run not found"
+        })),
+        Err(e) => {
+            error!("Failed to load backfill run {}: {}", run_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load backfill run"
+            }))
+        }
+    }
+}
+
+/// `pause`, `resume` (also retries a failed run from its checkpoint) or `cancel`.
+#[post("/api/admin/backfills/{id}/{action}")]
+pub async fn control_backfill(
+    path: web::Path<(Uuid, RunAction)>,
+    admin: AdminUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (run_id, action) = path.into_inner();
+
+    match transition(&state.db, admin, run_id, action).await {
+        Ok(Transition::Changed(run)) => {
+            if action == RunAction::Resume {
+                if let Err(e) = spawn_worker(state.clone(), run_id).await {
+                    error!("Failed to resume backfill run {}: {}", run_id, e);
+                }
+            }
+            info!("Backfill run {} is now {}", run_id, run.status);
+            HttpResponse::Ok().json(run.progress())
+        }
+        Ok(Transition::NotFound) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Backfill run not found"
+        })),
+        Ok(Transition::NotAllowed(status)) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Can't {} a {} run", action.as_str(), status),
+            "status": status
+        })),
+        Err(e) => {
+            error!("Failed to update backfill run {}: {}", run_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update backfill run"
+            }))
+        }
+    }
+}
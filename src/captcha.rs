@@ -0,0 +1,390 @@
+// JARVIS2026 - Bot protection for public forms
+// Registration, inquiries and contact reveals carry two defences. Their
+// request bodies include a `website` honeypot field that real clients leave
+// empty. And a middleware scores each submission's risk (bot-like headers,
+// bursts from one IP); above the threshold the request must carry a solved
+// CAPTCHA in `X-Captcha-Token`, verified with the configured provider
+// (Cloudflare Turnstile or hCaptcha). The middleware also guards password
+// login against credential stuffing and reset requests against mail spam.
+// Without a provider, nothing is challenged.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::ResponseError,
+    http::{header, header::HeaderMap, Method, StatusCode},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::geoip;
+use crate::secrets;
+use crate::AppState;
+
+const CAPTCHA_HEADER: &str = "X-Captcha-Token";
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_RISK_THRESHOLD: u32 = 50;
+/// Window over which repeated submissions from one IP raise the risk.
+const BURST_WINDOW: Duration = Duration::from_secs(600);
+const TRACKER_CAPACITY: usize = 10_000;
+const BOT_USER_AGENTS: &[&str] = &[
+    "curl",
+    "wget",
+    "python",
+    "httpclient",
+    "okhttp",
+    "go-http",
+    "headless",
+    "scrapy",
+    "bot",
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy)]
+pub enum CaptchaProvider {
+    Turnstile,
+    HCaptcha,
+}
+
+pub trait CaptchaVerifier: Send + Sync {
+    /// Provider name and public site key for the client widget; `None` when
+    /// challenges are disabled.
+    fn site(&self) -> Option<(&'static str, &str)>;
+
+    /// Whether `token` is a valid, unused solution.
+    fn verify<'a>(
+        &'a self,
+        token: &'a str,
+        remote_ip: Option<IpAddr>,
+    ) -> BoxFuture<'a, Result<bool, String>>;
+}
+
+/// Verifies through the provider's `siteverify` endpoint; Turnstile and
+/// hCaptcha share the same request and response shape.
+pub struct SiteVerifyCaptcha {
+    provider: CaptchaProvider,
+    secret: String,
+    site_key: String,
+    client: reqwest::Client,
+}
+
+/// Used when no provider is configured.
+pub struct NoCaptcha;
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+    #[serde(rename = "error-codes", default)]
+    error_codes: Vec<String>,
+}
+
+/// Recent protected submissions per client IP, for burst detection.
+pub struct RiskTracker {
+    threshold: u32,
+    submissions: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+#[derive(Debug)]
+pub enum CaptchaError {
+    Required {
+        provider: &'static str,
+        site_key: String,
+    },
+    Failed,
+    Unavailable,
+}
+
+// ============================================================================
+// PROVIDERS
+// ============================================================================
+
+impl CaptchaProvider {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "turnstile" => Some(CaptchaProvider::Turnstile),
+            "hcaptcha" => Some(CaptchaProvider::HCaptcha),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            CaptchaProvider::Turnstile => "turnstile",
+            CaptchaProvider::HCaptcha => "hcaptcha",
+        }
+    }
+
+    fn verify_url(self) -> &'static str {
+        match self {
+            CaptchaProvider::Turnstile => {
+                "https://challenges.cloudflare.com/turnstile/v0/siteverify"
+            }
+            CaptchaProvider::HCaptcha => "https://api.hcaptcha.com/siteverify",
+        }
+    }
+}
+
+impl CaptchaVerifier for SiteVerifyCaptcha {
+    fn site(&self) -> Option<(&'static str, &str)> {
+        Some((self.provider.name(), &self.site_key))
+    }
+
+    fn verify<'a>(
+        &'a self,
+        token: &'a str,
+        remote_ip: Option<IpAddr>,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            let mut form = vec![
+                ("secret", self.secret.clone()),
+                ("response", token.to_string()),
+            ];
+            if let Some(ip) = remote_ip {
+                form.push(("remoteip", ip.to_string()));
+            }
+            let response: SiteVerifyResponse = self
+                .client
+                .post(self.provider.verify_url())
+                .form(&form)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("CAPTCHA verification request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid CAPTCHA verification response: {}", e))?;
+            if !response.success && !response.error_codes.is_empty() {
+                info!("CAPTCHA rejected: {}", response.error_codes.join(", "));
+            }
+            Ok(response.success)
+        })
+    }
+}
+
+impl CaptchaVerifier for NoCaptcha {
+    fn site(&self) -> Option<(&'static str, &str)> {
+        None
+    }
+
+    fn verify<'a>(&'a self, _: &'a str, _: Option<IpAddr>) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async { Ok(true) })
+    }
+}
+
+/// Configured by `CAPTCHA_PROVIDER` (`turnstile` or `hcaptcha`),
+/// `CAPTCHA_SITE_KEY` and `CAPTCHA_SECRET`.
+pub fn verifier_from_env() -> Box<dyn CaptchaVerifier> {
+    let Ok(raw) = std::env::var("CAPTCHA_PROVIDER") else {
+        return Box::new(NoCaptcha);
+    };
+    let Some(provider) = CaptchaProvider::parse(&raw) else {
+        warn!("Unknown CAPTCHA_PROVIDER '{}'; challenges disabled", raw);
+        return Box::new(NoCaptcha);
+    };
+    let (Ok(secret), Ok(site_key)) = (
+        secrets::var("CAPTCHA_SECRET"),
+        std::env::var("CAPTCHA_SITE_KEY"),
+    ) else {
+        warn!("CAPTCHA_SECRET/CAPTCHA_SITE_KEY not set; challenges disabled");
+        return Box::new(NoCaptcha);
+    };
+    let client = match reqwest::Client::builder().timeout(VERIFY_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to build CAPTCHA client: {}; challenges disabled", e);
+            return Box::new(NoCaptcha);
+        }
+    };
+    info!("CAPTCHA challenges enabled via {}", provider.name());
+    Box::new(SiteVerifyCaptcha {
+        provider,
+        secret,
+        site_key,
+        client,
+    })
+}
+
+// ============================================================================
+// HONEYPOT
+// ============================================================================
+
+/// True when the hidden form field was filled in, which only bots do.
+pub fn honeypot_tripped(field: Option<&str>) -> bool {
+    field.is_some_and(|value| !value.trim().is_empty())
+}
+
+pub fn honeypot_response() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Invalid submission"
+    }))
+}
+
+// ============================================================================
+// RISK SCORING
+// ============================================================================
+
+impl RiskTracker {
+    /// Threshold from `CAPTCHA_RISK_THRESHOLD` (default 50, out of ~100).
+    pub fn from_env() -> Self {
+        RiskTracker {
+            threshold: std::env::var("CAPTCHA_RISK_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_RISK_THRESHOLD),
+            submissions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a submission and returns how many earlier ones the IP made
+    /// within the burst window.
+    fn record(&self, ip: IpAddr) -> usize {
+        let now = Instant::now();
+        let mut submissions = self.submissions.lock().unwrap();
+        if submissions.len() >= TRACKER_CAPACITY {
+            submissions.retain(|_, times| {
+                times
+                    .back()
+                    .is_some_and(|t| now.duration_since(*t) < BURST_WINDOW)
+            });
+        }
+        let times = submissions.entry(ip).or_default();
+        while times
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= BURST_WINDOW)
+        {
+            times.pop_front();
+        }
+        let earlier = times.len();
+        times.push_back(now);
+        earlier
+    }
+}
+
+fn is_protected(method: &Method, path: &str) -> bool {
+    method == Method::POST
+        && (path == "/api/users"
+            || path == "/api/auth/login"
+            || path == "/api/auth/forgot-password"
+            || (path.starts_with("/api/properties/")
+                && (path.ends_with("/inquiries") || path.ends_with("/reveal-contact"))))
+}
+
+fn risk_score(headers: &HeaderMap, earlier_submissions: usize) -> u32 {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_ascii_lowercase);
+
+    let mut score = match user_agent {
+        None => 40,
+        Some(ua) if BOT_USER_AGENTS.iter().any(|bot| ua.contains(bot)) => 40,
+        Some(_) => 0,
+    };
+    if !headers.contains_key(header::ACCEPT_LANGUAGE) {
+        score += 20;
+    }
+    score += match earlier_submissions {
+        0..=2 => 0,
+        3..=9 => 30,
+        _ => 60,
+    };
+    score
+}
+
+// ============================================================================
+// MIDDLEWARE
+// ============================================================================
+
+impl fmt::Display for CaptchaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptchaError::Required { .. } => write!(f, "CAPTCHA required"),
+            CaptchaError::Failed => write!(f, "CAPTCHA verification failed"),
+            CaptchaError::Unavailable => write!(f, "CAPTCHA verification unavailable"),
+        }
+    }
+}
+
+impl ResponseError for CaptchaError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CaptchaError::Required { .. } | CaptchaError::Failed => StatusCode::FORBIDDEN,
+            CaptchaError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut body = serde_json::json!({ "error": self.to_string() });
+        if let CaptchaError::Required { provider, site_key } = self {
+            body["captcha"] = serde_json::json!({
+                "provider": provider,
+                "site_key": site_key,
+                "header": CAPTCHA_HEADER,
+            });
+        }
+        HttpResponse::build(self.status_code()).json(body)
+    }
+}
+
+pub async fn challenge(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if !is_protected(req.method(), req.path()) {
+        return next.call(req).await;
+    }
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await;
+    };
+    let Some((provider, site_key)) = state.captcha.site() else {
+        return next.call(req).await;
+    };
+
+    let ip = geoip::client_ip(req.request());
+    let earlier = ip.map_or(0, |ip| state.risk_tracker.record(ip));
+    let score = risk_score(req.headers(), earlier);
+    if score < state.risk_tracker.threshold {
+        return next.call(req).await;
+    }
+
+    let Some(token) = req
+        .headers()
+        .get(CAPTCHA_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    else {
+        info!(
+            "Challenging {} {} from {:?} (risk {})",
+            req.method(),
+            req.path(),
+            ip,
+            score
+        );
+        return Err(CaptchaError::Required {
+            provider,
+            site_key: site_key.to_string(),
+        }
+        .into());
+    };
+
+    match state.captcha.verify(&token, ip).await {
+        Ok(true) => next.call(req).await,
+        Ok(false) => Err(CaptchaError::Failed.into()),
+        Err(e) => {
+            warn!("{}", e);
+            Err(CaptchaError::Unavailable.into())
+        }
+    }
+}
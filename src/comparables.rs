@@ -0,0 +1,337 @@
+// JARVIS2026 - Comparable sales
+// For a listing, finds recently sold and currently active listings that are
+// alike in area, type, size and layout, and shows how their price per sqm
+// compares. Candidates are narrowed in SQL by type and area, then ranked by
+// `similarity`.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::geo::haversine_km;
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+const DEFAULT_SOLD_MONTHS: i32 = 12;
+const MAX_SOLD_MONTHS: i32 = 60;
+/// Listings this close count as the same area even under another location name
+const NEARBY_KM: f64 = 5.0;
+/// Rows fetched per kind before scoring
+const CANDIDATE_POOL: i64 = 300;
+const MIN_SIMILARITY: f64 = 0.6;
+
+const LOCATION_WEIGHT: f64 = 3.0;
+const TYPE_WEIGHT: f64 = 2.0;
+const AREA_WEIGHT: f64 = 2.0;
+const BEDROOMS_WEIGHT: f64 = 1.5;
+const BATHROOMS_WEIGHT: f64 = 1.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct ComparablesQuery {
+    limit: Option<usize>,
+    /// How far back sales count
+    months: Option<i32>,
+}
+
+/// What similarity is judged on; shared by listings and sold records.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Features {
+    pub location: String,
+    pub property_type: Option<String>,
+    pub bedrooms: Option<i32>,
+    pub bathrooms: Option<i32>,
+    pub area_sqm: Option<f64>,
+    #[serde(skip)]
+    pub latitude: Option<f64>,
+    #[serde(skip)]
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Subject {
+    price: f64,
+    #[sqlx(flatten)]
+    features: Features,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ActiveComparable {
+    id: Uuid,
+    title: String,
+    price: f64,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    features: Features,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct SoldComparable {
+    /// The sold record; `property_id` is gone once the listing is deleted
+    id: Uuid,
+    property_id: Option<Uuid>,
+    list_price: f64,
+    sale_price: f64,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    features: Features,
+    sold_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct Scored<T> {
+    #[serde(flatten)]
+    listing: T,
+    similarity: f64,
+    price_per_sqm: Option<f64>,
+    /// Relative to the subject's price per sqm, in percent
+    price_per_sqm_delta_pct: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ComparablesResponse {
+    property_id: Uuid,
+    price_per_sqm: Option<f64>,
+    median_sold_price_per_sqm: Option<f64>,
+    median_active_price_per_sqm: Option<f64>,
+    sold: Vec<Scored<SoldComparable>>,
+    active: Vec<Scored<ActiveComparable>>,
+}
+
+// ============================================================================
+// SIMILARITY
+// ============================================================================
+
+impl Features {
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    fn normalized_location(&self) -> String {
+        self.location.trim().to_lowercase()
+    }
+}
+
+/// 0-1 likeness of two properties. Attributes missing on either side are
+/// left out rather than counted against the pair.
+pub fn similarity(a: &Features, b: &Features) -> f64 {
+    let location = if a.normalized_location() == b.normalized_location() {
+        1.0
+    } else {
+        a.coordinates().zip(b.coordinates()).map_or(0.0, |(a, b)| {
+            (1.0 - haversine_km(a, b) / NEARBY_KM).max(0.0)
+        })
+    };
+    let property_type = a
+        .property_type
+        .as_deref()
+        .zip(b.property_type.as_deref())
+        .map(|(a, b)| if a.eq_ignore_ascii_case(b) { 1.0 } else { 0.0 });
+    let area = a
+        .area_sqm
+        .zip(b.area_sqm)
+        .filter(|(a, b)| *a > 0.0 && *b > 0.0)
+        .map(|(a, b)| (1.0 - (a / b).ln().abs()).max(0.0));
+    let rooms =
+        |a: Option<i32>, b: Option<i32>| a.zip(b).map(|(a, b)| 1.0 / (1.0 + (a - b).abs() as f64));
+
+    let components = [
+        (LOCATION_WEIGHT, Some(location)),
+        (TYPE_WEIGHT, property_type),
+        (AREA_WEIGHT, area),
+        (BEDROOMS_WEIGHT, rooms(a.bedrooms, b.bedrooms)),
+        (BATHROOMS_WEIGHT, rooms(a.bathrooms, b.bathrooms)),
+    ];
+    let (weighted, weights) = components
+        .iter()
+        .filter_map(|(weight, score)| score.map(|s| (weight * s, *weight)))
+        .fold((0.0, 0.0), |(sum, total), (s, w)| (sum + s, total + w));
+    weighted / weights
+}
+
+fn price_per_sqm(price: f64, area_sqm: Option<f64>) -> Option<f64> {
+    area_sqm.filter(|area| *area > 0.0).map(|area| price / area)
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Scores candidates against the subject and keeps the closest `limit`.
+fn rank<T>(
+    subject: &Subject,
+    candidates: Vec<T>,
+    limit: usize,
+    features: impl Fn(&T) -> &Features,
+    price: impl Fn(&T) -> f64,
+) -> Vec<Scored<T>> {
+    let subject_ppsqm = price_per_sqm(subject.price, subject.features.area_sqm);
+    let mut scored: Vec<Scored<T>> = candidates
+        .into_iter()
+        .filter_map(|listing| {
+            let similarity = similarity(&subject.features, features(&listing));
+            if similarity < MIN_SIMILARITY {
+                return None;
+            }
+            let ppsqm = price_per_sqm(price(&listing), features(&listing).area_sqm);
+            Some(Scored {
+                similarity: (similarity * 1000.0).round() / 1000.0,
+                price_per_sqm: ppsqm,
+                price_per_sqm_delta_pct: ppsqm
+                    .zip(subject_ppsqm)
+                    .map(|(theirs, ours)| ((theirs - ours) / ours * 1000.0).round() / 10.0),
+                listing,
+            })
+        })
+        .collect();
+    scored.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    scored.truncate(limit);
+    scored
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+/// Candidate filter: same type when known, and the same location name or
+/// within a bounding box around the subject. `$1`-`$6` are bound by callers.
+const CANDIDATE_CONDITION: &str = r#"($2::TEXT IS NULL OR LOWER(property_type) = LOWER($2))
+    AND (LOWER(TRIM(location)) = LOWER(TRIM($3))
+         OR (latitude BETWEEN $4 - $6 AND $4 + $6
+             AND longitude BETWEEN $5 - $6 / GREATEST(COS(RADIANS($4)), 0.01)
+                               AND $5 + $6 / GREATEST(COS(RADIANS($4)), 0.01)))"#;
+
+async fn load_candidates(
+    pool: &PgPool,
+    property_id: Uuid,
+    subject: &Subject,
+    months: i32,
+) -> Result<(Vec<SoldComparable>, Vec<ActiveComparable>), sqlx::Error> {
+    let features = &subject.features;
+    // Degrees of latitude spanning NEARBY_KM
+    let degrees = NEARBY_KM / 111.0;
+
+    let sold = sqlx::query_as::<_, SoldComparable>(&format!(
+        r#"SELECT id, property_id, list_price, sale_price, location, property_type, bedrooms,
+                  bathrooms, area_sqm, latitude, longitude, sold_at
+        FROM sold_records
+        WHERE property_id IS DISTINCT FROM $1 AND {}
+          AND sold_at >= NOW() - make_interval(months => $7)
+        ORDER BY sold_at DESC
+        LIMIT $8"#,
+        CANDIDATE_CONDITION
+    ))
+    .bind(property_id)
+    .bind(&features.property_type)
+    .bind(&features.location)
+    .bind(features.latitude)
+    .bind(features.longitude)
+    .bind(degrees)
+    .bind(months)
+    .bind(CANDIDATE_POOL)
+    .fetch_all(pool)
+    .await?;
+
+    let active = sqlx::query_as::<_, ActiveComparable>(&format!(
+        r#"SELECT id, title, price, location, property_type, bedrooms, bathrooms, area_sqm,
+                  latitude, longitude, created_at
+        FROM properties
+        WHERE id <> $1 AND {} AND {} AND {}
+        ORDER BY created_at DESC NULLS LAST
+        LIMIT $7"#,
+        CANDIDATE_CONDITION, PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(property_id)
+    .bind(&features.property_type)
+    .bind(&features.location)
+    .bind(features.latitude)
+    .bind(features.longitude)
+    .bind(degrees)
+    .bind(CANDIDATE_POOL)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((sold, active))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/{id}/comparables")]
+pub async fn property_comparables(
+    path: web::Path<Uuid>,
+    query: web::Query<ComparablesQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let months = query
+        .months
+        .unwrap_or(DEFAULT_SOLD_MONTHS)
+        .clamp(1, MAX_SOLD_MONTHS);
+
+    let result: Result<Option<ComparablesResponse>, sqlx::Error> = async {
+        let Some(subject) = sqlx::query_as::<_, Subject>(&format!(
+            r#"SELECT price, location, property_type, bedrooms, bathrooms, area_sqm,
+                      latitude, longitude
+            FROM properties WHERE id = $1 AND {}"#,
+            PUBLIC_LISTING_CONDITION
+        ))
+        .bind(property_id)
+        .fetch_optional(&state.db)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let (sold, active) = load_candidates(&state.db, property_id, &subject, months).await?;
+        let sold = rank(&subject, sold, limit, |s| &s.features, |s| s.sale_price);
+        let active = rank(&subject, active, limit, |a| &a.features, |a| a.price);
+
+        Ok(Some(ComparablesResponse {
+            property_id,
+            price_per_sqm: price_per_sqm(subject.price, subject.features.area_sqm),
+            median_sold_price_per_sqm: median(
+                sold.iter().filter_map(|s| s.price_per_sqm).collect(),
+            ),
+            median_active_price_per_sqm: median(
+                active.iter().filter_map(|a| a.price_per_sqm).collect(),
+            ),
+            sold,
+            active,
+        }))
+    }
+    .await;
+
+    match result {
+        Ok(Some(response)) => HttpResponse::Ok().json(response),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Err(e) => {
+            error!("Failed to find comparables for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to find comparables"
+            }))
+        }
+    }
+}
@@ -0,0 +1,447 @@
+// JARVIS2026 - Shared property comparisons
+// A buyer saves a shortlist of listings as a comparison set and gets a
+// `/compare/{code}` link to send to family. The link resolves to the saved
+// set side by side, counts its views and stops working once it expires.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::amenities;
+use crate::auth::CurrentUser;
+use crate::images;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::sharing;
+use crate::AppState;
+
+const MIN_PROPERTIES: usize = 2;
+const MAX_PROPERTIES: usize = 10;
+const DEFAULT_TTL_DAYS: i32 = 30;
+const MAX_TTL_DAYS: i32 = 180;
+const MAX_TITLE_CHARS: usize = 120;
+/// Photos per listing in a side-by-side view
+const THUMBNAILS_PER_PROPERTY: i64 = 6;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct CreateComparisonRequest {
+    property_ids: Vec<Uuid>,
+    title: Option<String>,
+    expires_in_days: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    /// Comma-separated listing ids
+    ids: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ComparisonSet {
+    id: Uuid,
+    code: String,
+    title: Option<String>,
+    property_ids: Vec<Uuid>,
+    view_count: i64,
+    created_at: chrono::DateTime<chrono::Utc>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ComparedProperty {
+    id: Uuid,
+    title: String,
+    location: String,
+    price: f64,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    property_type: Option<String>,
+    certificate_type: Option<String>,
+    image_thumb_webp: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ComparisonResponse {
+    code: String,
+    url: String,
+    title: Option<String>,
+    view_count: i64,
+    created_at: chrono::DateTime<chrono::Utc>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    /// In the order they were saved; listings since taken down are left out
+    properties: Vec<ComparedProperty>,
+    unavailable: usize,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ThumbnailRow {
+    property_id: Uuid,
+    media_id: Uuid,
+    alt_text: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Thumbnail {
+    media_id: Uuid,
+    url: String,
+    alt_text: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SideBySideProperty {
+    #[serde(flatten)]
+    property: ComparedProperty,
+    /// `None` without a floor area
+    price_per_sqm: Option<f64>,
+    thumbnails: Vec<Thumbnail>,
+    /// Amenities the owner attached to the listing
+    amenities: Vec<String>,
+    /// Amenities only some of the compared listings have, this one among them
+    distinct_amenities: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SideBySideResponse {
+    properties: Vec<SideBySideProperty>,
+    /// Amenities every compared listing has
+    common_amenities: Vec<String>,
+    cheapest_per_sqm: Option<Uuid>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS comparison_sets (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            code TEXT UNIQUE NOT NULL,
+            owner_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            title TEXT,
+            property_ids UUID[] NOT NULL,
+            view_count BIGINT NOT NULL DEFAULT 0,
+            last_viewed_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
+fn compare_url(base_url: &str, code: &str) -> String {
+    format!("{}/compare/{}", base_url, code)
+}
+
+/// Listings of the set the public may see, in the saved order.
+async fn load_properties(
+    pool: &PgPool,
+    property_ids: &[Uuid],
+) -> Result<Vec<ComparedProperty>, sqlx::Error> {
+    sqlx::query_as::<_, ComparedProperty>(&format!(
+        r#"SELECT id, title, location, price, bedrooms, bathrooms, area_sqm,
+                  property_type, certificate_type, image_thumb_webp
+        FROM properties
+        WHERE id = ANY($1) AND {}
+        ORDER BY array_position($1, id)"#,
+        PUBLIC_LISTING_CONDITION
+    ))
+    .bind(property_ids)
+    .fetch_all(pool)
+    .await
+}
+
+/// Listing id to its first public photos, as thumbnail variants.
+async fn load_thumbnails(
+    pool: &PgPool,
+    property_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<Thumbnail>>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ThumbnailRow>(&format!(
+        r#"SELECT property_id, media_id, alt_text FROM (
+            SELECT property_id, id AS media_id, alt_text, uploaded_at,
+                   ROW_NUMBER() OVER (PARTITION BY property_id ORDER BY uploaded_at, id) AS position
+            FROM media_uploads
+            WHERE property_id = ANY($1) AND file_type = 'image' AND {}
+        ) ranked
+        WHERE position <= $2
+        ORDER BY property_id, position"#,
+        PUBLIC_LISTING_CONDITION
+    ))
+    .bind(property_ids)
+    .bind(THUMBNAILS_PER_PROPERTY)
+    .fetch_all(pool)
+    .await?;
+
+    let mut thumbnails: HashMap<Uuid, Vec<Thumbnail>> = HashMap::new();
+    for row in rows {
+        thumbnails
+            .entry(row.property_id)
+            .or_default()
+            .push(Thumbnail {
+                url: images::variant_path(row.media_id, Some(images::THUMB_WIDTH)),
+                media_id: row.media_id,
+                alt_text: row.alt_text,
+            });
+    }
+    Ok(thumbnails)
+}
+
+/// Distinct ids in the given order, checked against the comparison limits.
+fn validate_ids(ids: impl IntoIterator<Item = Uuid>) -> Result<Vec<Uuid>, String> {
+    let mut seen = HashSet::new();
+    let property_ids: Vec<Uuid> = ids.into_iter().filter(|id| seen.insert(*id)).collect();
+    if !(MIN_PROPERTIES..=MAX_PROPERTIES).contains(&property_ids.len()) {
+        return Err(format!(
+            "A comparison needs between {} and {} different properties",
+            MIN_PROPERTIES, MAX_PROPERTIES
+        ));
+    }
+    Ok(property_ids)
+}
+
+impl ComparisonSet {
+    fn into_response(
+        self,
+        base_url: &str,
+        properties: Vec<ComparedProperty>,
+    ) -> ComparisonResponse {
+        ComparisonResponse {
+            url: compare_url(base_url, &self.code),
+            unavailable: self.property_ids.len() - properties.len(),
+            code: self.code,
+            title: self.title,
+            view_count: self.view_count,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            properties,
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/comparisons")]
+pub async fn create_comparison(
+    user: Option<CurrentUser>,
+    req: web::Json<CreateComparisonRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let req = req.into_inner();
+
+    let property_ids = match validate_ids(req.property_ids) {
+        Ok(property_ids) => property_ids,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+    let ttl_days = req.expires_in_days.unwrap_or(DEFAULT_TTL_DAYS);
+    if !(1..=MAX_TTL_DAYS).contains(&ttl_days) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("expires_in_days must be between 1 and {}", MAX_TTL_DAYS)
+        }));
+    }
+    let title = req
+        .title
+        .map(|t| t.trim().chars().take(MAX_TITLE_CHARS).collect::<String>())
+        .filter(|t| !t.is_empty());
+
+    let properties = match load_properties(&state.db, &property_ids).await {
+        Ok(properties) => properties,
+        Err(e) => {
+            error!("Failed to load properties for comparison: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to save comparison"
+            }));
+        }
+    };
+    if properties.len() != property_ids.len() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "One or more properties were not found"
+        }));
+    }
+
+    // Codes are short; retry the rare collision
+    let mut attempts = 0;
+    let set = loop {
+        attempts += 1;
+        let result = sqlx::query_as::<_, ComparisonSet>(
+            r#"INSERT INTO comparison_sets (code, owner_user_id, title, property_ids, expires_at)
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5))
+            RETURNING id, code, title, property_ids, view_count, created_at, expires_at"#,
+        )
+        .bind(sharing::generate_short_code())
+        .bind(user.map(|u| u.id))
+        .bind(&title)
+        .bind(&property_ids)
+        .bind(ttl_days)
+        .fetch_one(&state.db)
+        .await;
+        match result {
+            Ok(set) => break set,
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() && attempts < 5 => continue,
+            Err(e) => {
+                error!("Failed to save comparison: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to save comparison"
+                }));
+            }
+        }
+    };
+
+    info!(
+        "Comparison {} saved with {} properties",
+        set.id,
+        property_ids.len()
+    );
+    HttpResponse::Ok().json(set.into_response(&state.public_base_url, properties))
+}
+
+#[get("/compare/{code}")]
+pub async fn view_comparison(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let code = path.into_inner();
+
+    let set = match sqlx::query_as::<_, ComparisonSet>(
+        r#"UPDATE comparison_sets
+        SET view_count = view_count + 1, last_viewed_at = NOW()
+        WHERE code = $1 AND expires_at > NOW()
+        RETURNING id, code, title, property_ids, view_count, created_at, expires_at"#,
+    )
+    .bind(&code)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(set)) => set,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Comparison not found or expired"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to resolve comparison {}: {}", code, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load comparison"
+            }));
+        }
+    };
+
+    match load_properties(&state.db, &set.property_ids).await {
+        Ok(properties) => {
+            HttpResponse::Ok().json(set.into_response(&state.public_base_url, properties))
+        }
+        Err(e) => {
+            error!("Failed to load properties of comparison {}: {}", set.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load comparison"
+            }))
+        }
+    }
+}
+
+/// The listings side by side without saving a set: price per square metre,
+/// thumbnails and which amenities set them apart, in the order requested.
+#[get("/api/properties/compare")]
+pub async fn compare_properties(
+    query: web::Query<CompareQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let mut ids = Vec::new();
+    for raw in query
+        .ids
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+    {
+        match Uuid::parse_str(raw) {
+            Ok(id) => ids.push(id),
+            Err(_) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("'{}' is not a property id", raw)
+                }))
+            }
+        }
+    }
+    let property_ids = match validate_ids(ids) {
+        Ok(property_ids) => property_ids,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+
+    let result: Result<_, sqlx::Error> = async {
+        let properties = load_properties(&state.db, &property_ids).await?;
+        let thumbnails = load_thumbnails(&state.db, &property_ids).await?;
+        let amenities = amenities::slugs_for(&state.db, &property_ids).await?;
+        Ok((properties, thumbnails, amenities))
+    }
+    .await;
+    let (properties, mut thumbnails, mut amenities) = match result {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("Failed to load properties for comparison: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load comparison"
+            }));
+        }
+    };
+    if properties.len() != property_ids.len() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "One or more properties were not found"
+        }));
+    }
+
+    let amenity_sets: Vec<BTreeSet<String>> = properties
+        .iter()
+        .map(|p| amenities.remove(&p.id).unwrap_or_default())
+        .collect();
+    let common: BTreeSet<String> = amenity_sets
+        .iter()
+        .skip(1)
+        .fold(amenity_sets[0].clone(), |common, set| {
+            common.intersection(set).cloned().collect()
+        });
+
+    let properties: Vec<SideBySideProperty> = properties
+        .into_iter()
+        .zip(amenity_sets)
+        .map(|(property, amenities)| SideBySideProperty {
+            price_per_sqm: property
+                .area_sqm
+                .filter(|area| *area > 0.0)
+                .map(|area| (property.price / area).round()),
+            thumbnails: thumbnails.remove(&property.id).unwrap_or_default(),
+            distinct_amenities: amenities.difference(&common).cloned().collect(),
+            amenities: amenities.into_iter().collect(),
+            property,
+        })
+        .collect();
+    let cheapest_per_sqm = properties
+        .iter()
+        .filter_map(|p| p.price_per_sqm.map(|price| (p.property.id, price)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id);
+
+    HttpResponse::Ok().json(SideBySideResponse {
+        properties,
+        common_amenities: common.into_iter().collect(),
+        cheapest_per_sqm,
+    })
+}
@@ -0,0 +1,359 @@
+// JARVIS2026 - Listing completeness
+// Scores how fully a listing is filled in (photos and their alt text, video,
+// map pin, certificate, layout, amenities, description) out of 100; only a listing
+// scoring 100 counts as complete. The score is stored on the
+// property whenever the listing or its media change and feeds search ranking;
+// owners get the per-check breakdown with hints on what to add next.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Postgres};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::amenities;
+use crate::auth::CurrentUser;
+use crate::formatting::Locale;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+const TARGET_PHOTOS: i64 = 5;
+const TARGET_DESCRIPTION_CHARS: i32 = 200;
+const TARGET_AMENITIES: usize = 3;
+const BACKFILL_BATCH: i64 = 200;
+
+const PHOTO_POINTS: i32 = 25;
+/// Every public photo described for screen readers, see `alt_text`
+const ALT_TEXT_POINTS: i32 = 5;
+const VIDEO_POINTS: i32 = 15;
+const COORDINATE_POINTS: i32 = 15;
+const CERTIFICATE_POINTS: i32 = 10;
+const LAYOUT_POINTS: i32 = 10;
+/// Listed in the amenity catalog, see `amenities`
+const AMENITY_POINTS: i32 = 10;
+const DESCRIPTION_POINTS: i32 = 10;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// The inputs to the score, gathered in one query.
+#[derive(Debug, sqlx::FromRow)]
+struct ListingFacts {
+    owner_user_id: Option<Uuid>,
+    photos: i64,
+    photos_missing_alt: i64,
+    has_video: bool,
+    has_coordinates: bool,
+    has_certificate: bool,
+    has_layout: bool,
+    description_chars: i32,
+    #[sqlx(skip)]
+    amenities: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    key: &'static str,
+    points: i32,
+    max_points: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Assessment {
+    score: i32,
+    complete: bool,
+    checks: Vec<Check>,
+}
+
+#[derive(Serialize)]
+struct CompletenessResponse {
+    property_id: Uuid,
+    #[serde(flatten)]
+    assessment: Assessment,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    // NULL until first scored; the startup backfill picks those up
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS completeness_score SMALLINT")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// SCORING
+// ============================================================================
+
+async fn load_facts(
+    conn: &mut PgConnection,
+    property_id: Uuid,
+) -> Result<Option<ListingFacts>, sqlx::Error> {
+    let sql = format!(
+        r#"SELECT p.user_id AS owner_user_id,
+            (SELECT COUNT(*) FROM media_uploads m
+             WHERE m.property_id = p.id AND m.file_type = 'image' AND {public}) AS photos,
+            (SELECT COUNT(*) FROM media_uploads m
+             WHERE m.property_id = p.id AND m.file_type = 'image' AND {public}
+               AND COALESCE(TRIM(m.alt_text), '') = '') AS photos_missing_alt,
+            EXISTS (SELECT 1 FROM media_uploads m
+             WHERE m.property_id = p.id AND m.file_type IN ('video', 'drone_video')
+               AND {public}) AS has_video,
+            (p.latitude IS NOT NULL AND p.longitude IS NOT NULL) AS has_coordinates,
+            COALESCE(TRIM(p.certificate_type) <> '', false) AS has_certificate,
+            (p.bedrooms IS NOT NULL AND p.bathrooms IS NOT NULL AND p.area_sqm IS NOT NULL)
+                AS has_layout,
+            CHAR_LENGTH(TRIM(COALESCE(p.description, ''))) AS description_chars
+        FROM properties p WHERE p.id = $1"#,
+        public = PUBLIC_LISTING_CONDITION
+    );
+    let Some(mut facts) = sqlx::query_as::<_, ListingFacts>(&sql)
+        .bind(property_id)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+    facts.amenities = amenities::slugs_for(&mut *conn, &[property_id])
+        .await?
+        .remove(&property_id)
+        .map_or(0, |slugs| slugs.len());
+    Ok(Some(facts))
+}
+
+fn check(key: &'static str, max_points: i32, points: i32, hint: String) -> Check {
+    Check {
+        key,
+        points,
+        max_points,
+        hint: (points < max_points).then_some(hint),
+    }
+}
+
+fn all_or_nothing(done: bool, max_points: i32) -> i32 {
+    if done {
+        max_points
+    } else {
+        0
+    }
+}
+
+fn assess(facts: &ListingFacts, locale: Locale) -> Assessment {
+    let indonesian = locale == Locale::Indonesian;
+    let missing_photos = (TARGET_PHOTOS - facts.photos).max(0);
+    let missing_amenities = TARGET_AMENITIES.saturating_sub(facts.amenities);
+    let hint = |id: String, en: String| if indonesian { id } else { en };
+
+    let checks = vec![
+        check(
+            "photos",
+            PHOTO_POINTS,
+            (PHOTO_POINTS as i64 * facts.photos.min(TARGET_PHOTOS) / TARGET_PHOTOS) as i32,
+            hint(
+                format!(
+                    "Tambahkan {} foto lagi (minimal {})",
+                    missing_photos, TARGET_PHOTOS
+                ),
+                format!(
+                    "Add {} more photos (at least {})",
+                    missing_photos, TARGET_PHOTOS
+                ),
+            ),
+        ),
+        check(
+            "alt_text",
+            ALT_TEXT_POINTS,
+            all_or_nothing(
+                facts.photos > 0 && facts.photos_missing_alt == 0,
+                ALT_TEXT_POINTS,
+            ),
+            hint(
+                format!(
+                    "Tambahkan teks alternatif untuk {} foto",
+                    facts.photos_missing_alt
+                ),
+                format!("Add alt text to {} photos", facts.photos_missing_alt),
+            ),
+        ),
+        check(
+            "video",
+            VIDEO_POINTS,
+            all_or_nothing(facts.has_video, VIDEO_POINTS),
+            hint(
+                "Tambahkan video tur properti".to_string(),
+                "Add a video tour of the property".to_string(),
+            ),
+        ),
+        check(
+            "coordinates",
+            COORDINATE_POINTS,
+            all_or_nothing(facts.has_coordinates, COORDINATE_POINTS),
+            hint(
+                "Tandai lokasi properti di peta".to_string(),
+                "Pin the property's location on the map".to_string(),
+            ),
+        ),
+        check(
+            "certificate",
+            CERTIFICATE_POINTS,
+            all_or_nothing(facts.has_certificate, CERTIFICATE_POINTS),
+            hint(
+                "Cantumkan jenis sertifikat (mis. SHM, HGB)".to_string(),
+                "Specify the certificate type (e.g. SHM, HGB)".to_string(),
+            ),
+        ),
+        check(
+            "layout",
+            LAYOUT_POINTS,
+            all_or_nothing(facts.has_layout, LAYOUT_POINTS),
+            hint(
+                "Lengkapi jumlah kamar tidur, kamar mandi, dan luas bangunan".to_string(),
+                "Fill in bedrooms, bathrooms and floor area".to_string(),
+            ),
+        ),
+        check(
+            "amenities",
+            AMENITY_POINTS,
+            (AMENITY_POINTS as usize * facts.amenities.min(TARGET_AMENITIES) / TARGET_AMENITIES)
+                as i32,
+            hint(
+                format!(
+                    "Tambahkan {} fasilitas lagi (mis. kolam renang, garasi)",
+                    missing_amenities
+                ),
+                format!(
+                    "Add {} more amenities (e.g. pool, garage)",
+                    missing_amenities
+                ),
+            ),
+        ),
+        check(
+            "description",
+            DESCRIPTION_POINTS,
+            all_or_nothing(
+                facts.description_chars >= TARGET_DESCRIPTION_CHARS,
+                DESCRIPTION_POINTS,
+            ),
+            hint(
+                format!(
+                    "Tulis deskripsi minimal {} karakter",
+                    TARGET_DESCRIPTION_CHARS
+                ),
+                format!(
+                    "Write a description of at least {} characters",
+                    TARGET_DESCRIPTION_CHARS
+                ),
+            ),
+        ),
+    ];
+
+    let score = checks.iter().map(|c| c.points).sum();
+    Assessment {
+        score,
+        complete: checks.iter().all(|c| c.points == c.max_points),
+        checks,
+    }
+}
+
+/// Recomputes and stores a listing's score. Call after any write to the
+/// listing or its media; pass a transaction to keep the score in step with it.
+pub async fn refresh<'a, A>(conn: A, property_id: Uuid) -> Result<Option<i32>, sqlx::Error>
+where
+    A: sqlx::Acquire<'a, Database = Postgres>,
+{
+    let mut conn = conn.acquire().await?;
+    let Some(facts) = load_facts(&mut conn, property_id).await? else {
+        return Ok(None);
+    };
+    let score = assess(&facts, Locale::English).score;
+
+    sqlx::query("UPDATE properties SET completeness_score = $2 WHERE id = $1")
+        .bind(property_id)
+        .bind(score as i16)
+        .execute(&mut *conn)
+        .await?;
+    Ok(Some(score))
+}
+
+/// Scores listings created before completeness existed.
+pub fn spawn_backfill(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut scored = 0usize;
+        loop {
+            let ids = match sqlx::query_scalar::<_, Uuid>(
+                "SELECT id FROM properties WHERE completeness_score IS NULL LIMIT $1",
+            )
+            .bind(BACKFILL_BATCH)
+            .fetch_all(&pool)
+            .await
+            {
+                Ok(ids) => ids,
+                Err(e) => {
+                    error!("Completeness backfill failed: {}", e);
+                    return;
+                }
+            };
+            if ids.is_empty() {
+                break;
+            }
+            for id in ids {
+                if let Err(e) = refresh(&pool, id).await {
+                    error!("Completeness backfill failed for {}: {}", id, e);
+                    return;
+                }
+                scored += 1;
+            }
+        }
+        if scored > 0 {
+            info!("Completeness backfill scored {} listings", scored);
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// The owner's (or an admin's) view of what the listing is still missing.
+#[get("/api/properties/{id}/completeness")]
+pub async fn property_completeness(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    let facts = async {
+        let mut conn = state.db.acquire().await?;
+        load_facts(&mut conn, property_id).await
+    }
+    .await;
+    match facts {
+        Ok(Some(facts)) if facts.owner_user_id == Some(user.id) || user.is_admin() => {
+            HttpResponse::Ok().json(CompletenessResponse {
+                property_id,
+                assessment: assess(&facts, locale),
+            })
+        }
+        Ok(Some(_)) => HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the owner can view listing completeness"
+        })),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Err(e) => {
+            error!("Failed to assess completeness of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load listing completeness"
+            }))
+        }
+    }
+}
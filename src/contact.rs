@@ -0,0 +1,402 @@
+// JARVIS2026 - Owner contact reveal
+// Owner phone numbers are never part of listing responses. Signed-in users
+// reveal them one listing at a time; every reveal is logged as a lead for the
+// owner and counts against a per-user quota. When `CONTACT_RELAY_NUMBERS` is
+// set, callers get a masked relay number instead, and the telephony provider
+// resolves the real number through `/api/contact/relay/route`.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::captcha;
+use crate::secrets;
+use crate::AppState;
+
+const VISITOR_HEADER: &str = "X-Visitor-Id";
+const RELAY_SECRET_HEADER: &str = "X-Relay-Secret";
+const MAX_REVEALS_PER_HOUR: i64 = 10;
+const MAX_REVEALS_PER_DAY: i64 = 30;
+/// Revealing the same listing again within this window is free and reuses the result.
+const REPEAT_WINDOW_HOURS: i64 = 24;
+const RELAY_TTL_DAYS: i64 = 7;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize, Default)]
+pub struct RevealRequest {
+    /// Where the lead came from, e.g. `listing_page` or a short-link channel
+    source: Option<String>,
+    /// Honeypot; see `captcha`
+    website: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RelayRouteQuery {
+    /// The relay number that was dialled
+    to: String,
+    /// The caller's number
+    from: String,
+}
+
+#[derive(Serialize)]
+struct RevealedContact {
+    property_id: Uuid,
+    phone: String,
+    /// True when `phone` is a relay number rather than the owner's own
+    masked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RevealCounts {
+    last_hour: i64,
+    last_day: i64,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS phone TEXT")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS contact_reveals (
+            id BIGSERIAL PRIMARY KEY,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            owner_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            visitor_id TEXT,
+            source TEXT,
+            masked BOOLEAN NOT NULL DEFAULT false,
+            revealed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_contact_reveals_user ON contact_reveals(user_id, revealed_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_contact_reveals_owner ON contact_reveals(owner_user_id, revealed_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    // A relay number routes one caller to one owner until it expires
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS contact_relays (
+            relay_number TEXT NOT NULL,
+            caller_phone TEXT NOT NULL,
+            caller_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            owner_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            expires_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (relay_number, caller_phone)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// RELAY
+// ============================================================================
+
+fn relay_pool() -> Vec<String> {
+    std::env::var("CONTACT_RELAY_NUMBERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+/// Finds or assigns the relay number this caller uses to reach the owner.
+/// The same caller can't hold one number for two owners, so calls route
+/// unambiguously from `(relay_number, caller_phone)`.
+async fn assign_relay(
+    pool: &PgPool,
+    numbers: &[String],
+    caller: (Uuid, &str),
+    owner_user_id: Uuid,
+    property_id: Uuid,
+) -> Result<Option<(String, DateTime<Utc>)>, sqlx::Error> {
+    let expires_at = Utc::now() + Duration::days(RELAY_TTL_DAYS);
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM contact_relays WHERE caller_phone = $1 AND expires_at < NOW()")
+        .bind(caller.1)
+        .execute(&mut *tx)
+        .await?;
+
+    let in_use: Vec<(String, Uuid)> = sqlx::query_as(
+        "SELECT relay_number, owner_user_id FROM contact_relays WHERE caller_phone = $1 FOR UPDATE",
+    )
+    .bind(caller.1)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let number = match in_use.iter().find(|(_, owner)| *owner == owner_user_id) {
+        Some((number, _)) => number.clone(),
+        None => match numbers
+            .iter()
+            .find(|n| in_use.iter().all(|(used, _)| used != *n))
+        {
+            Some(number) => number.clone(),
+            None => return Ok(None),
+        },
+    };
+
+    let assigned = sqlx::query(
+        r#"INSERT INTO contact_relays
+        (relay_number, caller_phone, caller_user_id, owner_user_id, property_id, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (relay_number, caller_phone) DO UPDATE
+        SET property_id = EXCLUDED.property_id, expires_at = EXCLUDED.expires_at
+        WHERE contact_relays.owner_user_id = EXCLUDED.owner_user_id"#,
+    )
+    .bind(&number)
+    .bind(caller.1)
+    .bind(caller.0)
+    .bind(owner_user_id)
+    .bind(property_id)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+    if assigned.rows_affected() == 0 {
+        // Taken for another owner by a concurrent reveal
+        return Ok(None);
+    }
+
+    tx.commit().await?;
+    Ok(Some((number, expires_at)))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/properties/{id}/reveal-contact")]
+pub async fn reveal_contact(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    body: Option<web::Json<RevealRequest>>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    if captcha::honeypot_tripped(body.website.as_deref()) {
+        return captcha::honeypot_response();
+    }
+
+    let (owner_user_id, owner_phone) = match sqlx::query_as::<_, (Uuid, Option<String>)>(
+        r#"SELECT u.id, u.phone FROM properties p
+        JOIN users u ON u.id = p.user_id
+        WHERE p.id = $1"#,
+    )
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some((owner, Some(phone)))) => (owner, phone),
+        Ok(Some((_, None))) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "The owner hasn't shared a phone number"
+            }))
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up contact for {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reveal contact"
+            }));
+        }
+    };
+
+    let repeat = sqlx::query_scalar::<_, bool>(
+        r#"SELECT EXISTS (SELECT 1 FROM contact_reveals
+        WHERE user_id = $1 AND property_id = $2
+          AND revealed_at > NOW() - make_interval(hours => $3))"#,
+    )
+    .bind(user.id)
+    .bind(property_id)
+    .bind(REPEAT_WINDOW_HOURS as i32)
+    .fetch_one(&state.db);
+
+    let counts = sqlx::query_as::<_, RevealCounts>(
+        r#"SELECT
+            COUNT(DISTINCT property_id) FILTER (WHERE revealed_at > NOW() - INTERVAL '1 hour') AS last_hour,
+            COUNT(DISTINCT property_id) AS last_day
+        FROM contact_reveals
+        WHERE user_id = $1 AND revealed_at > NOW() - INTERVAL '1 day'"#,
+    )
+    .bind(user.id)
+    .fetch_one(&state.db);
+
+    let (repeat, counts) = match tokio::try_join!(repeat, counts) {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Failed to check reveal quota for {}: {}", user.id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reveal contact"
+            }));
+        }
+    };
+    if !repeat
+        && (counts.last_hour >= MAX_REVEALS_PER_HOUR || counts.last_day >= MAX_REVEALS_PER_DAY)
+    {
+        return HttpResponse::TooManyRequests().json(serde_json::json!({
+            "error": "Contact reveal limit reached, try again later"
+        }));
+    }
+
+    let numbers = relay_pool();
+    let contact = if numbers.is_empty() || owner_user_id == user.id {
+        RevealedContact {
+            property_id,
+            phone: owner_phone,
+            masked: false,
+            expires_at: None,
+        }
+    } else {
+        let caller_phone =
+            match sqlx::query_scalar::<_, Option<String>>("SELECT phone FROM users WHERE id = $1")
+                .bind(user.id)
+                .fetch_one(&state.db)
+                .await
+            {
+                Ok(Some(phone)) => phone,
+                Ok(None) => {
+                    return HttpResponse::Conflict().json(serde_json::json!({
+                        "error": "Add a phone number to your account to call through the relay"
+                    }))
+                }
+                Err(e) => {
+                    error!("Failed to look up caller phone for {}: {}", user.id, e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to reveal contact"
+                    }));
+                }
+            };
+
+        match assign_relay(
+            &state.db,
+            &numbers,
+            (user.id, &caller_phone),
+            owner_user_id,
+            property_id,
+        )
+        .await
+        {
+            Ok(Some((phone, expires_at))) => RevealedContact {
+                property_id,
+                phone,
+                masked: true,
+                expires_at: Some(expires_at),
+            },
+            Ok(None) => {
+                return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                    "error": "No relay numbers available, try again later"
+                }))
+            }
+            Err(e) => {
+                error!("Failed to assign relay number: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to reveal contact"
+                }));
+            }
+        }
+    };
+
+    let visitor_id = req
+        .headers()
+        .get(VISITOR_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if let Err(e) = sqlx::query(
+        r#"INSERT INTO contact_reveals (property_id, owner_user_id, user_id, visitor_id, source, masked)
+        VALUES ($1, $2, $3, $4, $5, $6)"#,
+    )
+    .bind(property_id)
+    .bind(owner_user_id)
+    .bind(user.id)
+    .bind(visitor_id)
+    .bind(body.source.as_deref().map(str::trim))
+    .bind(contact.masked)
+    .execute(&state.db)
+    .await
+    {
+        // The log is the quota, so don't hand out numbers we can't count
+        error!("Failed to log contact reveal for {}: {}", property_id, e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to reveal contact"
+        }));
+    }
+
+    info!("Contact for {} revealed to {}", property_id, user.id);
+    HttpResponse::Ok().json(contact)
+}
+
+/// Telephony webhook: which number should a call to `to` from `from` ring?
+#[get("/api/contact/relay/route")]
+pub async fn relay_route(
+    req: HttpRequest,
+    query: web::Query<RelayRouteQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let expected = secrets::var("CONTACT_RELAY_SECRET").ok();
+    let given = req
+        .headers()
+        .get(RELAY_SECRET_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if expected.is_none() || expected.as_deref() != given {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Invalid relay secret"
+        }));
+    }
+
+    match sqlx::query_scalar::<_, Option<String>>(
+        r#"SELECT u.phone FROM contact_relays r
+        JOIN users u ON u.id = r.owner_user_id
+        WHERE r.relay_number = $1 AND r.caller_phone = $2 AND r.expires_at > NOW()"#,
+    )
+    .bind(query.to.trim())
+    .bind(query.from.trim())
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(Some(phone))) => {
+            HttpResponse::Ok().json(serde_json::json!({ "forward_to": phone }))
+        }
+        Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No active relay for this caller"
+        })),
+        Err(e) => {
+            error!("Failed to route relay call: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to route call"
+            }))
+        }
+    }
+}
@@ -0,0 +1,454 @@
+// JARVIS2026 - Email and password sign-in
+// Registration may attach an email and password to the new account. Only an
+// Argon2id hash (PHC string, salt and parameters included) is stored.
+// `/api/auth/login` checks the password and starts a regular session, the
+// same as a wallet sign-in. A forgotten password is replaced through a
+// one-time emailed reset token; using it signs out every existing session.
+
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::OnceLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::agencies;
+use crate::captcha;
+use crate::fraud;
+use crate::sessions::{self, IssuedSession};
+use crate::AppState;
+
+const MIN_PASSWORD_LEN: usize = 8;
+/// Argon2 cost grows with input; cap it so huge bodies can't tie up workers.
+const MAX_PASSWORD_LEN: usize = 128;
+const MAX_EMAIL_LEN: usize = 254;
+const RESET_TOKEN_TTL_MINUTES: i32 = 30;
+/// Reset mails one account may receive per hour.
+const MAX_RESETS_PER_HOUR: i64 = 3;
+/// Minimum gap between two reset mails to one account.
+const RESET_COOLDOWN_SECS: f64 = 60.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    email: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct LoginResponse {
+    user_id: Uuid,
+    #[serde(flatten)]
+    session: IssuedSession,
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    email: String,
+    /// Honeypot; see `captcha`
+    website: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    token: String,
+    password: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredCredential {
+    user_id: Uuid,
+    password_hash: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS credentials (
+            user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            email TEXT NOT NULL,
+            password_hash TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_credentials_email ON credentials (LOWER(email))",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS password_reset_tokens (
+            token_hash TEXT PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_password_reset_user ON password_reset_tokens (user_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// HASHING
+// ============================================================================
+
+/// Trims and sanity-checks an address. Case is kept as typed; lookups compare
+/// case-insensitively.
+pub fn normalize_email(email: &str) -> Result<String, String> {
+    let email = email.trim();
+    let valid = email.len() <= MAX_EMAIL_LEN
+        && !email.chars().any(char::is_whitespace)
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if valid {
+        Ok(email.to_string())
+    } else {
+        Err("Invalid email address".to_string())
+    }
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    let len = password.chars().count();
+    if !(MIN_PASSWORD_LEN..=MAX_PASSWORD_LEN).contains(&len) {
+        return Err(format!(
+            "Password must be between {} and {} characters",
+            MIN_PASSWORD_LEN, MAX_PASSWORD_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Argon2id with the crate's default (OWASP-recommended) parameters.
+fn hash_password_blocking(password: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes()).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn verify_password_blocking(password: &str, stored: &str) -> bool {
+    PasswordHash::new(stored).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// A valid hash of a throwaway password, checked against when the email is
+/// unknown so that the response time doesn't reveal which emails exist.
+fn dummy_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| hash_password_blocking(&Uuid::new_v4().to_string()).unwrap_or_default())
+}
+
+/// Hashes off the async executor; Argon2 is deliberately slow.
+pub async fn hash_password(password: String) -> Result<String, String> {
+    web::block(move || hash_password_blocking(&password))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+async fn verify_password(password: String, stored: Option<String>) -> bool {
+    web::block(move || match stored {
+        Some(stored) => verify_password_blocking(&password, &stored),
+        None => {
+            verify_password_blocking(&password, dummy_hash());
+            false
+        }
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Attaches an email and password hash to a freshly created user.
+pub async fn attach(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    email: &str,
+    password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO credentials (user_id, email, password_hash) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(email)
+        .bind(password_hash)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// PASSWORD RESET
+// ============================================================================
+
+/// Issues a reset token for the account behind `email` and mails it, unless
+/// the email is unknown or the account hit its reset allowance. The caller
+/// can't tell these cases apart, so the endpoint reveals nothing.
+async fn send_reset(state: &AppState, email: &str) -> Result<(), String> {
+    let account = sqlx::query_as::<_, (Uuid, String, i64, bool)>(
+        r#"SELECT c.user_id, c.email,
+                  (SELECT COUNT(*) FROM password_reset_tokens t
+                   WHERE t.user_id = c.user_id AND t.created_at > NOW() - INTERVAL '1 hour'),
+                  EXISTS (SELECT 1 FROM password_reset_tokens t
+                          WHERE t.user_id = c.user_id
+                            AND t.created_at > NOW() - make_interval(secs => $2))
+        FROM credentials c WHERE LOWER(c.email) = LOWER($1)"#,
+    )
+    .bind(email)
+    .bind(RESET_COOLDOWN_SECS)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| format!("Failed to look up account: {}", e))?;
+
+    let Some((user_id, email, sent_last_hour, in_cooldown)) = account else {
+        return Ok(());
+    };
+    if sent_last_hour >= MAX_RESETS_PER_HOUR || in_cooldown {
+        info!("Password reset for user {} rate limited", user_id);
+        return Ok(());
+    }
+
+    let token = sessions::new_token();
+    sqlx::query(
+        r#"INSERT INTO password_reset_tokens (token_hash, user_id, expires_at)
+        VALUES ($1, $2, NOW() + make_interval(mins => $3))"#,
+    )
+    .bind(sessions::hash_token(&token))
+    .bind(user_id)
+    .bind(RESET_TOKEN_TTL_MINUTES)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store reset token: {}", e))?;
+
+    // Old rows are kept only as long as the hourly allowance needs them
+    sqlx::query(
+        "DELETE FROM password_reset_tokens WHERE user_id = $1 AND created_at < NOW() - INTERVAL '1 day'",
+    )
+    .bind(user_id)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to prune reset tokens: {}", e))?;
+
+    let link = format!("{}/?reset_password={}", state.public_base_url, token);
+    let body = format!(
+        "Someone asked to reset the password of your JARVIS2026 account. To choose a new \
+         password, open this link:\n\n{}\n\nThe link expires in {} minutes. If this wasn't \
+         you, ignore this message; your password stays unchanged.",
+        link, RESET_TOKEN_TTL_MINUTES
+    );
+    let body = agencies::with_footer(&state.db, user_id, body).await;
+    state
+        .mailer
+        .send(&email, "Reset your password", &body)
+        .await
+}
+
+/// Whether `token` can still be redeemed, checked before paying for the hash.
+async fn reset_token_is_live(pool: &PgPool, token: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"SELECT EXISTS (SELECT 1 FROM password_reset_tokens
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW())"#,
+    )
+    .bind(sessions::hash_token(token))
+    .fetch_one(pool)
+    .await
+}
+
+/// Replaces the password if `token` is live. Returns the affected user.
+async fn reset_password_with_token(
+    pool: &PgPool,
+    token: &str,
+    password_hash: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some(user_id) = sqlx::query_scalar::<_, Uuid>(
+        r#"UPDATE password_reset_tokens SET used_at = NOW()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id"#,
+    )
+    .bind(sessions::hash_token(token))
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query("UPDATE credentials SET password_hash = $1, updated_at = NOW() WHERE user_id = $2")
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Other outstanding links die with the old password
+    sqlx::query(
+        "UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sessions::revoke_all(&mut *tx, user_id).await?;
+
+    tx.commit().await?;
+    Ok(Some(user_id))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/auth/login")]
+pub async fn login(
+    http_req: HttpRequest,
+    req: web::Json<LoginRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let req = req.into_inner();
+    // Over-long input can't match anything stored, so skip the hashing
+    if req.password.chars().count() > MAX_PASSWORD_LEN {
+        return invalid_credentials();
+    }
+
+    let stored = match sqlx::query_as::<_, StoredCredential>(
+        "SELECT user_id, password_hash FROM credentials WHERE LOWER(email) = LOWER($1)",
+    )
+    .bind(req.email.trim())
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(stored) => stored,
+        Err(e) => {
+            error!("Failed to look up credentials: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to sign in"
+            }));
+        }
+    };
+
+    let user_id = stored.as_ref().map(|s| s.user_id);
+    let verified = verify_password(req.password, stored.map(|s| s.password_hash)).await;
+    let Some(user_id) = user_id.filter(|_| verified) else {
+        return invalid_credentials();
+    };
+
+    match sessions::issue(&state.db, user_id, "password").await {
+        Ok(session) => {
+            info!("Password sign-in for user {}", user_id);
+            fraud::record_request(&state.db, user_id, &http_req).await;
+            HttpResponse::Ok().json(LoginResponse { user_id, session })
+        }
+        Err(e) => {
+            error!("Failed to start session for {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to sign in"
+            }))
+        }
+    }
+}
+
+fn invalid_credentials() -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "error": "Invalid email or password"
+    }))
+}
+
+fn invalid_reset_link() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Reset link is invalid, expired or already used"
+    }))
+}
+
+#[post("/api/auth/forgot-password")]
+pub async fn forgot_password(
+    req: web::Json<ForgotPasswordRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if captcha::honeypot_tripped(req.website.as_deref()) {
+        return captcha::honeypot_response();
+    }
+
+    // Sent in the background so that the same answer, in the same time,
+    // comes back whether or not the email belongs to an account
+    let email = req.into_inner().email;
+    tokio::spawn(async move {
+        if let Err(e) = send_reset(&state, email.trim()).await {
+            warn!("Password reset request failed: {}", e);
+        }
+    });
+    HttpResponse::Accepted().json(serde_json::json!({
+        "message": "If an account uses this email, a reset link is on its way"
+    }))
+}
+
+#[post("/api/auth/reset-password")]
+pub async fn reset_password(
+    req: web::Json<ResetPasswordRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let req = req.into_inner();
+    if let Err(message) = validate_password(&req.password) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }));
+    }
+    let token = req.token.trim();
+    match reset_token_is_live(&state.db, token).await {
+        Ok(true) => {}
+        Ok(false) => return invalid_reset_link(),
+        Err(e) => {
+            error!("Failed to look up reset token: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reset password"
+            }));
+        }
+    }
+    let password_hash = match hash_password(req.password).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            error!("Failed to hash password: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reset password"
+            }));
+        }
+    };
+
+    // Checked again on redemption, in case the link was used meanwhile
+    match reset_password_with_token(&state.db, token, &password_hash).await {
+        Ok(Some(user_id)) => {
+            info!("Password reset for user {}", user_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "user_id": user_id,
+                "message": "Password updated; sign in again on every device"
+            }))
+        }
+        Ok(None) => invalid_reset_link(),
+        Err(e) => {
+            error!("Failed to reset password: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reset password"
+            }))
+        }
+    }
+}
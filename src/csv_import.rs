@@ -0,0 +1,670 @@
+// JARVIS2026 - Spreadsheet import
+// Agents upload their listings as a CSV, one row per property. Every row is
+// validated on its own, so a bad row is reported without holding up the rest,
+// and rows matching a listing the agent already has are skipped. Imported
+// listings start as drafts, since photos come later. A dry run reports what
+// would happen without writing anything. Small files are processed while the
+// agent waits; larger ones are queued for a background worker and the report
+// is fetched once it's done.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, PgPool};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::account_status;
+use crate::auth::CurrentUser;
+use crate::completeness;
+use crate::filters::parse_number;
+use crate::listing_checks;
+use crate::listing_status::ListingStatus;
+use crate::timezones;
+use crate::upload_policy;
+use crate::AppState;
+
+const MAX_CSV_BYTES: usize = 10 * 1024 * 1024;
+const MAX_ROWS: usize = 5000;
+/// Files up to this many rows are processed within the request
+const INLINE_MAX_ROWS: usize = 100;
+const WORKER_INTERVAL: Duration = Duration::from_secs(10);
+/// A running import is picked up again after this long if its worker died;
+/// rows it already created are then skipped as duplicates
+const CLAIM_LEASE_MINUTES: i32 = 30;
+
+/// Recognised columns; header names are matched case-insensitively.
+/// `reference` is the agent's own id for the listing, used to skip rows
+/// imported before.
+const COLUMNS: &[&str] = &[
+    "reference",
+    "title",
+    "location",
+    "price",
+    "description",
+    "bedrooms",
+    "bathrooms",
+    "area_sqm",
+    "latitude",
+    "longitude",
+    "property_type",
+    "certificate_type",
+];
+const REQUIRED_COLUMNS: &[&str] = &["title", "location", "price"];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// A data row keyed by column, with its line number in the file.
+struct CsvRow {
+    line: usize,
+    fields: HashMap<&'static str, String>,
+}
+
+struct ImportedRow {
+    reference: Option<String>,
+    title: String,
+    location: String,
+    price: f64,
+    description: String,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    property_type: Option<String>,
+    certificate_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RowStatus {
+    Created,
+    /// Dry run: the row would be created
+    Valid,
+    Skipped,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+struct RowOutcome {
+    line: usize,
+    status: RowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    property_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    messages: Vec<String>,
+    /// What the listing checks objected to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    issues: Vec<listing_checks::Issue>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ImportReport {
+    total_rows: usize,
+    created: usize,
+    skipped: usize,
+    errors: usize,
+    rows: Vec<RowOutcome>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct PropertyImport {
+    id: Uuid,
+    dry_run: bool,
+    /// pending, running, done or failed
+    status: String,
+    total_rows: i32,
+    report: Option<Json<serde_json::Value>>,
+    error: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ClaimedImport {
+    id: Uuid,
+    user_id: Uuid,
+    dry_run: bool,
+    csv_data: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS property_imports (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            dry_run BOOLEAN NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            csv_data TEXT,
+            total_rows INTEGER NOT NULL,
+            report JSONB,
+            error TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            started_at TIMESTAMPTZ,
+            finished_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_property_imports_user ON property_imports(user_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE INDEX IF NOT EXISTS idx_properties_user_external
+        ON properties(user_id, external_id) WHERE import_feed_id IS NULL"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// PARSING
+// ============================================================================
+
+/// Reads the header and data rows; only a file that can't be read as a
+/// whole is an error, bad values are left for row validation.
+fn parse_csv(text: &str) -> Result<Vec<CsvRow>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Unreadable header row: {}", e))?
+        .clone();
+    let mut columns: Vec<Option<&'static str>> = Vec::with_capacity(headers.len());
+    for header in headers.iter() {
+        let name = header.to_lowercase().replace([' ', '-'], "_");
+        match COLUMNS.iter().find(|c| **c == name) {
+            Some(column) => columns.push(Some(column)),
+            None if name.is_empty() => columns.push(None),
+            None => {
+                return Err(format!(
+                    "Unknown column '{}'; columns: {}",
+                    header,
+                    COLUMNS.join(", ")
+                ))
+            }
+        }
+    }
+    if let Some(missing) = REQUIRED_COLUMNS
+        .iter()
+        .find(|required| !columns.contains(&Some(**required)))
+    {
+        return Err(format!("Missing required column '{}'", missing));
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Unreadable CSV: {}", e))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        if rows.len() == MAX_ROWS {
+            return Err(format!("A file can hold at most {} rows", MAX_ROWS));
+        }
+        let fields = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(column, value)| column.map(|c| (c, value.to_string())))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        rows.push(CsvRow {
+            line: record.position().map_or(0, |p| p.line() as usize),
+            fields,
+        });
+    }
+    Ok(rows)
+}
+
+/// Checks one row's values, collecting every problem rather than the first.
+fn validate_row(row: &CsvRow) -> Result<ImportedRow, Vec<String>> {
+    let mut problems = Vec::new();
+    let text = |column: &str| row.fields.get(column).cloned();
+
+    let mut required = |column: &str| {
+        let value = text(column);
+        if value.is_none() {
+            problems.push(format!("{} is required", column));
+        }
+        value
+    };
+    let title = required("title");
+    let location = required("location");
+    let price = required("price");
+
+    let price = price.and_then(|raw| match parse_number(&raw).filter(|p| *p > 0.0) {
+        Some(price) => Some(price),
+        None => {
+            problems.push(format!("price must be a positive amount, got '{}'", raw));
+            None
+        }
+    });
+    let mut count = |column: &str| {
+        let raw = text(column)?;
+        match raw.parse::<i32>().ok().filter(|n| *n >= 0) {
+            Some(n) => Some(n),
+            None => {
+                problems.push(format!("{} must be a whole number, got '{}'", column, raw));
+                None
+            }
+        }
+    };
+    let bedrooms = count("bedrooms");
+    let bathrooms = count("bathrooms");
+    let area_sqm = text("area_sqm").and_then(|raw| match parse_number(&raw).filter(|a| *a > 0.0) {
+        Some(area) => Some(area),
+        None => {
+            problems.push(format!("area_sqm must be a positive number, got '{}'", raw));
+            None
+        }
+    });
+    let mut coordinate = |column: &str, range: std::ops::RangeInclusive<f64>| {
+        let raw = text(column)?;
+        match raw.parse::<f64>().ok().filter(|v| range.contains(v)) {
+            Some(value) => Some(value),
+            None => {
+                problems.push(format!("{} is out of range: '{}'", column, raw));
+                None
+            }
+        }
+    };
+    let latitude = coordinate("latitude", -90.0..=90.0);
+    let longitude = coordinate("longitude", -180.0..=180.0);
+    if latitude.is_some() != longitude.is_some() {
+        problems.push("latitude and longitude go together".to_string());
+    }
+
+    match (title, location, price) {
+        (Some(title), Some(location), Some(price)) if problems.is_empty() => Ok(ImportedRow {
+            reference: text("reference"),
+            title,
+            location,
+            price,
+            description: text("description").unwrap_or_default(),
+            bedrooms,
+            bathrooms,
+            area_sqm,
+            latitude,
+            longitude,
+            property_type: text("property_type").map(|v| v.to_lowercase()),
+            certificate_type: text("certificate_type").map(|v| v.to_uppercase()),
+        }),
+        _ => Err(problems),
+    }
+}
+
+// ============================================================================
+// IMPORT
+// ============================================================================
+
+/// Whether the agent already has this listing, by reference or by title and
+/// location.
+async fn already_listed(
+    pool: &PgPool,
+    user_id: Uuid,
+    row: &ImportedRow,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM properties
+            WHERE user_id = $1
+              AND ((import_feed_id IS NULL AND external_id = $2)
+                   OR (LOWER(TRIM(title)) = LOWER($3) AND LOWER(TRIM(location)) = LOWER($4)))
+        )"#,
+    )
+    .bind(user_id)
+    .bind(&row.reference)
+    .bind(&row.title)
+    .bind(&row.location)
+    .fetch_one(pool)
+    .await
+}
+
+async fn create_listing(
+    pool: &PgPool,
+    user_id: Uuid,
+    moderation_status: &str,
+    row: &ImportedRow,
+) -> Result<Uuid, sqlx::Error> {
+    let property_id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO properties
+        (id, title, location, price, description, bedrooms, bathrooms, area_sqm, latitude, longitude,
+         property_type, certificate_type, timezone, user_id, moderation_status, status, slug, external_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)"#,
+    )
+    .bind(property_id)
+    .bind(&row.title)
+    .bind(&row.location)
+    .bind(row.price)
+    .bind(&row.description)
+    .bind(row.bedrooms)
+    .bind(row.bathrooms)
+    .bind(row.area_sqm)
+    .bind(row.latitude)
+    .bind(row.longitude)
+    .bind(&row.property_type)
+    .bind(&row.certificate_type)
+    .bind(timezones::for_coordinates(row.latitude, row.longitude).name())
+    .bind(user_id)
+    .bind(moderation_status)
+    .bind(ListingStatus::Draft.as_str())
+    .bind(crate::listing_slug(&row.title, property_id))
+    .bind(&row.reference)
+    .execute(pool)
+    .await?;
+    completeness::refresh(pool, property_id).await?;
+    Ok(property_id)
+}
+
+/// Validates and, unless it's a dry run, creates every row.
+async fn run_import(
+    pool: &PgPool,
+    user_id: Uuid,
+    rows: Vec<CsvRow>,
+    dry_run: bool,
+) -> Result<ImportReport, sqlx::Error> {
+    let moderation_status = upload_policy::for_user(pool, user_id)
+        .await?
+        .moderation
+        .initial_status();
+    let mut report = ImportReport {
+        total_rows: rows.len(),
+        ..ImportReport::default()
+    };
+    let mut seen = HashSet::new();
+
+    for csv_row in rows {
+        let mut outcome = RowOutcome {
+            line: csv_row.line,
+            status: RowStatus::Error,
+            reference: csv_row.fields.get("reference").cloned(),
+            property_id: None,
+            messages: Vec::new(),
+            issues: Vec::new(),
+        };
+        match validate_row(&csv_row) {
+            Err(problems) => outcome.messages = problems,
+            Ok(row) => {
+                let key = (
+                    row.title.to_lowercase(),
+                    row.location.to_lowercase(),
+                    row.reference.clone(),
+                );
+                if !seen.insert(key) {
+                    outcome.status = RowStatus::Skipped;
+                    outcome
+                        .messages
+                        .push("Repeats an earlier row of this file".to_string());
+                } else if already_listed(pool, user_id, &row).await? {
+                    outcome.status = RowStatus::Skipped;
+                    outcome
+                        .messages
+                        .push("You already have this listing".to_string());
+                } else {
+                    let draft = listing_checks::Draft {
+                        location: &row.location,
+                        property_type: row.property_type.as_deref(),
+                        price: row.price,
+                        description: &row.description,
+                        files: &[],
+                    };
+                    let review = listing_checks::run(pool, &draft).await?;
+                    if !review.issues.is_empty() {
+                        outcome.issues = review.issues;
+                    } else {
+                        if review.price_outlier.is_some() {
+                            outcome.messages.push(
+                                "Price is unusual for the area; the listing will be held for review"
+                                    .to_string(),
+                            );
+                        }
+                        outcome.status = if dry_run {
+                            RowStatus::Valid
+                        } else {
+                            let property_id =
+                                create_listing(pool, user_id, moderation_status, &row).await?;
+                            if let Some(outlier) = &review.price_outlier {
+                                listing_checks::flag_price(pool, property_id, outlier).await?;
+                            }
+                            outcome.property_id = Some(property_id);
+                            RowStatus::Created
+                        };
+                    }
+                }
+            }
+        }
+
+        match outcome.status {
+            RowStatus::Created | RowStatus::Valid => report.created += 1,
+            RowStatus::Skipped => report.skipped += 1,
+            RowStatus::Error => report.errors += 1,
+        }
+        report.rows.push(outcome);
+    }
+    Ok(report)
+}
+
+/// Records the outcome of an import and drops the file it came from.
+async fn finish(
+    pool: &PgPool,
+    import_id: Uuid,
+    result: &Result<ImportReport, sqlx::Error>,
+) -> Result<Option<PropertyImport>, sqlx::Error> {
+    let (status, report, failure) = match result {
+        Ok(report) => ("done", serde_json::to_value(report).ok(), None),
+        Err(e) => ("failed", None, Some(format!("Database error: {}", e))),
+    };
+    sqlx::query_as::<_, PropertyImport>(
+        r#"UPDATE property_imports
+        SET status = $2, report = $3, error = $4, csv_data = NULL, finished_at = NOW()
+        WHERE id = $1
+        RETURNING id, dry_run, status, total_rows, report, error, created_at, finished_at"#,
+    )
+    .bind(import_id)
+    .bind(status)
+    .bind(report.map(Json))
+    .bind(failure)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn process_next(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    let claimed = sqlx::query_as::<_, ClaimedImport>(
+        r#"UPDATE property_imports SET status = 'running', started_at = NOW()
+        WHERE id = (
+            SELECT id FROM property_imports
+            WHERE status = 'pending'
+               OR (status = 'running' AND started_at < NOW() - make_interval(mins => $1))
+            ORDER BY created_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED)
+        RETURNING id, user_id, dry_run, COALESCE(csv_data, '') AS csv_data"#,
+    )
+    .bind(CLAIM_LEASE_MINUTES)
+    .fetch_optional(pool)
+    .await?;
+    let Some(import) = claimed else {
+        return Ok(false);
+    };
+
+    // The file was read once already when it was accepted
+    let rows = parse_csv(&import.csv_data).unwrap_or_default();
+    let result = run_import(pool, import.user_id, rows, import.dry_run).await;
+    match &result {
+        Ok(report) => info!(
+            "Import {} finished: {} created, {} skipped, {} errors",
+            import.id, report.created, report.skipped, report.errors
+        ),
+        Err(e) => warn!("Import {} failed: {}", import.id, e),
+    }
+    finish(pool, import.id, &result).await?;
+    Ok(true)
+}
+
+pub fn spawn_worker(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WORKER_INTERVAL);
+        loop {
+            interval.tick().await;
+            loop {
+                match process_next(&pool).await {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => {
+                        error!("Property import worker failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// The CSV is the raw request body. Returns the report for small files and
+/// `202 Accepted` with the import to poll for larger ones.
+#[post("/api/properties/import")]
+pub async fn import_properties(
+    user: CurrentUser,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+    if body.len() > MAX_CSV_BYTES {
+        return HttpResponse::PayloadTooLarge().json(serde_json::json!({
+            "error": format!("A CSV file can be at most {} bytes", MAX_CSV_BYTES)
+        }));
+    }
+    let Ok(text) = String::from_utf8(body.to_vec()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "The CSV file must be UTF-8"
+        }));
+    };
+    let rows = match parse_csv(&text) {
+        Ok(rows) if rows.is_empty() => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "The CSV file has no rows"
+            }))
+        }
+        Ok(rows) => rows,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+    let total_rows = rows.len();
+    let inline = total_rows <= INLINE_MAX_ROWS;
+
+    let import_id = match sqlx::query_scalar::<_, Uuid>(
+        r#"INSERT INTO property_imports (user_id, dry_run, status, csv_data, total_rows, started_at)
+        VALUES ($1, $2, $3, $4, $5, CASE WHEN $3 = 'running' THEN NOW() END)
+        RETURNING id"#,
+    )
+    .bind(user.id)
+    .bind(query.dry_run)
+    .bind(if inline { "running" } else { "pending" })
+    .bind(if inline { None } else { Some(&text) })
+    .bind(total_rows as i32)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            error!("Failed to record import for {}: {}", user.id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to import properties"
+            }));
+        }
+    };
+
+    if !inline {
+        info!(
+            "Import {} of {} rows queued for {}",
+            import_id, total_rows, user.id
+        );
+        return HttpResponse::Accepted().json(serde_json::json!({
+            "id": import_id,
+            "status": "pending",
+            "dry_run": query.dry_run,
+            "total_rows": total_rows
+        }));
+    }
+
+    let result = run_import(&state.db, user.id, rows, query.dry_run).await;
+    if let Err(e) = &result {
+        error!("Import {} for {} failed: {}", import_id, user.id, e);
+    }
+    match finish(&state.db, import_id, &result).await {
+        Ok(Some(import)) if result.is_ok() => HttpResponse::Ok().json(import),
+        Ok(_) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to import properties",
+            "id": import_id
+        })),
+        Err(e) => {
+            error!("Failed to record import {}: {}", import_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to import properties"
+            }))
+        }
+    }
+}
+
+#[get("/api/properties/imports/{id}")]
+pub async fn get_import(
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query_as::<_, PropertyImport>(
+        r#"SELECT id, dry_run, status, total_rows, report, error, created_at, finished_at
+        FROM property_imports
+        WHERE id = $1 AND (user_id = $2 OR $3)"#,
+    )
+    .bind(path.into_inner())
+    .bind(user.id)
+    .bind(user.is_admin())
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(import)) => HttpResponse::Ok().json(import),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Import not found"
+        })),
+        Err(e) => {
+            error!("Failed to load import for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load import"
+            }))
+        }
+    }
+}
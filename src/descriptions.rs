@@ -0,0 +1,544 @@
+// JARVIS2026 - Generated descriptions
+// Sellers can ask for a listing description written from what the listing
+// already says about itself: its structured fields and the tags found in
+// its photos. The text comes from a `DescriptionWriter`, today any
+// OpenAI-compatible chat model set up with `DESCRIPTION_LLM_URL`, and is
+// kept as a draft; nothing reaches the listing until the seller accepts one,
+// at which point it goes through the same checks as a typed description.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::account_status;
+use crate::audit;
+use crate::auth::CurrentUser;
+use crate::completeness;
+use crate::embeddings;
+use crate::listing_checks;
+use crate::media_tags::{self, ListingTag};
+use crate::secrets;
+use crate::syndication;
+use crate::{AppState, Property};
+
+const LLM_TIMEOUT: Duration = Duration::from_secs(60);
+/// Generations per listing per day; each one is a paid model call
+const MAX_DRAFTS_PER_DAY: i64 = 10;
+const MAX_DRAFTS_LISTED: i64 = 20;
+const MAX_DESCRIPTION_CHARS: usize = 5000;
+/// Photo tags named in the prompt
+const MAX_PROMPT_TAGS: usize = 15;
+
+const SYSTEM_PROMPT: &str = "You write property listing descriptions for an Indonesian real estate marketplace. Use only the facts you are given; never invent features, distances or prices. Write two or three short paragraphs of plain text, no headings, no markdown, no emoji, in a warm but factual tone. Write in the language the seller asks for.";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// What the writer is told about the listing.
+#[derive(Debug, Clone)]
+pub struct ListingBrief {
+    pub title: String,
+    pub location: String,
+    pub property_type: Option<String>,
+    pub bedrooms: Option<i32>,
+    pub bathrooms: Option<i32>,
+    pub area_sqm: Option<f64>,
+    pub certificate_type: Option<String>,
+    pub verified_aerial: bool,
+    pub photo_tags: Vec<ListingTag>,
+    /// `en` or `id`
+    pub language: &'static str,
+}
+
+pub trait DescriptionWriter: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn write<'a>(&'a self, brief: &'a ListingBrief) -> BoxFuture<'a, Result<String, String>>;
+}
+
+/// Any endpoint speaking the OpenAI `/v1/chat/completions` shape.
+pub struct LlmWriter {
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct GenerateRequest {
+    /// `en` (default) or `id`
+    language: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct DescriptionDraft {
+    id: Uuid,
+    property_id: Uuid,
+    generator: String,
+    text: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    accepted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS description_drafts (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            generator TEXT NOT NULL,
+            text TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            accepted_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_description_drafts_property ON description_drafts(property_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// WRITERS
+// ============================================================================
+
+impl ListingBrief {
+    fn prompt(&self) -> String {
+        let mut facts = vec![
+            format!("Title: {}", self.title),
+            format!("Location: {}", self.location),
+        ];
+        if let Some(property_type) = &self.property_type {
+            facts.push(format!("Type: {}", property_type));
+        }
+        if let Some(bedrooms) = self.bedrooms {
+            facts.push(format!("Bedrooms: {}", bedrooms));
+        }
+        if let Some(bathrooms) = self.bathrooms {
+            facts.push(format!("Bathrooms: {}", bathrooms));
+        }
+        if let Some(area) = self.area_sqm {
+            facts.push(format!("Area: {} m²", area.round()));
+        }
+        if let Some(certificate) = &self.certificate_type {
+            facts.push(format!("Land certificate: {}", certificate));
+        }
+        if self.verified_aerial {
+            facts.push("Has verified drone footage".to_string());
+        }
+        if !self.photo_tags.is_empty() {
+            let tags: Vec<&str> = self
+                .photo_tags
+                .iter()
+                .take(MAX_PROMPT_TAGS)
+                .map(|t| t.tag.as_str())
+                .collect();
+            facts.push(format!("Seen in the photos: {}", tags.join(", ")));
+        }
+        let language = match self.language {
+            "id" => "Indonesian",
+            _ => "English",
+        };
+        format!(
+            "Write the description in {}.\n\n{}",
+            language,
+            facts.join("\n")
+        )
+    }
+}
+
+impl DescriptionWriter for LlmWriter {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    fn write<'a>(&'a self, brief: &'a ListingBrief) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let mut request = self.client.post(&self.url).json(&serde_json::json!({
+                "model": self.model,
+                "temperature": 0.7,
+                "messages": [
+                    { "role": "system", "content": SYSTEM_PROMPT },
+                    { "role": "user", "content": brief.prompt() }
+                ]
+            }));
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let response: ChatResponse = request
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Description request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid description response: {}", e))?;
+            let content = response
+                .choices
+                .into_iter()
+                .next()
+                .ok_or("Description response has no choices")?
+                .message
+                .content;
+            let text = content.trim();
+            if text.is_empty() {
+                return Err("Model returned an empty description".to_string());
+            }
+            Ok(text.chars().take(MAX_DESCRIPTION_CHARS).collect())
+        })
+    }
+}
+
+/// Configured by `DESCRIPTION_LLM_URL`, `DESCRIPTION_LLM_MODEL` and
+/// optionally `DESCRIPTION_LLM_API_KEY`; generation is off otherwise.
+pub fn writer_from_env() -> Option<Box<dyn DescriptionWriter>> {
+    let url = std::env::var("DESCRIPTION_LLM_URL").ok()?;
+    let Ok(model) = std::env::var("DESCRIPTION_LLM_MODEL") else {
+        warn!("DESCRIPTION_LLM_MODEL not set; description generation disabled");
+        return None;
+    };
+    let client = match reqwest::Client::builder().timeout(LLM_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(
+                "Failed to build description client: {}; generation disabled",
+                e
+            );
+            return None;
+        }
+    };
+    info!("Generating listing descriptions with {} via {}", model, url);
+    Some(Box::new(LlmWriter {
+        url,
+        api_key: secrets::var("DESCRIPTION_LLM_API_KEY").ok(),
+        model,
+        client,
+    }))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// The listing, if the caller may edit it; the error response otherwise.
+async fn load_editable(
+    pool: &PgPool,
+    property_id: Uuid,
+    user: &CurrentUser,
+) -> Result<Property, HttpResponse> {
+    match sqlx::query_as::<_, Property>("SELECT * FROM properties WHERE id = $1")
+        .bind(property_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(property)) if property.user_id == Some(user.id) || user.is_admin() => Ok(property),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only manage descriptions of your own listings"
+        }))),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        }))),
+        Err(e) => {
+            error!("Failed to load property {}: {}", property_id, e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load property"
+            })))
+        }
+    }
+}
+
+/// Writes a new draft; the listing's description is left as it is.
+#[post("/api/properties/{id}/generate-description")]
+pub async fn generate_description(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: Option<web::Json<GenerateRequest>>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let req = req.map(web::Json::into_inner).unwrap_or_default();
+    let language = match req.language.as_deref() {
+        None | Some("en") => "en",
+        Some("id") => "id",
+        Some(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "language must be 'en' or 'id'"
+            }))
+        }
+    };
+    let Some(writer) = &state.description_writer else {
+        return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "error": "Description generation is not configured"
+        }));
+    };
+
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+    let property = match load_editable(&state.db, property_id, &user).await {
+        Ok(property) => property,
+        Err(response) => return response,
+    };
+
+    let recent: Result<(i64, Vec<ListingTag>), sqlx::Error> = async {
+        let recent: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM description_drafts WHERE property_id = $1 AND created_at > NOW() - INTERVAL '1 day'",
+        )
+        .bind(property_id)
+        .fetch_one(&state.db)
+        .await?;
+        let tags = media_tags::for_property(&state.db, property_id).await?;
+        Ok((recent, tags))
+    }
+    .await;
+    let photo_tags = match recent {
+        Ok((recent, _)) if recent >= MAX_DRAFTS_PER_DAY => {
+            return HttpResponse::TooManyRequests().json(serde_json::json!({
+                "error": format!(
+                    "At most {} descriptions can be generated per listing per day",
+                    MAX_DRAFTS_PER_DAY
+                )
+            }))
+        }
+        Ok((_, tags)) => tags,
+        Err(e) => {
+            error!("Failed to prepare description for {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate description"
+            }));
+        }
+    };
+
+    let brief = ListingBrief {
+        title: property.title,
+        location: property.location,
+        property_type: property.property_type,
+        bedrooms: property.bedrooms,
+        bathrooms: property.bathrooms,
+        area_sqm: property.area_sqm,
+        certificate_type: property.certificate_type,
+        verified_aerial: property.verified_aerial,
+        photo_tags,
+        language,
+    };
+    let text = match writer.write(&brief).await {
+        Ok(text) => text,
+        Err(e) => {
+            warn!("Description generation for {} failed: {}", property_id, e);
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "The description service failed; try again later"
+            }));
+        }
+    };
+
+    match sqlx::query_as::<_, DescriptionDraft>(
+        r#"INSERT INTO description_drafts (property_id, requested_by, generator, text)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, property_id, generator, text, created_at, accepted_at"#,
+    )
+    .bind(property_id)
+    .bind(user.id)
+    .bind(writer.name())
+    .bind(&text)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(draft) => {
+            info!(
+                "Description draft {} generated for {} by {}",
+                draft.id, property_id, user.id
+            );
+            HttpResponse::Created().json(draft)
+        }
+        Err(e) => {
+            error!(
+                "Failed to store description draft for {}: {}",
+                property_id, e
+            );
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate description"
+            }))
+        }
+    }
+}
+
+#[get("/api/properties/{id}/description-drafts")]
+pub async fn list_drafts(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    if let Err(response) = load_editable(&state.db, property_id, &user).await {
+        return response;
+    }
+
+    match sqlx::query_as::<_, DescriptionDraft>(
+        r#"SELECT id, property_id, generator, text, created_at, accepted_at
+        FROM description_drafts
+        WHERE property_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2"#,
+    )
+    .bind(property_id)
+    .bind(MAX_DRAFTS_LISTED)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(drafts) => HttpResponse::Ok().json(serde_json::json!({
+            "property_id": property_id,
+            "drafts": drafts
+        })),
+        Err(e) => {
+            error!(
+                "Failed to list description drafts for {}: {}",
+                property_id, e
+            );
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list description drafts"
+            }))
+        }
+    }
+}
+
+/// Makes the draft the listing's description.
+#[post("/api/properties/{id}/description-drafts/{draft_id}/accept")]
+pub async fn accept_draft(
+    path: web::Path<(Uuid, Uuid)>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (property_id, draft_id) = path.into_inner();
+
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+    let property = match load_editable(&state.db, property_id, &user).await {
+        Ok(property) => property,
+        Err(response) => return response,
+    };
+    let draft = match sqlx::query_as::<_, DescriptionDraft>(
+        r#"SELECT id, property_id, generator, text, created_at, accepted_at
+        FROM description_drafts WHERE id = $1 AND property_id = $2"#,
+    )
+    .bind(draft_id)
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(draft)) => draft,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Description draft not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load description draft {}: {}", draft_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to accept description"
+            }));
+        }
+    };
+
+    // Held to the same rules as a description the seller typed
+    let checked = listing_checks::Draft {
+        location: &property.location,
+        property_type: property.property_type.as_deref(),
+        price: property.price,
+        description: &draft.text,
+        files: &[],
+    };
+    match listing_checks::run(&state.db, &checked).await {
+        Ok(mut review) => {
+            review.issues.retain(|issue| issue.field() == "description");
+            if !review.issues.is_empty() {
+                return listing_checks::rejected_response(&review);
+            }
+        }
+        Err(e) => {
+            error!("Failed to run listing checks for {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to accept description"
+            }));
+        }
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("UPDATE properties SET description = $2 WHERE id = $1")
+            .bind(property_id)
+            .bind(&draft.text)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE description_drafts SET accepted_at = NOW() WHERE id = $1")
+            .bind(draft_id)
+            .execute(&mut *tx)
+            .await?;
+        completeness::refresh(&mut *tx, property_id).await?;
+        syndication::requeue(&mut *tx, property_id).await?;
+        audit::record(
+            &mut tx,
+            user.id,
+            "property.description_generated",
+            "property",
+            property_id,
+            serde_json::json!({ "draft_id": draft_id, "generator": draft.generator }),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            state.property_cache.invalidate(property_id).await;
+            embeddings::spawn_refresh(state.clone(), property_id);
+            info!(
+                "Description draft {} accepted for {} by {}",
+                draft_id, property_id, user.id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "draft_id": draft_id,
+                "description": draft.text
+            }))
+        }
+        Err(e) => {
+            error!("Failed to accept description draft {}: {}", draft_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to accept description"
+            }))
+        }
+    }
+}
@@ -0,0 +1,710 @@
+// JARVIS2026 - Private documents
+// KYC papers and proof of ownership (certificates, deeds) are encrypted
+// before they touch disk. Each file gets its own AES-256-GCM key, which is
+// stored only in wrapped form: sealed by a master key behind `KeyWrapper`,
+// so the master key can live in the environment today and in a KMS later
+// without touching stored files. Downloads go through short-lived signed
+// URLs; the file is decrypted on the way out and never written in clear.
+
+use actix_web::{delete, get, http::header, post, web, HttpRequest, HttpResponse, Responder};
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, Nonce};
+use futures_util::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tokio::fs as async_fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::CurrentUser;
+use crate::geoip;
+use crate::secrets;
+use crate::AppState;
+
+const DOCUMENTS_DIR: &str = "documents";
+const MAX_DOCUMENT_BYTES: usize = 20 * 1024 * 1024;
+const NONCE_LEN: usize = 12;
+const DEFAULT_URL_TTL_SECS: i64 = 300;
+const MAX_URL_TTL_SECS: i64 = 3600;
+const MAX_FILENAME_CHARS: usize = 120;
+/// Extensions accepted, with the type served back
+const CONTENT_TYPES: &[(&str, &str)] = &[
+    ("pdf", "application/pdf"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    /// Identity papers of the uploader
+    Kyc,
+    /// Certificate or deed of one of the uploader's listings
+    Ownership,
+}
+
+/// Seals per-file keys. Implementations must be able to unwrap keys sealed
+/// under any `key_id` they have handed out.
+pub trait KeyWrapper: Send + Sync {
+    /// Identifies the master key that `wrap` uses now
+    fn key_id(&self) -> &str;
+
+    fn wrap<'a>(&'a self, key: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, String>>;
+
+    fn unwrap<'a>(
+        &'a self,
+        key_id: &'a str,
+        wrapped: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, String>>;
+}
+
+/// A 256-bit master key from `DOCUMENTS_MASTER_KEY` (64 hex characters).
+pub struct EnvMasterKey {
+    key_id: String,
+    cipher: Aes256Gcm,
+}
+
+#[derive(Deserialize)]
+pub struct UploadDocumentQuery {
+    kind: DocumentKind,
+    filename: String,
+    property_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct DocumentUrlRequest {
+    ttl_secs: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct SignedQuery {
+    expires: i64,
+    sig: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Document {
+    id: Uuid,
+    owner_user_id: Uuid,
+    property_id: Option<Uuid>,
+    kind: String,
+    filename: String,
+    content_type: String,
+    bytes: i64,
+    /// Of the plaintext, so an owner can check what they uploaded
+    sha256: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A freshly encrypted file and the key that opens it
+struct Sealed {
+    key: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredDocument {
+    owner_user_id: Uuid,
+    filename: String,
+    content_type: String,
+    storage_path: String,
+    key_id: String,
+    wrapped_key: Vec<u8>,
+    nonce: Vec<u8>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS private_documents (
+            id UUID PRIMARY KEY,
+            owner_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            property_id UUID REFERENCES properties(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL CHECK (kind IN ('kyc', 'ownership')),
+            filename TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            bytes BIGINT NOT NULL,
+            sha256 TEXT NOT NULL,
+            storage_path TEXT NOT NULL,
+            key_id TEXT NOT NULL,
+            wrapped_key BYTEA NOT NULL,
+            nonce BYTEA NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_private_documents_owner ON private_documents(owner_user_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// KEYS
+// ============================================================================
+
+impl EnvMasterKey {
+    fn from_hex(raw: &str) -> Result<Self, String> {
+        let bytes = hex::decode(raw.trim()).map_err(|e| e.to_string())?;
+        if bytes.len() != 32 {
+            return Err(format!("expected 32 bytes, got {}", bytes.len()));
+        }
+        // Names the key without revealing it, so rotated keys can coexist
+        let key_id = format!("env:{}", &hex::encode(Sha256::digest(&bytes))[..16]);
+        Ok(EnvMasterKey {
+            key_id,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)),
+        })
+    }
+}
+
+impl KeyWrapper for EnvMasterKey {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// `nonce || ciphertext`
+    fn wrap<'a>(&'a self, key: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(async move {
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let sealed = self
+                .cipher
+                .encrypt(&nonce, key)
+                .map_err(|_| "Failed to wrap document key".to_string())?;
+            Ok([nonce.as_slice(), &sealed].concat())
+        })
+    }
+
+    fn unwrap<'a>(
+        &'a self,
+        key_id: &'a str,
+        wrapped: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(async move {
+            if key_id != self.key_id {
+                return Err(format!("Master key {} is not available", key_id));
+            }
+            if wrapped.len() <= NONCE_LEN {
+                return Err("Wrapped document key is truncated".to_string());
+            }
+            let (nonce, sealed) = wrapped.split_at(NONCE_LEN);
+            self.cipher
+                .decrypt(Nonce::from_slice(nonce), sealed)
+                .map_err(|_| "Failed to unwrap document key".to_string())
+        })
+    }
+}
+
+/// `None` when `DOCUMENTS_MASTER_KEY` is unset or invalid; document uploads
+/// are refused rather than stored unencrypted.
+pub fn key_wrapper_from_env() -> Option<Box<dyn KeyWrapper>> {
+    let raw = secrets::var("DOCUMENTS_MASTER_KEY").ok()?;
+    match EnvMasterKey::from_hex(&raw) {
+        Ok(key) => {
+            info!("Private documents sealed with master key {}", key.key_id);
+            Some(Box::new(key))
+        }
+        Err(e) => {
+            warn!(
+                "Invalid DOCUMENTS_MASTER_KEY ({}); document uploads disabled",
+                e
+            );
+            None
+        }
+    }
+}
+
+// ============================================================================
+// ENCRYPTION
+// ============================================================================
+
+/// Encrypts under a fresh key; the document id is bound in as associated
+/// data so a ciphertext can't be swapped onto another record.
+fn seal(document_id: Uuid, plaintext: &[u8]) -> Result<Sealed, String> {
+    let key = Aes256Gcm::generate_key(OsRng);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(&key)
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: document_id.as_bytes(),
+            },
+        )
+        .map_err(|_| "Failed to encrypt document".to_string())?;
+    Ok(Sealed {
+        key: key.to_vec(),
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
+}
+
+fn open(document_id: Uuid, key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if key.len() != 32 || nonce.len() != NONCE_LEN {
+        return Err("Document key material is malformed".to_string());
+    }
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: document_id.as_bytes(),
+            },
+        )
+        .map_err(|_| "Document failed authentication".to_string())
+}
+
+// ============================================================================
+// SIGNED URLS
+// ============================================================================
+
+/// From `DOCUMENT_URL_SIGNING_KEY`; without it a key is made per process,
+/// so outstanding links stop working on restart.
+fn signing_key() -> &'static str {
+    static KEY: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    KEY.get_or_init(|| {
+        secrets::var("DOCUMENT_URL_SIGNING_KEY")
+            .unwrap_or_else(|_| format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()))
+    })
+}
+
+fn url_mac(document_id: Uuid, expires: i64) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(signing_key().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}:{}", document_id, expires).as_bytes());
+    mac
+}
+
+fn signed_path(document_id: Uuid, expires: i64) -> String {
+    let sig = hex::encode(url_mac(document_id, expires).finalize().into_bytes());
+    format!(
+        "/api/documents/{}/content?expires={}&sig={}",
+        document_id, expires, sig
+    )
+}
+
+fn verify_url(document_id: Uuid, query: &SignedQuery) -> bool {
+    if query.expires < chrono::Utc::now().timestamp() {
+        return false;
+    }
+    let Ok(sig) = hex::decode(&query.sig) else {
+        return false;
+    };
+    url_mac(document_id, query.expires)
+        .verify_slice(&sig)
+        .is_ok()
+}
+
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
+fn content_type(filename: &str) -> Option<&'static str> {
+    let extension = filename.rsplit_once('.')?.1.to_lowercase();
+    CONTENT_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, content_type)| *content_type)
+}
+
+/// Safe for a `Content-Disposition` header.
+fn clean_filename(filename: &str) -> String {
+    filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILENAME_CHARS)
+        .collect()
+}
+
+/// Storage paths of every document belonging to the user or their listings,
+/// for cleanup when the account goes.
+pub async fn delete_for_user(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        r#"DELETE FROM private_documents
+        WHERE owner_user_id = $1 OR property_id IN (SELECT id FROM properties WHERE user_id = $1)
+        RETURNING storage_path"#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+}
+
+fn unavailable() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(serde_json::json!({
+        "error": "Private document storage is not configured"
+    }))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// The raw file is the request body. Ownership documents must name one of
+/// the caller's listings.
+#[post("/api/documents")]
+pub async fn upload_document(
+    user: CurrentUser,
+    query: web::Query<UploadDocumentQuery>,
+    body: web::Bytes,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let Some(keys) = state.document_keys.as_deref() else {
+        return unavailable();
+    };
+    let filename = clean_filename(&query.filename);
+    let Some(content_type) = content_type(&filename) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Documents must be PDF, JPEG or PNG files"
+        }));
+    };
+    if body.is_empty() || body.len() > MAX_DOCUMENT_BYTES {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Documents must be between 1 byte and {} MiB", MAX_DOCUMENT_BYTES / (1024 * 1024))
+        }));
+    }
+    let property_id = match (query.kind, query.property_id) {
+        (DocumentKind::Ownership, None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Ownership documents need a property_id"
+            }))
+        }
+        (_, property_id) => property_id,
+    };
+    if let Some(property_id) = property_id {
+        match sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM properties WHERE id = $1")
+            .bind(property_id)
+            .fetch_optional(&state.db)
+            .await
+        {
+            Ok(Some(owner)) if owner == Some(user.id) => {}
+            Ok(Some(_)) => {
+                return HttpResponse::Forbidden().json(serde_json::json!({
+                    "error": "You can only attach documents to your own listings"
+                }))
+            }
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Property not found"
+                }))
+            }
+            Err(e) => {
+                error!("Failed to load owner of {}: {}", property_id, e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to store document"
+                }));
+            }
+        }
+    }
+
+    let document_id = Uuid::new_v4();
+    let sha256 = hex::encode(Sha256::digest(&body));
+    let bytes = body.len() as i64;
+    let sealed = match web::block(move || seal(document_id, &body)).await {
+        Ok(Ok(sealed)) => sealed,
+        Ok(Err(e)) => {
+            error!("Failed to encrypt document {}: {}", document_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store document"
+            }));
+        }
+        Err(e) => {
+            error!("Document encryption task failed: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store document"
+            }));
+        }
+    };
+    let wrapped_key = match keys.wrap(&sealed.key).await {
+        Ok(wrapped) => wrapped,
+        Err(e) => {
+            error!("Failed to wrap key of document {}: {}", document_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store document"
+            }));
+        }
+    };
+
+    let storage_path = format!("{}/{}.enc", DOCUMENTS_DIR, document_id);
+    async_fs::create_dir_all(DOCUMENTS_DIR).await.ok();
+    if let Err(e) = async_fs::write(&storage_path, &sealed.ciphertext).await {
+        error!("Failed to write document {}: {}", storage_path, e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to store document"
+        }));
+    }
+
+    let result: Result<Document, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let document = sqlx::query_as::<_, Document>(
+            r#"INSERT INTO private_documents
+            (id, owner_user_id, property_id, kind, filename, content_type, bytes, sha256,
+             storage_path, key_id, wrapped_key, nonce)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING id, owner_user_id, property_id, kind, filename, content_type, bytes,
+                      sha256, created_at"#,
+        )
+        .bind(document_id)
+        .bind(user.id)
+        .bind(property_id)
+        .bind(match query.kind {
+            DocumentKind::Kyc => "kyc",
+            DocumentKind::Ownership => "ownership",
+        })
+        .bind(&filename)
+        .bind(content_type)
+        .bind(bytes)
+        .bind(&sha256)
+        .bind(&storage_path)
+        .bind(keys.key_id())
+        .bind(&wrapped_key)
+        .bind(&sealed.nonce)
+        .fetch_one(&mut *tx)
+        .await?;
+        audit::record(
+            &mut tx,
+            user.id,
+            "document.upload",
+            "document",
+            document_id,
+            serde_json::json!({ "kind": document.kind, "property_id": property_id }),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(document)
+    }
+    .await;
+
+    match result {
+        Ok(document) => {
+            info!(
+                "User {} stored {} document {}",
+                user.id, document.kind, document_id
+            );
+            HttpResponse::Created().json(document)
+        }
+        Err(e) => {
+            async_fs::remove_file(&storage_path).await.ok();
+            error!("Failed to record document {}: {}", document_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to store document"
+            }))
+        }
+    }
+}
+
+#[get("/api/me/documents")]
+pub async fn my_documents(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, Document>(
+        r#"SELECT id, owner_user_id, property_id, kind, filename, content_type, bytes, sha256,
+                  created_at
+        FROM private_documents WHERE owner_user_id = $1
+        ORDER BY created_at DESC"#,
+    )
+    .bind(user.id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(documents) => HttpResponse::Ok().json(documents),
+        Err(e) => {
+            error!("Failed to list documents of {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list documents"
+            }))
+        }
+    }
+}
+
+/// A link the owner (or an admin reviewing KYC) can open in a browser until
+/// it expires.
+#[post("/api/documents/{id}/url")]
+pub async fn document_url(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: Option<web::Json<DocumentUrlRequest>>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let document_id = path.into_inner();
+    let ttl = req
+        .and_then(|r| r.ttl_secs)
+        .unwrap_or(DEFAULT_URL_TTL_SECS)
+        .clamp(1, MAX_URL_TTL_SECS);
+
+    match sqlx::query_scalar::<_, Uuid>("SELECT owner_user_id FROM private_documents WHERE id = $1")
+        .bind(document_id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(owner)) if owner == user.id || user.is_admin() => {
+            let expires = chrono::Utc::now().timestamp() + ttl;
+            if owner != user.id {
+                info!("Admin {} opened document {}", user.id, document_id);
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "url": format!("{}{}", state.public_base_url, signed_path(document_id, expires)),
+                "expires_at": chrono::DateTime::from_timestamp(expires, 0)
+            }))
+        }
+        Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Document not found"
+        })),
+        Err(e) => {
+            error!("Failed to load document {}: {}", document_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to sign document URL"
+            }))
+        }
+    }
+}
+
+/// Serves the decrypted file to holders of a valid signed URL.
+#[get("/api/documents/{id}/content")]
+pub async fn document_content(
+    path: web::Path<Uuid>,
+    query: web::Query<SignedQuery>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let document_id = path.into_inner();
+    if !verify_url(document_id, &query) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Invalid or expired link"
+        }));
+    }
+    let Some(keys) = state.document_keys.as_deref() else {
+        return unavailable();
+    };
+
+    let stored = match sqlx::query_as::<_, StoredDocument>(
+        r#"SELECT owner_user_id, filename, content_type, storage_path, key_id, wrapped_key, nonce
+        FROM private_documents WHERE id = $1"#,
+    )
+    .bind(document_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(stored)) => stored,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Document not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load document {}: {}", document_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load document"
+            }));
+        }
+    };
+
+    let plaintext = async {
+        let ciphertext = async_fs::read(&stored.storage_path)
+            .await
+            .map_err(|e| format!("unreadable file {}: {}", stored.storage_path, e))?;
+        let key = keys.unwrap(&stored.key_id, &stored.wrapped_key).await?;
+        let nonce = stored.nonce.clone();
+        web::block(move || open(document_id, &key, &nonce, &ciphertext))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+    .await;
+
+    match plaintext {
+        Ok(plaintext) => {
+            info!(
+                "Document {} of {} downloaded from {:?}",
+                document_id,
+                stored.owner_user_id,
+                geoip::client_ip(&req)
+            );
+            HttpResponse::Ok()
+                .content_type(stored.content_type)
+                .insert_header((
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", stored.filename),
+                ))
+                .insert_header((header::CACHE_CONTROL, "private, no-store"))
+                .body(plaintext)
+        }
+        Err(e) => {
+            error!("Failed to decrypt document {}: {}", document_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load document"
+            }))
+        }
+    }
+}
+
+#[delete("/api/documents/{id}")]
+pub async fn delete_document(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let document_id = path.into_inner();
+
+    let result: Result<Option<String>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let removed = sqlx::query_scalar::<_, String>(
+            "DELETE FROM private_documents WHERE id = $1 AND owner_user_id = $2 RETURNING storage_path",
+        )
+        .bind(document_id)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if removed.is_some() {
+            audit::record(
+                &mut tx,
+                user.id,
+                "document.delete",
+                "document",
+                document_id,
+                serde_json::json!({}),
+            )
+            .await?;
+            tx.commit().await?;
+        }
+        Ok(removed)
+    }
+    .await;
+
+    match result {
+        Ok(Some(storage_path)) => {
+            if let Err(e) = async_fs::remove_file(&storage_path).await {
+                warn!("Failed to remove document file {}: {}", storage_path, e);
+            }
+            HttpResponse::NoContent().finish()
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Document not found"
+        })),
+        Err(e) => {
+            error!("Failed to delete document {}: {}", document_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete document"
+            }))
+        }
+    }
+}
@@ -0,0 +1,251 @@
+// JARVIS2026 - Email verification
+// Registering with an email sends a one-time link to that address; following
+// it stamps `users.email_verified_at`. Unverified users can browse, but
+// uploading (and with it earning tokens) needs a verified email or a wallet
+// proven through Sign-In with Ethereum.
+
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::agencies;
+use crate::auth::CurrentUser;
+use crate::sessions;
+use crate::AppState;
+
+const TOKEN_TTL_HOURS: i32 = 24;
+/// Minimum gap between two verification mails to one user.
+const RESEND_COOLDOWN_SECS: f64 = 60.0;
+const EXPIRY_SWEEP_INTERVAL_SECS: u64 = 3600;
+
+/// SQL condition on `users` for accounts allowed to upload and earn tokens.
+pub const VERIFIED_USER_CONDITION: &str =
+    "(email_verified_at IS NOT NULL OR wallet_verified_at IS NOT NULL)";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    token: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS email_verification_tokens (
+            token_hash TEXT PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            email TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_email_verification_user ON email_verification_tokens (user_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// VERIFICATION
+// ============================================================================
+
+pub async fn is_verified(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(&format!(
+        "SELECT {} FROM users WHERE id = $1",
+        VERIFIED_USER_CONDITION
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map(|verified| verified.unwrap_or(false))
+}
+
+pub fn unverified_response() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Verify your email address before uploading"
+    }))
+}
+
+/// Issues a fresh token for `email` and mails the verification link.
+pub async fn send_verification(state: &AppState, user_id: Uuid, email: &str) -> Result<(), String> {
+    let token = sessions::new_token();
+    sqlx::query(
+        r#"INSERT INTO email_verification_tokens (token_hash, user_id, email, expires_at)
+        VALUES ($1, $2, $3, NOW() + make_interval(hours => $4))"#,
+    )
+    .bind(sessions::hash_token(&token))
+    .bind(user_id)
+    .bind(email)
+    .bind(TOKEN_TTL_HOURS)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store verification token: {}", e))?;
+
+    let link = format!("{}/?verify_email={}", state.public_base_url, token);
+    let body = format!(
+        "Confirm your email address for JARVIS2026 by opening this link:\n\n{}\n\n\
+         The link expires in {} hours. If you did not sign up, ignore this message.",
+        link, TOKEN_TTL_HOURS
+    );
+    let body = agencies::with_footer(&state.db, user_id, body).await;
+    state
+        .mailer
+        .send(email, "Confirm your email address", &body)
+        .await
+}
+
+/// Periodically drops tokens past their expiry.
+pub fn spawn_expiry(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(EXPIRY_SWEEP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match sqlx::query("DELETE FROM email_verification_tokens WHERE expires_at <= NOW()")
+                .execute(&pool)
+                .await
+            {
+                Ok(result) if result.rows_affected() > 0 => {
+                    info!(
+                        "Removed {} expired email verification tokens",
+                        result.rows_affected()
+                    )
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to expire email verification tokens: {}", e),
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/auth/verify-email")]
+pub async fn verify_email(
+    req: web::Json<VerifyEmailRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let token_hash = sessions::hash_token(req.token.trim());
+
+    let outcome: Result<Option<Uuid>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        // The token only counts while it still matches the account's email
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            r#"DELETE FROM email_verification_tokens t
+            USING credentials c
+            WHERE t.token_hash = $1 AND t.expires_at > NOW()
+              AND c.user_id = t.user_id AND LOWER(c.email) = LOWER(t.email)
+            RETURNING t.user_id"#,
+        )
+        .bind(&token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(user_id) = user_id {
+            sqlx::query(
+                "UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1",
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM email_verification_tokens WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(user_id)
+    }
+    .await;
+
+    match outcome {
+        Ok(Some(user_id)) => {
+            info!("Email verified for user {}", user_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "user_id": user_id,
+                "email_verified": true
+            }))
+        }
+        Ok(None) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Verification link is invalid or has expired"
+        })),
+        Err(e) => {
+            error!("Failed to verify email: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify email"
+            }))
+        }
+    }
+}
+
+#[post("/api/auth/verify-email/resend")]
+pub async fn resend_verification(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    let pending = sqlx::query_as::<_, (String, bool, bool)>(
+        r#"SELECT c.email, u.email_verified_at IS NOT NULL,
+                  EXISTS (SELECT 1 FROM email_verification_tokens t
+                          WHERE t.user_id = u.id
+                            AND t.created_at > NOW() - make_interval(secs => $2))
+        FROM users u JOIN credentials c ON c.user_id = u.id
+        WHERE u.id = $1"#,
+    )
+    .bind(user.id)
+    .bind(RESEND_COOLDOWN_SECS)
+    .fetch_optional(&state.db)
+    .await;
+
+    let email = match pending {
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "No email address on this account"
+            }))
+        }
+        Ok(Some((_, true, _))) => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Email address is already verified"
+            }))
+        }
+        Ok(Some((_, _, true))) => {
+            return HttpResponse::TooManyRequests().json(serde_json::json!({
+                "error": "A verification email was sent recently; try again shortly"
+            }))
+        }
+        Ok(Some((email, false, false))) => email,
+        Err(e) => {
+            error!("Failed to load email for {}: {}", user.id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to send verification email"
+            }));
+        }
+    };
+
+    match send_verification(&state, user.id, &email).await {
+        Ok(()) => HttpResponse::Accepted().json(serde_json::json!({ "sent": true })),
+        Err(e) => {
+            warn!("Verification email for {} failed: {}", user.id, e);
+            HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "Failed to send verification email"
+            }))
+        }
+    }
+}
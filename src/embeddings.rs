@@ -0,0 +1,408 @@
+// JARVIS2026 - Semantic search
+// Listing titles and descriptions are turned into vectors and stored in a
+// pgvector column, so `/api/search/semantic` can match "bright family home
+// near good schools" by meaning rather than shared words. Vectors come from
+// an `EmbeddingProvider`: any OpenAI-compatible embeddings endpoint when
+// `EMBEDDINGS_URL` is set, otherwise a local hashing embedder that keeps the
+// pipeline working (lexically) without an external service. New and edited
+// listings are embedded in the background; the `embeddings` backfill covers
+// everything else, including feed imports.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::formatting::{Locale, PriceDisplay};
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::scraping;
+use crate::secrets;
+use crate::{AppState, Property, PropertyView};
+
+/// Fixed by the column type; HTTP providers are asked for vectors this size.
+pub const EMBEDDING_DIMENSIONS: usize = 384;
+const EMBED_TIMEOUT: Duration = Duration::from_secs(20);
+/// Long descriptions add little beyond their opening paragraphs.
+const MAX_SOURCE_CHARS: usize = 4000;
+const MIN_QUERY_CHARS: usize = 3;
+const MAX_QUERY_CHARS: usize = 500;
+const DEFAULT_SEMANTIC_LIMIT: i64 = 20;
+const MAX_SEMANTIC_LIMIT: i64 = 100;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+pub trait EmbeddingProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// One vector of `EMBEDDING_DIMENSIONS` per input, in order.
+    fn embed<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>, String>>;
+}
+
+/// Any endpoint speaking the OpenAI `/v1/embeddings` shape.
+pub struct HttpEmbeddings {
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    client: reqwest::Client,
+}
+
+/// Signed feature hashing of words and word pairs. Only catches shared
+/// vocabulary, but needs no model or network.
+pub struct HashingEmbeddings;
+
+#[derive(Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    dimensions: usize,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+pub struct SemanticQuery {
+    q: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    /// `next_cursor` of the previous page, see `scraping`
+    cursor: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SemanticRow {
+    #[sqlx(flatten)]
+    property: Property,
+    similarity: f64,
+}
+
+#[derive(Serialize)]
+struct SemanticListing {
+    #[serde(flatten)]
+    view: PropertyView,
+    /// Cosine similarity to the query, 1.0 being identical in meaning
+    similarity: f64,
+}
+
+#[derive(Serialize)]
+struct SemanticResponse {
+    query: String,
+    provider: String,
+    results: Vec<SemanticListing>,
+    limit: i64,
+    offset: i64,
+    /// Set when opaque cursors are on and there may be another page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("CREATE EXTENSION IF NOT EXISTS vector")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(&format!(
+        "ALTER TABLE properties ADD COLUMN IF NOT EXISTS embedding vector({})",
+        EMBEDDING_DIMENSIONS
+    ))
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_properties_embedding ON properties USING hnsw (embedding vector_cosine_ops)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// PROVIDERS
+// ============================================================================
+
+impl EmbeddingProvider for HttpEmbeddings {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>, String>> {
+        Box::pin(async move {
+            let mut request = self.client.post(&self.url).json(&EmbeddingsRequest {
+                model: &self.model,
+                input: texts,
+                dimensions: EMBEDDING_DIMENSIONS,
+            });
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let mut response: EmbeddingsResponse = request
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Embeddings request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid embeddings response: {}", e))?;
+
+            response.data.sort_by_key(|d| d.index);
+            if response.data.len() != texts.len() {
+                return Err(format!(
+                    "Embeddings response has {} vectors for {} inputs",
+                    response.data.len(),
+                    texts.len()
+                ));
+            }
+            response
+                .data
+                .into_iter()
+                .map(|d| {
+                    if d.embedding.len() == EMBEDDING_DIMENSIONS {
+                        Ok(d.embedding)
+                    } else {
+                        Err(format!(
+                            "Embedding has {} dimensions, expected {}",
+                            d.embedding.len(),
+                            EMBEDDING_DIMENSIONS
+                        ))
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+impl HashingEmbeddings {
+    fn embed_one(text: &str) -> Vec<f32> {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let mut vector = vec![0f32; EMBEDDING_DIMENSIONS];
+        let pairs = words.windows(2).map(|w| format!("{} {}", w[0], w[1]));
+        for feature in words.iter().cloned().chain(pairs) {
+            let hash = fnv1a(feature.as_bytes());
+            let slot = (hash % EMBEDDING_DIMENSIONS as u64) as usize;
+            vector[slot] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+impl EmbeddingProvider for HashingEmbeddings {
+    fn name(&self) -> &str {
+        "hashing"
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>, String>> {
+        Box::pin(async move { Ok(texts.iter().map(|t| Self::embed_one(t)).collect()) })
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Configured by `EMBEDDINGS_URL`, `EMBEDDINGS_MODEL` and optionally
+/// `EMBEDDINGS_API_KEY`. Switching providers leaves old vectors
+/// incomparable with new ones; clear the column and rerun the backfill.
+pub fn provider_from_env() -> Box<dyn EmbeddingProvider> {
+    let Ok(url) = std::env::var("EMBEDDINGS_URL") else {
+        return Box::new(HashingEmbeddings);
+    };
+    let Ok(model) = std::env::var("EMBEDDINGS_MODEL") else {
+        warn!("EMBEDDINGS_MODEL not set; using the hashing embedder");
+        return Box::new(HashingEmbeddings);
+    };
+    let client = match reqwest::Client::builder().timeout(EMBED_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(
+                "Failed to build embeddings client: {}; using the hashing embedder",
+                e
+            );
+            return Box::new(HashingEmbeddings);
+        }
+    };
+    info!("Embedding listings with {} via {}", model, url);
+    Box::new(HttpEmbeddings {
+        url,
+        api_key: secrets::var("EMBEDDINGS_API_KEY").ok(),
+        model,
+        client,
+    })
+}
+
+// ============================================================================
+// PIPELINE
+// ============================================================================
+
+/// pgvector's text form, bound as `$n::vector`.
+fn to_pgvector(vector: &[f32]) -> String {
+    let values: Vec<String> = vector.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn source_text(title: &str, description: &str) -> String {
+    format!("{}\n\n{}", title.trim(), description.trim())
+        .chars()
+        .take(MAX_SOURCE_CHARS)
+        .collect()
+}
+
+/// Embeds a listing's current title and description.
+pub async fn refresh(state: &AppState, property_id: Uuid) -> Result<(), String> {
+    let Some((title, description)) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT title, description FROM properties WHERE id = $1",
+    )
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+
+    let texts = [source_text(&title, description.as_deref().unwrap_or(""))];
+    let vector = state
+        .embedder
+        .embed(&texts)
+        .await?
+        .pop()
+        .ok_or("Provider returned no embedding")?;
+    sqlx::query("UPDATE properties SET embedding = $2::vector WHERE id = $1")
+        .bind(property_id)
+        .bind(to_pgvector(&vector))
+        .execute(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Embeds in the background so uploads and edits don't wait on the provider.
+/// A failure leaves the old (or no) vector for the backfill to pick up.
+pub fn spawn_refresh(state: web::Data<AppState>, property_id: Uuid) {
+    tokio::spawn(async move {
+        if let Err(e) = refresh(&state, property_id).await {
+            warn!("Failed to embed property {}: {}", property_id, e);
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Active listings closest in meaning to a free-text description.
+#[get("/api/search/semantic")]
+pub async fn search_semantic(
+    query: web::Query<SemanticQuery>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let q = query.q.trim();
+    let chars = q.chars().count();
+    if !(MIN_QUERY_CHARS..=MAX_QUERY_CHARS).contains(&chars) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!(
+                "q must be between {} and {} characters",
+                MIN_QUERY_CHARS, MAX_QUERY_CHARS
+            )
+        }));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEMANTIC_LIMIT)
+        .clamp(1, MAX_SEMANTIC_LIMIT);
+    let offset = match scraping::resolve_offset(&state, q, query.cursor.as_deref(), query.offset) {
+        Ok(offset) => offset,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+        }
+    };
+
+    let texts = [q.to_string()];
+    let vector = match state.embedder.embed(&texts).await.map(|mut v| v.pop()) {
+        Ok(Some(vector)) => vector,
+        Ok(None) => {
+            error!("Embedding provider returned nothing for a search query");
+            return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "Semantic search is unavailable"
+            }));
+        }
+        Err(e) => {
+            error!("Failed to embed search query: {}", e);
+            return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "Semantic search is unavailable"
+            }));
+        }
+    };
+
+    // `<=>` on the HNSW index walks listings from the nearest vector outward
+    let rows = sqlx::query_as::<_, SemanticRow>(&format!(
+        r#"SELECT p.*, (1 - (p.embedding <=> $1::vector))::float8 AS similarity
+        FROM properties p
+        WHERE p.embedding IS NOT NULL AND {} AND {}
+        ORDER BY p.embedding <=> $1::vector
+        LIMIT $2 OFFSET $3"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(to_pgvector(&vector))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await;
+
+    match rows {
+        Ok(rows) => HttpResponse::Ok().json(SemanticResponse {
+            next_cursor: (rows.len() as i64 == limit)
+                .then(|| scraping::next_cursor(&state, q, offset + limit))
+                .flatten(),
+            query: q.to_string(),
+            provider: state.embedder.name().to_string(),
+            results: rows
+                .into_iter()
+                .map(|row| SemanticListing {
+                    view: PropertyView {
+                        price_display: PriceDisplay::new(row.property.price, locale),
+                        property: row.property,
+                    },
+                    similarity: row.similarity,
+                })
+                .collect(),
+            limit,
+            offset,
+        }),
+        Err(e) => {
+            error!("Semantic search failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Search failed"
+            }))
+        }
+    }
+}
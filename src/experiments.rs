@@ -0,0 +1,343 @@
+// JARVIS2026 - A/B experiment assignment and exposure logging
+// Buckets are derived from a hash of (experiment key, subject), so the same
+// user or visitor always lands in the same variant without storing assignments.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json, PgPool};
+use std::collections::BTreeMap;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::AppState;
+
+const VISITOR_HEADER: &str = "X-Visitor-Id";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Variant {
+    name: String,
+    weight: u32,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Experiment {
+    id: Uuid,
+    key: String,
+    description: Option<String>,
+    variants: Json<Vec<Variant>>,
+    status: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+struct CreateExperimentRequest {
+    key: String,
+    description: Option<String>,
+    variants: Vec<Variant>,
+}
+
+#[derive(Deserialize)]
+struct SubjectQuery {
+    user_id: Option<Uuid>,
+    visitor_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AssignmentsResponse {
+    subject: String,
+    assignments: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ExposureRequest {
+    experiment_key: String,
+    user_id: Option<Uuid>,
+    visitor_id: Option<String>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct VariantExposure {
+    variant: String,
+    exposures: i64,
+    subjects: i64,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS experiments (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            key TEXT UNIQUE NOT NULL,
+            description TEXT,
+            variants JSONB NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            created_at TIMESTAMPTZ DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS experiment_exposures (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            experiment_id UUID NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+            subject TEXT NOT NULL,
+            variant TEXT NOT NULL,
+            exposed_at TIMESTAMPTZ DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_experiment_exposures_experiment ON experiment_exposures(experiment_id, variant)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// ASSIGNMENT
+// ============================================================================
+
+/// Subjects are namespaced so a visitor id can never collide with a user id.
+fn subject_key(user_id: Option<Uuid>, visitor_id: Option<&str>) -> Option<String> {
+    match (user_id, visitor_id.map(str::trim)) {
+        (Some(id), _) => Some(format!("user:{}", id)),
+        (None, Some(v)) if !v.is_empty() && v.len() <= 128 => Some(format!("visitor:{}", v)),
+        _ => None,
+    }
+}
+
+fn bucket_variant(experiment_key: &str, subject: &str, variants: &[Variant]) -> Option<String> {
+    let total: u64 = variants.iter().map(|v| v.weight as u64).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(experiment_key.as_bytes());
+    hasher.update(b":");
+    hasher.update(subject.as_bytes());
+    let digest = hasher.finalize();
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    let mut point = u64::from_be_bytes(prefix) % total;
+
+    for variant in variants {
+        if point < variant.weight as u64 {
+            return Some(variant.name.clone());
+        }
+        point -= variant.weight as u64;
+    }
+    None
+}
+
+fn visitor_from_request(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(VISITOR_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/experiments")]
+pub async fn create_experiment(
+    _admin: AdminUser,
+    req: web::Json<CreateExperimentRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let key = req.key.trim();
+    let valid_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_key {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "key must be non-empty and use letters, digits, '-' or '_'"
+        }));
+    }
+    if req.variants.len() < 2 || req.variants.iter().all(|v| v.weight == 0) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "at least two variants with a non-zero total weight are required"
+        }));
+    }
+
+    match sqlx::query_as::<_, Experiment>(
+        "INSERT INTO experiments (key, description, variants) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(key)
+    .bind(&req.description)
+    .bind(Json(&req.variants))
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(experiment) => {
+            info!("Experiment created: {}", experiment.key);
+            HttpResponse::Ok().json(experiment)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict()
+            .json(serde_json::json!({"error": "Experiment key already exists"})),
+        Err(e) => {
+            error!("Failed to create experiment: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create experiment"
+            }))
+        }
+    }
+}
+
+#[get("/api/experiments/assignments")]
+pub async fn get_assignments(
+    req: HttpRequest,
+    query: web::Query<SubjectQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let visitor = query
+        .visitor_id
+        .clone()
+        .or_else(|| visitor_from_request(&req));
+    let Some(subject) = subject_key(query.user_id, visitor.as_deref()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "user_id or visitor_id required"
+        }));
+    };
+
+    let experiments =
+        match sqlx::query_as::<_, Experiment>("SELECT * FROM experiments WHERE status = 'running'")
+            .fetch_all(&state.db)
+            .await
+        {
+            Ok(experiments) => experiments,
+            Err(e) => {
+                error!("Failed to fetch experiments: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to fetch assignments"
+                }));
+            }
+        };
+
+    let assignments = experiments
+        .iter()
+        .filter_map(|e| {
+            bucket_variant(&e.key, &subject, &e.variants).map(|variant| (e.key.clone(), variant))
+        })
+        .collect();
+
+    HttpResponse::Ok().json(AssignmentsResponse {
+        subject,
+        assignments,
+    })
+}
+
+#[post("/api/experiments/exposures")]
+pub async fn log_exposure(
+    http_req: HttpRequest,
+    req: web::Json<ExposureRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let visitor = req
+        .visitor_id
+        .clone()
+        .or_else(|| visitor_from_request(&http_req));
+    let Some(subject) = subject_key(req.user_id, visitor.as_deref()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "user_id or visitor_id required"
+        }));
+    };
+
+    let experiment = match sqlx::query_as::<_, Experiment>(
+        "SELECT * FROM experiments WHERE key = $1 AND status = 'running'",
+    )
+    .bind(&req.experiment_key)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(experiment)) => experiment,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Experiment not found or not running"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to fetch experiment: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to log exposure"
+            }));
+        }
+    };
+
+    // The variant is recomputed rather than trusted from the client
+    let Some(variant) = bucket_variant(&experiment.key, &subject, &experiment.variants) else {
+        return HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "error": "Experiment has no assignable variants"
+        }));
+    };
+
+    match sqlx::query(
+        "INSERT INTO experiment_exposures (experiment_id, subject, variant) VALUES ($1, $2, $3)",
+    )
+    .bind(experiment.id)
+    .bind(&subject)
+    .bind(&variant)
+    .execute(&state.db)
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "experiment_key": experiment.key,
+            "variant": variant
+        })),
+        Err(e) => {
+            error!("Failed to log exposure: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to log exposure"
+            }))
+        }
+    }
+}
+
+#[get("/api/experiments/{key}/results")]
+pub async fn experiment_results(
+    _admin: AdminUser,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let key = path.into_inner();
+
+    match sqlx::query_as::<_, VariantExposure>(
+        r#"SELECT x.variant, COUNT(*) AS exposures, COUNT(DISTINCT x.subject) AS subjects
+        FROM experiment_exposures x
+        JOIN experiments e ON e.id = x.experiment_id
+        WHERE e.key = $1
+        GROUP BY x.variant ORDER BY x.variant"#,
+    )
+    .bind(&key)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(variants) => HttpResponse::Ok().json(serde_json::json!({
+            "experiment_key": key,
+            "variants": variants
+        })),
+        Err(e) => {
+            error!("Failed to fetch experiment results: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch experiment results"
+            }))
+        }
+    }
+}
@@ -0,0 +1,246 @@
+// JARVIS2026 - Query plan audit
+// Runs EXPLAIN on the queries the busiest endpoints issue, built from the
+// same conditions and filter rendering they use, against the live schema.
+// A sequential scan over a large table usually means a filter was added
+// without an index. `--explain-audit` runs the audit and exits non-zero on
+// findings, for CI or a deploy check; `EXPLAIN_AUDIT=warn|fail` runs it at
+// startup and logs, or refuses to start.
+
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::filters::FilterSet;
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+
+const CLI_FLAG: &str = "--explain-audit";
+/// Tables the planner estimates below this many rows may be scanned freely
+const DEFAULT_MIN_ROWS: f64 = 10_000.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditMode {
+    Off,
+    Warn,
+    Fail,
+}
+
+/// A query as an endpoint issues it, with representative bind values,
+/// prefixed with `EXPLAIN`.
+struct CanonicalQuery {
+    name: &'static str,
+    sql: QueryBuilder<'static, Postgres>,
+}
+
+#[derive(Debug)]
+struct SeqScan {
+    table: String,
+    estimated_rows: f64,
+}
+
+// ============================================================================
+// CONFIGURATION
+// ============================================================================
+
+impl AuditMode {
+    pub fn from_env() -> Self {
+        match std::env::var("EXPLAIN_AUDIT").as_deref() {
+            Ok("warn") => AuditMode::Warn,
+            Ok("fail") => AuditMode::Fail,
+            Ok("off") | Err(_) => AuditMode::Off,
+            Ok(other) => {
+                warn!("Unknown EXPLAIN_AUDIT '{}', expected warn or fail", other);
+                AuditMode::Off
+            }
+        }
+    }
+}
+
+/// Whether the process was started to run the audit and exit.
+pub fn requested_from_cli() -> bool {
+    std::env::args().skip(1).any(|arg| arg == CLI_FLAG)
+}
+
+/// Query names from `EXPLAIN_AUDIT_IGNORE`, for scans accepted on purpose.
+fn ignored() -> Vec<String> {
+    std::env::var("EXPLAIN_AUDIT_IGNORE")
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn min_rows() -> f64 {
+    std::env::var("EXPLAIN_AUDIT_MIN_ROWS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v: &f64| *v > 0.0)
+        .unwrap_or(DEFAULT_MIN_ROWS)
+}
+
+// ============================================================================
+// CANONICAL QUERIES
+// ============================================================================
+
+fn explained(sql: &str) -> QueryBuilder<'static, Postgres> {
+    QueryBuilder::new(format!("EXPLAIN (FORMAT JSON) {}", sql))
+}
+
+fn listing_query(name: &'static str, search: &str) -> CanonicalQuery {
+    let mut sql = explained("SELECT * FROM properties WHERE ");
+    sql.push(PUBLIC_LISTING_CONDITION);
+    sql.push(" AND ");
+    sql.push(ACTIVE_LISTING_CONDITION);
+    if let Ok(filters) = FilterSet::parse_query(search) {
+        filters.push_and(&mut sql);
+    }
+    sql.push(" ORDER BY created_at DESC LIMIT 50");
+    CanonicalQuery { name, sql }
+}
+
+fn keyed_query(name: &'static str, sql: &str, key: Uuid) -> CanonicalQuery {
+    let mut builder = explained(sql);
+    builder.push_bind(key);
+    CanonicalQuery { name, sql: builder }
+}
+
+fn canonical_queries() -> Vec<CanonicalQuery> {
+    let id = Uuid::nil();
+    let mut queries = vec![
+        listing_query("listings", ""),
+        listing_query("listings_by_price_and_rooms", "price<=2b beds>=3"),
+        listing_query("listings_by_type", "type=villa"),
+        listing_query("listings_by_photo_tag", "has:pool"),
+        listing_query("listings_by_amenity", "amenity:pool"),
+        keyed_query(
+            "property_detail",
+            "SELECT * FROM properties WHERE id = ",
+            id,
+        ),
+        keyed_query(
+            "property_media",
+            "SELECT * FROM media_uploads WHERE property_id = ",
+            id,
+        ),
+        keyed_query(
+            "owner_listings",
+            "SELECT id FROM properties WHERE user_id = ",
+            id,
+        ),
+        keyed_query(
+            "notification_inbox",
+            "SELECT * FROM notifications WHERE user_id = ",
+            id,
+        ),
+        keyed_query(
+            "token_statement",
+            "SELECT * FROM token_transactions WHERE user_id = ",
+            id,
+        ),
+    ];
+
+    let mut duplicate_check = explained("SELECT COUNT(*) FROM media_uploads WHERE content_hash = ");
+    duplicate_check.push_bind("0".repeat(64));
+    queries.push(CanonicalQuery {
+        name: "media_duplicate_check",
+        sql: duplicate_check,
+    });
+
+    let mut by_slug = explained("SELECT id FROM properties WHERE slug = ");
+    by_slug.push_bind("canonical-listing-00000000");
+    queries.push(CanonicalQuery {
+        name: "property_by_slug",
+        sql: by_slug,
+    });
+
+    queries
+}
+
+// ============================================================================
+// AUDIT
+// ============================================================================
+
+/// Sequential scans anywhere in a JSON plan node and its children.
+fn collect_seq_scans(node: &Value, scans: &mut Vec<SeqScan>) {
+    if node["Node Type"] == "Seq Scan" {
+        if let Some(table) = node["Relation Name"].as_str() {
+            scans.push(SeqScan {
+                table: table.to_string(),
+                estimated_rows: 0.0,
+            });
+        }
+    }
+    for child in node["Plans"].as_array().into_iter().flatten() {
+        collect_seq_scans(child, scans);
+    }
+}
+
+async fn explain(pool: &PgPool, query: &mut CanonicalQuery) -> Result<Vec<SeqScan>, sqlx::Error> {
+    let plan: Value = query
+        .sql
+        .build_query_scalar::<Value>()
+        .fetch_one(pool)
+        .await?;
+
+    let mut scans = Vec::new();
+    collect_seq_scans(&plan[0]["Plan"], &mut scans);
+    for scan in &mut scans {
+        scan.estimated_rows = sqlx::query_scalar::<_, f32>(
+            "SELECT reltuples FROM pg_class WHERE oid = to_regclass($1)",
+        )
+        .bind(&scan.table)
+        .fetch_optional(pool)
+        .await?
+        .map_or(0.0, f64::from);
+    }
+    Ok(scans)
+}
+
+/// Explains every canonical query; true when none scans a large table.
+pub async fn run(pool: &PgPool) -> bool {
+    let min_rows = min_rows();
+    let ignored = ignored();
+    let mut queries = canonical_queries();
+    let mut findings = 0;
+
+    for query in &mut queries {
+        if ignored.iter().any(|name| name == query.name) {
+            continue;
+        }
+        match explain(pool, query).await {
+            Ok(scans) => {
+                for scan in scans.iter().filter(|s| s.estimated_rows >= min_rows) {
+                    findings += 1;
+                    warn!(
+                        "Explain audit: '{}' scans {} sequentially (~{} rows)",
+                        query.name, scan.table, scan.estimated_rows as i64
+                    );
+                }
+            }
+            Err(e) => {
+                findings += 1;
+                error!(
+                    "Explain audit: '{}' could not be explained: {}",
+                    query.name, e
+                );
+            }
+        }
+    }
+
+    if findings == 0 {
+        info!(
+            "Explain audit passed: {} queries, no sequential scans over {} rows",
+            queries.len(),
+            min_rows as i64
+        );
+    } else {
+        warn!("Explain audit found {} problems", findings);
+    }
+    findings == 0
+}
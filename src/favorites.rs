@@ -0,0 +1,266 @@
+// JARVIS2026 - Favorites
+// Buyers shortlist listings with a heart. Saving and unsaving are idempotent,
+// and `/api/me/favorites` lists the shortlist newest first. A favorited
+// listing that later sells or is archived stays on the list with its status;
+// one that is taken down or moved back to draft drops out until it returns.
+
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::formatting::{Locale, PriceDisplay};
+use crate::listing_status::PUBLISHED_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct FavoritesQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct FavoriteRow {
+    id: Uuid,
+    title: String,
+    location: String,
+    price: f64,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    property_type: Option<String>,
+    image_thumb_webp: String,
+    status: String,
+    saved_at: chrono::DateTime<chrono::Utc>,
+    total: i64,
+}
+
+#[derive(Serialize)]
+struct FavoriteListing {
+    id: Uuid,
+    title: String,
+    location: String,
+    price: f64,
+    price_display: PriceDisplay,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    property_type: Option<String>,
+    image_thumb_webp: String,
+    status: String,
+    saved_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize)]
+struct FavoritesResponse {
+    favorites: Vec<FavoriteListing>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS favorites (
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (user_id, property_id)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_favorites_user ON favorites(user_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_favorites_property ON favorites(property_id)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
+/// How many buyers have saved a listing.
+async fn favorite_count(pool: &PgPool, property_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM favorites WHERE property_id = $1")
+        .bind(property_id)
+        .fetch_one(pool)
+        .await
+}
+
+impl FavoriteRow {
+    fn into_listing(self, locale: Locale) -> FavoriteListing {
+        FavoriteListing {
+            price_display: PriceDisplay::new(self.price, locale),
+            id: self.id,
+            title: self.title,
+            location: self.location,
+            price: self.price,
+            bedrooms: self.bedrooms,
+            bathrooms: self.bathrooms,
+            area_sqm: self.area_sqm,
+            property_type: self.property_type,
+            image_thumb_webp: self.image_thumb_webp,
+            status: self.status,
+            saved_at: self.saved_at,
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/properties/{id}/favorite")]
+pub async fn add_favorite(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    let result: Result<bool, sqlx::Error> = async {
+        let visible = sqlx::query_scalar::<_, bool>(&format!(
+            "SELECT EXISTS(SELECT 1 FROM properties WHERE id = $1 AND {} AND {})",
+            PUBLIC_LISTING_CONDITION, PUBLISHED_LISTING_CONDITION
+        ))
+        .bind(property_id)
+        .fetch_one(&state.db)
+        .await?;
+        if !visible {
+            return Ok(false);
+        }
+        sqlx::query(
+            r#"INSERT INTO favorites (user_id, property_id) VALUES ($1, $2)
+            ON CONFLICT (user_id, property_id) DO NOTHING"#,
+        )
+        .bind(user.id)
+        .bind(property_id)
+        .execute(&state.db)
+        .await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            info!("User {} saved property {}", user.id, property_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "favorited": true,
+                "favorite_count": favorite_count(&state.db, property_id).await.unwrap_or(0)
+            }))
+        }
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Err(e) => {
+            error!("Failed to save favorite {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to save favorite"
+            }))
+        }
+    }
+}
+
+/// Unsaving a listing that isn't saved is not an error.
+#[delete("/api/properties/{id}/favorite")]
+pub async fn remove_favorite(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    match sqlx::query("DELETE FROM favorites WHERE user_id = $1 AND property_id = $2")
+        .bind(user.id)
+        .bind(property_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "property_id": property_id,
+            "favorited": false,
+            "favorite_count": favorite_count(&state.db, property_id).await.unwrap_or(0)
+        })),
+        Err(e) => {
+            error!("Failed to remove favorite {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to remove favorite"
+            }))
+        }
+    }
+}
+
+#[get("/api/me/favorites")]
+pub async fn my_favorites(
+    user: CurrentUser,
+    query: web::Query<FavoritesQuery>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, FavoriteRow>(&format!(
+        r#"SELECT p.id, p.title, p.location, p.price, p.bedrooms, p.bathrooms, p.area_sqm,
+                  p.property_type, p.image_thumb_webp, p.status,
+                  f.created_at AS saved_at, COUNT(*) OVER () AS total
+        FROM favorites f
+        JOIN properties p ON p.id = f.property_id
+        WHERE f.user_id = $1 AND {} AND {}
+        ORDER BY f.created_at DESC
+        LIMIT $2 OFFSET $3"#,
+        PUBLIC_LISTING_CONDITION, PUBLISHED_LISTING_CONDITION
+    ))
+    .bind(user.id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(rows) => {
+            let total = rows.first().map_or(0, |row| row.total);
+            HttpResponse::Ok().json(FavoritesResponse {
+                favorites: rows
+                    .into_iter()
+                    .map(|row| row.into_listing(locale))
+                    .collect(),
+                total,
+                limit,
+                offset,
+            })
+        }
+        Err(e) => {
+            error!("Failed to load favorites of {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load favorites"
+            }))
+        }
+    }
+}
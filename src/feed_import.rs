@@ -0,0 +1,722 @@
+// JARVIS2026 - External listing feed import
+// Periodically pulls agency/portal exports (XML or JSON), maps their fields
+// onto properties, and skips listings we already have. Items go through the
+// same checks as uploaded listings and arrive as drafts for their owner to
+// publish. Every run is recorded with its counts so admins can spot broken
+// feeds.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json, PgPool};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::completeness;
+use crate::embeddings;
+use crate::filters::parse_number;
+use crate::listing_checks;
+use crate::listing_status::ListingStatus;
+use crate::timezones;
+use crate::upload_policy;
+use crate::AppState;
+
+const SCHEDULER_INTERVAL_SECS: u64 = 300;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_FEED_BYTES: usize = 50 * 1024 * 1024;
+const MIN_INTERVAL_MINUTES: i32 = 15;
+const DEFAULT_INTERVAL_MINUTES: i32 = 360;
+/// A run that has not finished after this long is assumed to have crashed.
+const STALE_RUN_MINUTES: i32 = 60;
+
+/// Property fields a feed can populate; `external_id`, `title`, `location`
+/// and `price` are required for an item to be imported.
+const MAPPABLE_FIELDS: &[&str] = &[
+    "external_id",
+    "title",
+    "location",
+    "price",
+    "description",
+    "bedrooms",
+    "bathrooms",
+    "area_sqm",
+    "latitude",
+    "longitude",
+    "property_type",
+    "certificate_type",
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FeedFormat {
+    Xml,
+    Json,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ImportFeed {
+    id: Uuid,
+    name: String,
+    url: String,
+    format: String,
+    item_path: String,
+    field_map: Json<HashMap<String, String>>,
+    owner_user_id: Option<Uuid>,
+    interval_minutes: i32,
+    enabled: bool,
+    last_run_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateFeedRequest {
+    name: String,
+    url: String,
+    format: FeedFormat,
+    /// XML: element name of each listing. JSON: dotted path to the listing array
+    /// (empty when the document itself is the array).
+    item_path: String,
+    /// Property field -> source field. Unmapped fields use the property field name.
+    /// XML sources may name an attribute as `@attr`.
+    #[serde(default)]
+    field_map: HashMap<String, String>,
+    owner_user_id: Option<Uuid>,
+    interval_minutes: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize, sqlx::FromRow)]
+struct RunStats {
+    fetched: i32,
+    created: i32,
+    duplicates: i32,
+    invalid: i32,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ImportRun {
+    id: Uuid,
+    started_at: chrono::DateTime<chrono::Utc>,
+    finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    stats: RunStats,
+    error: Option<String>,
+}
+
+/// One feed item after field mapping, before validation.
+type RawItem = HashMap<&'static str, String>;
+
+struct ImportedListing {
+    external_id: String,
+    title: String,
+    location: String,
+    price: f64,
+    description: Option<String>,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    property_type: Option<String>,
+    certificate_type: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS import_feeds (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            format TEXT NOT NULL,
+            item_path TEXT NOT NULL,
+            field_map JSONB NOT NULL DEFAULT '{}'::jsonb,
+            owner_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            interval_minutes INTEGER NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT true,
+            last_run_at TIMESTAMPTZ,
+            running_since TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS import_runs (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            feed_id UUID NOT NULL REFERENCES import_feeds(id) ON DELETE CASCADE,
+            started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            finished_at TIMESTAMPTZ,
+            fetched INTEGER NOT NULL DEFAULT 0,
+            created INTEGER NOT NULL DEFAULT 0,
+            duplicates INTEGER NOT NULL DEFAULT 0,
+            invalid INTEGER NOT NULL DEFAULT 0,
+            error TEXT
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS import_feed_id UUID")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS external_id TEXT")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS idx_properties_external
+        ON properties(import_feed_id, external_id) WHERE import_feed_id IS NOT NULL"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_properties_content_hash ON properties(content_hash)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// PARSING
+// ============================================================================
+
+impl FeedFormat {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "xml" => Some(FeedFormat::Xml),
+            "json" => Some(FeedFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+fn source_field<'a>(field_map: &'a HashMap<String, String>, field: &'a str) -> &'a str {
+    field_map.get(field).map(String::as_str).unwrap_or(field)
+}
+
+fn parse_xml_items(
+    body: &str,
+    item_path: &str,
+    field_map: &HashMap<String, String>,
+) -> Result<Vec<RawItem>, String> {
+    let doc = roxmltree::Document::parse(body).map_err(|e| format!("Invalid XML: {}", e))?;
+
+    let items = doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == item_path)
+        .map(|node| {
+            let mut item = RawItem::new();
+            for field in MAPPABLE_FIELDS {
+                let source = source_field(field_map, field);
+                let value = match source.strip_prefix('@') {
+                    Some(attr) => node.attribute(attr).map(str::to_string),
+                    None => node
+                        .children()
+                        .find(|c| c.is_element() && c.tag_name().name() == source)
+                        .map(|c| c.text().unwrap_or_default().to_string()),
+                };
+                if let Some(value) = value {
+                    item.insert(field, value);
+                }
+            }
+            item
+        })
+        .collect();
+
+    Ok(items)
+}
+
+fn parse_json_items(
+    body: &str,
+    item_path: &str,
+    field_map: &HashMap<String, String>,
+) -> Result<Vec<RawItem>, String> {
+    let doc: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let mut node = &doc;
+    for key in item_path.split('.').filter(|k| !k.is_empty()) {
+        node = node
+            .get(key)
+            .ok_or_else(|| format!("item_path segment '{}' not found", key))?;
+    }
+    let array = node
+        .as_array()
+        .ok_or_else(|| "item_path does not point to an array".to_string())?;
+
+    let items = array
+        .iter()
+        .map(|entry| {
+            let mut item = RawItem::new();
+            for field in MAPPABLE_FIELDS {
+                let source = source_field(field_map, field);
+                let value = source.split('.').try_fold(entry, |node, key| node.get(key));
+                let value = match value {
+                    Some(serde_json::Value::String(s)) => Some(s.clone()),
+                    Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+                    _ => None,
+                };
+                if let Some(value) = value {
+                    item.insert(field, value);
+                }
+            }
+            item
+        })
+        .collect();
+
+    Ok(items)
+}
+
+fn map_item(mut raw: RawItem) -> Option<ImportedListing> {
+    let mut text = |field: &str| {
+        raw.remove(field)
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let external_id = text("external_id")?;
+    let title = text("title")?;
+    let location = text("location")?;
+    let price = parse_number(&text("price")?).filter(|p| *p > 0.0)?;
+
+    let description = text("description");
+    let bedrooms = text("bedrooms").and_then(|v| v.parse().ok());
+    let bathrooms = text("bathrooms").and_then(|v| v.parse().ok());
+    let area_sqm = text("area_sqm").and_then(|v| v.parse().ok());
+    let latitude = text("latitude")
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|v| (-90.0..=90.0).contains(v));
+    let longitude = text("longitude")
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|v| (-180.0..=180.0).contains(v));
+    let property_type = text("property_type").map(|v| v.to_lowercase());
+    let certificate_type = text("certificate_type").map(|v| v.to_uppercase());
+
+    Some(ImportedListing {
+        external_id,
+        title,
+        location,
+        price,
+        description,
+        bedrooms,
+        bathrooms,
+        area_sqm,
+        latitude,
+        longitude,
+        property_type,
+        certificate_type,
+    })
+}
+
+/// Fingerprint used to spot the same listing arriving from several sources.
+fn listing_hash(listing: &ImportedListing) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(listing.title.trim().to_lowercase().as_bytes());
+    hasher.update(b"|");
+    hasher.update(listing.location.trim().to_lowercase().as_bytes());
+    hasher.update(b"|");
+    hasher.update((listing.price.round() as i64).to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// ============================================================================
+// IMPORT
+// ============================================================================
+
+async fn fetch_feed(url: &str) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Fetch failed: {}", e))?;
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Fetch failed: {}", e))?;
+    if body.len() > MAX_FEED_BYTES {
+        return Err(format!("Feed exceeds {} bytes", MAX_FEED_BYTES));
+    }
+    String::from_utf8(body.to_vec()).map_err(|_| "Feed is not valid UTF-8".to_string())
+}
+
+/// Inserts the listing unless it matches an earlier import from this feed,
+/// an existing content hash, or an existing listing with the same title and address.
+/// What became of one feed item.
+enum Imported {
+    Created,
+    Duplicate,
+    /// Failed the checks an uploaded listing must pass
+    Rejected,
+}
+
+async fn import_listing(
+    state: &web::Data<AppState>,
+    feed: &ImportFeed,
+    listing: &ImportedListing,
+) -> Result<Imported, sqlx::Error> {
+    let pool = &state.db;
+    let content_hash = listing_hash(listing);
+
+    let exists = sqlx::query_scalar::<_, i64>(
+        r#"SELECT COUNT(*) FROM properties
+        WHERE (import_feed_id = $1 AND external_id = $2)
+           OR content_hash = $3
+           OR (LOWER(TRIM(title)) = LOWER($4) AND LOWER(TRIM(location)) = LOWER($5))"#,
+    )
+    .bind(feed.id)
+    .bind(&listing.external_id)
+    .bind(&content_hash)
+    .bind(&listing.title)
+    .bind(&listing.location)
+    .fetch_one(pool)
+    .await?;
+    if exists > 0 {
+        return Ok(Imported::Duplicate);
+    }
+
+    let draft = listing_checks::Draft {
+        location: &listing.location,
+        property_type: listing.property_type.as_deref(),
+        price: listing.price,
+        description: listing.description.as_deref().unwrap_or(""),
+        files: &[],
+    };
+    let review = listing_checks::run(pool, &draft).await?;
+    if !review.issues.is_empty() {
+        return Ok(Imported::Rejected);
+    }
+
+    let moderation_status = match feed.owner_user_id {
+        Some(owner) => upload_policy::for_user(pool, owner).await?,
+        None => upload_policy::UploadPolicy::default(),
+    }
+    .moderation
+    .initial_status();
+
+    // Imported listings start as drafts, like spreadsheet imports
+    let property_id = Uuid::new_v4();
+    let created = sqlx::query_scalar::<_, Uuid>(
+        r#"INSERT INTO properties
+        (id, title, location, price, description, bedrooms, bathrooms, area_sqm, latitude, longitude,
+         property_type, certificate_type, timezone, user_id, content_hash, import_feed_id, external_id,
+         moderation_status, status, slug)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+        ON CONFLICT DO NOTHING
+        RETURNING id"#,
+    )
+    .bind(property_id)
+    .bind(&listing.title)
+    .bind(&listing.location)
+    .bind(listing.price)
+    .bind(&listing.description)
+    .bind(listing.bedrooms)
+    .bind(listing.bathrooms)
+    .bind(listing.area_sqm)
+    .bind(listing.latitude)
+    .bind(listing.longitude)
+    .bind(&listing.property_type)
+    .bind(&listing.certificate_type)
+    .bind(timezones::for_coordinates(listing.latitude, listing.longitude).name())
+    .bind(feed.owner_user_id)
+    .bind(&content_hash)
+    .bind(feed.id)
+    .bind(&listing.external_id)
+    .bind(moderation_status)
+    .bind(ListingStatus::Draft.as_str())
+    .bind(crate::listing_slug(&listing.title, property_id))
+    .fetch_optional(pool)
+    .await?;
+    if created.is_none() {
+        return Ok(Imported::Duplicate);
+    }
+
+    if let Some(outlier) = &review.price_outlier {
+        listing_checks::flag_price(pool, property_id, outlier).await?;
+    }
+    completeness::refresh(pool, property_id).await?;
+    embeddings::spawn_refresh(state.clone(), property_id);
+    Ok(Imported::Created)
+}
+
+async fn import_items(state: &web::Data<AppState>, feed: &ImportFeed) -> Result<RunStats, String> {
+    let format = FeedFormat::parse(&feed.format)
+        .ok_or_else(|| format!("Unknown feed format '{}'", feed.format))?;
+    let body = fetch_feed(&feed.url).await?;
+    let items = match format {
+        FeedFormat::Xml => parse_xml_items(&body, &feed.item_path, &feed.field_map)?,
+        FeedFormat::Json => parse_json_items(&body, &feed.item_path, &feed.field_map)?,
+    };
+
+    let mut stats = RunStats {
+        fetched: items.len() as i32,
+        ..RunStats::default()
+    };
+    for item in items {
+        let Some(listing) = map_item(item) else {
+            stats.invalid += 1;
+            continue;
+        };
+        match import_listing(state, feed, &listing).await {
+            Ok(Imported::Created) => stats.created += 1,
+            Ok(Imported::Duplicate) => stats.duplicates += 1,
+            Ok(Imported::Rejected) => stats.invalid += 1,
+            Err(e) => return Err(format!("Database error: {}", e)),
+        }
+    }
+    Ok(stats)
+}
+
+/// Runs one feed and records the outcome. Returns `None` when the feed is
+/// unknown or another run of it is still in progress.
+async fn run_feed(
+    state: &web::Data<AppState>,
+    feed_id: Uuid,
+) -> Result<Option<ImportRun>, sqlx::Error> {
+    let pool = &state.db;
+    let feed = sqlx::query_as::<_, ImportFeed>(
+        r#"UPDATE import_feeds SET running_since = NOW()
+        WHERE id = $1
+          AND (running_since IS NULL OR running_since < NOW() - make_interval(mins => $2))
+        RETURNING id, name, url, format, item_path, field_map, owner_user_id,
+                  interval_minutes, enabled, last_run_at"#,
+    )
+    .bind(feed_id)
+    .bind(STALE_RUN_MINUTES)
+    .fetch_optional(pool)
+    .await?;
+    let Some(feed) = feed else {
+        return Ok(None);
+    };
+
+    let run_id: Uuid =
+        sqlx::query_scalar("INSERT INTO import_runs (feed_id) VALUES ($1) RETURNING id")
+            .bind(feed.id)
+            .fetch_one(pool)
+            .await?;
+
+    let (stats, failure) = match import_items(state, &feed).await {
+        Ok(stats) => (stats, None),
+        Err(message) => (RunStats::default(), Some(message)),
+    };
+
+    match &failure {
+        None => info!(
+            "Feed '{}' imported: {} fetched, {} created, {} duplicates, {} invalid",
+            feed.name, stats.fetched, stats.created, stats.duplicates, stats.invalid
+        ),
+        Some(message) => warn!("Feed '{}' import failed: {}", feed.name, message),
+    }
+
+    let run = sqlx::query_as::<_, ImportRun>(
+        r#"UPDATE import_runs
+        SET finished_at = NOW(), fetched = $2, created = $3, duplicates = $4, invalid = $5, error = $6
+        WHERE id = $1
+        RETURNING id, started_at, finished_at, fetched, created, duplicates, invalid, error"#,
+    )
+    .bind(run_id)
+    .bind(stats.fetched)
+    .bind(stats.created)
+    .bind(stats.duplicates)
+    .bind(stats.invalid)
+    .bind(&failure)
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query("UPDATE import_feeds SET running_since = NULL, last_run_at = NOW() WHERE id = $1")
+        .bind(feed.id)
+        .execute(pool)
+        .await?;
+
+    Ok(Some(run))
+}
+
+pub fn spawn_scheduler(state: web::Data<AppState>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let due = sqlx::query_scalar::<_, Uuid>(
+                r#"SELECT id FROM import_feeds
+                WHERE enabled
+                  AND (last_run_at IS NULL
+                       OR last_run_at < NOW() - make_interval(mins => interval_minutes))"#,
+            )
+            .fetch_all(&state.db)
+            .await;
+
+            match due {
+                Ok(feed_ids) => {
+                    for feed_id in feed_ids {
+                        if let Err(e) = run_feed(&state, feed_id).await {
+                            error!("Feed import {} failed: {}", feed_id, e);
+                        }
+                    }
+                }
+                Err(e) => error!("Failed to load due import feeds: {}", e),
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/admin/feeds")]
+pub async fn create_feed(
+    _admin: AdminUser,
+    req: web::Json<CreateFeedRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if req.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "name is required"
+        }));
+    }
+    if !(req.url.starts_with("https://") || req.url.starts_with("http://")) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "url must be http(s)"
+        }));
+    }
+    if req.format == FeedFormat::Xml && req.item_path.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "item_path is required for XML feeds"
+        }));
+    }
+    if let Some(field) = req
+        .field_map
+        .keys()
+        .find(|f| !MAPPABLE_FIELDS.contains(&f.as_str()))
+    {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unknown field '{}'; mappable fields: {}", field, MAPPABLE_FIELDS.join(", "))
+        }));
+    }
+
+    let interval_minutes = req
+        .interval_minutes
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+        .max(MIN_INTERVAL_MINUTES);
+    let format = match req.format {
+        FeedFormat::Xml => "xml",
+        FeedFormat::Json => "json",
+    };
+
+    match sqlx::query_as::<_, ImportFeed>(
+        r#"INSERT INTO import_feeds (name, url, format, item_path, field_map, owner_user_id, interval_minutes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, name, url, format, item_path, field_map, owner_user_id,
+                  interval_minutes, enabled, last_run_at"#,
+    )
+    .bind(req.name.trim())
+    .bind(&req.url)
+    .bind(format)
+    .bind(req.item_path.trim())
+    .bind(Json(&req.field_map))
+    .bind(req.owner_user_id)
+    .bind(interval_minutes)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(feed) => {
+            info!("Import feed '{}' created", feed.name);
+            HttpResponse::Ok().json(feed)
+        }
+        Err(e) => {
+            error!("Failed to create import feed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create import feed"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/feeds")]
+pub async fn list_feeds(_admin: AdminUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, ImportFeed>(
+        r#"SELECT id, name, url, format, item_path, field_map, owner_user_id,
+                  interval_minutes, enabled, last_run_at
+        FROM import_feeds ORDER BY name"#,
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(feeds) => HttpResponse::Ok().json(feeds),
+        Err(e) => {
+            error!("Failed to fetch import feeds: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch import feeds"
+            }))
+        }
+    }
+}
+
+#[post("/api/admin/feeds/{id}/run")]
+pub async fn run_feed_now(
+    _admin: AdminUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let feed_id = path.into_inner();
+
+    match run_feed(&state, feed_id).await {
+        Ok(Some(run)) => HttpResponse::Ok().json(run),
+        Ok(None) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Feed not found or already running"
+        })),
+        Err(e) => {
+            error!("Feed import {} failed: {}", feed_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Feed import failed"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/feeds/{id}/runs")]
+pub async fn feed_runs(
+    _admin: AdminUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query_as::<_, ImportRun>(
+        r#"SELECT id, started_at, finished_at, fetched, created, duplicates, invalid, error
+        FROM import_runs WHERE feed_id = $1
+        ORDER BY started_at DESC LIMIT 50"#,
+    )
+    .bind(path.into_inner())
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(runs) => HttpResponse::Ok().json(runs),
+        Err(e) => {
+            error!("Failed to fetch import runs: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch import runs"
+            }))
+        }
+    }
+}
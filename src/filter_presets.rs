@@ -0,0 +1,184 @@
+// JARVIS2026 - Saved filter presets
+// Named filter combinations a user can re-apply on any device. Unlike saved
+// searches they never trigger alerts.
+
+use actix_web::{delete, get, put, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, PgPool};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::filters::FilterSet;
+use crate::AppState;
+
+const MAX_PRESETS_PER_USER: i64 = 50;
+const MAX_PRESET_NAME_LEN: usize = 64;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct FilterPreset {
+    id: Uuid,
+    name: String,
+    filters: Json<serde_json::Value>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+struct SavePresetRequest {
+    /// Free-form filter state; a `query` key holds search syntax and is validated.
+    filters: serde_json::Value,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS filter_presets (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            filters JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            UNIQUE (user_id, name)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
+fn validate_filters(filters: &serde_json::Value) -> Result<(), String> {
+    let object = filters
+        .as_object()
+        .ok_or_else(|| "filters must be a JSON object".to_string())?;
+    if let Some(query) = object.get("query") {
+        let query = query
+            .as_str()
+            .ok_or_else(|| "filters.query must be a string".to_string())?;
+        FilterSet::parse_query(query)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/users/me/filters")]
+pub async fn list_filter_presets(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, FilterPreset>(
+        r#"SELECT id, name, filters, created_at, updated_at FROM filter_presets
+        WHERE user_id = $1 ORDER BY updated_at DESC"#,
+    )
+    .bind(user.id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(presets) => HttpResponse::Ok().json(presets),
+        Err(e) => {
+            error!("Failed to fetch filter presets for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch filter presets"
+            }))
+        }
+    }
+}
+
+/// Creates or replaces the preset with this name, so devices sync by name.
+#[put("/api/users/me/filters/{name}")]
+pub async fn save_filter_preset(
+    user: CurrentUser,
+    path: web::Path<String>,
+    req: web::Json<SavePresetRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let name = path.into_inner().trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_PRESET_NAME_LEN {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("name must be 1-{} characters", MAX_PRESET_NAME_LEN)
+        }));
+    }
+    if let Err(message) = validate_filters(&req.filters) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }));
+    }
+
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM filter_presets WHERE user_id = $1 AND name <> $2",
+    )
+    .bind(user.id)
+    .bind(&name)
+    .fetch_one(&state.db)
+    .await
+    .unwrap_or(0);
+    if count >= MAX_PRESETS_PER_USER {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("A user can keep at most {} presets", MAX_PRESETS_PER_USER)
+        }));
+    }
+
+    match sqlx::query_as::<_, FilterPreset>(
+        r#"INSERT INTO filter_presets (user_id, name, filters) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, name) DO UPDATE
+        SET filters = EXCLUDED.filters, updated_at = NOW()
+        RETURNING id, name, filters, created_at, updated_at"#,
+    )
+    .bind(user.id)
+    .bind(&name)
+    .bind(Json(&req.filters))
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(preset) => {
+            info!("Filter preset '{}' saved for {}", preset.name, user.id);
+            HttpResponse::Ok().json(preset)
+        }
+        Err(e) => {
+            error!("Failed to save filter preset for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to save filter preset"
+            }))
+        }
+    }
+}
+
+#[delete("/api/users/me/filters/{name}")]
+pub async fn delete_filter_preset(
+    user: CurrentUser,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let name = path.into_inner();
+
+    match sqlx::query("DELETE FROM filter_presets WHERE user_id = $1 AND name = $2")
+        .bind(user.id)
+        .bind(name.trim())
+        .execute(&state.db)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            HttpResponse::Ok().json(serde_json::json!({ "deleted": true }))
+        }
+        Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Filter preset not found"
+        })),
+        Err(e) => {
+            error!("Failed to delete filter preset for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete filter preset"
+            }))
+        }
+    }
+}
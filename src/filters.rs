@@ -0,0 +1,736 @@
+// JARVIS2026 - Listing filter AST
+// Structured filters shared by search and listing endpoints. Queries such as
+// `location:canggu price<2b bedrooms>=3 has:pool amenity:garage -apartment` parse
+// into the same AST
+// that the filter endpoints build from query parameters, and every filter is
+// rendered with bound parameters, never interpolated.
+
+use serde::Deserialize;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::amenities;
+use crate::media_tags;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+
+const MAX_POLYGON_VERTICES: usize = 1000;
+/// Upper bound for bedroom and bathroom counts in filters
+const MAX_ROOM_COUNT: f64 = 100.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Location,
+    Description,
+    PropertyType,
+    CertificateType,
+    Price,
+    Bedrooms,
+    Bathrooms,
+    AreaSqm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Free text matched against title, location and description
+    Text { term: String, negated: bool },
+    Compare {
+        field: Field,
+        op: Op,
+        value: Value,
+        negated: bool,
+    },
+    /// A public photo of the listing was tagged with `tag`, see `media_tags`
+    HasTag { tag: String, negated: bool },
+    /// The owner attached the amenity to the listing, see `amenities`
+    HasAmenity { slug: String, negated: bool },
+    /// Listing coordinates inside the outer ring and outside every hole
+    WithinPolygon {
+        exterior: Vec<[f64; 2]>,
+        holes: Vec<Vec<[f64; 2]>>,
+    },
+}
+
+/// GeoJSON `Polygon` geometry, positions as `[longitude, latitude]`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoJsonPolygon {
+    #[serde(rename = "type")]
+    kind: String,
+    coordinates: Vec<Vec<[f64; 2]>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterSet {
+    pub filters: Vec<Filter>,
+}
+
+/// Orderings clients may request; each maps to a fixed `ORDER BY`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListingSort {
+    PriceAsc,
+    PriceDesc,
+    Newest,
+    AreaDesc,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SortParams {
+    sort: Option<String>,
+}
+
+/// Query parameters accepted by the listing endpoint. Amounts take the same
+/// shorthands as the search language (`500jt`, `2b`); room counts are minimums.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListingFilterParams {
+    min_price: Option<String>,
+    max_price: Option<String>,
+    bedrooms: Option<String>,
+    bathrooms: Option<String>,
+    min_area: Option<String>,
+    location: Option<String>,
+    /// Comma-separated photo tags the listing must have, e.g. `pool,garden`
+    has: Option<String>,
+    #[serde(flatten)]
+    amenities: AmenityParams,
+}
+
+/// `?amenities=pool,garage`, taken by listing and search alike.
+#[derive(Debug, Default, Deserialize)]
+pub struct AmenityParams {
+    /// Comma-separated amenity slugs the listing must have
+    amenities: Option<String>,
+}
+
+// ============================================================================
+// FIELDS AND OPERATORS
+// ============================================================================
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "title" => Some(Field::Title),
+            "location" | "loc" | "in" => Some(Field::Location),
+            "description" | "desc" => Some(Field::Description),
+            "type" | "property_type" => Some(Field::PropertyType),
+            "cert" | "certificate" | "certificate_type" => Some(Field::CertificateType),
+            "price" => Some(Field::Price),
+            "bedrooms" | "beds" | "br" => Some(Field::Bedrooms),
+            "bathrooms" | "baths" | "ba" => Some(Field::Bathrooms),
+            "area" | "area_sqm" | "sqm" => Some(Field::AreaSqm),
+            _ => None,
+        }
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Location => "location",
+            Field::Description => "description",
+            Field::PropertyType => "property_type",
+            Field::CertificateType => "certificate_type",
+            Field::Price => "price",
+            Field::Bedrooms => "bedrooms",
+            Field::Bathrooms => "bathrooms",
+            Field::AreaSqm => "area_sqm",
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Field::Price | Field::Bedrooms | Field::Bathrooms | Field::AreaSqm
+        )
+    }
+
+    /// Room counts are stored as integers, so only whole numbers in range
+    /// can be compared against them.
+    fn check_number(self, n: f64) -> Result<(), String> {
+        match self {
+            Field::Bedrooms | Field::Bathrooms
+                if n.fract() != 0.0 || !(0.0..=MAX_ROOM_COUNT).contains(&n) =>
+            {
+                Err(format!(
+                    "{} must be a whole number from 0 to {}",
+                    self.column(),
+                    MAX_ROOM_COUNT
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Op {
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+            Op::Contains => "LIKE",
+        }
+    }
+}
+
+impl ListingSort {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "price_asc" => Some(ListingSort::PriceAsc),
+            "price_desc" => Some(ListingSort::PriceDesc),
+            "newest" => Some(ListingSort::Newest),
+            "area_desc" => Some(ListingSort::AreaDesc),
+            _ => None,
+        }
+    }
+
+    /// Ties fall back to id so pages stay stable.
+    pub fn order_by(self) -> &'static str {
+        match self {
+            ListingSort::PriceAsc => "price ASC, id",
+            ListingSort::PriceDesc => "price DESC, id",
+            ListingSort::Newest => "created_at DESC NULLS LAST, id",
+            ListingSort::AreaDesc => "area_sqm DESC NULLS LAST, id",
+        }
+    }
+}
+
+impl SortParams {
+    /// `Ok(None)` when no sort was requested.
+    pub fn resolve(&self) -> Result<Option<ListingSort>, String> {
+        match self.sort.as_deref().filter(|raw| !raw.trim().is_empty()) {
+            None => Ok(None),
+            Some(raw) => ListingSort::parse(raw).map(Some).ok_or_else(|| {
+                format!(
+                    "sort must be price_asc, price_desc, newest or area_desc, got '{}'",
+                    raw
+                )
+            }),
+        }
+    }
+}
+
+// ============================================================================
+// QUERY STRING PARSING
+// ============================================================================
+
+/// Accepts plain numbers plus the shorthands people type for rupiah amounts:
+/// `500k`, `2jt`/`2m` (million), `2b`/`2mil` (billion). Grouped numbers such
+/// as `2.000.000` or `2,000,000` are read with dots/commas as separators.
+pub fn parse_number(raw: &str) -> Option<f64> {
+    let lower = raw.trim().to_lowercase();
+    let (digits, multiplier) = [
+        ("mil", 1e9),
+        ("jt", 1e6),
+        ("k", 1e3),
+        ("m", 1e6),
+        ("b", 1e9),
+    ]
+    .iter()
+    .find_map(|(suffix, mult)| lower.strip_suffix(suffix).map(|d| (d, *mult)))
+    .unwrap_or((lower.as_str(), 1.0));
+
+    let parsed = if multiplier > 1.0 {
+        // "2,5jt" and "2.5b" both keep their decimal part
+        digits.replace(',', ".").parse::<f64>().ok()
+    } else {
+        digits
+            .parse::<f64>()
+            .ok()
+            .or_else(|| digits.replace([',', '.'], "").parse::<f64>().ok())
+    };
+    parsed.filter(|n| n.is_finite()).map(|n| n * multiplier)
+}
+
+/// Splits on whitespace while keeping `"quoted phrases"` together.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for ch in query.chars() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn split_operator(token: &str) -> Option<(&str, Op, &str)> {
+    const OPERATORS: [(&str, Op); 6] = [
+        (">=", Op::Gte),
+        ("<=", Op::Lte),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+        ("=", Op::Eq),
+        (":", Op::Contains),
+    ];
+    let (index, symbol, op) = OPERATORS
+        .iter()
+        .filter_map(|(symbol, op)| token.find(symbol).map(|i| (i, *symbol, *op)))
+        .min_by_key(|(i, symbol, _)| (*i, std::cmp::Reverse(symbol.len())))?;
+    Some((&token[..index], op, &token[index + symbol.len()..]))
+}
+
+impl FilterSet {
+    /// Parses the search mini-language. Tokens whose prefix is not a known
+    /// field are kept as free text, so ordinary queries behave as before.
+    pub fn parse_query(query: &str) -> Result<Self, String> {
+        let mut filters = Vec::new();
+
+        for token in tokenize(query) {
+            let (negated, body) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+
+            if let Some(tag) = body
+                .strip_prefix("has:")
+                .or_else(|| body.strip_prefix("has="))
+            {
+                let tag = media_tags::normalize_tag(tag)
+                    .ok_or_else(|| format!("missing tag in '{}'", body))?;
+                filters.push(Filter::HasTag { tag, negated });
+                continue;
+            }
+
+            if let Some(slug) = body
+                .strip_prefix("amenity:")
+                .or_else(|| body.strip_prefix("amenity="))
+            {
+                let slug = amenities::normalize_slug(slug)
+                    .ok_or_else(|| format!("missing amenity in '{}'", body))?;
+                filters.push(Filter::HasAmenity { slug, negated });
+                continue;
+            }
+
+            let structured = split_operator(body)
+                .and_then(|(name, op, raw)| Field::parse(name).map(|field| (field, op, raw)));
+
+            let Some((field, op, raw)) = structured else {
+                filters.push(Filter::Text {
+                    term: body.to_string(),
+                    negated,
+                });
+                continue;
+            };
+
+            if raw.is_empty() {
+                return Err(format!("missing value for '{}'", body));
+            }
+
+            let filter = if field.is_numeric() {
+                let value = parse_number(raw)
+                    .ok_or_else(|| format!("'{}' is not a number in '{}'", raw, body))?;
+                field
+                    .check_number(value)
+                    .map_err(|message| format!("{} in '{}'", message, body))?;
+                let op = if op == Op::Contains { Op::Eq } else { op };
+                Filter::Compare {
+                    field,
+                    op,
+                    value: Value::Number(value),
+                    negated,
+                }
+            } else {
+                if !matches!(op, Op::Contains | Op::Eq) {
+                    return Err(format!("'{}' only supports ':' or '='", field.column()));
+                }
+                Filter::Compare {
+                    field,
+                    op,
+                    value: Value::Text(raw.to_lowercase()),
+                    negated,
+                }
+            };
+            filters.push(filter);
+        }
+
+        Ok(FilterSet { filters })
+    }
+
+    /// Positive free-text terms, used for highlighting matches.
+    pub fn text_terms(&self) -> Vec<String> {
+        self.filters
+            .iter()
+            .filter_map(|f| match f {
+                Filter::Text {
+                    term,
+                    negated: false,
+                } => Some(term.clone()),
+                Filter::Compare {
+                    field: Field::Title | Field::Description,
+                    value: Value::Text(term),
+                    negated: false,
+                    ..
+                } => Some(term.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl ListingFilterParams {
+    pub fn into_filter_set(self) -> Result<FilterSet, String> {
+        let numeric = [
+            ("min_price", self.min_price, Field::Price, Op::Gte),
+            ("max_price", self.max_price, Field::Price, Op::Lte),
+            ("bedrooms", self.bedrooms, Field::Bedrooms, Op::Gte),
+            ("bathrooms", self.bathrooms, Field::Bathrooms, Op::Gte),
+            ("min_area", self.min_area, Field::AreaSqm, Op::Gte),
+        ];
+
+        let mut filters = Vec::new();
+        for (name, raw, field, op) in numeric {
+            let Some(raw) = raw.as_deref().map(str::trim).filter(|r| !r.is_empty()) else {
+                continue;
+            };
+            let value = parse_number(raw)
+                .filter(|n| *n >= 0.0)
+                .ok_or_else(|| format!("{} must be a non-negative number, got '{}'", name, raw))?;
+            field
+                .check_number(value)
+                .map_err(|message| format!("{}, got '{}'", message, raw))?;
+            filters.push(Filter::Compare {
+                field,
+                op,
+                value: Value::Number(value),
+                negated: false,
+            });
+        }
+
+        if let Some(location) = self
+            .location
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+        {
+            filters.push(Filter::Compare {
+                field: Field::Location,
+                op: Op::Contains,
+                value: Value::Text(location.to_lowercase()),
+                negated: false,
+            });
+        }
+
+        for tag in self.has.as_deref().unwrap_or("").split(',') {
+            if let Some(tag) = media_tags::normalize_tag(tag) {
+                filters.push(Filter::HasTag {
+                    tag,
+                    negated: false,
+                });
+            }
+        }
+
+        filters.extend(self.amenities.filters());
+
+        Ok(FilterSet { filters })
+    }
+}
+
+impl AmenityParams {
+    pub fn filters(&self) -> Vec<Filter> {
+        self.amenities
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter_map(amenities::normalize_slug)
+            .map(|slug| Filter::HasAmenity {
+                slug,
+                negated: false,
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// GEOMETRY
+// ============================================================================
+
+fn validate_ring(ring: &[[f64; 2]]) -> Result<(), String> {
+    if ring.len() < 4 {
+        return Err("polygon rings need at least four positions".to_string());
+    }
+    if ring.len() > MAX_POLYGON_VERTICES {
+        return Err(format!(
+            "polygon rings are limited to {} positions",
+            MAX_POLYGON_VERTICES
+        ));
+    }
+    let in_range = ring
+        .iter()
+        .all(|[lng, lat]| (-180.0..=180.0).contains(lng) && (-90.0..=90.0).contains(lat));
+    if !in_range {
+        return Err("polygon positions must be valid [longitude, latitude] pairs".to_string());
+    }
+    Ok(())
+}
+
+impl GeoJsonPolygon {
+    pub fn into_filter(self) -> Result<Filter, String> {
+        if self.kind != "Polygon" {
+            return Err("only GeoJSON Polygon geometries are supported".to_string());
+        }
+        let mut rings = self.coordinates.into_iter();
+        let exterior = rings
+            .next()
+            .ok_or_else(|| "polygon has no coordinates".to_string())?;
+        validate_ring(&exterior)?;
+        let holes: Vec<_> = rings.collect();
+        for hole in &holes {
+            validate_ring(hole)?;
+        }
+        Ok(Filter::WithinPolygon { exterior, holes })
+    }
+}
+
+/// Postgres `polygon` literal, e.g. `((106.8,-6.2),(106.9,-6.2),(106.9,-6.1))`.
+fn polygon_literal(ring: &[[f64; 2]]) -> String {
+    let points: Vec<String> = ring
+        .iter()
+        .map(|[lng, lat]| format!("({},{})", lng, lat))
+        .collect();
+    format!("({})", points.join(","))
+}
+
+// ============================================================================
+// SQL RENDERING
+// ============================================================================
+
+pub fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl FilterSet {
+    /// Appends ` AND ...` for each filter; the query must already have a `WHERE` clause.
+    pub fn push_and(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        for filter in &self.filters {
+            qb.push(" AND ");
+            self.push_filter(qb, filter);
+        }
+    }
+
+    fn push_filter(&self, qb: &mut QueryBuilder<'_, Postgres>, filter: &Filter) {
+        match filter {
+            Filter::Text { term, negated } => {
+                let pattern = format!("%{}%", escape_like(&term.to_lowercase()));
+                if *negated {
+                    qb.push("NOT ");
+                }
+                qb.push("(LOWER(title) LIKE ");
+                qb.push_bind(pattern.clone());
+                qb.push(" OR LOWER(location) LIKE ");
+                qb.push_bind(pattern.clone());
+                qb.push(" OR LOWER(COALESCE(description, '')) LIKE ");
+                qb.push_bind(pattern);
+                qb.push(")");
+            }
+            Filter::Compare {
+                field,
+                op,
+                value,
+                negated,
+            } => {
+                if *negated {
+                    // NULLs count as "not matching" rather than disappearing
+                    qb.push("(");
+                    qb.push(field.column());
+                    qb.push(" IS NULL OR NOT ");
+                }
+                qb.push("(");
+                match value {
+                    Value::Number(n) => {
+                        qb.push(field.column());
+                        qb.push(" ");
+                        qb.push(op.sql());
+                        qb.push(" ");
+                        match field {
+                            Field::Bedrooms | Field::Bathrooms => qb.push_bind(*n as i32),
+                            _ => qb.push_bind(*n),
+                        };
+                    }
+                    Value::Text(text) => {
+                        qb.push("LOWER(");
+                        qb.push(field.column());
+                        qb.push(")");
+                        if *op == Op::Contains {
+                            qb.push(" LIKE ");
+                            qb.push_bind(format!("%{}%", escape_like(text)));
+                        } else {
+                            qb.push(" = ");
+                            qb.push_bind(text.clone());
+                        }
+                    }
+                }
+                qb.push(")");
+                if *negated {
+                    qb.push(")");
+                }
+            }
+            Filter::HasTag { tag, negated } => {
+                if *negated {
+                    qb.push("NOT ");
+                }
+                qb.push(
+                    "(id IN (SELECT m.property_id FROM media_uploads m \
+                     JOIN media_tags t ON t.media_id = m.id WHERE t.tag = ",
+                );
+                qb.push_bind(tag.clone());
+                qb.push(" AND t.confidence >= ");
+                qb.push_bind(media_tags::MIN_CONFIDENCE);
+                qb.push(" AND ");
+                qb.push(PUBLIC_LISTING_CONDITION);
+                qb.push("))");
+            }
+            Filter::HasAmenity { slug, negated } => {
+                if *negated {
+                    qb.push("NOT ");
+                }
+                qb.push(
+                    "(id IN (SELECT pa.property_id FROM property_amenities pa \
+                     JOIN amenities a ON a.id = pa.amenity_id WHERE a.slug = ",
+                );
+                qb.push_bind(slug.clone());
+                qb.push("))");
+            }
+            Filter::WithinPolygon { exterior, holes } => {
+                // Matches the GiST index on point(longitude, latitude)
+                qb.push("(point(longitude, latitude) <@ ");
+                qb.push_bind(polygon_literal(exterior));
+                qb.push("::polygon");
+                for hole in holes {
+                    qb.push(" AND NOT point(longitude, latitude) <@ ");
+                    qb.push_bind(polygon_literal(hole));
+                    qb.push("::polygon");
+                }
+                qb.push(")");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(field: Field, op: Op, value: Value, negated: bool) -> Filter {
+        Filter::Compare {
+            field,
+            op,
+            value,
+            negated,
+        }
+    }
+
+    #[test]
+    fn number_shorthands() {
+        assert_eq!(parse_number("500k"), Some(500_000.0));
+        assert_eq!(parse_number("2jt"), Some(2_000_000.0));
+        assert_eq!(parse_number("2,5jt"), Some(2_500_000.0));
+        assert_eq!(parse_number("2M"), Some(2_000_000.0));
+        assert_eq!(parse_number("2b"), Some(2_000_000_000.0));
+        assert_eq!(parse_number("1.5mil"), Some(1_500_000_000.0));
+        assert_eq!(parse_number("2.000.000"), Some(2_000_000.0));
+        assert_eq!(parse_number("2,000,000"), Some(2_000_000.0));
+        assert_eq!(parse_number(" 3 "), Some(3.0));
+        assert_eq!(parse_number("lots"), None);
+        assert_eq!(parse_number("inf"), None);
+    }
+
+    #[test]
+    fn query_language_parses_fields_tags_and_text() {
+        let parsed = FilterSet::parse_query(
+            r#"location:canggu price<2b beds>=3 has:pool amenity:garage -apartment "rice field""#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.filters,
+            vec![
+                compare(
+                    Field::Location,
+                    Op::Contains,
+                    Value::Text("canggu".into()),
+                    false
+                ),
+                compare(Field::Price, Op::Lt, Value::Number(2e9), false),
+                compare(Field::Bedrooms, Op::Gte, Value::Number(3.0), false),
+                Filter::HasTag {
+                    tag: "pool".into(),
+                    negated: false
+                },
+                Filter::HasAmenity {
+                    slug: "garage".into(),
+                    negated: false
+                },
+                Filter::Text {
+                    term: "apartment".into(),
+                    negated: true
+                },
+                Filter::Text {
+                    term: "rice field".into(),
+                    negated: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn numeric_fields_treat_colon_as_equals() {
+        let parsed = FilterSet::parse_query("-baths:2").unwrap();
+        assert_eq!(
+            parsed.filters,
+            vec![compare(Field::Bathrooms, Op::Eq, Value::Number(2.0), true)]
+        );
+    }
+
+    #[test]
+    fn query_language_rejects_bad_values() {
+        assert!(FilterSet::parse_query("price<").is_err());
+        assert!(FilterSet::parse_query("price<cheap").is_err());
+        assert!(FilterSet::parse_query("type>villa").is_err());
+        assert!(FilterSet::parse_query("has:").is_err());
+    }
+
+    #[test]
+    fn room_counts_must_be_whole_numbers_in_range() {
+        assert!(FilterSet::parse_query("bedrooms>=2.5").is_err());
+        assert!(FilterSet::parse_query("bedrooms>=3b").is_err());
+        assert!(FilterSet::parse_query("baths=1k").is_err());
+        assert!(FilterSet::parse_query("bedrooms>=100").is_ok());
+
+        let params = ListingFilterParams {
+            bedrooms: Some("1.5".into()),
+            ..Default::default()
+        };
+        assert!(params.into_filter_set().is_err());
+    }
+}
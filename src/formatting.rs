@@ -0,0 +1,167 @@
+// JARVIS2026 - Locale-aware display formatting
+// The server formats rupiah amounts so every client (web, apps, share images)
+// shows the same strings. Raw numbers are always returned alongside.
+
+use actix_web::{dev::Payload, http::header, FromRequest, HttpRequest};
+use futures_util::future::{ready, Ready};
+use serde::Serialize;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Locale {
+    #[default]
+    Indonesian,
+    English,
+}
+
+/// A price in every form a client needs.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceDisplay {
+    amount: f64,
+    currency: &'static str,
+    /// `Rp 2.500.000.000`
+    formatted: String,
+    /// `Rp 2,5 M`
+    compact: String,
+}
+
+// ============================================================================
+// LOCALES
+// ============================================================================
+
+impl Locale {
+    /// Matches on the language part, so `id`, `id-ID` and `en-GB` all work.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let language = raw.trim().split(['-', '_']).next()?.to_lowercase();
+        match language.as_str() {
+            "id" | "in" => Some(Locale::Indonesian),
+            "en" => Some(Locale::English),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Indonesian => "id",
+            Locale::English => "en",
+        }
+    }
+
+    fn separators(self) -> (char, char) {
+        match self {
+            Locale::Indonesian => ('.', ','),
+            Locale::English => (',', '.'),
+        }
+    }
+
+    fn from_request_parts(req: &HttpRequest) -> Self {
+        let from_query = req.query_string().split('&').find_map(|pair| {
+            let value = pair.strip_prefix("locale=")?;
+            Locale::parse(value)
+        });
+
+        let from_header = || {
+            req.headers()
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| {
+                    v.split(',')
+                        .find_map(|tag| Locale::parse(tag.split(';').next().unwrap_or("")))
+                })
+        };
+
+        let from_region = || {
+            crate::geoip::region_for_request(req)
+                .map(|region| crate::geoip::locale_for_country(&region.country_code))
+        };
+
+        from_query
+            .or_else(from_header)
+            .or_else(from_region)
+            .unwrap_or_default()
+    }
+}
+
+/// Resolved from `?locale=`, then `Accept-Language`, then the caller's IP
+/// region, defaulting to Indonesian.
+impl FromRequest for Locale {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Locale::from_request_parts(req)))
+    }
+}
+
+// ============================================================================
+// NUMBERS
+// ============================================================================
+
+fn group_digits(value: f64, separator: char) -> String {
+    let digits = format!("{:.0}", value.abs());
+    let mut grouped = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(ch);
+    }
+    if value < 0.0 && digits != "0" {
+        grouped.insert(0, '-');
+    }
+    grouped
+}
+
+/// One decimal place, dropped when it is zero: `2,5` / `2.5` / `750`.
+fn short_decimal(value: f64, decimal: char) -> String {
+    let rounded = (value * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{:.0}", rounded)
+    } else {
+        format!("{:.1}", rounded).replace('.', &decimal.to_string())
+    }
+}
+
+/// `Rp 2.500.000.000` (id) or `Rp 2,500,000,000` (en).
+pub fn format_price(amount: f64, locale: Locale) -> String {
+    let (thousands, _) = locale.separators();
+    format!("Rp {}", group_digits(amount, thousands))
+}
+
+/// `Rp 2,5 M`, `Rp 750 jt` (id) or `Rp 2.5B`, `Rp 750M` (en).
+pub fn format_price_compact(amount: f64, locale: Locale) -> String {
+    let (_, decimal) = locale.separators();
+    let units: [(f64, &str); 4] = match locale {
+        Locale::Indonesian => [(1e12, " T"), (1e9, " M"), (1e6, " jt"), (1e3, " rb")],
+        Locale::English => [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")],
+    };
+
+    let magnitude = amount.abs();
+    for (scale, suffix) in units {
+        // 999.96 jt would round to "1000 jt"; show it as 1 M instead
+        if magnitude / scale >= 0.99995 {
+            let sign = if amount < 0.0 { "-" } else { "" };
+            return format!(
+                "Rp {}{}{}",
+                sign,
+                short_decimal(magnitude / scale, decimal),
+                suffix
+            );
+        }
+    }
+    format_price(amount, locale)
+}
+
+impl PriceDisplay {
+    pub fn new(amount: f64, locale: Locale) -> Self {
+        PriceDisplay {
+            amount,
+            currency: "IDR",
+            formatted: format_price(amount, locale),
+            compact: format_price_compact(amount, locale),
+        }
+    }
+}
@@ -0,0 +1,531 @@
+// JARVIS2026 - Multi-account detection
+// Reward farming usually means one person running several accounts. Sign-ins,
+// sign-ups and uploads record the IP, device (`X-Visitor-Id`) and file hashes
+// behind each account; a periodic job links accounts that share them (plus
+// wallet addresses) into clusters that admins review.
+
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::AdminUser;
+use crate::geoip;
+use crate::AppState;
+
+const VISITOR_HEADER: &str = "X-Visitor-Id";
+const DETECTION_INTERVAL_SECS: u64 = 6 * 3600;
+/// Only signals seen this recently link accounts.
+const SIGNAL_WINDOW_DAYS: i32 = 90;
+/// Values shared by more accounts than this (carrier NAT, office Wi-Fi) are
+/// too common to mean anything.
+const MAX_ACCOUNTS_PER_VALUE: i64 = 20;
+/// Clusters scoring below this are not flagged; one shared IP alone isn't enough.
+const FLAG_SCORE: i32 = 3;
+/// Shared values listed per signal kind on a cluster.
+const MAX_LISTED_VALUES: usize = 10;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy)]
+pub enum SignalKind {
+    Ip,
+    Device,
+    MediaHash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ClusterStatus {
+    Open,
+    Dismissed,
+    Confirmed,
+}
+
+#[derive(sqlx::FromRow)]
+struct SharedValue {
+    kind: String,
+    value: String,
+    user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ClusterSignals {
+    /// Shared values by signal kind, a sample of at most `MAX_LISTED_VALUES`
+    shared: BTreeMap<String, Vec<String>>,
+    /// Number of shared values by signal kind
+    counts: BTreeMap<String, i64>,
+}
+
+struct DetectedCluster {
+    user_ids: Vec<Uuid>,
+    score: i32,
+    signals: ClusterSignals,
+}
+
+#[derive(Deserialize)]
+pub struct ClusterQuery {
+    status: Option<ClusterStatus>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct ClusterMember {
+    id: Uuid,
+    username: String,
+    token_balance: i64,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct AccountCluster {
+    id: Uuid,
+    user_ids: Vec<Uuid>,
+    score: i32,
+    signals: Json<ClusterSignals>,
+    /// Combined token balance of the members
+    total_tokens: i64,
+    status: String,
+    note: Option<String>,
+    reviewed_by: Option<Uuid>,
+    reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+    first_detected_at: chrono::DateTime<chrono::Utc>,
+    last_detected_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip)]
+    total: i64,
+}
+
+#[derive(Serialize)]
+struct ClusterView {
+    #[serde(flatten)]
+    cluster: AccountCluster,
+    members: Vec<ClusterMember>,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewRequest {
+    status: ClusterStatus,
+    note: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS account_signals (
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (user_id, kind, value)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_account_signals_value ON account_signals(kind, value)",
+    )
+    .execute(pool)
+    .await?;
+
+    // `cluster_key` identifies a membership, so a reviewed cluster keeps its
+    // verdict across runs until someone joins or leaves it
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS account_clusters (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            cluster_key TEXT UNIQUE NOT NULL,
+            user_ids UUID[] NOT NULL,
+            score INTEGER NOT NULL,
+            signals JSONB NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open'
+                CHECK (status IN ('open', 'dismissed', 'confirmed')),
+            note TEXT,
+            reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            reviewed_at TIMESTAMPTZ,
+            first_detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// SIGNAL RECORDING
+// ============================================================================
+
+impl SignalKind {
+    fn name(self) -> &'static str {
+        match self {
+            SignalKind::Ip => "ip",
+            SignalKind::Device => "device",
+            SignalKind::MediaHash => "media_hash",
+        }
+    }
+}
+
+impl ClusterStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            ClusterStatus::Open => "open",
+            ClusterStatus::Dismissed => "dismissed",
+            ClusterStatus::Confirmed => "confirmed",
+        }
+    }
+}
+
+/// Notes that the account was seen with `value`. Failures are only logged;
+/// detection must never get in the way of the request that feeds it.
+pub async fn record(pool: &PgPool, user_id: Uuid, kind: SignalKind, value: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+        return;
+    }
+    let result = sqlx::query(
+        r#"INSERT INTO account_signals (user_id, kind, value) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, kind, value) DO UPDATE SET last_seen_at = NOW()"#,
+    )
+    .bind(user_id)
+    .bind(kind.name())
+    .bind(value.chars().take(256).collect::<String>())
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        warn!(
+            "Failed to record {} signal for {}: {}",
+            kind.name(),
+            user_id,
+            e
+        );
+    }
+}
+
+/// Records the IP and device a request came from.
+pub async fn record_request(pool: &PgPool, user_id: Uuid, req: &HttpRequest) {
+    if let Some(ip) = geoip::client_ip(req) {
+        record(pool, user_id, SignalKind::Ip, &ip.to_string()).await;
+    }
+    let device = req
+        .headers()
+        .get(VISITOR_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if let Some(device) = device {
+        record(pool, user_id, SignalKind::Device, device).await;
+    }
+}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+/// How strongly one shared value of a kind suggests a single owner.
+fn weight(kind: &str) -> i32 {
+    match kind {
+        "wallet" => 5,
+        "media_hash" => 4,
+        "device" => 3,
+        _ => 1,
+    }
+}
+
+fn find(parent: &mut HashMap<Uuid, Uuid>, user: Uuid) -> Uuid {
+    let mut root = user;
+    while let Some(&next) = parent.get(&root) {
+        if next == root {
+            break;
+        }
+        root = next;
+    }
+    // Path compression
+    let mut current = user;
+    while current != root {
+        let next = parent[&current];
+        parent.insert(current, root);
+        current = next;
+    }
+    root
+}
+
+/// Groups accounts connected through any shared value and scores each group.
+fn build_clusters(shared: Vec<SharedValue>) -> Vec<DetectedCluster> {
+    let mut parent: HashMap<Uuid, Uuid> = HashMap::new();
+    for group in &shared {
+        for user in &group.user_ids {
+            parent.entry(*user).or_insert(*user);
+        }
+        let first = find(&mut parent, group.user_ids[0]);
+        for user in &group.user_ids[1..] {
+            let root = find(&mut parent, *user);
+            if root != first {
+                parent.insert(root, first);
+            }
+        }
+    }
+
+    let mut clusters: HashMap<Uuid, DetectedCluster> = HashMap::new();
+    for group in shared {
+        let root = find(&mut parent, group.user_ids[0]);
+        let cluster = clusters.entry(root).or_insert_with(|| DetectedCluster {
+            user_ids: Vec::new(),
+            score: 0,
+            signals: ClusterSignals::default(),
+        });
+        cluster.score += weight(&group.kind);
+        cluster.user_ids.extend(&group.user_ids);
+        *cluster
+            .signals
+            .counts
+            .entry(group.kind.clone())
+            .or_default() += 1;
+        let listed = cluster.signals.shared.entry(group.kind).or_default();
+        if listed.len() < MAX_LISTED_VALUES {
+            listed.push(group.value);
+        }
+    }
+
+    clusters
+        .into_values()
+        .filter(|c| c.score >= FLAG_SCORE)
+        .map(|mut c| {
+            c.user_ids.sort_unstable();
+            c.user_ids.dedup();
+            c
+        })
+        .collect()
+}
+
+fn cluster_key(user_ids: &[Uuid]) -> String {
+    let mut hasher = Sha256::new();
+    for id in user_ids {
+        hasher.update(id.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Rebuilds the open clusters from current signals. Reviewed clusters keep
+/// their status; open ones that no longer form are dropped.
+async fn run_detection(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let shared = sqlx::query_as::<_, SharedValue>(
+        r#"WITH signals AS (
+            SELECT user_id, kind, value FROM account_signals
+            WHERE last_seen_at > NOW() - make_interval(days => $1)
+            UNION
+            SELECT user_id, 'device', visitor_id FROM property_views
+            WHERE user_id IS NOT NULL AND visitor_id IS NOT NULL
+              AND viewed_at > NOW() - make_interval(days => $1)
+            UNION
+            SELECT user_id, 'device', visitor_id FROM contact_reveals
+            WHERE visitor_id IS NOT NULL
+              AND revealed_at > NOW() - make_interval(days => $1)
+            UNION
+            SELECT id, 'wallet', LOWER(wallet_address) FROM users
+            WHERE wallet_address IS NOT NULL AND wallet_address <> ''
+        )
+        SELECT kind, value, array_agg(DISTINCT user_id) AS user_ids
+        FROM signals
+        GROUP BY kind, value
+        HAVING COUNT(DISTINCT user_id) BETWEEN 2 AND $2"#,
+    )
+    .bind(SIGNAL_WINDOW_DAYS)
+    .bind(MAX_ACCOUNTS_PER_VALUE)
+    .fetch_all(pool)
+    .await?;
+
+    let clusters = build_clusters(shared);
+    let started = chrono::Utc::now();
+
+    let mut tx = pool.begin().await?;
+    for cluster in &clusters {
+        sqlx::query(
+            r#"INSERT INTO account_clusters (cluster_key, user_ids, score, signals)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (cluster_key) DO UPDATE
+            SET score = EXCLUDED.score, signals = EXCLUDED.signals, last_detected_at = NOW()"#,
+        )
+        .bind(cluster_key(&cluster.user_ids))
+        .bind(&cluster.user_ids)
+        .bind(cluster.score)
+        .bind(Json(&cluster.signals))
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM account_clusters WHERE status = 'open' AND last_detected_at < $1")
+        .bind(started)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(clusters.len())
+}
+
+pub fn spawn_detection(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(DETECTION_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match run_detection(&pool).await {
+                Ok(0) => {}
+                Ok(count) => info!("Multi-account detection flagged {} clusters", count),
+                Err(e) => error!("Multi-account detection failed: {}", e),
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/admin/fraud/clusters")]
+pub async fn list_clusters(
+    _admin: AdminUser,
+    query: web::Query<ClusterQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let status = query.status.unwrap_or(ClusterStatus::Open);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let result: Result<Vec<ClusterView>, sqlx::Error> = async {
+        let clusters = sqlx::query_as::<_, AccountCluster>(
+            r#"SELECT c.id, c.user_ids, c.score, c.signals,
+                      (SELECT COALESCE(SUM(token_balance), 0)::BIGINT FROM users
+                       WHERE id = ANY(c.user_ids)) AS total_tokens,
+                      c.status, c.note, c.reviewed_by, c.reviewed_at,
+                      c.first_detected_at, c.last_detected_at,
+                      COUNT(*) OVER () AS total
+            FROM account_clusters c
+            WHERE c.status = $1
+            ORDER BY c.score DESC, c.last_detected_at DESC
+            LIMIT $2 OFFSET $3"#,
+        )
+        .bind(status.as_str())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&state.db)
+        .await?;
+
+        let mut views = Vec::with_capacity(clusters.len());
+        for cluster in clusters {
+            let members = sqlx::query_as::<_, ClusterMember>(
+                r#"SELECT id, username, COALESCE(token_balance, 0) AS token_balance, created_at
+                FROM users WHERE id = ANY($1) ORDER BY created_at"#,
+            )
+            .bind(&cluster.user_ids)
+            .fetch_all(&state.db)
+            .await?;
+            views.push(ClusterView { cluster, members });
+        }
+        Ok(views)
+    }
+    .await;
+
+    match result {
+        Ok(clusters) => {
+            let total = clusters.first().map_or(0, |c| c.cluster.total);
+            HttpResponse::Ok().json(serde_json::json!({
+                "total": total,
+                "limit": limit,
+                "offset": offset,
+                "clusters": clusters
+            }))
+        }
+        Err(e) => {
+            error!("Failed to list account clusters: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list account clusters"
+            }))
+        }
+    }
+}
+
+#[put("/api/admin/fraud/clusters/{id}")]
+pub async fn review_cluster(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    req: web::Json<ReviewRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let cluster_id = path.into_inner();
+    let note = req.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let updated = sqlx::query(
+            r#"UPDATE account_clusters
+            SET status = $2, note = $3, reviewed_by = $4, reviewed_at = NOW()
+            WHERE id = $1"#,
+        )
+        .bind(cluster_id)
+        .bind(req.status.as_str())
+        .bind(note)
+        .bind(admin.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if updated {
+            audit::record(
+                &mut tx,
+                admin.id,
+                &format!("fraud.cluster_{}", req.status.as_str()),
+                "account_cluster",
+                cluster_id,
+                serde_json::json!({ "note": note }),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+    .await;
+
+    match result {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "id": cluster_id,
+            "status": req.status
+        })),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Cluster not found"
+        })),
+        Err(e) => {
+            error!("Failed to review cluster {}: {}", cluster_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to review cluster"
+            }))
+        }
+    }
+}
+
+/// Runs detection now instead of waiting for the next scheduled pass.
+#[post("/api/admin/fraud/scan")]
+pub async fn scan_now(_admin: AdminUser, state: web::Data<AppState>) -> impl Responder {
+    match run_detection(&state.db).await {
+        Ok(flagged) => HttpResponse::Ok().json(serde_json::json!({ "flagged": flagged })),
+        Err(e) => {
+            error!("Manual multi-account scan failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Scan failed"
+            }))
+        }
+    }
+}
@@ -0,0 +1,401 @@
+// JARVIS2026 - Personal data export and account deletion
+// Users can download everything held about them as a zip of JSON files, and
+// close their account. Deletion removes the user's listings and uploads along
+// with the files on disk; everything keyed to the account cascades from the
+// `users` row. Admins can act on any account. Both need a signed-in session,
+// and deletion a fresh sign-in.
+
+use actix_web::{delete, get, http::header, web, HttpResponse, Responder};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::io::Write;
+use tokio::fs as async_fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::SessionUser;
+use crate::completeness;
+use crate::documents;
+use crate::moderation;
+use crate::AppState;
+
+/// How recently the caller must have signed in to delete an account
+const REAUTH_WINDOW_MINUTES: i64 = 10;
+
+/// One JSON file per entry, each built from a query over `$1` = user id.
+/// Secrets (password hashes, token hashes) are deliberately left out.
+const EXPORT_SECTIONS: &[(&str, &str)] = &[
+    (
+        "listings.json",
+        "SELECT * FROM properties WHERE user_id = $1 ORDER BY created_at",
+    ),
+    (
+        "media.json",
+        r#"SELECT id, property_id, file_type, file_size, content_hash, is_original,
+                  tokens_earned, moderation_status, uploaded_at
+        FROM media_uploads WHERE user_id = $1 ORDER BY uploaded_at"#,
+    ),
+    (
+        "token_transactions.json",
+        "SELECT * FROM token_transactions WHERE user_id = $1 ORDER BY created_at",
+    ),
+    (
+        "property_shares.json",
+        "SELECT * FROM property_shares WHERE user_id = $1 ORDER BY created_at",
+    ),
+    (
+        "sold_records.json",
+        r#"SELECT id, property_id, location, list_price, sale_price, anonymized, snapshot, sold_at
+        FROM sold_records WHERE seller_user_id = $1 ORDER BY sold_at"#,
+    ),
+    (
+        "inquiries.json",
+        r#"SELECT * FROM inquiries WHERE sender_user_id = $1 OR owner_user_id = $1
+        ORDER BY created_at"#,
+    ),
+    (
+        "inquiry_messages.json",
+        "SELECT * FROM inquiry_messages WHERE sender_user_id = $1 ORDER BY created_at",
+    ),
+    (
+        "viewings.json",
+        r#"SELECT * FROM viewings WHERE visitor_user_id = $1 OR agent_user_id = $1
+        ORDER BY created_at"#,
+    ),
+    (
+        "favorites.json",
+        "SELECT property_id, created_at FROM favorites WHERE user_id = $1 ORDER BY created_at",
+    ),
+    (
+        "contact_reveals.json",
+        "SELECT * FROM contact_reveals WHERE user_id = $1 ORDER BY revealed_at",
+    ),
+    (
+        "linked_accounts.json",
+        r#"SELECT provider, subject, email, created_at, last_login_at
+        FROM user_identities WHERE user_id = $1 ORDER BY created_at"#,
+    ),
+    (
+        "sign_in_signals.json",
+        r#"SELECT kind, value, first_seen_at, last_seen_at
+        FROM account_signals WHERE user_id = $1 ORDER BY first_seen_at"#,
+    ),
+    (
+        "sessions.json",
+        r#"SELECT method, created_at, expires_at, revoked_at
+        FROM sessions WHERE user_id = $1 ORDER BY created_at"#,
+    ),
+    (
+        "documents.json",
+        r#"SELECT id, property_id, kind, filename, content_type, bytes, sha256, created_at
+        FROM private_documents WHERE owner_user_id = $1 ORDER BY created_at"#,
+    ),
+];
+
+// ============================================================================
+// EXPORT
+// ============================================================================
+
+async fn section_json(pool: &PgPool, sql: &str, user_id: Uuid) -> Result<Value, sqlx::Error> {
+    sqlx::query_scalar::<_, Value>(&format!(
+        "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]'::jsonb) FROM ({}) t",
+        sql
+    ))
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Every export file as (name, pretty JSON); `None` when the user is unknown.
+async fn collect_export(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<Vec<(&'static str, String)>>, sqlx::Error> {
+    let Some(profile) = sqlx::query_scalar::<_, Value>(
+        r#"SELECT to_jsonb(t) FROM (
+            SELECT u.*, c.email FROM users u
+            LEFT JOIN credentials c ON c.user_id = u.id
+            WHERE u.id = $1
+        ) t"#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let mut files = vec![("profile.json", pretty(&profile))];
+    for (name, sql) in EXPORT_SECTIONS {
+        let section = section_json(pool, sql, user_id).await?;
+        files.push((name, pretty(&section)));
+    }
+    Ok(Some(files))
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn build_zip(files: &[(&str, String)]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, contents) in files {
+        zip.start_file(*name, options)?;
+        zip.write_all(contents.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// ============================================================================
+// DELETION
+// ============================================================================
+
+enum Deletion {
+    Deleted { listings: u64, files: Vec<String> },
+    NotFound,
+    Blocked(&'static str),
+}
+
+/// Deletes the account and its content in one transaction. Files are only
+/// returned, so the caller removes them once the rows are gone for good.
+async fn delete_account(
+    tx: &mut Transaction<'_, Postgres>,
+    actor_id: Uuid,
+    user_id: Uuid,
+) -> Result<Deletion, sqlx::Error> {
+    let found = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+    if found.is_none() {
+        return Ok(Deletion::NotFound);
+    }
+
+    // Other investors' positions hang off these; they can't vanish with the account
+    let (offerings, shares_held) = sqlx::query_as::<_, (i64, i64)>(
+        r#"SELECT
+            (SELECT COUNT(*) FROM property_offerings WHERE issuer_user_id = $1),
+            (SELECT COUNT(*) FROM (
+                SELECT 1 FROM property_shares WHERE user_id = $1
+                GROUP BY property_id HAVING SUM(delta) > 0
+            ) held)"#,
+    )
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    if offerings > 0 {
+        return Ok(Deletion::Blocked(
+            "Close your tokenized property offerings before deleting the account",
+        ));
+    }
+    if shares_held > 0 {
+        return Ok(Deletion::Blocked(
+            "Sell your property shares before deleting the account",
+        ));
+    }
+
+    // The user's own uploads, plus anything others uploaded to their listings
+    let media_condition =
+        "user_id = $1 OR property_id IN (SELECT id FROM properties WHERE user_id = $1)";
+
+    // Other users keep the tokens they earned for media that is going away
+    sqlx::query(&format!(
+        "UPDATE token_transactions SET media_id = NULL WHERE media_id IN (SELECT id FROM media_uploads WHERE {})",
+        media_condition
+    ))
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    let removed = sqlx::query_as::<_, (String, Option<Uuid>)>(&format!(
+        "DELETE FROM media_uploads WHERE {} RETURNING file_path, property_id",
+        media_condition
+    ))
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Before the listings go, so the cascade doesn't hide their files
+    let documents = documents::delete_for_user(tx, user_id).await?;
+
+    let listings = sqlx::query("DELETE FROM properties WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+    // Listings owned by others lost this user's uploads
+    let mut touched: Vec<Uuid> = removed.iter().filter_map(|(_, p)| *p).collect();
+    touched.sort_unstable();
+    touched.dedup();
+    for property_id in touched {
+        completeness::refresh(&mut **tx, property_id).await?;
+    }
+
+    for sql in [
+        "DELETE FROM token_transactions WHERE user_id = $1",
+        "DELETE FROM property_shares WHERE user_id = $1",
+        "DELETE FROM storage_usage WHERE user_id = $1",
+    ] {
+        sqlx::query(sql).bind(user_id).execute(&mut **tx).await?;
+    }
+
+    audit::record(
+        tx,
+        actor_id,
+        "user.delete",
+        "user",
+        user_id,
+        serde_json::json!({
+            "self_service": actor_id == user_id,
+            "listings": listings,
+            "media_files": removed.len()
+        }),
+    )
+    .await?;
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let media_files = removed.into_iter().map(|(path, _)| path).collect();
+    let media_files = moderation::unreferenced_files(tx, media_files).await?;
+
+    Ok(Deletion::Deleted {
+        listings,
+        files: media_files.into_iter().chain(documents).collect(),
+    })
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "You can only manage your own account data"
+    }))
+}
+
+#[get("/api/users/{user_id}/export")]
+pub async fn export_user_data(
+    path: web::Path<Uuid>,
+    caller: SessionUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    if caller.id != user_id && !caller.is_admin() {
+        return forbidden();
+    }
+
+    let files = match collect_export(&state.db, user_id).await {
+        Ok(Some(files)) => files,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to collect data export for {}: {}", user_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export account data"
+            }));
+        }
+    };
+
+    match web::block(move || build_zip(&files)).await {
+        Ok(Ok(archive)) => {
+            info!("Data export for {} downloaded by {}", user_id, caller.id);
+            HttpResponse::Ok()
+                .content_type("application/zip")
+                .insert_header((
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"jarvis-export-{}.zip\"", user_id),
+                ))
+                .body(archive)
+        }
+        Ok(Err(e)) => {
+            error!("Failed to build data export for {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export account data"
+            }))
+        }
+        Err(e) => {
+            error!("Data export for {} was cancelled: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export account data"
+            }))
+        }
+    }
+}
+
+#[delete("/api/users/{user_id}")]
+pub async fn delete_user(
+    path: web::Path<Uuid>,
+    caller: SessionUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    if caller.id != user_id && !caller.is_admin() {
+        return forbidden();
+    }
+    // A stolen or forgotten session shouldn't be enough to erase an account
+    if !caller.signed_in_within(REAUTH_WINDOW_MINUTES) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Sign in again to delete this account",
+            "reauthenticate": true
+        }));
+    }
+
+    let result: Result<Deletion, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let outcome = delete_account(&mut tx, caller.id, user_id).await?;
+        if matches!(outcome, Deletion::Deleted { .. }) {
+            tx.commit().await?;
+        }
+        Ok(outcome)
+    }
+    .await;
+
+    match result {
+        Ok(Deletion::Deleted { listings, files }) => {
+            if listings > 0 {
+                state.property_cache.invalidate_all();
+            }
+            for path in &files {
+                if let Err(e) = async_fs::remove_file(path).await {
+                    warn!("Failed to remove file {} of deleted user: {}", path, e);
+                }
+            }
+            info!(
+                "User {} deleted by {} ({} listings, {} files)",
+                user_id,
+                caller.id,
+                listings,
+                files.len()
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "deleted": true,
+                "user_id": user_id,
+                "listings_deleted": listings,
+                "media_files_deleted": files.len()
+            }))
+        }
+        Ok(Deletion::NotFound) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        })),
+        Ok(Deletion::Blocked(message)) => {
+            HttpResponse::Conflict().json(serde_json::json!({ "error": message }))
+        }
+        Err(e) => {
+            error!("Failed to delete user {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete account"
+            }))
+        }
+    }
+}
@@ -0,0 +1,403 @@
+// JARVIS2026 - Distance and commute-time helpers for "near me" searches
+// Commute times start as straight-line estimates; a routing provider can be
+// plugged in later by implementing `CommuteEstimator`. Radius search runs in
+// PostGIS: every listing with coordinates gets a `geo_point` geography, so
+// `/api/search/nearby` can filter and order by distance on a spatial index.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+
+use crate::formatting::{Locale, PriceDisplay};
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::{AppState, Property, PropertyView};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// Roads are rarely straight; scale crow-flies distance up for travel estimates.
+const DETOUR_FACTOR: f64 = 1.3;
+const MAX_COMMUTE_MINUTES: f64 = 240.0;
+const MAX_RADIUS_KM: f64 = 100.0;
+const DEFAULT_NEARBY_LIMIT: i64 = 20;
+const MAX_NEARBY_LIMIT: i64 = 100;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommuteMode {
+    Driving,
+    Transit,
+    Cycling,
+    Walking,
+}
+
+/// Query-string options accepted by search endpoints.
+#[derive(Debug, Deserialize)]
+pub struct ProximityParams {
+    /// `lat,lng`
+    near: Option<String>,
+    sort: Option<String>,
+    commute_minutes: Option<f64>,
+    commute_mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProximityRequest {
+    pub origin: (f64, f64),
+    pub sort_by_distance: bool,
+    pub commute: Option<(CommuteMode, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Proximity {
+    distance_km: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commute_minutes: Option<f64>,
+}
+
+pub trait CommuteEstimator: Send + Sync {
+    /// Estimated travel minutes from `origin` to each destination, `None` when unknown.
+    fn estimate_minutes<'a>(
+        &'a self,
+        origin: (f64, f64),
+        destinations: &'a [(f64, f64)],
+        mode: CommuteMode,
+    ) -> BoxFuture<'a, Vec<Option<f64>>>;
+}
+
+/// Distance at an average door-to-door speed for the mode.
+pub struct StraightLineEstimator;
+
+#[derive(Deserialize)]
+pub struct NearbyQuery {
+    lat: f64,
+    lng: f64,
+    radius_km: f64,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct NearbyRow {
+    #[sqlx(flatten)]
+    property: Property,
+    distance_km: f64,
+    total: i64,
+}
+
+#[derive(Serialize)]
+struct NearbyListing {
+    #[serde(flatten)]
+    view: PropertyView,
+    distance_km: f64,
+}
+
+#[derive(Serialize)]
+struct NearbyResponse {
+    lat: f64,
+    lng: f64,
+    radius_km: f64,
+    /// Nearest first
+    results: Vec<NearbyListing>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("CREATE EXTENSION IF NOT EXISTS postgis")
+        .execute(pool)
+        .await?;
+
+    // Kept in step with latitude/longitude by Postgres itself
+    sqlx::query(
+        r#"ALTER TABLE properties ADD COLUMN IF NOT EXISTS geo_point geography(Point, 4326)
+            GENERATED ALWAYS AS (
+                CASE WHEN latitude IS NOT NULL AND longitude IS NOT NULL
+                THEN ST_SetSRID(ST_MakePoint(longitude, latitude), 4326)::geography
+                END
+            ) STORED"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_properties_geo_point ON properties USING GIST (geo_point)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// DISTANCE
+// ============================================================================
+
+/// Great-circle distance in kilometres between two `(lat, lng)` points.
+pub fn haversine_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lng1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lng2) = (b.0.to_radians(), b.1.to_radians());
+    let dlat = lat2 - lat1;
+    let dlng = lng2 - lng1;
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+pub fn parse_lat_lng(raw: &str) -> Option<(f64, f64)> {
+    let (lat, lng) = raw.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lng: f64 = lng.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng)).then_some((lat, lng))
+}
+
+impl CommuteMode {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "driving" | "drive" => Some(CommuteMode::Driving),
+            "transit" => Some(CommuteMode::Transit),
+            "cycling" | "bike" => Some(CommuteMode::Cycling),
+            "walking" | "walk" => Some(CommuteMode::Walking),
+            _ => None,
+        }
+    }
+
+    /// Average speed in km/h, tuned for congested Indonesian cities.
+    fn average_speed_kmh(self) -> f64 {
+        match self {
+            CommuteMode::Driving => 25.0,
+            CommuteMode::Transit => 18.0,
+            CommuteMode::Cycling => 14.0,
+            CommuteMode::Walking => 4.5,
+        }
+    }
+
+    /// Straight-line radius that can possibly be covered in `minutes`.
+    pub fn max_reach_km(self, minutes: f64) -> f64 {
+        self.average_speed_kmh() * minutes / 60.0
+    }
+}
+
+impl CommuteEstimator for StraightLineEstimator {
+    fn estimate_minutes<'a>(
+        &'a self,
+        origin: (f64, f64),
+        destinations: &'a [(f64, f64)],
+        mode: CommuteMode,
+    ) -> BoxFuture<'a, Vec<Option<f64>>> {
+        Box::pin(async move {
+            destinations
+                .iter()
+                .map(|dest| {
+                    let km = haversine_km(origin, *dest) * DETOUR_FACTOR;
+                    Some(km / mode.average_speed_kmh() * 60.0)
+                })
+                .collect()
+        })
+    }
+}
+
+// ============================================================================
+// SEARCH INTEGRATION
+// ============================================================================
+
+impl ProximityParams {
+    /// `Ok(None)` when no proximity options were given.
+    pub fn resolve(&self) -> Result<Option<ProximityRequest>, String> {
+        let sort_by_distance = self.sort.as_deref() == Some("distance");
+        let wants_commute = self.commute_minutes.is_some() || self.commute_mode.is_some();
+
+        let Some(raw) = self.near.as_deref() else {
+            if sort_by_distance || wants_commute {
+                return Err(
+                    "near=lat,lng is required for distance sorting and commute filters".to_string(),
+                );
+            }
+            return Ok(None);
+        };
+        let origin = parse_lat_lng(raw).ok_or_else(|| "near must be lat,lng".to_string())?;
+
+        let commute = match (self.commute_minutes, self.commute_mode.as_deref()) {
+            (None, None) => None,
+            (Some(minutes), mode) => {
+                if !(minutes > 0.0 && minutes <= MAX_COMMUTE_MINUTES) {
+                    return Err(format!(
+                        "commute_minutes must be between 0 and {}",
+                        MAX_COMMUTE_MINUTES
+                    ));
+                }
+                let mode = match mode {
+                    Some(m) => CommuteMode::parse(m).ok_or_else(|| {
+                        "commute_mode must be driving, transit, cycling or walking".to_string()
+                    })?,
+                    None => CommuteMode::Driving,
+                };
+                Some((mode, minutes))
+            }
+            (None, Some(_)) => {
+                return Err("commute_mode requires commute_minutes".to_string());
+            }
+        };
+
+        Ok(Some(ProximityRequest {
+            origin,
+            sort_by_distance,
+            commute,
+        }))
+    }
+}
+
+/// Annotates results with distance (and commute time), drops listings outside
+/// the commute budget, and optionally sorts nearest first.
+pub async fn apply_proximity(
+    results: Vec<Property>,
+    request: &ProximityRequest,
+    estimator: &dyn CommuteEstimator,
+) -> Vec<(Property, Option<Proximity>)> {
+    let coords: Vec<Option<(f64, f64)>> = results
+        .iter()
+        .map(|p| p.latitude.zip(p.longitude))
+        .collect();
+
+    let commute_minutes: Vec<Option<f64>> = match request.commute {
+        Some((mode, _)) => {
+            let known: Vec<(f64, f64)> = coords.iter().flatten().copied().collect();
+            let mut estimates = estimator
+                .estimate_minutes(request.origin, &known, mode)
+                .await
+                .into_iter();
+            coords
+                .iter()
+                .map(|c| c.and_then(|_| estimates.next().flatten()))
+                .collect()
+        }
+        None => vec![None; results.len()],
+    };
+
+    let mut annotated: Vec<(Property, Option<Proximity>)> = results
+        .into_iter()
+        .zip(coords)
+        .zip(commute_minutes)
+        .filter_map(|((property, coord), minutes)| {
+            if let Some((_, budget)) = request.commute {
+                if !minutes.is_some_and(|m| m <= budget) {
+                    return None;
+                }
+            }
+            let proximity = Proximity {
+                distance_km: coord.map(|c| haversine_km(request.origin, c)),
+                commute_minutes: minutes,
+            };
+            Some((property, Some(proximity)))
+        })
+        .collect();
+
+    if request.sort_by_distance {
+        annotated.sort_by(|(_, a), (_, b)| {
+            let a = a.and_then(|p| p.distance_km).unwrap_or(f64::INFINITY);
+            let b = b.and_then(|p| p.distance_km).unwrap_or(f64::INFINITY);
+            a.total_cmp(&b)
+        });
+    }
+
+    annotated
+}
+
+impl NearbyQuery {
+    fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&self.lat) || !(-180.0..=180.0).contains(&self.lng) {
+            return Err("lat must be within ±90 and lng within ±180".to_string());
+        }
+        if !(self.radius_km > 0.0 && self.radius_km <= MAX_RADIUS_KM) {
+            return Err(format!("radius_km must be between 0 and {}", MAX_RADIUS_KM));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Active listings within `radius_km` of a point, nearest first. Listings
+/// without coordinates never match.
+#[get("/api/search/nearby")]
+pub async fn search_nearby(
+    query: web::Query<NearbyQuery>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if let Err(message) = query.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_NEARBY_LIMIT)
+        .clamp(1, MAX_NEARBY_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    // `<->` on the GiST index walks listings outward from the origin
+    let rows = sqlx::query_as::<_, NearbyRow>(&format!(
+        r#"WITH origin AS (
+            SELECT ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography AS point
+        )
+        SELECT p.*, ST_Distance(p.geo_point, origin.point) / 1000.0 AS distance_km,
+               COUNT(*) OVER () AS total
+        FROM properties p, origin
+        WHERE ST_DWithin(p.geo_point, origin.point, $3 * 1000.0)
+          AND {} AND {}
+        ORDER BY p.geo_point <-> origin.point
+        LIMIT $4 OFFSET $5"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(query.lat)
+    .bind(query.lng)
+    .bind(query.radius_km)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let total = rows.first().map_or(0, |row| row.total);
+            info!(
+                "Nearby search at {},{} within {} km found {} listings",
+                query.lat, query.lng, query.radius_km, total
+            );
+            let results = rows
+                .into_iter()
+                .map(|row| NearbyListing {
+                    view: PropertyView {
+                        price_display: PriceDisplay::new(row.property.price, locale),
+                        property: row.property,
+                    },
+                    distance_km: row.distance_km,
+                })
+                .collect();
+            HttpResponse::Ok().json(NearbyResponse {
+                lat: query.lat,
+                lng: query.lng,
+                radius_km: query.radius_km,
+                results,
+                total,
+                limit,
+                offset,
+            })
+        }
+        Err(e) => {
+            error!("Nearby search failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Search failed"
+            }))
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// JARVIS2026 - IP geolocation
+// Approximates the caller's region from their IP so responses can default to
+// a sensible language, currency and search area. Lookups go through
+// `RegionResolver`; a MaxMind GeoIP2/GeoLite2 City database is used when
+// `GEOIP_DB_PATH` is set. The caller's address is the connection's peer;
+// `X-Forwarded-For` is only believed when the peer is one of the proxies in
+// `TRUSTED_PROXIES`, since anyone else can write whatever they like there.
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use ipnet::IpNet;
+use maxminddb::geoip2;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+use crate::formatting::Locale;
+use crate::timezones;
+use crate::AppState;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Region {
+    /// ISO 3166-1 alpha-2, e.g. `ID`
+    pub country_code: String,
+    pub subdivision: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub time_zone: Option<String>,
+}
+
+pub trait RegionResolver: Send + Sync {
+    fn resolve(&self, ip: IpAddr) -> Option<Region>;
+}
+
+pub struct MaxMindResolver {
+    reader: maxminddb::Reader<Vec<u8>>,
+}
+
+/// Used when no database is configured; every lookup misses.
+pub struct NoRegionResolver;
+
+#[derive(Serialize)]
+struct SearchDefaults {
+    location: Option<String>,
+    /// `lat,lng`, usable as the `near` search parameter
+    near: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RequestContext {
+    region: Option<Region>,
+    locale: &'static str,
+    currency: &'static str,
+    timezone: String,
+    search: SearchDefaults,
+}
+
+// ============================================================================
+// RESOLVERS
+// ============================================================================
+
+impl RegionResolver for MaxMindResolver {
+    fn resolve(&self, ip: IpAddr) -> Option<Region> {
+        let city: geoip2::City = self.reader.lookup(ip).ok()?;
+        let english = |names: Option<std::collections::BTreeMap<&str, &str>>| {
+            names.and_then(|n| n.get("en").map(|s| s.to_string()))
+        };
+        let location = city.location;
+        Some(Region {
+            country_code: city.country?.iso_code?.to_string(),
+            subdivision: city
+                .subdivisions
+                .and_then(|s| s.into_iter().next())
+                .and_then(|s| english(s.names)),
+            city: city.city.and_then(|c| english(c.names)),
+            latitude: location.as_ref().and_then(|l| l.latitude),
+            longitude: location.as_ref().and_then(|l| l.longitude),
+            time_zone: location.and_then(|l| l.time_zone.map(str::to_string)),
+        })
+    }
+}
+
+impl RegionResolver for NoRegionResolver {
+    fn resolve(&self, _: IpAddr) -> Option<Region> {
+        None
+    }
+}
+
+pub fn resolver_from_env() -> Box<dyn RegionResolver> {
+    let Ok(path) = std::env::var("GEOIP_DB_PATH") else {
+        return Box::new(NoRegionResolver);
+    };
+    match maxminddb::Reader::open_readfile(&path) {
+        Ok(reader) => {
+            info!("Loaded GeoIP database from {}", path);
+            Box::new(MaxMindResolver { reader })
+        }
+        Err(e) => {
+            warn!("Failed to open GeoIP database {}: {}", path, e);
+            Box::new(NoRegionResolver)
+        }
+    }
+}
+
+fn parse_address(raw: &str) -> Option<IpAddr> {
+    raw.parse::<IpAddr>()
+        .ok()
+        .or_else(|| raw.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// Proxies in front of the server, from `TRUSTED_PROXIES` (comma-separated
+/// addresses or CIDRs, e.g. `10.0.0.0/8,127.0.0.1`). Empty by default.
+fn trusted_proxies() -> &'static [IpNet] {
+    static TRUSTED: OnceLock<Vec<IpNet>> = OnceLock::new();
+    TRUSTED.get_or_init(|| {
+        parse_trusted_proxies(&std::env::var("TRUSTED_PROXIES").unwrap_or_default())
+    })
+}
+
+fn parse_trusted_proxies(raw: &str) -> Vec<IpNet> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let net = entry
+                .parse::<IpNet>()
+                .ok()
+                .or_else(|| entry.parse::<IpAddr>().ok().map(IpNet::from));
+            if net.is_none() {
+                warn!("Ignoring invalid TRUSTED_PROXIES entry '{}'", entry);
+            }
+            net
+        })
+        .collect()
+}
+
+/// The peer itself, or when the peer is a trusted proxy, the nearest address
+/// in `X-Forwarded-For` that isn't one. Proxies append to the header, so it
+/// is read right to left and whatever the client wrote on the left is only
+/// reached through proxies we trust.
+fn resolve_client_ip(peer: IpAddr, forwarded_for: &str, trusted: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    let mut client = peer;
+    for hop in forwarded_for.rsplit(',') {
+        if !is_trusted(&client) {
+            break;
+        }
+        match parse_address(hop.trim()) {
+            Some(ip) => client = ip,
+            None => break,
+        }
+    }
+    client
+}
+
+/// The address a request came from, see `resolve_client_ip`.
+pub fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let forwarded_for = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|v| v.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",");
+    Some(resolve_client_ip(peer, &forwarded_for, trusted_proxies()))
+}
+
+pub fn region_for_request(req: &HttpRequest) -> Option<Region> {
+    let state = req.app_data::<web::Data<AppState>>()?;
+    state.region_resolver.resolve(client_ip(req)?)
+}
+
+// ============================================================================
+// DEFAULTS
+// ============================================================================
+
+pub fn locale_for_country(country_code: &str) -> Locale {
+    match country_code {
+        "ID" => Locale::Indonesian,
+        _ => Locale::English,
+    }
+}
+
+fn currency_for_country(country_code: &str) -> &'static str {
+    match country_code {
+        "ID" => "IDR",
+        "SG" => "SGD",
+        "MY" => "MYR",
+        "AU" => "AUD",
+        "GB" => "GBP",
+        "JP" => "JPY",
+        "AT" | "BE" | "DE" | "ES" | "FI" | "FR" | "IE" | "IT" | "NL" | "PT" => "EUR",
+        _ => "USD",
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Defaults derived from the caller's IP. Explicit `?locale=` or
+/// `Accept-Language` still wins for the locale.
+#[get("/api/context")]
+pub async fn request_context(req: HttpRequest, locale: Locale) -> impl Responder {
+    let region = region_for_request(&req);
+
+    let currency = region
+        .as_ref()
+        .map(|r| currency_for_country(&r.country_code))
+        .unwrap_or("IDR");
+    let timezone = region
+        .as_ref()
+        .and_then(|r| r.time_zone.as_deref())
+        .and_then(|tz| timezones::parse(tz).ok())
+        .unwrap_or(timezones::DEFAULT_TIMEZONE);
+    let search = SearchDefaults {
+        location: region.as_ref().and_then(|r| r.city.clone()),
+        near: region
+            .as_ref()
+            .and_then(|r| r.latitude.zip(r.longitude))
+            .map(|(lat, lng)| format!("{:.4},{:.4}", lat, lng)),
+    };
+
+    HttpResponse::Ok().json(RequestContext {
+        region,
+        locale: locale.code(),
+        currency,
+        timezone: timezone.name().to_string(),
+        search,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn forwarding_headers_are_ignored_from_untrusted_peers() {
+        let trusted = parse_trusted_proxies("10.0.0.0/8");
+        assert_eq!(
+            resolve_client_ip(ip("203.0.113.9"), "198.51.100.1", &trusted),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn trusted_proxies_are_skipped_right_to_left() {
+        let trusted = parse_trusted_proxies("10.0.0.0/8, 127.0.0.1");
+        // The client forged the left-most entry; the proxies appended the rest
+        assert_eq!(
+            resolve_client_ip(ip("127.0.0.1"), "1.2.3.4, 203.0.113.9, 10.1.2.3", &trusted),
+            ip("203.0.113.9")
+        );
+        assert_eq!(
+            resolve_client_ip(ip("127.0.0.1"), "", &trusted),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn invalid_trusted_proxy_entries_are_dropped() {
+        assert_eq!(parse_trusted_proxies("nonsense, ,192.168.1.1").len(), 1);
+    }
+}
@@ -0,0 +1,423 @@
+// JARVIS2026 - On-the-fly image variants
+// `/img/{media_id}` resizes uploaded images to the requested size and format
+// and caches each variant on disk. When `IMAGE_SIGNING_KEY` is set, every
+// request must carry a `sig` so clients can't mint unlimited variants.
+
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse, Responder};
+use hmac::{Hmac, Mac};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::io::Cursor;
+use tokio::fs as async_fs;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::media_files;
+use crate::secrets;
+use crate::AppState;
+
+const IMG_CACHE_DIR: &str = "img-cache";
+const MAX_DIMENSION: u32 = 4096;
+/// Widths of the listing-card and hero images
+pub const THUMB_WIDTH: u32 = 640;
+const LARGE_WIDTH: u32 = 2048;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct VariantQuery {
+    w: Option<u32>,
+    h: Option<u32>,
+    fit: Option<String>,
+    format: Option<String>,
+    sig: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fit {
+    /// Scale to fit inside the box, keeping aspect ratio
+    Contain,
+    /// Fill the box, cropping the overflow
+    Cover,
+    /// Stretch to exactly the box
+    Fill,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Webp,
+    Jpeg,
+    Png,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Variant {
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: Fit,
+    format: OutputFormat,
+}
+
+// ============================================================================
+// PARAMETERS
+// ============================================================================
+
+impl Fit {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        match raw.unwrap_or("contain") {
+            "contain" => Some(Fit::Contain),
+            "cover" => Some(Fit::Cover),
+            "fill" => Some(Fit::Fill),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Fit::Contain => "contain",
+            Fit::Cover => "cover",
+            Fit::Fill => "fill",
+        }
+    }
+}
+
+impl OutputFormat {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        match raw.unwrap_or("webp") {
+            "webp" => Some(OutputFormat::Webp),
+            "jpeg" | "jpg" => Some(OutputFormat::Jpeg),
+            "png" => Some(OutputFormat::Png),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Webp => "webp",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Webp => "image/webp",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Png => "image/png",
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            OutputFormat::Webp => ImageFormat::WebP,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::Png => ImageFormat::Png,
+        }
+    }
+}
+
+impl Variant {
+    fn from_query(query: &VariantQuery) -> Result<Self, String> {
+        let dimension = |value: Option<u32>, name: &str| match value {
+            Some(v) if v == 0 || v > MAX_DIMENSION => {
+                Err(format!("{} must be between 1 and {}", name, MAX_DIMENSION))
+            }
+            other => Ok(other),
+        };
+        let width = dimension(query.w, "w")?;
+        let height = dimension(query.h, "h")?;
+        let fit = Fit::parse(query.fit.as_deref())
+            .ok_or_else(|| "fit must be contain, cover or fill".to_string())?;
+        let format = OutputFormat::parse(query.format.as_deref())
+            .ok_or_else(|| "format must be webp, jpeg or png".to_string())?;
+        if fit != Fit::Contain && (width.is_none() || height.is_none()) {
+            return Err("fit=cover and fit=fill need both w and h".to_string());
+        }
+        Ok(Variant {
+            width,
+            height,
+            fit,
+            format,
+        })
+    }
+
+    /// The string covered by `sig`.
+    fn canonical(&self, media_id: Uuid) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            media_id,
+            self.width.unwrap_or(0),
+            self.height.unwrap_or(0),
+            self.fit.name(),
+            self.format.extension()
+        )
+    }
+
+    fn cache_path(&self, media_id: Uuid) -> String {
+        format!(
+            "{}/{}-{}x{}-{}.{}",
+            IMG_CACHE_DIR,
+            media_id,
+            self.width.unwrap_or(0),
+            self.height.unwrap_or(0),
+            self.fit.name(),
+            self.format.extension()
+        )
+    }
+}
+
+fn sign(key: &str, payload: &str) -> Option<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).ok()?;
+    mac.update(payload.as_bytes());
+    Some(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Path of a WebP variant `width` pixels wide (full size when `None`),
+/// signed when `IMAGE_SIGNING_KEY` is set.
+pub fn variant_path(media_id: Uuid, width: Option<u32>) -> String {
+    let variant = Variant {
+        width,
+        height: None,
+        fit: Fit::Contain,
+        format: OutputFormat::Webp,
+    };
+    let mut path = match width {
+        Some(width) => format!("/img/{}?w={}&format=webp", media_id, width),
+        None => format!("/img/{}?format=webp", media_id),
+    };
+    let key = secrets::var("IMAGE_SIGNING_KEY").ok();
+    if let Some(sig) = key.and_then(|key| sign(&key, &variant.canonical(media_id))) {
+        path.push_str("&sig=");
+        path.push_str(&sig);
+    }
+    path
+}
+
+/// Expected `sig`: hex HMAC-SHA256 of `{media_id}:{w}:{h}:{fit}:{ext}` with
+/// missing dimensions as 0, e.g. `…:800:0:contain:webp`.
+fn verify_signature(key: &str, payload: &str, sig: Option<&str>) -> bool {
+    let Some(sig) = sig.and_then(|s| hex::decode(s).ok()) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(key.as_bytes()) else {
+        return false;
+    };
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&sig).is_ok()
+}
+
+// ============================================================================
+// RENDERING
+// ============================================================================
+
+fn render_variant(source: &[u8], variant: Variant) -> Result<Vec<u8>, image::ImageError> {
+    let img = image::load_from_memory(source)?;
+    let (src_w, src_h) = (img.width(), img.height());
+
+    // Never upscale beyond the original
+    let width = variant.width.map(|w| w.min(src_w));
+    let height = variant.height.map(|h| h.min(src_h));
+
+    let resized = match (variant.fit, width, height) {
+        (_, None, None) => img,
+        (Fit::Cover, Some(w), Some(h)) => img.resize_to_fill(w, h, FilterType::Lanczos3),
+        (Fit::Fill, Some(w), Some(h)) => img.resize_exact(w, h, FilterType::Lanczos3),
+        (_, w, h) => img.resize(w.unwrap_or(src_w), h.unwrap_or(src_h), FilterType::Lanczos3),
+    };
+
+    // JPEG has no alpha channel
+    let resized = match variant.format {
+        OutputFormat::Jpeg => DynamicImage::ImageRgb8(resized.to_rgb8()),
+        _ => resized,
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    resized.write_to(&mut out, variant.format.image_format())?;
+    Ok(out.into_inner())
+}
+
+/// Drops the cached variants of deleted media.
+pub async fn remove_variants(media_ids: &[Uuid]) {
+    if media_ids.is_empty() {
+        return;
+    }
+    let Ok(mut entries) = async_fs::read_dir(IMG_CACHE_DIR).await else {
+        return;
+    };
+    let prefixes: Vec<String> = media_ids.iter().map(|id| format!("{}-", id)).collect();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if prefixes
+            .iter()
+            .any(|prefix| name.starts_with(prefix.as_str()))
+        {
+            if let Err(e) = async_fs::remove_file(entry.path()).await {
+                warn!("Failed to remove image variant {}: {}", name, e);
+            }
+        }
+    }
+}
+
+/// Renders the card and hero variants of a listing's first photo into the
+/// cache and points the listing at them. `Ok(false)` when it has no photos.
+pub async fn generate_listing_images(pool: &PgPool, property_id: Uuid) -> Result<bool, String> {
+    let Some((media_id, file_path)) = sqlx::query_as::<_, (Uuid, String)>(
+        r#"SELECT id, file_path FROM media_uploads
+        WHERE property_id = $1 AND file_type = 'image'
+          AND moderation_status NOT IN ('rejected', 'unpublished')
+        ORDER BY uploaded_at, id
+        LIMIT 1"#,
+    )
+    .bind(property_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    else {
+        return Ok(false);
+    };
+
+    let source = async_fs::read(&file_path)
+        .await
+        .map_err(|e| format!("{} unreadable: {}", file_path, e))?;
+    async_fs::create_dir_all(IMG_CACHE_DIR).await.ok();
+    for width in [THUMB_WIDTH, LARGE_WIDTH] {
+        let variant = Variant {
+            width: Some(width),
+            height: None,
+            fit: Fit::Contain,
+            format: OutputFormat::Webp,
+        };
+        let source = source.clone();
+        let body = web::block(move || render_variant(&source, variant))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        async_fs::write(variant.cache_path(media_id), &body)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    sqlx::query("UPDATE properties SET image_thumb_webp = $2, image_large_webp = $3 WHERE id = $1")
+        .bind(property_id)
+        .bind(variant_path(media_id, Some(THUMB_WIDTH)))
+        .bind(variant_path(media_id, Some(LARGE_WIDTH)))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/img/{media_id}")]
+pub async fn image_variant(
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+    query: web::Query<VariantQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let media_id = path.into_inner();
+
+    let variant = match Variant::from_query(&query) {
+        Ok(variant) => variant,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+
+    if let Ok(key) = secrets::var("IMAGE_SIGNING_KEY") {
+        if !verify_signature(&key, &variant.canonical(media_id), query.sig.as_deref()) {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Invalid or missing signature"
+            }));
+        }
+    }
+
+    let cache_path = variant.cache_path(media_id);
+
+    let (file_path, content_hash, uploaded_at) =
+        match sqlx::query_as::<_, (String, String, chrono::DateTime<chrono::Utc>)>(
+            r#"SELECT file_path, content_hash, uploaded_at FROM media_uploads
+        WHERE id = $1 AND file_type = 'image'
+          AND moderation_status NOT IN ('rejected', 'unpublished')"#,
+        )
+        .bind(media_id)
+        .fetch_optional(&state.db)
+        .await
+        {
+            Ok(Some(media)) => media,
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Image not found"
+                }))
+            }
+            Err(e) => {
+                error!("Failed to look up media {}: {}", media_id, e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to load image"
+                }));
+            }
+        };
+
+    let validators = media_files::Validators::new(
+        &content_hash,
+        Some(&variant.canonical(media_id)),
+        uploaded_at,
+    );
+    if let Some(response) = validators.not_modified(&req) {
+        return response;
+    }
+    let respond = |body: Vec<u8>| {
+        let mut response = HttpResponse::Ok()
+            .content_type(variant.format.content_type())
+            .insert_header((header::CACHE_CONTROL, "public, max-age=31536000, immutable"))
+            .body(body);
+        validators.apply(&mut response);
+        response
+    };
+
+    // Checked after the lookup so moderated-away media stop being served
+    if let Ok(cached) = async_fs::read(&cache_path).await {
+        return respond(cached);
+    }
+
+    let source = match async_fs::read(&file_path).await {
+        Ok(source) => source,
+        Err(e) => {
+            error!("Media {} file {} unreadable: {}", media_id, file_path, e);
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Image not found"
+            }));
+        }
+    };
+
+    let body = match web::block(move || render_variant(&source, variant)).await {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => {
+            warn!("Failed to resize media {}: {}", media_id, e);
+            return HttpResponse::UnprocessableEntity().json(serde_json::json!({
+                "error": "Image could not be processed"
+            }));
+        }
+        Err(e) => {
+            error!("Image resize task failed: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to resize image"
+            }));
+        }
+    };
+
+    async_fs::create_dir_all(IMG_CACHE_DIR).await.ok();
+    if let Err(e) = async_fs::write(&cache_path, &body).await {
+        warn!("Failed to cache image variant {}: {}", cache_path, e);
+    }
+
+    respond(body)
+}
@@ -0,0 +1,467 @@
+// JARVIS2026 - Listing inquiries
+// A buyer opens an inquiry on a listing and the owner replies in the same
+// thread. The first owner reply is timestamped so response times can be
+// measured per agent.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::auto_replies;
+use crate::captcha;
+use crate::notifications::notify;
+use crate::presence::{self, PresenceStatus};
+use crate::AppState;
+
+const MAX_MESSAGE_LEN: usize = 2000;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct MessageRequest {
+    message: String,
+    /// Honeypot; see `captcha`
+    website: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Inquiry {
+    id: Uuid,
+    property_id: Uuid,
+    property_title: Option<String>,
+    owner_user_id: Uuid,
+    sender_user_id: Uuid,
+    created_at: DateTime<Utc>,
+    first_response_at: Option<DateTime<Utc>>,
+    last_message_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct InquiryMessage {
+    id: Uuid,
+    sender_user_id: Uuid,
+    body: String,
+    /// Sent by the owner's auto-responder rather than typed by them
+    automated: bool,
+    created_at: DateTime<Utc>,
+}
+
+/// An inquiry as one of its parties sees it.
+#[derive(Serialize)]
+struct InquiryView {
+    #[serde(flatten)]
+    inquiry: Inquiry,
+    /// Whether the other party is around, see `presence`
+    counterpart: PresenceStatus,
+}
+
+#[derive(Serialize)]
+struct InquiryThread {
+    #[serde(flatten)]
+    view: InquiryView,
+    messages: Vec<InquiryMessage>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS inquiries (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            owner_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            sender_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            first_response_at TIMESTAMPTZ,
+            last_message_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_inquiries_owner ON inquiries(owner_user_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_inquiries_sender ON inquiries(sender_user_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS inquiry_messages (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            inquiry_id UUID NOT NULL REFERENCES inquiries(id) ON DELETE CASCADE,
+            sender_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            body TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_inquiry_messages_thread ON inquiry_messages(inquiry_id, created_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+const INQUIRY_COLUMNS: &str = r#"i.id, i.property_id, p.title AS property_title, i.owner_user_id,
+    i.sender_user_id, i.created_at, i.first_response_at, i.last_message_at"#;
+
+fn validate_message(raw: &str) -> Result<&str, HttpResponse> {
+    let message = raw.trim();
+    if message.is_empty() || message.chars().count() > MAX_MESSAGE_LEN {
+        return Err(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("message must be 1-{} characters", MAX_MESSAGE_LEN)
+        })));
+    }
+    Ok(message)
+}
+
+async fn fetch_inquiry(pool: &PgPool, inquiry_id: Uuid) -> Result<Option<Inquiry>, sqlx::Error> {
+    sqlx::query_as::<_, Inquiry>(&format!(
+        "SELECT {} FROM inquiries i JOIN properties p ON p.id = i.property_id WHERE i.id = $1",
+        INQUIRY_COLUMNS
+    ))
+    .bind(inquiry_id)
+    .fetch_optional(pool)
+    .await
+}
+
+impl Inquiry {
+    fn counterpart_of(&self, user_id: Uuid) -> Uuid {
+        if user_id == self.owner_user_id {
+            self.sender_user_id
+        } else {
+            self.owner_user_id
+        }
+    }
+}
+
+/// Pairs each inquiry with its other party's presence.
+async fn with_presence(
+    state: &AppState,
+    user_id: Uuid,
+    inquiries: Vec<Inquiry>,
+) -> Result<Vec<InquiryView>, sqlx::Error> {
+    let counterparts: Vec<Uuid> = inquiries
+        .iter()
+        .map(|i| i.counterpart_of(user_id))
+        .collect();
+    let last_seen = presence::last_seen(&state.db, &counterparts).await?;
+    Ok(inquiries
+        .into_iter()
+        .map(|inquiry| InquiryView {
+            counterpart: state.presence.status(
+                inquiry.counterpart_of(user_id),
+                inquiry.id,
+                &last_seen,
+            ),
+            inquiry,
+        })
+        .collect())
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/properties/{id}/inquiries")]
+pub async fn create_inquiry(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<MessageRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    if captcha::honeypot_tripped(req.website.as_deref()) {
+        return captcha::honeypot_response();
+    }
+    let message = match validate_message(&req.message) {
+        Ok(message) => message,
+        Err(response) => return response,
+    };
+
+    let owner_user_id =
+        match sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM properties WHERE id = $1")
+            .bind(property_id)
+            .fetch_optional(&state.db)
+            .await
+        {
+            Ok(Some(Some(owner))) if owner == user.id => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "You can't send an inquiry about your own listing"
+                }))
+            }
+            Ok(Some(Some(owner))) => owner,
+            Ok(_) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Property not found"
+                }))
+            }
+            Err(e) => {
+                error!("Failed to look up property {}: {}", property_id, e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to send inquiry"
+                }));
+            }
+        };
+
+    let result: Result<Uuid, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let inquiry_id = sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO inquiries (property_id, owner_user_id, sender_user_id)
+            VALUES ($1, $2, $3) RETURNING id"#,
+        )
+        .bind(property_id)
+        .bind(owner_user_id)
+        .bind(user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO inquiry_messages (inquiry_id, sender_user_id, body) VALUES ($1, $2, $3)",
+        )
+        .bind(inquiry_id)
+        .bind(user.id)
+        .bind(message)
+        .execute(&mut *tx)
+        .await?;
+
+        notify(
+            &mut *tx,
+            owner_user_id,
+            "inquiry_received",
+            serde_json::json!({ "inquiry_id": inquiry_id, "property_id": property_id }),
+        )
+        .await?;
+
+        if auto_replies::reply_if_away(
+            &mut tx,
+            &state.public_base_url,
+            inquiry_id,
+            owner_user_id,
+            property_id,
+        )
+        .await?
+        {
+            notify(
+                &mut *tx,
+                user.id,
+                "inquiry_message",
+                serde_json::json!({ "inquiry_id": inquiry_id, "property_id": property_id }),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(inquiry_id)
+    }
+    .await;
+
+    match result {
+        Ok(inquiry_id) => {
+            info!("Inquiry {} opened on {}", inquiry_id, property_id);
+            match fetch_inquiry(&state.db, inquiry_id).await {
+                Ok(Some(inquiry)) => HttpResponse::Ok().json(inquiry),
+                _ => HttpResponse::Ok().json(serde_json::json!({ "id": inquiry_id })),
+            }
+        }
+        Err(e) => {
+            error!("Failed to create inquiry on {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to send inquiry"
+            }))
+        }
+    }
+}
+
+#[post("/api/inquiries/{id}/messages")]
+pub async fn reply_to_inquiry(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<MessageRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let inquiry_id = path.into_inner();
+    let message = match validate_message(&req.message) {
+        Ok(message) => message,
+        Err(response) => return response,
+    };
+
+    let inquiry = match fetch_inquiry(&state.db, inquiry_id).await {
+        Ok(Some(inquiry))
+            if user.id == inquiry.owner_user_id || user.id == inquiry.sender_user_id =>
+        {
+            inquiry
+        }
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Inquiry not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load inquiry {}: {}", inquiry_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to send message"
+            }));
+        }
+    };
+    let recipient = inquiry.counterpart_of(user.id);
+
+    let result: Result<InquiryMessage, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let sent = sqlx::query_as::<_, InquiryMessage>(
+            r#"INSERT INTO inquiry_messages (inquiry_id, sender_user_id, body)
+            VALUES ($1, $2, $3)
+            RETURNING id, sender_user_id, body, automated, created_at"#,
+        )
+        .bind(inquiry_id)
+        .bind(user.id)
+        .bind(message)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"UPDATE inquiries
+            SET last_message_at = $2,
+                first_response_at = CASE
+                    WHEN first_response_at IS NULL AND owner_user_id = $3 THEN $2
+                    ELSE first_response_at
+                END
+            WHERE id = $1"#,
+        )
+        .bind(inquiry_id)
+        .bind(sent.created_at)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+        notify(
+            &mut *tx,
+            recipient,
+            "inquiry_message",
+            serde_json::json!({ "inquiry_id": inquiry_id, "property_id": inquiry.property_id }),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(sent)
+    }
+    .await;
+
+    match result {
+        Ok(sent) => {
+            state
+                .presence
+                .message_sent(inquiry_id, user.id, recipient, &sent);
+            HttpResponse::Ok().json(sent)
+        }
+        Err(e) => {
+            error!("Failed to reply to inquiry {}: {}", inquiry_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to send message"
+            }))
+        }
+    }
+}
+
+#[get("/api/inquiries/{id}")]
+pub async fn get_inquiry(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let inquiry_id = path.into_inner();
+
+    let inquiry = match fetch_inquiry(&state.db, inquiry_id).await {
+        Ok(Some(inquiry))
+            if user.id == inquiry.owner_user_id || user.id == inquiry.sender_user_id =>
+        {
+            inquiry
+        }
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Inquiry not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load inquiry {}: {}", inquiry_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load inquiry"
+            }));
+        }
+    };
+
+    let thread: Result<InquiryThread, sqlx::Error> = async {
+        let messages = sqlx::query_as::<_, InquiryMessage>(
+            r#"SELECT id, sender_user_id, body, automated, created_at FROM inquiry_messages
+            WHERE inquiry_id = $1 ORDER BY created_at"#,
+        )
+        .bind(inquiry_id)
+        .fetch_all(&state.db)
+        .await?;
+        let view = with_presence(&state, user.id, vec![inquiry])
+            .await?
+            .remove(0);
+        Ok(InquiryThread { view, messages })
+    }
+    .await;
+
+    match thread {
+        Ok(thread) => HttpResponse::Ok().json(thread),
+        Err(e) => {
+            error!("Failed to load messages for inquiry {}: {}", inquiry_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load inquiry"
+            }))
+        }
+    }
+}
+
+/// Inquiries the caller sent or received, most recently active first.
+#[get("/api/users/me/inquiries")]
+pub async fn my_inquiries(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    let inquiries: Result<Vec<InquiryView>, sqlx::Error> = async {
+        let inquiries = sqlx::query_as::<_, Inquiry>(&format!(
+            r#"SELECT {} FROM inquiries i JOIN properties p ON p.id = i.property_id
+            WHERE i.owner_user_id = $1 OR i.sender_user_id = $1
+            ORDER BY i.last_message_at DESC"#,
+            INQUIRY_COLUMNS
+        ))
+        .bind(user.id)
+        .fetch_all(&state.db)
+        .await?;
+        with_presence(&state, user.id, inquiries).await
+    }
+    .await;
+
+    match inquiries {
+        Ok(inquiries) => HttpResponse::Ok().json(inquiries),
+        Err(e) => {
+            error!("Failed to load inquiries for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load inquiries"
+            }))
+        }
+    }
+}
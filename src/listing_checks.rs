@@ -0,0 +1,274 @@
+// JARVIS2026 - Pre-publish listing checks
+// Uploads are checked before anything is stored: photos must meet a minimum
+// resolution and the description must say something. Every failed check is
+// reported at once so the uploader can fix them in one go. A price far
+// outside the range of comparable listings in the same area is usually a
+// typo, so it has to be confirmed; confirmed outliers are held for
+// moderation instead of going live.
+
+use actix_web::HttpResponse;
+use image::ImageReader;
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
+use std::io::Cursor;
+use uuid::Uuid;
+
+use crate::aerial;
+use crate::models3d;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+
+const MIN_PHOTO_LONG_EDGE: u32 = 1024;
+const MIN_PHOTO_SHORT_EDGE: u32 = 720;
+const MIN_DESCRIPTION_CHARS: usize = 80;
+
+/// Fewer comparable listings than this and the price isn't judged
+const MIN_COMPARABLES: i64 = 5;
+/// Allowed distance outside the interquartile range, in IQRs of ln(price)
+const PRICE_FENCE_IQRS: f64 = 3.0;
+/// Floor for the spread so areas with near-identical prices still allow some
+/// variation (ln 1.5 ≈ 0.4)
+const MIN_LOG_PRICE_SPREAD: f64 = 0.4;
+/// Digit slips tried when suggesting a corrected price (10^-6 ..= 10^6)
+const MAX_TYPO_EXPONENT: i32 = 6;
+
+/// Moderation status of listings held back by a confirmed price outlier.
+pub const PRICE_FLAGGED_STATUS: &str = "flagged";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// What the uploader submitted, before it is stored.
+pub struct Draft<'a> {
+    pub location: &'a str,
+    pub property_type: Option<&'a str>,
+    pub price: f64,
+    pub description: &'a str,
+    /// (filename, bytes) of the regular media files
+    pub files: &'a [(String, Vec<u8>)],
+}
+
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    field: &'static str,
+    code: &'static str,
+    message: String,
+}
+
+/// A price far from its comparables, which the uploader must confirm.
+#[derive(Debug, Serialize)]
+pub struct PriceOutlier {
+    code: &'static str,
+    message: String,
+    comparables: i64,
+    typical_low: f64,
+    typical_high: f64,
+    /// The price with digits added or dropped, when that lands in range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggested_price: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+pub struct Review {
+    /// Problems that block publishing
+    pub issues: Vec<Issue>,
+    pub price_outlier: Option<PriceOutlier>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PriceRange {
+    comparables: i64,
+    q1: Option<f64>,
+    q3: Option<f64>,
+}
+
+impl Issue {
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+}
+
+// ============================================================================
+// CHECKS
+// ============================================================================
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    // The outlier the uploader confirmed, kept for the moderator
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS price_flag JSONB")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Runs every check. Publishing is blocked while `issues` is non-empty.
+pub async fn run(pool: &PgPool, draft: &Draft<'_>) -> Result<Review, sqlx::Error> {
+    let mut issues: Vec<Issue> = draft
+        .files
+        .iter()
+        .filter(|(filename, _)| is_photo(filename))
+        .filter_map(|(filename, data)| check_photo(filename, data))
+        .collect();
+
+    let description_chars = draft.description.trim().chars().count();
+    if description_chars < MIN_DESCRIPTION_CHARS {
+        issues.push(Issue {
+            field: "description",
+            code: "description_too_short",
+            message: format!(
+                "Description has {} characters; write at least {} about the property",
+                description_chars, MIN_DESCRIPTION_CHARS
+            ),
+        });
+    }
+
+    Ok(Review {
+        issues,
+        price_outlier: check_price(pool, draft).await?,
+    })
+}
+
+fn is_photo(filename: &str) -> bool {
+    !aerial::is_video_file(filename) && !models3d::is_model_file(filename)
+}
+
+fn check_photo(filename: &str, data: &[u8]) -> Option<Issue> {
+    let dimensions = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+
+    let Some((width, height)) = dimensions else {
+        return Some(Issue {
+            field: "files",
+            code: "photo_unreadable",
+            message: format!("{} is not a PNG, JPEG or WebP image", filename),
+        });
+    };
+
+    let (long_edge, short_edge) = (width.max(height), width.min(height));
+    if long_edge < MIN_PHOTO_LONG_EDGE || short_edge < MIN_PHOTO_SHORT_EDGE {
+        return Some(Issue {
+            field: "files",
+            code: "photo_resolution_too_low",
+            message: format!(
+                "{} is {}x{}; photos need at least {}x{}",
+                filename, width, height, MIN_PHOTO_LONG_EDGE, MIN_PHOTO_SHORT_EDGE
+            ),
+        });
+    }
+    None
+}
+
+/// Compares ln(price) against the interquartile range of public listings
+/// with the same location (and property type, when given).
+async fn check_price(
+    pool: &PgPool,
+    draft: &Draft<'_>,
+) -> Result<Option<PriceOutlier>, sqlx::Error> {
+    if draft.price <= 0.0 || draft.location.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let range = sqlx::query_as::<_, PriceRange>(&format!(
+        r#"SELECT COUNT(*) AS comparables,
+               percentile_cont(0.25) WITHIN GROUP (ORDER BY ln(price)) AS q1,
+               percentile_cont(0.75) WITHIN GROUP (ORDER BY ln(price)) AS q3
+        FROM properties
+        WHERE LOWER(TRIM(location)) = LOWER(TRIM($1))
+          AND ($2::TEXT IS NULL OR property_type = $2)
+          AND price > 0
+          AND {}"#,
+        PUBLIC_LISTING_CONDITION
+    ))
+    .bind(draft.location)
+    .bind(draft.property_type)
+    .fetch_one(pool)
+    .await?;
+
+    let (Some(q1), Some(q3)) = (range.q1, range.q3) else {
+        return Ok(None);
+    };
+    if range.comparables < MIN_COMPARABLES {
+        return Ok(None);
+    }
+
+    let spread = (q3 - q1).max(MIN_LOG_PRICE_SPREAD);
+    let (low, high) = (
+        q1 - PRICE_FENCE_IQRS * spread,
+        q3 + PRICE_FENCE_IQRS * spread,
+    );
+    let log_price = draft.price.ln();
+    if (low..=high).contains(&log_price) {
+        return Ok(None);
+    }
+
+    let in_range = |p: f64| (low..=high).contains(&p.ln());
+    let median = (q1 + q3) / 2.0;
+    let suggested_price = (-MAX_TYPO_EXPONENT..=MAX_TYPO_EXPONENT)
+        .filter(|exponent| *exponent != 0)
+        .map(|exponent| draft.price * 10f64.powi(exponent))
+        .filter(|candidate| in_range(*candidate))
+        .min_by(|a, b| (a.ln() - median).abs().total_cmp(&(b.ln() - median).abs()));
+
+    let direction = if log_price < low { "below" } else { "above" };
+    let mut message = format!(
+        "Price {:.0} is far {} comparable listings in {}, which mostly ask {:.0}-{:.0}",
+        draft.price,
+        direction,
+        draft.location.trim(),
+        q1.exp(),
+        q3.exp()
+    );
+    match suggested_price {
+        Some(suggested) => message.push_str(&format!("; did you mean {:.0}?", suggested)),
+        None => message.push_str("; check for missing or extra digits"),
+    }
+
+    Ok(Some(PriceOutlier {
+        code: "price_outlier",
+        message,
+        comparables: range.comparables,
+        typical_low: q1.exp().round(),
+        typical_high: q3.exp().round(),
+        suggested_price,
+    }))
+}
+
+/// Holds a listing whose outlier price was confirmed for moderation.
+pub async fn flag_price<'e, E: PgExecutor<'e>>(
+    executor: E,
+    property_id: Uuid,
+    outlier: &PriceOutlier,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE properties SET moderation_status = $2, moderated_at = NOW(), price_flag = $3 \
+         WHERE id = $1",
+    )
+    .bind(property_id)
+    .bind(PRICE_FLAGGED_STATUS)
+    .bind(serde_json::json!(outlier))
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub fn rejected_response(review: &Review) -> HttpResponse {
+    HttpResponse::UnprocessableEntity().json(serde_json::json!({
+        "error": "Listing did not pass the publishing checks",
+        "issues": review.issues,
+        "price_outlier": review.price_outlier
+    }))
+}
+
+pub fn confirmation_response(outlier: &PriceOutlier) -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "The price needs confirming; resubmit with confirm_price=true to send the listing for review",
+        "confirmation_required": true,
+        "price_outlier": outlier
+    }))
+}
@@ -0,0 +1,284 @@
+// JARVIS2026 - Listing export
+// Signed-in users download the listings matching the same filters, sort and
+// status as `/api/properties` as CSV or JSON. Rows are streamed from Postgres
+// into the response as they're read, so a large export is never held in
+// memory.
+
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::filters::{FilterSet, ListingFilterParams, ListingSort, SortParams};
+use crate::listing_status::StatusQuery;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+/// Rows buffered between the database reader and a slow client.
+const STREAM_BUFFER: usize = 64;
+/// Listings per export; narrow the filters for more
+const MAX_EXPORT_ROWS: i64 = 10_000;
+const EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "slug",
+    "title",
+    "location",
+    "price",
+    "bedrooms",
+    "bathrooms",
+    "area_sqm",
+    "latitude",
+    "longitude",
+    "property_type",
+    "certificate_type",
+    "status",
+    "created_at",
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ExportRow {
+    id: Uuid,
+    slug: Option<String>,
+    title: String,
+    location: String,
+    price: f64,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    property_type: Option<String>,
+    certificate_type: Option<String>,
+    status: String,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// What to export, moved into the task that writes it.
+struct ExportRequest {
+    format: ExportFormat,
+    filters: FilterSet,
+    sort: Option<ListingSort>,
+    status: &'static str,
+}
+
+// ============================================================================
+// FORMATS
+// ============================================================================
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn header(self) -> web::Bytes {
+        match self {
+            ExportFormat::Csv => csv_record(EXPORT_COLUMNS.iter().copied()),
+            ExportFormat::Json => web::Bytes::from_static(b"["),
+        }
+    }
+
+    fn footer(self) -> Option<web::Bytes> {
+        match self {
+            ExportFormat::Csv => None,
+            ExportFormat::Json => Some(web::Bytes::from_static(b"]\n")),
+        }
+    }
+
+    fn row(self, row: &ExportRow, first: bool) -> web::Bytes {
+        match self {
+            ExportFormat::Csv => {
+                let optional = |value: Option<String>| value.unwrap_or_default();
+                csv_record(
+                    [
+                        row.id.to_string(),
+                        optional(row.slug.clone()),
+                        row.title.clone(),
+                        row.location.clone(),
+                        row.price.to_string(),
+                        optional(row.bedrooms.map(|v| v.to_string())),
+                        optional(row.bathrooms.map(|v| v.to_string())),
+                        optional(row.area_sqm.map(|v| v.to_string())),
+                        optional(row.latitude.map(|v| v.to_string())),
+                        optional(row.longitude.map(|v| v.to_string())),
+                        optional(row.property_type.clone()),
+                        optional(row.certificate_type.clone()),
+                        row.status.clone(),
+                        optional(row.created_at.map(|t| t.to_rfc3339())),
+                    ]
+                    .iter(),
+                )
+            }
+            ExportFormat::Json => {
+                let mut chunk = if first { Vec::new() } else { b",".to_vec() };
+                chunk.push(b'\n');
+                serde_json::to_writer(&mut chunk, row).ok();
+                web::Bytes::from(chunk)
+            }
+        }
+    }
+}
+
+/// One CSV line, quoted where needed.
+fn csv_record<I, T>(fields: I) -> web::Bytes
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(Vec::new());
+    writer.write_record(fields).ok();
+    web::Bytes::from(writer.into_inner().unwrap_or_default())
+}
+
+// ============================================================================
+// STREAMING
+// ============================================================================
+
+/// Reads the matching listings row by row and feeds the response body until
+/// they run out or the client goes away.
+async fn write_export(
+    pool: PgPool,
+    user_id: Uuid,
+    request: ExportRequest,
+    tx: mpsc::Sender<Result<web::Bytes, std::io::Error>>,
+) {
+    let format = request.format;
+    if tx.send(Ok(format.header())).await.is_err() {
+        return;
+    }
+
+    let mut sql = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM properties WHERE ",
+        EXPORT_COLUMNS.join(", ")
+    ));
+    sql.push(PUBLIC_LISTING_CONDITION);
+    sql.push(" AND status = ");
+    sql.push_bind(request.status);
+    request.filters.push_and(&mut sql);
+    sql.push(" ORDER BY ");
+    sql.push(
+        request
+            .sort
+            .map_or("created_at DESC, id", ListingSort::order_by),
+    );
+    sql.push(" LIMIT ");
+    sql.push_bind(MAX_EXPORT_ROWS);
+
+    let mut rows = sql.build_query_as::<ExportRow>().fetch(&pool);
+    let mut exported = 0usize;
+    while let Some(row) = rows.next().await {
+        let chunk = match row {
+            Ok(row) => Ok(format.row(&row, exported == 0)),
+            Err(e) => {
+                error!("Listing export for {} failed mid-stream: {}", user_id, e);
+                Err(std::io::Error::other("listing export failed"))
+            }
+        };
+        let failed = chunk.is_err();
+        if tx.send(chunk).await.is_err() || failed {
+            return;
+        }
+        exported += 1;
+    }
+
+    if let Some(footer) = format.footer() {
+        if tx.send(Ok(footer)).await.is_err() {
+            return;
+        }
+    }
+    info!("Exported {} listings for {}", exported, user_id);
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/export")]
+pub async fn export_properties(
+    user: CurrentUser,
+    query: web::Query<ExportQuery>,
+    params: web::Query<ListingFilterParams>,
+    sort_params: web::Query<SortParams>,
+    status_query: web::Query<StatusQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let status = match status_query.resolve() {
+        Ok(status) => status,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+    let filters = match params.into_inner().into_filter_set() {
+        Ok(filters) => filters,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+    let sort = match sort_params.resolve() {
+        Ok(sort) => sort,
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }))
+        }
+    };
+
+    let format = query.format;
+    let filename = format!(
+        "listings-{}-{}.{}",
+        status.as_str(),
+        chrono::Utc::now().format("%Y-%m-%d"),
+        format.extension()
+    );
+    let request = ExportRequest {
+        format,
+        filters,
+        sort,
+        status: status.as_str(),
+    };
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(write_export(state.db.clone(), user.id, request, tx));
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .streaming(body)
+}
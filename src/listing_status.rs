@@ -0,0 +1,253 @@
+// JARVIS2026 - Listing status lifecycle
+// Listings move draft → active → sold/rented → archived. Drafts are only
+// visible to their owner and admins; the listing and search endpoints show
+// active listings by default, while sold, rented and archived ones stay
+// reachable by link. Selling goes through `sold`, which records the sale.
+
+use actix_web::{put, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::account_status;
+use crate::audit;
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+/// SQL condition for listings still on the market.
+pub const ACTIVE_LISTING_CONDITION: &str = "status = 'active'";
+/// SQL condition for listings that can be opened by link.
+pub const PUBLISHED_LISTING_CONDITION: &str = "status <> 'draft'";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListingStatus {
+    Draft,
+    Active,
+    Sold,
+    Rented,
+    Archived,
+}
+
+#[derive(Deserialize)]
+pub struct StatusQuery {
+    /// `active` (default), `sold`, `rented` or `archived`
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangeStatusRequest {
+    status: ListingStatus,
+    reason: Option<String>,
+}
+
+enum Outcome {
+    Changed(ListingStatus),
+    NotFound,
+    Forbidden,
+    NotAllowed(ListingStatus),
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"ALTER TABLE properties ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'active'
+            CHECK (status IN ('draft', 'active', 'sold', 'rented', 'archived'))"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS status_changed_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+
+    // Listings sold before the lifecycle existed only carry sold_at
+    sqlx::query(
+        r#"UPDATE properties SET status = 'sold', status_changed_at = sold_at
+        WHERE sold_at IS NOT NULL AND status = 'active'"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_properties_status ON properties(status, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// TRANSITIONS
+// ============================================================================
+
+impl ListingStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ListingStatus::Draft => "draft",
+            ListingStatus::Active => "active",
+            ListingStatus::Sold => "sold",
+            ListingStatus::Rented => "rented",
+            ListingStatus::Archived => "archived",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "draft" => Some(ListingStatus::Draft),
+            "active" => Some(ListingStatus::Active),
+            "sold" => Some(ListingStatus::Sold),
+            "rented" => Some(ListingStatus::Rented),
+            "archived" => Some(ListingStatus::Archived),
+            _ => None,
+        }
+    }
+
+    /// Statuses reachable through the status endpoint. Selling needs the
+    /// sale price, so it only happens through `sold`.
+    fn next(self) -> &'static [ListingStatus] {
+        use ListingStatus::*;
+        match self {
+            Draft => &[Active, Archived],
+            Active => &[Draft, Rented, Archived],
+            Sold => &[Archived],
+            Rented => &[Active, Archived],
+            Archived => &[Draft],
+        }
+    }
+}
+
+impl StatusQuery {
+    /// Drafts are never listed publicly.
+    pub fn resolve(&self) -> Result<ListingStatus, String> {
+        match self.status.as_deref().map(|s| s.trim().to_lowercase()) {
+            None => Ok(ListingStatus::Active),
+            Some(raw) => match ListingStatus::parse(&raw) {
+                Some(ListingStatus::Draft) | None => {
+                    Err("status must be active, sold, rented or archived".to_string())
+                }
+                Some(status) => Ok(status),
+            },
+        }
+    }
+}
+
+async fn change_status(
+    pool: &PgPool,
+    user: CurrentUser,
+    property_id: Uuid,
+    req: &ChangeStatusRequest,
+) -> Result<Outcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some((owner, current)) = sqlx::query_as::<_, (Option<Uuid>, String)>(
+        "SELECT user_id, status FROM properties WHERE id = $1 FOR UPDATE",
+    )
+    .bind(property_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(Outcome::NotFound);
+    };
+    if owner != Some(user.id) && !user.is_admin() {
+        return Ok(Outcome::Forbidden);
+    }
+    let current = ListingStatus::parse(&current).unwrap_or(ListingStatus::Active);
+    if !current.next().contains(&req.status) {
+        return Ok(Outcome::NotAllowed(current));
+    }
+
+    sqlx::query("UPDATE properties SET status = $2, status_changed_at = NOW() WHERE id = $1")
+        .bind(property_id)
+        .bind(req.status.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+    audit::record(
+        &mut tx,
+        user.id,
+        "property.status",
+        "property",
+        property_id,
+        serde_json::json!({
+            "from": current,
+            "to": req.status,
+            "reason": req.reason
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Outcome::Changed(current))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[put("/api/properties/{id}/status")]
+pub async fn set_listing_status(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<ChangeStatusRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    if req.status == ListingStatus::Sold {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Mark listings sold through POST /api/properties/{id}/sold with the sale price"
+        }));
+    }
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+
+    match change_status(&state.db, user, property_id, &req).await {
+        Ok(Outcome::Changed(previous)) => {
+            state.property_cache.invalidate(property_id).await;
+            info!(
+                "Property {} moved from {} to {} by {}",
+                property_id,
+                previous.as_str(),
+                req.status.as_str(),
+                user.id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "previous_status": previous,
+                "status": req.status
+            }))
+        }
+        Ok(Outcome::NotFound) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Ok(Outcome::Forbidden) => HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only change the status of your own listings"
+        })),
+        Ok(Outcome::NotAllowed(current)) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!(
+                "A {} listing can't become {}",
+                current.as_str(),
+                req.status.as_str()
+            ),
+            "status": current,
+            "allowed": current.next()
+        })),
+        Err(e) => {
+            error!("Failed to change status of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to change listing status"
+            }))
+        }
+    }
+}
@@ -0,0 +1,519 @@
+// JARVIS2026 - Live video tours
+// Agents schedule live walkthroughs on their listings. Media goes through an
+// external WebRTC provider (LiveKit-compatible): we only mint room access
+// tokens, and the provider calls back with the recording when a tour ends,
+// which is archived as a regular video upload on the listing.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::fs as async_fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::completeness;
+use crate::secrets::{self, JwtKeys};
+use crate::AppState;
+
+const VISITOR_HEADER: &str = "X-Visitor-Id";
+const WEBHOOK_SECRET_HEADER: &str = "X-Live-Webhook-Secret";
+const TOKEN_TTL_HOURS: i64 = 4;
+const RECORDING_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+/// Viewers see tours that start within this window or are live now.
+const UPCOMING_WINDOW_DAYS: i32 = 14;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// The provider at `LIVE_PROVIDER_URL`. Tokens are signed with the shared
+/// JWT key, whose `kid` is the provider's API key.
+struct LiveProvider<'a> {
+    url: String,
+    keys: &'a JwtKeys,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct LiveTour {
+    id: Uuid,
+    property_id: Uuid,
+    host_user_id: Uuid,
+    title: Option<String>,
+    room: String,
+    status: String,
+    scheduled_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    ended_at: Option<DateTime<Utc>>,
+    recording_media_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct ScheduleTourRequest {
+    scheduled_at: DateTime<Utc>,
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RecordingWebhook {
+    room: String,
+    /// Where the provider stored the finished recording
+    recording_url: String,
+}
+
+#[derive(Serialize)]
+struct JoinInfo {
+    provider_url: String,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct TourWithAccess {
+    #[serde(flatten)]
+    tour: LiveTour,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    join: Option<JoinInfo>,
+}
+
+#[derive(Serialize)]
+struct VideoGrant<'a> {
+    room: &'a str,
+    #[serde(rename = "roomJoin")]
+    room_join: bool,
+    #[serde(rename = "canPublish")]
+    can_publish: bool,
+    #[serde(rename = "canSubscribe")]
+    can_subscribe: bool,
+}
+
+#[derive(Serialize)]
+struct AccessClaims<'a> {
+    iss: String,
+    sub: &'a str,
+    nbf: i64,
+    exp: i64,
+    video: VideoGrant<'a>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS live_tours (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            host_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            title TEXT,
+            room TEXT UNIQUE NOT NULL,
+            status TEXT NOT NULL DEFAULT 'scheduled',
+            scheduled_at TIMESTAMPTZ NOT NULL,
+            started_at TIMESTAMPTZ,
+            ended_at TIMESTAMPTZ,
+            recording_media_id UUID REFERENCES media_uploads(id) ON DELETE SET NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_live_tours_property ON live_tours(property_id, scheduled_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// PROVIDER TOKENS
+// ============================================================================
+
+impl<'a> LiveProvider<'a> {
+    fn from_env(keys: &'a JwtKeys) -> Option<Self> {
+        if !keys.is_configured() {
+            return None;
+        }
+        Some(LiveProvider {
+            url: std::env::var("LIVE_PROVIDER_URL").ok()?,
+            keys,
+        })
+    }
+
+    /// Room access token; only the host may publish.
+    fn join_info(&self, room: &str, identity: &str, can_publish: bool) -> Option<JoinInfo> {
+        let now = Utc::now();
+        let signed = self.keys.sign(|kid| AccessClaims {
+            iss: kid.to_string(),
+            sub: identity,
+            nbf: now.timestamp(),
+            exp: (now + ChronoDuration::hours(TOKEN_TTL_HOURS)).timestamp(),
+            video: VideoGrant {
+                room,
+                room_join: true,
+                can_publish,
+                can_subscribe: true,
+            },
+        });
+        match signed {
+            Ok(token) => Some(JoinInfo {
+                provider_url: self.url.clone(),
+                token,
+            }),
+            Err(e) => {
+                error!("Failed to sign live tour token: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn provider_unavailable() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(serde_json::json!({
+        "error": "Live tours are not configured"
+    }))
+}
+
+async fn fetch_tour(pool: &PgPool, tour_id: Uuid) -> Result<Option<LiveTour>, sqlx::Error> {
+    sqlx::query_as::<_, LiveTour>(
+        r#"SELECT id, property_id, host_user_id, title, room, status, scheduled_at,
+                  started_at, ended_at, recording_media_id
+        FROM live_tours WHERE id = $1"#,
+    )
+    .bind(tour_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ============================================================================
+// RECORDING ARCHIVE
+// ============================================================================
+
+/// Downloads the recording and stores it as a video upload on the listing.
+async fn archive_recording(pool: &PgPool, tour: &LiveTour, url: &str) -> Result<Uuid, String> {
+    let client = reqwest::Client::builder()
+        .timeout(RECORDING_DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let data = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Download failed: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+
+    let content_hash = crate::calculate_file_hash(&data).await;
+    let media_id = Uuid::new_v4();
+    let file_path = format!("uploads/live-{}.mp4", tour.id);
+
+    async_fs::create_dir_all("uploads")
+        .await
+        .map_err(|e| e.to_string())?;
+    async_fs::write(&file_path, &data)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        r#"INSERT INTO media_uploads
+        (id, property_id, user_id, file_path, file_type, content_hash, file_size, is_original, tokens_earned)
+        VALUES ($1, $2, $3, $4, 'video', $5, $6, true, 0)"#,
+    )
+    .bind(media_id)
+    .bind(tour.property_id)
+    .bind(tour.host_user_id)
+    .bind(&file_path)
+    .bind(&content_hash)
+    .bind(data.len() as i64)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query("UPDATE live_tours SET recording_media_id = $2 WHERE id = $1")
+        .bind(tour.id)
+        .bind(media_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    completeness::refresh(&mut *tx, tour.property_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(media_id)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/properties/{id}/live")]
+pub async fn schedule_tour(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<ScheduleTourRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    match sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM properties WHERE id = $1")
+        .bind(property_id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(owner)) if owner == Some(user.id) => {}
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only the listing's agent can schedule a live tour"
+            }))
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to schedule live tour"
+            }));
+        }
+    }
+
+    if req.scheduled_at < Utc::now() - ChronoDuration::minutes(5) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "scheduled_at must be in the future"
+        }));
+    }
+
+    match sqlx::query_as::<_, LiveTour>(
+        r#"INSERT INTO live_tours (property_id, host_user_id, title, room, scheduled_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, property_id, host_user_id, title, room, status, scheduled_at,
+                  started_at, ended_at, recording_media_id"#,
+    )
+    .bind(property_id)
+    .bind(user.id)
+    .bind(req.title.as_deref().map(str::trim))
+    .bind(format!("tour-{}", Uuid::new_v4().simple()))
+    .bind(req.scheduled_at)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(tour) => {
+            info!("Live tour {} scheduled for {}", tour.id, property_id);
+            HttpResponse::Ok().json(tour)
+        }
+        Err(e) => {
+            error!("Failed to schedule live tour: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to schedule live tour"
+            }))
+        }
+    }
+}
+
+/// Live and upcoming tours for a listing. Live tours include a viewer token.
+#[get("/api/properties/{id}/live")]
+pub async fn property_tours(
+    path: web::Path<Uuid>,
+    user: Option<CurrentUser>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let tours = match sqlx::query_as::<_, LiveTour>(
+        r#"SELECT id, property_id, host_user_id, title, room, status, scheduled_at,
+                  started_at, ended_at, recording_media_id
+        FROM live_tours
+        WHERE property_id = $1
+          AND (status = 'live'
+               OR (status = 'scheduled'
+                   AND scheduled_at <= NOW() + make_interval(days => $2)))
+        ORDER BY scheduled_at"#,
+    )
+    .bind(path.into_inner())
+    .bind(UPCOMING_WINDOW_DAYS)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(tours) => tours,
+        Err(e) => {
+            error!("Failed to fetch live tours: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch live tours"
+            }));
+        }
+    };
+
+    let provider = LiveProvider::from_env(&state.jwt_keys);
+    let identity = match (user, req.headers().get(VISITOR_HEADER)) {
+        (Some(user), _) => format!("user:{}", user.id),
+        (None, Some(visitor)) => format!("visitor:{}", visitor.to_str().unwrap_or_default()),
+        (None, None) => format!("guest:{}", Uuid::new_v4()),
+    };
+
+    let tours: Vec<TourWithAccess> = tours
+        .into_iter()
+        .map(|tour| {
+            let join = match (&provider, tour.status.as_str()) {
+                (Some(provider), "live") => provider.join_info(&tour.room, &identity, false),
+                _ => None,
+            };
+            TourWithAccess { tour, join }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(tours)
+}
+
+/// Marks the tour live and returns the host's publishing token.
+#[post("/api/live/{tour_id}/start")]
+pub async fn start_tour(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let Some(provider) = LiveProvider::from_env(&state.jwt_keys) else {
+        return provider_unavailable();
+    };
+
+    let tour = match sqlx::query_as::<_, LiveTour>(
+        r#"UPDATE live_tours SET status = 'live', started_at = COALESCE(started_at, NOW())
+        WHERE id = $1 AND host_user_id = $2 AND status IN ('scheduled', 'live')
+        RETURNING id, property_id, host_user_id, title, room, status, scheduled_at,
+                  started_at, ended_at, recording_media_id"#,
+    )
+    .bind(path.into_inner())
+    .bind(user.id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(tour)) => tour,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "No startable tour found for this host"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to start live tour: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to start live tour"
+            }));
+        }
+    };
+
+    let join = provider.join_info(&tour.room, &format!("user:{}", user.id), true);
+    if join.is_none() {
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to issue live tour token"
+        }));
+    }
+    info!("Live tour {} started", tour.id);
+    HttpResponse::Ok().json(TourWithAccess { tour, join })
+}
+
+#[post("/api/live/{tour_id}/end")]
+pub async fn end_tour(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query_as::<_, LiveTour>(
+        r#"UPDATE live_tours
+        SET status = CASE WHEN status = 'live' THEN 'ended' ELSE 'cancelled' END,
+            ended_at = NOW()
+        WHERE id = $1 AND host_user_id = $2 AND status IN ('scheduled', 'live')
+        RETURNING id, property_id, host_user_id, title, room, status, scheduled_at,
+                  started_at, ended_at, recording_media_id"#,
+    )
+    .bind(path.into_inner())
+    .bind(user.id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(tour)) => HttpResponse::Ok().json(tour),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No active tour found for this host"
+        })),
+        Err(e) => {
+            error!("Failed to end live tour: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to end live tour"
+            }))
+        }
+    }
+}
+
+/// Called by the provider once a room's recording is available.
+#[post("/api/live/webhook/recording")]
+pub async fn recording_webhook(
+    req: HttpRequest,
+    body: web::Json<RecordingWebhook>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let expected = secrets::var("LIVE_WEBHOOK_SECRET").ok();
+    let given = req
+        .headers()
+        .get(WEBHOOK_SECRET_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if expected.is_none() || expected.as_deref() != given {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Invalid webhook secret"
+        }));
+    }
+
+    let tour = match sqlx::query_as::<_, LiveTour>(
+        r#"SELECT id, property_id, host_user_id, title, room, status, scheduled_at,
+                  started_at, ended_at, recording_media_id
+        FROM live_tours WHERE room = $1"#,
+    )
+    .bind(&body.room)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(tour)) => tour,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Unknown room"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up live tour room {}: {}", body.room, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to archive recording"
+            }));
+        }
+    };
+
+    if let Some(media_id) = tour.recording_media_id {
+        return HttpResponse::Ok().json(serde_json::json!({ "media_id": media_id }));
+    }
+
+    match archive_recording(&state.db, &tour, &body.recording_url).await {
+        Ok(media_id) => {
+            state.property_cache.invalidate(tour.property_id).await;
+            info!("Live tour {} recording archived as {}", tour.id, media_id);
+            match fetch_tour(&state.db, tour.id).await {
+                Ok(Some(tour)) => HttpResponse::Ok().json(tour),
+                _ => HttpResponse::Ok().json(serde_json::json!({ "media_id": media_id })),
+            }
+        }
+        Err(message) => {
+            warn!(
+                "Failed to archive recording for tour {}: {}",
+                tour.id, message
+            );
+            HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "Failed to archive recording"
+            }))
+        }
+    }
+}
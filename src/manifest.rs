@@ -0,0 +1,194 @@
+// JARVIS2026 - Binary media manifests
+// A compact description of a listing's public media (sizes, hashes and the
+// image variants worth prefetching) for the mobile app. The encoding follows
+// `Accept`: MessagePack by default, CBOR on request, JSON for debugging.
+
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse, Responder};
+use serde::Serialize;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::images;
+use crate::media_files;
+use crate::models3d;
+use crate::{cached_property_detail, cached_public_media, AppState, MediaUpload};
+
+/// Widths of the WebP variants listed for every image
+const IMAGE_VARIANT_WIDTHS: &[u32] = &[320, 640, 1280, 2048];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    MessagePack,
+    Cbor,
+    Json,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    property_id: Uuid,
+    media: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    id: Uuid,
+    file_type: String,
+    bytes: i64,
+    /// Hex SHA-256 of the original upload
+    sha256: String,
+    /// Unix seconds
+    uploaded_at: i64,
+    url: String,
+    alt_text: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    variants: Vec<ManifestVariant>,
+}
+
+#[derive(Serialize)]
+struct ManifestVariant {
+    width: u32,
+    format: &'static str,
+    url: String,
+}
+
+// ============================================================================
+// ENCODING
+// ============================================================================
+
+impl Encoding {
+    /// First supported type in `Accept`, ignoring q-values; MessagePack when
+    /// the header is missing or a wildcard. `None` when nothing matches.
+    fn negotiate(req: &HttpRequest) -> Option<Self> {
+        let Some(accept) = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+        else {
+            return Some(Encoding::MessagePack);
+        };
+        accept
+            .split(',')
+            .map(|part| part.split(';').next().unwrap_or("").trim())
+            .find_map(|media_type| match media_type {
+                "application/msgpack"
+                | "application/x-msgpack"
+                | "application/vnd.msgpack"
+                | "*/*"
+                | "application/*" => Some(Encoding::MessagePack),
+                "application/cbor" => Some(Encoding::Cbor),
+                "application/json" => Some(Encoding::Json),
+                _ => None,
+            })
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Encoding::MessagePack => "application/msgpack",
+            Encoding::Cbor => "application/cbor",
+            Encoding::Json => "application/json",
+        }
+    }
+
+    fn encode(self, manifest: &Manifest) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::MessagePack => rmp_serde::to_vec_named(manifest)?,
+            Encoding::Cbor => {
+                let mut out = Vec::new();
+                ciborium::into_writer(manifest, &mut out)?;
+                out
+            }
+            Encoding::Json => serde_json::to_vec(manifest)?,
+        })
+    }
+}
+
+fn entry(media: &MediaUpload) -> ManifestEntry {
+    let (url, variants) = match media.file_type.as_str() {
+        "image" => (
+            images::variant_path(media.id, None),
+            IMAGE_VARIANT_WIDTHS
+                .iter()
+                .map(|width| ManifestVariant {
+                    width: *width,
+                    format: "webp",
+                    url: images::variant_path(media.id, Some(*width)),
+                })
+                .collect(),
+        ),
+        models3d::MODEL_FILE_TYPE => (models3d::model_path(media.id), Vec::new()),
+        _ => (media_files::media_path(media.id), Vec::new()),
+    };
+    ManifestEntry {
+        id: media.id,
+        file_type: media.file_type.clone(),
+        bytes: media.file_size,
+        sha256: media.content_hash.clone(),
+        uploaded_at: media.uploaded_at.timestamp(),
+        url,
+        alt_text: media.alt_text.clone(),
+        variants,
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/{id}/manifest")]
+pub async fn media_manifest(
+    http_req: HttpRequest,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let Some(encoding) = Encoding::negotiate(&http_req) else {
+        return HttpResponse::NotAcceptable().json(serde_json::json!({
+            "error": "Accept application/msgpack, application/cbor or application/json"
+        }));
+    };
+
+    let result: Result<Option<Manifest>, sqlx::Error> = async {
+        match cached_property_detail(&state, property_id).await? {
+            Some(detail) if detail.row.is_public => {}
+            _ => return Ok(None),
+        }
+        let media = cached_public_media(&state, property_id).await?;
+        Ok(Some(Manifest {
+            property_id,
+            media: media.iter().map(entry).collect(),
+        }))
+    }
+    .await;
+
+    let manifest = match result {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load media manifest of {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load media manifest"
+            }));
+        }
+    };
+
+    match encoding.encode(&manifest) {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(encoding.content_type())
+            .insert_header((header::VARY, "Accept"))
+            .body(body),
+        Err(e) => {
+            error!("Failed to encode media manifest of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to encode media manifest"
+            }))
+        }
+    }
+}
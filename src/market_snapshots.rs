@@ -0,0 +1,248 @@
+// JARVIS2026 - Inventory snapshots
+// Once a day the public active inventory is counted per area, property type
+// and price bucket, together with what joined and left it since the day
+// before, and kept as a time series. Trend charts read the series instead of
+// reconstructing past inventory from listing history on every request.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(3600);
+const DEFAULT_DAYS: i64 = 90;
+const MAX_DAYS: i64 = 730;
+/// (bucket, lower bound in rupiah); each runs up to the next bound
+const PRICE_BUCKETS: &[(&str, f64)] = &[
+    ("under_500m", 0.0),
+    ("500m_1b", 5e8),
+    ("1b_2b", 1e9),
+    ("2b_5b", 2e9),
+    ("5b_10b", 5e9),
+    ("10b_plus", 1e10),
+];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct InventoryQuery {
+    /// Matched case-insensitively against the listing location
+    area: Option<String>,
+    property_type: Option<String>,
+    /// One of `PRICE_BUCKETS`
+    price_bucket: Option<String>,
+    days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct InventoryPoint {
+    date: NaiveDate,
+    active_listings: i64,
+    new_listings: i64,
+    /// Sold or rented since the previous snapshot
+    sold: i64,
+    /// Archived since the previous snapshot
+    withdrawn: i64,
+    median_price: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct InventoryResponse {
+    area: Option<String>,
+    property_type: Option<String>,
+    price_bucket: Option<String>,
+    /// Change in active listings from the first point to the last
+    net_change: i64,
+    points: Vec<InventoryPoint>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS market_snapshots (
+            snapshot_date DATE NOT NULL,
+            area TEXT NOT NULL,
+            property_type TEXT NOT NULL,
+            price_bucket TEXT NOT NULL,
+            active_listings INTEGER NOT NULL,
+            new_listings INTEGER NOT NULL,
+            sold INTEGER NOT NULL,
+            withdrawn INTEGER NOT NULL,
+            median_price DOUBLE PRECISION,
+            PRIMARY KEY (snapshot_date, area, property_type, price_bucket)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_market_snapshots_area ON market_snapshots(area, snapshot_date)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// SNAPSHOTS
+// ============================================================================
+
+/// `CASE` mapping `price` onto `PRICE_BUCKETS`.
+fn bucket_expr() -> String {
+    let arms: Vec<String> = PRICE_BUCKETS
+        .iter()
+        .rev()
+        .map(|(name, lower)| format!("WHEN price >= {} THEN '{}'", lower, name))
+        .collect();
+    format!("CASE {} ELSE '{}' END", arms.join(" "), PRICE_BUCKETS[0].0)
+}
+
+/// Counts today's inventory; re-running on the same day replaces the rows.
+async fn take_snapshot(pool: &PgPool, date: NaiveDate) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM market_snapshots WHERE snapshot_date = $1")
+        .bind(date)
+        .execute(&mut *tx)
+        .await?;
+    // Listings that left the market in the last day are counted in the
+    // group they left from
+    let inserted = sqlx::query(&format!(
+        r#"INSERT INTO market_snapshots
+            (snapshot_date, area, property_type, price_bucket, active_listings,
+             new_listings, sold, withdrawn, median_price)
+        SELECT $1, LOWER(TRIM(location)), COALESCE(LOWER(TRIM(property_type)), 'unknown'),
+            {bucket},
+            COUNT(*) FILTER (WHERE {active}),
+            COUNT(*) FILTER (WHERE {active} AND created_at >= NOW() - INTERVAL '1 day'),
+            COUNT(*) FILTER (WHERE status IN ('sold', 'rented')
+                             AND status_changed_at >= NOW() - INTERVAL '1 day'),
+            COUNT(*) FILTER (WHERE status = 'archived'
+                             AND status_changed_at >= NOW() - INTERVAL '1 day'),
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY price) FILTER (WHERE {active})
+        FROM properties
+        WHERE {public}
+          AND ({active} OR status_changed_at >= NOW() - INTERVAL '1 day')
+        GROUP BY 2, 3, 4"#,
+        bucket = bucket_expr(),
+        active = ACTIVE_LISTING_CONDITION,
+        public = PUBLIC_LISTING_CONDITION
+    ))
+    .bind(date)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+/// Takes the day's snapshot shortly after midnight UTC, or at startup when
+/// today's is missing.
+pub fn spawn_scheduler(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let today = Utc::now().date_naive();
+            let exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM market_snapshots WHERE snapshot_date = $1)",
+            )
+            .bind(today)
+            .fetch_one(&pool)
+            .await
+            .unwrap_or(true);
+            if exists {
+                continue;
+            }
+
+            match take_snapshot(&pool, today).await {
+                Ok(groups) => info!("Market snapshot for {} taken ({} groups)", today, groups),
+                Err(e) => error!("Market snapshot failed: {}", e),
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Daily active inventory with what joined and left it, optionally narrowed
+/// to an area, property type or price bucket.
+#[get("/api/analytics/inventory")]
+pub async fn inventory_trend(
+    query: web::Query<InventoryQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let days = query.days.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS);
+    let normalize = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|v| v.trim().to_lowercase())
+            .filter(|v| !v.is_empty())
+    };
+    let area = normalize(&query.area);
+    let property_type = normalize(&query.property_type);
+    let price_bucket = normalize(&query.price_bucket);
+    if let Some(bucket) = &price_bucket {
+        if !PRICE_BUCKETS.iter().any(|(name, _)| name == bucket) {
+            let names: Vec<&str> = PRICE_BUCKETS.iter().map(|(name, _)| *name).collect();
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("price_bucket must be one of {}", names.join(", "))
+            }));
+        }
+    }
+
+    // Medians don't add up across groups; the largest group's stands in
+    match sqlx::query_as::<_, InventoryPoint>(
+        r#"SELECT snapshot_date AS date,
+            SUM(active_listings)::BIGINT AS active_listings,
+            SUM(new_listings)::BIGINT AS new_listings,
+            SUM(sold)::BIGINT AS sold,
+            SUM(withdrawn)::BIGINT AS withdrawn,
+            (ARRAY_AGG(median_price ORDER BY active_listings DESC))[1] AS median_price
+        FROM market_snapshots
+        WHERE snapshot_date >= CURRENT_DATE - $1::INTEGER
+          AND ($2::TEXT IS NULL OR area = $2)
+          AND ($3::TEXT IS NULL OR property_type = $3)
+          AND ($4::TEXT IS NULL OR price_bucket = $4)
+        GROUP BY snapshot_date
+        ORDER BY snapshot_date"#,
+    )
+    .bind(days as i32)
+    .bind(&area)
+    .bind(&property_type)
+    .bind(&price_bucket)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(points) => HttpResponse::Ok().json(InventoryResponse {
+            net_change: match (points.first(), points.last()) {
+                (Some(first), Some(last)) => last.active_listings - first.active_listings,
+                _ => 0,
+            },
+            area,
+            property_type,
+            price_bucket,
+            points,
+        }),
+        Err(e) => {
+            error!("Failed to load inventory trend: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load inventory trend"
+            }))
+        }
+    }
+}
@@ -0,0 +1,561 @@
+// JARVIS2026 - Media downloads
+// Original uploads are served from `/api/media/{id}` with range support.
+// Every media response (originals, image variants, 3D models) carries a
+// strong ETag derived from the upload's content hash plus `Last-Modified`,
+// and answers `If-None-Match` / `If-Modified-Since` with 304 so revisits
+// don't download multi-MB files again. `/api/media/{id}/parts` splits an
+// upload into fixed-size byte ranges with a SHA-256 per range, so download
+// managers and the mobile app can fetch large walkthrough videos in parallel
+// and verify each chunk before stitching. With `MEDIA_BANDWIDTH_KIB_PER_SEC`
+// set, each media response is paced to that rate after an initial burst, so a
+// few video viewers can't saturate the uplink of a small server.
+
+use actix_files::NamedFile;
+use actix_web::{
+    body::{BodySize, MessageBody},
+    get,
+    http::header::{
+        self, EntityTag, Header, HeaderValue, HttpDate, IfModifiedSince, IfNoneMatch,
+        TryIntoHeaderValue,
+    },
+    web::{self, Bytes},
+    HttpRequest, HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::future::Future;
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::time::Sleep;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::AppState;
+
+const MIB: i64 = 1024 * 1024;
+const DEFAULT_CHUNK_SIZE: i64 = 8 * MIB;
+const MAX_CHUNK_SIZE: i64 = 64 * MIB;
+const DEFAULT_BURST_SECONDS: u64 = 4;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// Cache validators of one media representation.
+pub struct Validators {
+    etag: EntityTag,
+    last_modified: HttpDate,
+}
+
+/// Pace applied to each media response: after sending `burst_bytes` at full
+/// speed, bytes go out no faster than `bytes_per_sec`.
+#[derive(Debug, Clone, Copy)]
+pub struct BandwidthLimit {
+    bytes_per_sec: u64,
+    burst_bytes: u64,
+}
+
+/// A response body paced by a token bucket that starts full. Bytes already
+/// sent may overdraw the bucket; the next chunk waits until it's back to zero.
+struct ThrottledBody<B> {
+    inner: B,
+    limit: BandwidthLimit,
+    tokens: f64,
+    refilled_at: Instant,
+    wait: Option<Pin<Box<Sleep>>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct MediaFile {
+    file_path: String,
+    content_hash: String,
+    uploaded_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct PartSource {
+    file_path: String,
+    content_hash: String,
+    uploaded_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Hashes of the stored file, which can differ from the upload's
+/// `content_hash` once a watermark has been applied.
+#[derive(sqlx::FromRow)]
+struct ChunkHashes {
+    file_bytes: i64,
+    file_sha256: String,
+    hashes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PartsQuery {
+    /// Bytes per chunk; a whole number of MiB between 1 and 64 (default 8)
+    chunk_size: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct Part {
+    index: usize,
+    /// First byte, inclusive
+    start: i64,
+    /// Last byte, inclusive — ready for a `Range: bytes=start-end` header
+    end: i64,
+    bytes: i64,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct PartsResponse {
+    media_id: Uuid,
+    url: String,
+    bytes: i64,
+    sha256: String,
+    /// Send as `If-Range` so a chunk never mixes two versions of the file
+    etag: String,
+    chunk_size: i64,
+    part_count: usize,
+    parts: Vec<Part>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    // Chunk hashes are computed on first request and reused; uploads are
+    // immutable, so they never go stale
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS media_parts (
+            media_id UUID NOT NULL REFERENCES media_uploads(id) ON DELETE CASCADE,
+            chunk_size BIGINT NOT NULL,
+            file_bytes BIGINT NOT NULL,
+            file_sha256 TEXT NOT NULL,
+            hashes TEXT[] NOT NULL,
+            computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (media_id, chunk_size)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// VALIDATORS
+// ============================================================================
+
+impl Validators {
+    /// `variant` tells apart representations derived from the same upload.
+    pub fn new(
+        content_hash: &str,
+        variant: Option<&str>,
+        uploaded_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        let tag = match variant {
+            Some(variant) => format!("{}-{}", content_hash, variant),
+            None => content_hash.to_string(),
+        };
+        let seconds = uploaded_at.timestamp().max(0) as u64;
+        Validators {
+            etag: EntityTag::new_strong(tag),
+            last_modified: HttpDate::from(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+        }
+    }
+
+    /// A 304 when the client's copy is current. `If-None-Match` wins over
+    /// `If-Modified-Since` when both are sent.
+    pub fn not_modified(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        let fresh = match IfNoneMatch::parse(req) {
+            Ok(IfNoneMatch::Any) => true,
+            Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&self.etag)),
+            Err(_) => match IfModifiedSince::parse(req) {
+                Ok(IfModifiedSince(since)) => self.last_modified <= since,
+                Err(_) => false,
+            },
+        };
+        fresh.then(|| {
+            let mut response = HttpResponse::NotModified().finish();
+            self.apply(&mut response);
+            response
+        })
+    }
+
+    fn etag(&self) -> String {
+        self.etag.to_string()
+    }
+
+    pub fn apply(&self, response: &mut HttpResponse) {
+        let headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag.to_string()) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Ok(last_modified) = self.last_modified.try_into_value() {
+            headers.insert(header::LAST_MODIFIED, last_modified);
+        }
+    }
+}
+
+// ============================================================================
+// THROTTLING
+// ============================================================================
+
+impl BandwidthLimit {
+    /// `MEDIA_BANDWIDTH_KIB_PER_SEC` turns pacing on; `MEDIA_BURST_SECONDS`
+    /// (default 4) is how many seconds' worth of that rate a response may send
+    /// up front, so playback starts without waiting on the cap.
+    pub fn from_env() -> Option<Self> {
+        let raw = std::env::var("MEDIA_BANDWIDTH_KIB_PER_SEC").ok()?;
+        let kib_per_sec = match raw.trim().parse::<u64>() {
+            Ok(0) => return None,
+            Ok(kib) => kib,
+            Err(_) => {
+                warn!(
+                    "Invalid MEDIA_BANDWIDTH_KIB_PER_SEC '{}'; media is not throttled",
+                    raw
+                );
+                return None;
+            }
+        };
+        let burst_seconds = std::env::var("MEDIA_BURST_SECONDS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_BURST_SECONDS);
+        let bytes_per_sec = kib_per_sec * 1024;
+        info!(
+            "Media responses paced to {} KiB/s after a {}s burst",
+            kib_per_sec, burst_seconds
+        );
+        Some(BandwidthLimit {
+            bytes_per_sec,
+            burst_bytes: bytes_per_sec * burst_seconds,
+        })
+    }
+}
+
+impl<B> ThrottledBody<B> {
+    fn new(inner: B, limit: BandwidthLimit) -> Self {
+        ThrottledBody {
+            inner,
+            limit,
+            tokens: limit.burst_bytes as f64,
+            refilled_at: Instant::now(),
+            wait: None,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let earned =
+            now.duration_since(self.refilled_at).as_secs_f64() * self.limit.bytes_per_sec as f64;
+        // Idle time never builds up more than the initial burst
+        self.tokens = (self.tokens + earned).min(self.limit.burst_bytes as f64);
+        self.refilled_at = now;
+    }
+}
+
+impl<B: MessageBody + Unpin> MessageBody for ThrottledBody<B> {
+    type Error = B::Error;
+
+    fn size(&self) -> BodySize {
+        self.inner.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(wait) = this.wait.as_mut() {
+                ready!(wait.as_mut().poll(cx));
+                this.wait = None;
+            }
+            this.refill();
+            if this.tokens >= 0.0 {
+                break;
+            }
+            let deficit = -this.tokens / this.limit.bytes_per_sec as f64;
+            this.wait = Some(Box::pin(tokio::time::sleep(Duration::from_secs_f64(
+                deficit,
+            ))));
+        }
+
+        let chunk = ready!(Pin::new(&mut this.inner).poll_next(cx));
+        if let Some(Ok(bytes)) = &chunk {
+            this.tokens -= bytes.len() as f64;
+        }
+        Poll::Ready(chunk)
+    }
+}
+
+/// Where an original upload is downloaded from.
+pub fn media_path(media_id: Uuid) -> String {
+    format!("/api/media/{}", media_id)
+}
+
+// ============================================================================
+// CHUNKING
+// ============================================================================
+
+impl PartsQuery {
+    fn chunk_size(&self) -> Result<i64, String> {
+        match self.chunk_size {
+            None => Ok(DEFAULT_CHUNK_SIZE),
+            Some(size) if (MIB..=MAX_CHUNK_SIZE).contains(&size) && size % MIB == 0 => Ok(size),
+            Some(_) => Err(format!(
+                "chunk_size must be a whole number of MiB between {} and {}",
+                MIB, MAX_CHUNK_SIZE
+            )),
+        }
+    }
+}
+
+/// SHA-256 of the whole file and of each `chunk_size` slice, streamed so a
+/// multi-GB video is never held in memory.
+fn hash_chunks(file_path: &str, chunk_size: i64) -> std::io::Result<ChunkHashes> {
+    let mut file = std::fs::File::open(file_path)?;
+    let mut buffer = vec![0u8; chunk_size as usize];
+    let mut whole = Sha256::new();
+    let mut file_bytes = 0;
+    let mut hashes = Vec::new();
+    loop {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            match file.read(&mut buffer[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        if filled == 0 {
+            break;
+        }
+        whole.update(&buffer[..filled]);
+        file_bytes += filled as i64;
+        hashes.push(hex::encode(Sha256::digest(&buffer[..filled])));
+        if filled < buffer.len() {
+            break;
+        }
+    }
+    Ok(ChunkHashes {
+        file_bytes,
+        file_sha256: hex::encode(whole.finalize()),
+        hashes,
+    })
+}
+
+async fn chunk_hashes(
+    pool: &PgPool,
+    media_id: Uuid,
+    file_path: &str,
+    chunk_size: i64,
+) -> Result<ChunkHashes, String> {
+    let stored = sqlx::query_as::<_, ChunkHashes>(
+        r#"SELECT file_bytes, file_sha256, hashes FROM media_parts
+        WHERE media_id = $1 AND chunk_size = $2"#,
+    )
+    .bind(media_id)
+    .bind(chunk_size)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if let Some(hashes) = stored {
+        return Ok(hashes);
+    }
+
+    let path = file_path.to_string();
+    let computed = web::block(move || hash_chunks(&path, chunk_size))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        r#"INSERT INTO media_parts (media_id, chunk_size, file_bytes, file_sha256, hashes)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (media_id, chunk_size) DO NOTHING"#,
+    )
+    .bind(media_id)
+    .bind(chunk_size)
+    .bind(computed.file_bytes)
+    .bind(&computed.file_sha256)
+    .bind(&computed.hashes)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    info!(
+        "Hashed {} parts of media {} at {} bytes",
+        computed.hashes.len(),
+        media_id,
+        chunk_size
+    );
+    Ok(computed)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Streams an original upload. Clients revalidate on every use so hidden or
+/// deleted media stop being served. Each response (and so each range request)
+/// gets its own bandwidth allowance.
+#[get("/api/media/{id}")]
+pub async fn serve_media(
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> HttpResponse {
+    let media_id = path.into_inner();
+
+    let media = match sqlx::query_as::<_, MediaFile>(
+        r#"SELECT file_path, content_hash, uploaded_at FROM media_uploads
+        WHERE id = $1 AND moderation_status NOT IN ('rejected', 'unpublished')"#,
+    )
+    .bind(media_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(media)) => media,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Media not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up media {}: {}", media_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load media"
+            }));
+        }
+    };
+
+    let validators = Validators::new(&media.content_hash, None, media.uploaded_at);
+    if let Some(response) = validators.not_modified(&req) {
+        return response;
+    }
+
+    let file = match NamedFile::open_async(&media.file_path).await {
+        Ok(file) => file,
+        Err(e) => {
+            error!(
+                "Media {} file {} unreadable: {}",
+                media_id, media.file_path, e
+            );
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Media not found"
+            }));
+        }
+    };
+
+    // The file's own mtime-based validators would change on every redeploy
+    let mut response = file
+        .use_etag(false)
+        .use_last_modified(false)
+        .disable_content_disposition()
+        .into_response(&req);
+    validators.apply(&mut response);
+    let headers = response.headers_mut();
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, no-cache"),
+    );
+    match state.media_bandwidth {
+        Some(limit) if response.status().is_success() => response
+            .map_body(|_, body| ThrottledBody::new(body, limit))
+            .map_into_boxed_body(),
+        _ => response,
+    }
+}
+
+/// Chunk boundaries and hashes of an upload. Fetch each part from the media
+/// URL with `Range` and `If-Range: <etag>`, check its hash, then concatenate.
+#[get("/api/media/{id}/parts")]
+pub async fn media_parts(
+    path: web::Path<Uuid>,
+    query: web::Query<PartsQuery>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> HttpResponse {
+    let media_id = path.into_inner();
+
+    let chunk_size = match query.chunk_size() {
+        Ok(size) => size,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+    };
+
+    let media = match sqlx::query_as::<_, PartSource>(
+        r#"SELECT file_path, content_hash, uploaded_at FROM media_uploads
+        WHERE id = $1 AND moderation_status NOT IN ('rejected', 'unpublished')"#,
+    )
+    .bind(media_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(media)) => media,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Media not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up media {}: {}", media_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load media"
+            }));
+        }
+    };
+
+    let listing_validators = Validators::new(
+        &media.content_hash,
+        Some(&format!("parts-{}", chunk_size)),
+        media.uploaded_at,
+    );
+    if let Some(response) = listing_validators.not_modified(&req) {
+        return response;
+    }
+
+    let chunks = match chunk_hashes(&state.db, media_id, &media.file_path, chunk_size).await {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            error!("Failed to hash parts of media {}: {}", media_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to compute media parts"
+            }));
+        }
+    };
+
+    let parts = chunks
+        .hashes
+        .into_iter()
+        .enumerate()
+        .map(|(index, sha256)| {
+            let start = index as i64 * chunk_size;
+            let end = (start + chunk_size).min(chunks.file_bytes) - 1;
+            Part {
+                index,
+                start,
+                end,
+                bytes: end - start + 1,
+                sha256,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut response = HttpResponse::Ok().json(PartsResponse {
+        media_id,
+        url: media_path(media_id),
+        bytes: chunks.file_bytes,
+        sha256: chunks.file_sha256,
+        etag: Validators::new(&media.content_hash, None, media.uploaded_at).etag(),
+        chunk_size,
+        part_count: parts.len(),
+        parts,
+    });
+    listing_validators.apply(&mut response);
+    response
+}
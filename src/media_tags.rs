@@ -0,0 +1,574 @@
+// JARVIS2026 - Media tags
+// Labels describing what a listing's images show (kitchen, pool, bedroom),
+// one row per image and tag with the confidence it was assigned at. A
+// background worker runs every new photo through the `VisionClassifier` set
+// by `IMAGE_TAGGER`: a local multi-label ONNX model or an external vision
+// API. Search filters on them (`has:pool`), and listing tools read them as a
+// summary of the whole listing: what appears in its photos, and how often.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use futures_util::future::BoxFuture;
+use image::imageops::FilterType;
+use ort::session::Session;
+use ort::value::Tensor;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::secrets;
+use crate::AppState;
+
+/// Tags below this confidence are too unsure to show or build on
+pub const MIN_CONFIDENCE: f32 = 0.5;
+/// Labels kept from a classification; the rest are noise
+const STORE_CONFIDENCE: f32 = 0.2;
+const MAX_TAG_CHARS: usize = 40;
+const WORKER_INTERVAL_SECS: u64 = 15;
+const BATCH_SIZE: i64 = 20;
+const MAX_ATTEMPTS: i32 = 5;
+const MAX_BACKOFF_MINUTES: i32 = 24 * 60;
+/// A claimed job is retried after this long if its worker died mid-way
+const CLAIM_LEASE_MINUTES: i32 = 10;
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_INPUT_SIZE: u32 = 224;
+/// ImageNet statistics most vision models are trained with
+const CHANNEL_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const CHANNEL_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// A tag across a listing's images.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ListingTag {
+    pub tag: String,
+    /// Images carrying the tag
+    pub images: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Label {
+    #[serde(alias = "label", alias = "name")]
+    pub tag: String,
+    #[serde(alias = "score")]
+    pub confidence: f32,
+}
+
+pub trait VisionClassifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Labels for an encoded image, any confidence.
+    fn classify<'a>(&'a self, image: &'a [u8]) -> BoxFuture<'a, Result<Vec<Label>, String>>;
+}
+
+/// A multi-label image model whose first output holds one logit per line of
+/// the labels file.
+pub struct OnnxClassifier {
+    model: Arc<OnnxModel>,
+}
+
+struct OnnxModel {
+    session: Mutex<Session>,
+    labels: Vec<String>,
+    input_size: u32,
+}
+
+/// POSTs the image bytes and reads `{"labels": [{"label", "score"}]}`.
+pub struct ApiClassifier {
+    url: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    labels: Vec<Label>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TaggingJob {
+    media_id: Uuid,
+    file_path: String,
+    attempts: i32,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS media_tags (
+            media_id UUID NOT NULL REFERENCES media_uploads(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            confidence REAL NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (media_id, tag)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_media_tags_tag ON media_tags(tag)")
+        .execute(pool)
+        .await?;
+
+    // One row per photo, queued when first seen
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS media_tagging_jobs (
+            media_id UUID PRIMARY KEY REFERENCES media_uploads(id) ON DELETE CASCADE,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'done', 'failed')),
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            classifier TEXT,
+            next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            tagged_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_media_tagging_jobs_due ON media_tagging_jobs(next_attempt_at) WHERE status <> 'done'",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+/// `Pool Area` and `pool-area` both become `pool_area`; `None` when nothing
+/// usable is left.
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let tag: String = raw
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let tag = tag
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    (!tag.is_empty()).then(|| tag.chars().take(MAX_TAG_CHARS).collect())
+}
+
+/// Confident tags on the listing's public images, most widespread first.
+pub async fn for_property(
+    pool: &PgPool,
+    property_id: Uuid,
+) -> Result<Vec<ListingTag>, sqlx::Error> {
+    sqlx::query_as::<_, ListingTag>(&format!(
+        r#"SELECT t.tag, COUNT(*) AS images
+        FROM media_tags t
+        JOIN media_uploads m ON m.id = t.media_id
+        WHERE m.property_id = $1 AND t.confidence >= $2 AND m.{}
+        GROUP BY t.tag
+        ORDER BY images DESC, t.tag"#,
+        PUBLIC_LISTING_CONDITION
+    ))
+    .bind(property_id)
+    .bind(MIN_CONFIDENCE)
+    .fetch_all(pool)
+    .await
+}
+
+/// Confident tags of each of the listing's images, most confident first.
+pub async fn by_media(
+    pool: &PgPool,
+    property_id: Uuid,
+) -> Result<HashMap<Uuid, Vec<String>>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        r#"SELECT t.media_id, t.tag
+        FROM media_tags t
+        JOIN media_uploads m ON m.id = t.media_id
+        WHERE m.property_id = $1 AND t.confidence >= $2
+        ORDER BY t.media_id, t.confidence DESC, t.tag"#,
+    )
+    .bind(property_id)
+    .bind(MIN_CONFIDENCE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (media_id, tag) in rows {
+        tags.entry(media_id).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+// ============================================================================
+// CLASSIFIERS
+// ============================================================================
+
+impl OnnxModel {
+    fn load(model_path: &str, labels_path: &str, input_size: u32) -> Result<Self, String> {
+        let labels: Vec<String> = std::fs::read_to_string(labels_path)
+            .map_err(|e| format!("{} unreadable: {}", labels_path, e))?
+            .lines()
+            .map(|line| normalize_tag(line).unwrap_or_default())
+            .collect();
+        let session = Session::builder()
+            .and_then(|builder| builder.commit_from_file(model_path))
+            .map_err(|e| format!("Failed to load {}: {}", model_path, e))?;
+        Ok(OnnxModel {
+            session: Mutex::new(session),
+            labels,
+            input_size,
+        })
+    }
+
+    /// Resized, normalized RGB in NCHW order.
+    fn input(&self, image: &[u8]) -> Result<Vec<f32>, String> {
+        let size = self.input_size;
+        let rgb = image::load_from_memory(image)
+            .map_err(|e| format!("Unreadable image: {}", e))?
+            .resize_exact(size, size, FilterType::Triangle)
+            .to_rgb8();
+        let plane = (size * size) as usize;
+        let mut input = vec![0.0f32; 3 * plane];
+        for (i, pixel) in rgb.pixels().enumerate() {
+            for channel in 0..3 {
+                let value = pixel[channel] as f32 / 255.0;
+                input[channel * plane + i] = (value - CHANNEL_MEAN[channel]) / CHANNEL_STD[channel];
+            }
+        }
+        Ok(input)
+    }
+
+    fn classify(&self, image: &[u8]) -> Result<Vec<Label>, String> {
+        let size = self.input_size as i64;
+        let tensor = Tensor::from_array(([1, 3, size, size], self.input(image)?))
+            .map_err(|e| e.to_string())?;
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|_| "Model lock poisoned".to_string())?;
+        let outputs = session
+            .run(ort::inputs![tensor])
+            .map_err(|e| format!("Inference failed: {}", e))?;
+        let (_, logits) = outputs[0]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| format!("Unexpected model output: {}", e))?;
+        if logits.len() != self.labels.len() {
+            return Err(format!(
+                "Model has {} outputs but {} labels",
+                logits.len(),
+                self.labels.len()
+            ));
+        }
+        Ok(self
+            .labels
+            .iter()
+            .zip(logits)
+            .filter(|(tag, _)| !tag.is_empty())
+            .map(|(tag, logit)| Label {
+                tag: tag.clone(),
+                confidence: 1.0 / (1.0 + (-logit).exp()),
+            })
+            .collect())
+    }
+}
+
+impl VisionClassifier for OnnxClassifier {
+    fn name(&self) -> &str {
+        "onnx"
+    }
+
+    fn classify<'a>(&'a self, image: &'a [u8]) -> BoxFuture<'a, Result<Vec<Label>, String>> {
+        Box::pin(async move {
+            let model = self.model.clone();
+            let image = image.to_vec();
+            tokio::task::spawn_blocking(move || model.classify(&image))
+                .await
+                .map_err(|e| format!("Classification task failed: {}", e))?
+        })
+    }
+}
+
+impl VisionClassifier for ApiClassifier {
+    fn name(&self) -> &str {
+        "api"
+    }
+
+    fn classify<'a>(&'a self, image: &'a [u8]) -> BoxFuture<'a, Result<Vec<Label>, String>> {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+                .body(image.to_vec());
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let response: ApiResponse = request
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Vision request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid vision response: {}", e))?;
+            Ok(response.labels)
+        })
+    }
+}
+
+/// `IMAGE_TAGGER` = `onnx` (`VISION_ONNX_MODEL`, `VISION_ONNX_LABELS`,
+/// optionally `VISION_ONNX_INPUT_SIZE`; the runtime library comes from
+/// `ORT_DYLIB_PATH`) or `api` (`VISION_API_URL`, optionally
+/// `VISION_API_KEY`). Tagging is off when unset.
+async fn classifier_from_env() -> Option<Box<dyn VisionClassifier>> {
+    match std::env::var("IMAGE_TAGGER").as_deref() {
+        Err(_) => None,
+        Ok("onnx") => {
+            let (Ok(model_path), Ok(labels_path)) = (
+                std::env::var("VISION_ONNX_MODEL"),
+                std::env::var("VISION_ONNX_LABELS"),
+            ) else {
+                warn!("VISION_ONNX_MODEL/VISION_ONNX_LABELS not set; image tagging disabled");
+                return None;
+            };
+            let input_size = std::env::var("VISION_ONNX_INPUT_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(DEFAULT_INPUT_SIZE);
+            // Blocking, and ort panics when the runtime library is missing
+            let loaded = tokio::task::spawn_blocking(move || {
+                OnnxModel::load(&model_path, &labels_path, input_size)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|loaded| loaded);
+            match loaded {
+                Ok(model) => {
+                    info!(
+                        "Tagging images with ONNX model ({} labels)",
+                        model.labels.len()
+                    );
+                    Some(Box::new(OnnxClassifier {
+                        model: Arc::new(model),
+                    }))
+                }
+                Err(e) => {
+                    warn!("Image tagging disabled, ONNX model failed to load: {}", e);
+                    None
+                }
+            }
+        }
+        Ok("api") => {
+            let Ok(url) = std::env::var("VISION_API_URL") else {
+                warn!("VISION_API_URL not set; image tagging disabled");
+                return None;
+            };
+            let client = match reqwest::Client::builder().timeout(API_TIMEOUT).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Image tagging disabled, HTTP client failed to build: {}", e);
+                    return None;
+                }
+            };
+            info!("Tagging images with the vision API at {}", url);
+            Some(Box::new(ApiClassifier {
+                url,
+                api_key: secrets::var("VISION_API_KEY").ok(),
+                client,
+            }))
+        }
+        Ok(other) => {
+            warn!("Unknown IMAGE_TAGGER '{}'; image tagging disabled", other);
+            None
+        }
+    }
+}
+
+// ============================================================================
+// WORKER
+// ============================================================================
+
+fn backoff_minutes(attempts: i32) -> i32 {
+    2i32.saturating_pow(attempts.clamp(0, 16) as u32)
+        .min(MAX_BACKOFF_MINUTES)
+}
+
+/// Queues photos that have never been through the tagger.
+async fn enqueue_new(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO media_tagging_jobs (media_id)
+        SELECT m.id FROM media_uploads m
+        WHERE m.file_type = 'image'
+          AND NOT EXISTS (SELECT 1 FROM media_tagging_jobs j WHERE j.media_id = m.id)
+        ON CONFLICT (media_id) DO NOTHING"#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Swaps an image's tags for a fresh classification.
+async fn store_tags(
+    pool: &PgPool,
+    media_id: Uuid,
+    classifier: &str,
+    labels: Vec<Label>,
+) -> Result<usize, sqlx::Error> {
+    let mut best: HashMap<String, f32> = HashMap::new();
+    for label in labels {
+        let Some(tag) = normalize_tag(&label.tag) else {
+            continue;
+        };
+        if label.confidence.is_nan() || label.confidence < STORE_CONFIDENCE {
+            continue;
+        }
+        let confidence = label.confidence.min(1.0);
+        let entry = best.entry(tag).or_insert(confidence);
+        *entry = entry.max(confidence);
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM media_tags WHERE media_id = $1")
+        .bind(media_id)
+        .execute(&mut *tx)
+        .await?;
+    for (tag, confidence) in &best {
+        sqlx::query("INSERT INTO media_tags (media_id, tag, confidence) VALUES ($1, $2, $3)")
+            .bind(media_id)
+            .bind(tag)
+            .bind(confidence)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query(
+        r#"UPDATE media_tagging_jobs
+        SET status = 'done', last_error = NULL, classifier = $2, tagged_at = NOW()
+        WHERE media_id = $1"#,
+    )
+    .bind(media_id)
+    .bind(classifier)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(best.len())
+}
+
+async fn process_due(pool: &PgPool, classifier: &dyn VisionClassifier) -> Result<(), sqlx::Error> {
+    // Claimed by pushing the next attempt out, so other instances skip them
+    let jobs = sqlx::query_as::<_, TaggingJob>(
+        r#"UPDATE media_tagging_jobs j
+        SET next_attempt_at = NOW() + make_interval(mins => $3)
+        FROM media_uploads m
+        WHERE m.id = j.media_id AND j.media_id IN (
+            SELECT media_id FROM media_tagging_jobs
+            WHERE status <> 'done' AND attempts < $1 AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED)
+        RETURNING j.media_id, m.file_path, j.attempts"#,
+    )
+    .bind(MAX_ATTEMPTS)
+    .bind(BATCH_SIZE)
+    .bind(CLAIM_LEASE_MINUTES)
+    .fetch_all(pool)
+    .await?;
+
+    for job in jobs {
+        let labels = match tokio::fs::read(&job.file_path).await {
+            Ok(bytes) => classifier.classify(&bytes).await,
+            Err(e) => Err(format!("{} unreadable: {}", job.file_path, e)),
+        };
+        match labels {
+            Ok(labels) => {
+                store_tags(pool, job.media_id, classifier.name(), labels).await?;
+            }
+            Err(message) => {
+                warn!("Tagging media {} failed: {}", job.media_id, message);
+                sqlx::query(
+                    r#"UPDATE media_tagging_jobs
+                    SET status = 'failed', attempts = attempts + 1, last_error = $2,
+                        next_attempt_at = NOW() + make_interval(mins => $3)
+                    WHERE media_id = $1"#,
+                )
+                .bind(job.media_id)
+                .bind(&message)
+                .bind(backoff_minutes(job.attempts))
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn spawn_tagger(pool: PgPool) {
+    tokio::spawn(async move {
+        let Some(classifier) = classifier_from_env().await else {
+            return;
+        };
+        let mut interval = tokio::time::interval(Duration::from_secs(WORKER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = enqueue_new(&pool).await {
+                error!("Failed to queue photos for tagging: {}", e);
+                continue;
+            }
+            if let Err(e) = process_due(&pool, classifier.as_ref()).await {
+                error!("Image tagging run failed: {}", e);
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// What the listing's photos show, e.g. for "pool" and "garden" badges.
+#[get("/api/properties/{id}/tags")]
+pub async fn property_tags(path: web::Path<Uuid>, state: web::Data<AppState>) -> impl Responder {
+    let property_id = path.into_inner();
+
+    let result: Result<Option<Vec<ListingTag>>, sqlx::Error> = async {
+        let visible: bool = sqlx::query_scalar(&format!(
+            "SELECT EXISTS (SELECT 1 FROM properties WHERE id = $1 AND {})",
+            PUBLIC_LISTING_CONDITION
+        ))
+        .bind(property_id)
+        .fetch_one(&state.db)
+        .await?;
+        if !visible {
+            return Ok(None);
+        }
+        Ok(Some(for_property(&state.db, property_id).await?))
+    }
+    .await;
+
+    match result {
+        Ok(Some(tags)) => HttpResponse::Ok().json(serde_json::json!({
+            "property_id": property_id,
+            "tags": tags
+        })),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Err(e) => {
+            error!("Failed to load tags of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load property tags"
+            }))
+        }
+    }
+}
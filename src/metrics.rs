@@ -0,0 +1,298 @@
+// JARVIS2026 - Live metrics for the admin dashboard
+// A small in-process registry of counters and gauges: requests served,
+// uploads in flight, tokens awarded and the depth of the moderation and
+// syndication queues. A sampler turns it into one snapshot per second and
+// `/ws/admin/metrics` pushes every snapshot to connected admins, so the
+// dashboard updates without polling.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    get,
+    http::header::{self, HeaderValue},
+    middleware::Next,
+    rt, web, Error, HttpRequest, HttpResponse,
+};
+use futures_util::StreamExt;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::auth::{AdminUser, AuthError, Role};
+use crate::listing_checks::PRICE_FLAGGED_STATUS;
+use crate::sessions;
+use crate::upload_policy::HELD_STATUS;
+use crate::AppState;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Queue depths come from the database, so they're refreshed less often.
+const QUEUE_REFRESH_TICKS: u32 = 5;
+const TOKEN_WINDOW: Duration = Duration::from_secs(60);
+/// Browsers can't set headers on a WebSocket, so the dashboard offers the
+/// subprotocols `bearer, <session token>` instead. Unlike a query string,
+/// this keeps the token out of access logs.
+pub const BEARER_PROTOCOL: &str = "bearer";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+pub struct Metrics {
+    requests: AtomicU64,
+    active_uploads: AtomicI64,
+    tokens_awarded: AtomicU64,
+    moderation_queue: AtomicI64,
+    syndication_queue: AtomicI64,
+    snapshots: watch::Sender<Snapshot>,
+}
+
+/// Decrements the active upload gauge when the upload handler returns.
+pub struct UploadGuard<'a>(&'a Metrics);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueueDepth {
+    /// Listings flagged or held for a moderator
+    moderation: i64,
+    /// Portal pushes waiting for the syndication worker
+    syndication: i64,
+    total: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Snapshot {
+    sampled_at: Option<chrono::DateTime<chrono::Utc>>,
+    active_uploads: i64,
+    requests_per_sec: f64,
+    requests_total: u64,
+    queue_depth: QueueDepth,
+    /// Tokens awarded over the last minute
+    tokens_per_min: u64,
+    tokens_total: u64,
+}
+
+// ============================================================================
+// REGISTRY
+// ============================================================================
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            requests: AtomicU64::new(0),
+            active_uploads: AtomicI64::new(0),
+            tokens_awarded: AtomicU64::new(0),
+            moderation_queue: AtomicI64::new(0),
+            syndication_queue: AtomicI64::new(0),
+            snapshots: watch::Sender::new(Snapshot::default()),
+        }
+    }
+
+    pub fn upload_started(&self) -> UploadGuard<'_> {
+        self.active_uploads.fetch_add(1, Ordering::Relaxed);
+        UploadGuard(self)
+    }
+
+    pub fn record_tokens(&self, amount: i64) {
+        if amount > 0 {
+            self.tokens_awarded
+                .fetch_add(amount as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn subscribe(&self) -> watch::Receiver<Snapshot> {
+        self.snapshots.subscribe()
+    }
+}
+
+impl Drop for UploadGuard<'_> {
+    fn drop(&mut self) {
+        self.0.active_uploads.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts every request the server handles.
+pub async fn count_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if let Some(state) = req.app_data::<web::Data<AppState>>() {
+        state.metrics.requests.fetch_add(1, Ordering::Relaxed);
+    }
+    next.call(req).await
+}
+
+// ============================================================================
+// SAMPLING
+// ============================================================================
+
+async fn refresh_queue_depth(pool: &PgPool, metrics: &Metrics) -> Result<(), sqlx::Error> {
+    let (moderation, syndication) = sqlx::query_as::<_, (i64, i64)>(
+        r#"SELECT
+            (SELECT COUNT(*) FROM properties WHERE moderation_status = ANY($1)),
+            (SELECT COUNT(*) FROM syndication_listings WHERE status = 'pending')"#,
+    )
+    .bind([PRICE_FLAGGED_STATUS, HELD_STATUS])
+    .fetch_one(pool)
+    .await?;
+    metrics
+        .moderation_queue
+        .store(moderation, Ordering::Relaxed);
+    metrics
+        .syndication_queue
+        .store(syndication, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Publishes a snapshot every second for as long as the server runs.
+pub fn spawn_sampler(pool: PgPool, metrics: Arc<Metrics>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        let mut last = (Instant::now(), metrics.requests.load(Ordering::Relaxed));
+        // (when, tokens_total) over the last minute
+        let mut token_samples: VecDeque<(Instant, u64)> = VecDeque::new();
+        let mut ticks = 0u32;
+
+        loop {
+            interval.tick().await;
+            if ticks.is_multiple_of(QUEUE_REFRESH_TICKS) {
+                if let Err(e) = refresh_queue_depth(&pool, &metrics).await {
+                    warn!("Failed to sample queue depth: {}", e);
+                }
+            }
+            ticks = ticks.wrapping_add(1);
+
+            let now = Instant::now();
+            let requests_total = metrics.requests.load(Ordering::Relaxed);
+            let elapsed = now.duration_since(last.0).as_secs_f64().max(f64::EPSILON);
+            let requests_per_sec = (requests_total - last.1) as f64 / elapsed;
+            last = (now, requests_total);
+
+            let tokens_total = metrics.tokens_awarded.load(Ordering::Relaxed);
+            token_samples.push_back((now, tokens_total));
+            while token_samples
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) > TOKEN_WINDOW)
+            {
+                token_samples.pop_front();
+            }
+            let window_start = token_samples.front().map_or(tokens_total, |(_, t)| *t);
+
+            let moderation = metrics.moderation_queue.load(Ordering::Relaxed);
+            let syndication = metrics.syndication_queue.load(Ordering::Relaxed);
+            metrics.snapshots.send_replace(Snapshot {
+                sampled_at: Some(chrono::Utc::now()),
+                active_uploads: metrics.active_uploads.load(Ordering::Relaxed),
+                requests_per_sec: (requests_per_sec * 10.0).round() / 10.0,
+                requests_total,
+                queue_depth: QueueDepth {
+                    moderation,
+                    syndication,
+                    total: moderation + syndication,
+                },
+                tokens_per_min: tokens_total - window_start,
+                tokens_total,
+            });
+        }
+    });
+}
+
+/// The session token offered through `Sec-WebSocket-Protocol`.
+pub fn protocol_token(req: &HttpRequest) -> Option<String> {
+    let offered = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)?
+        .to_str()
+        .ok()?;
+    let mut protocols = offered.split(',').map(str::trim);
+    (protocols.next()? == BEARER_PROTOCOL)
+        .then(|| protocols.next().map(str::to_string))
+        .flatten()
+}
+
+async fn admin_for_token(pool: &PgPool, token: &str) -> Result<bool, sqlx::Error> {
+    let Some(user_id) = sessions::user_for_token(pool, token).await? else {
+        return Ok(false);
+    };
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND role = 'admin' AND status <> 'banned')",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Streams a JSON snapshot per second. Clients only ever receive; anything
+/// they send other than ping and close is ignored.
+#[get("/ws/admin/metrics")]
+pub async fn admin_metrics_socket(
+    req: HttpRequest,
+    body: web::Payload,
+    admin: Result<AdminUser, AuthError>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, Error> {
+    let protocol_token = protocol_token(&req);
+    if let Err(auth_error) = admin {
+        let Some(token) = protocol_token.as_deref() else {
+            return Err(auth_error.into());
+        };
+        match admin_for_token(&state.db, token).await {
+            Ok(true) => {}
+            Ok(false) => return Err(AuthError::Forbidden(Role::Admin).into()),
+            Err(e) => {
+                error!("Failed to authenticate metrics socket: {}", e);
+                return Err(AuthError::Internal.into());
+            }
+        }
+    }
+
+    let (mut response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    if protocol_token.is_some() {
+        response.headers_mut().insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(BEARER_PROTOCOL),
+        );
+    }
+    let mut snapshots = state.metrics.subscribe();
+    info!("Admin metrics socket opened");
+
+    rt::spawn(async move {
+        let initial = serde_json::to_string(&*snapshots.borrow_and_update()).unwrap_or_default();
+        if session.text(initial).await.is_err() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                changed = snapshots.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    let snapshot =
+                        serde_json::to_string(&*snapshots.borrow_and_update()).unwrap_or_default();
+                    if session.text(snapshot).await.is_err() {
+                        return;
+                    }
+                }
+                message = messages.next() => match message {
+                    Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
@@ -0,0 +1,277 @@
+// JARVIS2026 - 3D models (glTF)
+// `.glb` and `.gltf` uploads are stored as `model` media. They are validated
+// at upload time and streamed with the glTF MIME types and range support so
+// web viewers can load them progressively.
+
+use actix_files::NamedFile;
+use actix_web::{
+    get,
+    http::header::{self, HeaderValue},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::media_files;
+use crate::AppState;
+
+pub const MODEL_FILE_TYPE: &str = "model";
+const MAX_MODEL_BYTES: usize = 100 * 1024 * 1024;
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_HEADER_LEN: usize = 12;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ModelFormat {
+    /// Binary container with embedded buffers
+    Glb,
+    /// JSON; buffers and textures must be embedded as data URIs
+    Gltf,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ModelAsset {
+    id: Uuid,
+    file_size: i64,
+    uploaded_at: chrono::DateTime<chrono::Utc>,
+    alt_text: Option<String>,
+    #[serde(skip)]
+    file_path: String,
+    #[sqlx(skip)]
+    format: &'static str,
+    #[sqlx(skip)]
+    url: String,
+}
+
+#[derive(Serialize)]
+struct PropertyModels {
+    property_id: Uuid,
+    models: Vec<ModelAsset>,
+}
+
+// ============================================================================
+// VALIDATION
+// ============================================================================
+
+impl ModelFormat {
+    fn from_filename(filename: &str) -> Option<Self> {
+        let lower = filename.to_lowercase();
+        if lower.ends_with(".glb") {
+            Some(ModelFormat::Glb)
+        } else if lower.ends_with(".gltf") {
+            Some(ModelFormat::Gltf)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ModelFormat::Glb => "glb",
+            ModelFormat::Gltf => "gltf",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ModelFormat::Glb => "model/gltf-binary",
+            ModelFormat::Gltf => "model/gltf+json",
+        }
+    }
+}
+
+/// Where a model asset is streamed from.
+pub fn model_path(media_id: Uuid) -> String {
+    format!("/api/media/{}/model", media_id)
+}
+
+pub fn is_model_file(filename: &str) -> bool {
+    ModelFormat::from_filename(filename).is_some()
+}
+
+/// Checks a model upload is a self-contained glTF 2.0 asset within the size limit.
+pub fn validate(filename: &str, data: &[u8]) -> Result<(), String> {
+    let Some(format) = ModelFormat::from_filename(filename) else {
+        return Err(format!("{} is not a glTF model", filename));
+    };
+    if data.len() > MAX_MODEL_BYTES {
+        return Err(format!(
+            "{} exceeds the {} MB model limit",
+            filename,
+            MAX_MODEL_BYTES / (1024 * 1024)
+        ));
+    }
+
+    match format {
+        ModelFormat::Glb => validate_glb(data),
+        ModelFormat::Gltf => validate_gltf(data),
+    }
+    .map_err(|reason| format!("{}: {}", filename, reason))
+}
+
+fn validate_glb(data: &[u8]) -> Result<(), String> {
+    if data.len() < GLB_HEADER_LEN || &data[0..4] != GLB_MAGIC {
+        return Err("not a binary glTF file".to_string());
+    }
+    let word = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    if word(4) != 2 {
+        return Err("only glTF 2.0 is supported".to_string());
+    }
+    if word(8) as usize != data.len() {
+        return Err("file is truncated or has trailing data".to_string());
+    }
+    Ok(())
+}
+
+fn validate_gltf(data: &[u8]) -> Result<(), String> {
+    let doc: serde_json::Value =
+        serde_json::from_slice(data).map_err(|_| "not valid glTF JSON".to_string())?;
+
+    let version = doc
+        .pointer("/asset/version")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "missing asset.version".to_string())?;
+    if !version.starts_with("2.") {
+        return Err("only glTF 2.0 is supported".to_string());
+    }
+
+    // Sibling .bin/texture files aren't uploaded with the model, so every
+    // resource has to be inlined.
+    for section in ["buffers", "images"] {
+        let external = doc
+            .get(section)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.get("uri").and_then(|u| u.as_str()))
+            .any(|uri| !uri.starts_with("data:"));
+        if external {
+            return Err(format!(
+                "{} reference external files; upload a .glb or embed them",
+                section
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+/// The `models` section of a property: every 3D asset with its stream URL.
+pub async fn models_for_property(
+    pool: &PgPool,
+    property_id: Uuid,
+) -> Result<Vec<ModelAsset>, sqlx::Error> {
+    let models = sqlx::query_as::<_, ModelAsset>(
+        r#"SELECT id, file_size, uploaded_at, alt_text, file_path FROM media_uploads
+        WHERE property_id = $1 AND file_type = $2
+          AND moderation_status NOT IN ('rejected', 'unpublished')
+        ORDER BY uploaded_at"#,
+    )
+    .bind(property_id)
+    .bind(MODEL_FILE_TYPE)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(models
+        .into_iter()
+        .map(|mut model| {
+            model.format = ModelFormat::from_filename(&model.file_path)
+                .unwrap_or(ModelFormat::Glb)
+                .name();
+            model.url = model_path(model.id);
+            model
+        })
+        .collect())
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/{id}/models")]
+pub async fn property_models(path: web::Path<Uuid>, state: web::Data<AppState>) -> impl Responder {
+    let property_id = path.into_inner();
+    match models_for_property(&state.db, property_id).await {
+        Ok(models) => HttpResponse::Ok().json(PropertyModels {
+            property_id,
+            models,
+        }),
+        Err(e) => {
+            error!("Failed to load models for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load models"
+            }))
+        }
+    }
+}
+
+/// Streams a model file; `NamedFile` takes care of `Range` requests.
+#[get("/api/media/{id}/model")]
+pub async fn serve_model(
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> HttpResponse {
+    let media_id = path.into_inner();
+
+    let (file_path, content_hash, uploaded_at) =
+        match sqlx::query_as::<_, (String, String, chrono::DateTime<chrono::Utc>)>(
+            r#"SELECT file_path, content_hash, uploaded_at FROM media_uploads
+        WHERE id = $1 AND file_type = $2
+          AND moderation_status NOT IN ('rejected', 'unpublished')"#,
+        )
+        .bind(media_id)
+        .bind(MODEL_FILE_TYPE)
+        .fetch_optional(&state.db)
+        .await
+        {
+            Ok(Some(media)) => media,
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Model not found"
+                }))
+            }
+            Err(e) => {
+                error!("Failed to look up media {}: {}", media_id, e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to load model"
+                }));
+            }
+        };
+
+    let validators = media_files::Validators::new(&content_hash, None, uploaded_at);
+    if let Some(response) = validators.not_modified(&req) {
+        return response;
+    }
+
+    let format = ModelFormat::from_filename(&file_path).unwrap_or(ModelFormat::Glb);
+    let file = match NamedFile::open_async(&file_path).await {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Media {} file {} unreadable: {}", media_id, file_path, e);
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Model not found"
+            }));
+        }
+    };
+
+    let mut response = file
+        .use_etag(false)
+        .use_last_modified(false)
+        .disable_content_disposition()
+        .into_response(&req);
+    validators.apply(&mut response);
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    response
+}
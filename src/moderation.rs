@@ -0,0 +1,431 @@
+// JARVIS2026 - Listing and media moderation
+// New listings are visible while pending review; rejecting or unpublishing
+// hides them from public listing and search endpoints, as does being flagged
+// by the pre-publish checks or held by a strict agency upload policy until a
+// moderator approves.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::fs as async_fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::AdminUser;
+use crate::completeness;
+use crate::listing_checks::PRICE_FLAGGED_STATUS;
+use crate::tokenization;
+use crate::upload_policy::HELD_STATUS;
+use crate::AppState;
+
+/// SQL condition for listings the public may see.
+pub const PUBLIC_LISTING_CONDITION: &str =
+    "moderation_status NOT IN ('rejected', 'unpublished', 'flagged', 'held')";
+const MAX_BULK_ITEMS: usize = 500;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ModerationAction {
+    Approve,
+    Reject,
+    Unpublish,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TargetKind {
+    Property,
+    Media,
+}
+
+#[derive(Deserialize)]
+pub struct BulkModerationRequest {
+    action: ModerationAction,
+    #[serde(default)]
+    property_ids: Vec<Uuid>,
+    #[serde(default)]
+    media_ids: Vec<Uuid>,
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ItemResult {
+    #[serde(rename = "type")]
+    kind: TargetKind,
+    id: Uuid,
+    /// `ok` or `not_found`
+    status: &'static str,
+}
+
+#[derive(Deserialize)]
+pub struct FlaggedQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// A listing held back by the pre-publish checks.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct FlaggedListing {
+    id: Uuid,
+    user_id: Option<Uuid>,
+    title: String,
+    location: String,
+    price: f64,
+    property_type: Option<String>,
+    price_flag: Option<serde_json::Value>,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip)]
+    total: i64,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "ALTER TABLE properties ADD COLUMN IF NOT EXISTS moderation_status TEXT NOT NULL DEFAULT 'pending'",
+    )
+    .execute(pool)
+    .await?;
+
+    // When a moderator last changed the listing's status, so a listing
+    // approved or restored after creation counts as newly live
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS moderated_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "ALTER TABLE media_uploads ADD COLUMN IF NOT EXISTS moderation_status TEXT NOT NULL DEFAULT 'pending'",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// ACTIONS
+// ============================================================================
+
+impl ModerationAction {
+    fn name(self) -> &'static str {
+        match self {
+            ModerationAction::Approve => "approve",
+            ModerationAction::Reject => "reject",
+            ModerationAction::Unpublish => "unpublish",
+            ModerationAction::Delete => "delete",
+        }
+    }
+
+    /// Status the target moves to, `None` for deletion.
+    fn status(self) -> Option<&'static str> {
+        match self {
+            ModerationAction::Approve => Some("approved"),
+            ModerationAction::Reject => Some("rejected"),
+            ModerationAction::Unpublish => Some("unpublished"),
+            ModerationAction::Delete => None,
+        }
+    }
+}
+
+impl TargetKind {
+    fn table(self) -> &'static str {
+        match self {
+            TargetKind::Property => "properties",
+            TargetKind::Media => "media_uploads",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TargetKind::Property => "property",
+            TargetKind::Media => "media",
+        }
+    }
+}
+
+/// Deletes a listing with its media rows, collecting the files to unlink
+/// once the transaction commits. Returns whether it existed.
+pub async fn delete_listing(
+    tx: &mut Transaction<'_, Postgres>,
+    property_id: Uuid,
+    removed_files: &mut Vec<String>,
+) -> Result<bool, sqlx::Error> {
+    moderate_item(
+        tx,
+        TargetKind::Property,
+        property_id,
+        ModerationAction::Delete,
+        removed_files,
+    )
+    .await
+}
+
+/// Of the files whose media rows were just deleted, those no other media row
+/// still points at. Uploads used to be stored under their client filename,
+/// so older rows can share a file.
+pub async fn unreferenced_files(
+    tx: &mut Transaction<'_, Postgres>,
+    files: Vec<String>,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        r#"SELECT DISTINCT path FROM UNNEST($1::text[]) AS path
+        WHERE NOT EXISTS (SELECT 1 FROM media_uploads m WHERE m.file_path = path)"#,
+    )
+    .bind(files)
+    .fetch_all(&mut **tx)
+    .await
+}
+
+/// Applies `action` to one target. Returns whether it existed, and collects
+/// files to remove from disk once the transaction commits.
+async fn moderate_item(
+    tx: &mut Transaction<'_, Postgres>,
+    kind: TargetKind,
+    id: Uuid,
+    action: ModerationAction,
+    removed_files: &mut Vec<String>,
+) -> Result<bool, sqlx::Error> {
+    // Media changes alter the parent listing's completeness
+    let parent_property = match kind {
+        TargetKind::Media => sqlx::query_scalar::<_, Option<Uuid>>(
+            "SELECT property_id FROM media_uploads WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .flatten(),
+        TargetKind::Property => None,
+    };
+
+    if let Some(status) = action.status() {
+        let stamp = match kind {
+            TargetKind::Property => ", moderated_at = NOW()",
+            TargetKind::Media => "",
+        };
+        let sql = format!(
+            "UPDATE {} SET moderation_status = $1{} WHERE id = $2",
+            kind.table(),
+            stamp
+        );
+        let result = sqlx::query(&sql)
+            .bind(status)
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+        if let Some(property_id) = parent_property {
+            completeness::refresh(&mut **tx, property_id).await?;
+        }
+        return Ok(result.rows_affected() > 0);
+    }
+
+    let media_filter = match kind {
+        TargetKind::Property => "property_id = $1",
+        TargetKind::Media => "id = $1",
+    };
+
+    // Token history outlives the media it was earned for
+    sqlx::query(&format!(
+        "UPDATE token_transactions SET media_id = NULL WHERE media_id IN (SELECT id FROM media_uploads WHERE {})",
+        media_filter
+    ))
+    .bind(id)
+    .execute(&mut **tx)
+    .await?;
+
+    let files: Vec<String> = sqlx::query_scalar(&format!(
+        "DELETE FROM media_uploads WHERE {} RETURNING file_path",
+        media_filter
+    ))
+    .bind(id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let existed = match kind {
+        TargetKind::Property => {
+            sqlx::query("DELETE FROM properties WHERE id = $1")
+                .bind(id)
+                .execute(&mut **tx)
+                .await?
+                .rows_affected()
+                > 0
+        }
+        TargetKind::Media => !files.is_empty(),
+    };
+
+    if let Some(property_id) = parent_property {
+        completeness::refresh(&mut **tx, property_id).await?;
+    }
+
+    removed_files.extend(unreferenced_files(tx, files).await?);
+    Ok(existed)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Runs one action over many listings and media in a single transaction.
+/// Missing ids are reported per item and do not abort the batch.
+#[post("/api/admin/moderation/bulk")]
+pub async fn bulk_moderate(
+    admin: AdminUser,
+    req: web::Json<BulkModerationRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let req = req.into_inner();
+    let total = req.property_ids.len() + req.media_ids.len();
+    if total == 0 || total > MAX_BULK_ITEMS {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Provide between 1 and {} property_ids/media_ids", MAX_BULK_ITEMS)
+        }));
+    }
+
+    let targets: Vec<(TargetKind, Uuid)> = req
+        .property_ids
+        .iter()
+        .map(|id| (TargetKind::Property, *id))
+        .chain(req.media_ids.iter().map(|id| (TargetKind::Media, *id)))
+        .collect();
+
+    if req.action == ModerationAction::Delete {
+        match tokenization::offered_listings(&state.db, &req.property_ids).await {
+            Ok(offered) if offered.is_empty() => {}
+            Ok(offered) => {
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Listings with a tokenized offering can't be deleted",
+                    "property_ids": offered
+                }))
+            }
+            Err(e) => {
+                error!("Failed to check offerings before bulk delete: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Bulk moderation failed; no changes were applied"
+                }));
+            }
+        }
+    }
+
+    let mut removed_files = Vec::new();
+    let outcome: Result<Vec<ItemResult>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let mut results = Vec::with_capacity(targets.len());
+
+        for (kind, id) in targets {
+            let found = moderate_item(&mut tx, kind, id, req.action, &mut removed_files).await?;
+            if found {
+                audit::record(
+                    &mut tx,
+                    admin.id,
+                    &format!("moderation.{}", req.action.name()),
+                    kind.name(),
+                    id,
+                    serde_json::json!({ "reason": req.reason }),
+                )
+                .await?;
+            }
+            results.push(ItemResult {
+                kind,
+                id,
+                status: if found { "ok" } else { "not_found" },
+            });
+        }
+
+        tx.commit().await?;
+        Ok(results)
+    }
+    .await;
+
+    match outcome {
+        Ok(results) => {
+            // Media ids don't say which listing they belong to
+            if req.media_ids.is_empty() {
+                for id in &req.property_ids {
+                    state.property_cache.invalidate(*id).await;
+                }
+            } else {
+                state.property_cache.invalidate_all();
+            }
+            for path in &removed_files {
+                if let Err(e) = async_fs::remove_file(path).await {
+                    warn!("Failed to remove moderated file {}: {}", path, e);
+                }
+            }
+            let applied = results.iter().filter(|r| r.status == "ok").count();
+            info!(
+                "Admin {} applied {} to {}/{} items",
+                admin.id,
+                req.action.name(),
+                applied,
+                results.len()
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "action": req.action.name(),
+                "applied": applied,
+                "results": results
+            }))
+        }
+        Err(e) => {
+            error!("Bulk moderation by {} failed: {}", admin.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Bulk moderation failed; no changes were applied"
+            }))
+        }
+    }
+}
+
+/// Listings waiting on a moderator: an unusual price the uploader confirmed,
+/// or an agency whose upload policy holds every new listing. Approve or
+/// reject them through the bulk endpoint.
+#[get("/api/admin/moderation/flagged")]
+pub async fn flagged_listings(
+    _admin: AdminUser,
+    query: web::Query<FlaggedQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, FlaggedListing>(
+        r#"SELECT id, user_id, title, location, price, property_type, price_flag, created_at,
+                  COUNT(*) OVER () AS total
+        FROM properties
+        WHERE moderation_status = ANY($1)
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3"#,
+    )
+    .bind([PRICE_FLAGGED_STATUS, HELD_STATUS])
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(listings) => {
+            let total = listings.first().map_or(0, |l| l.total);
+            HttpResponse::Ok().json(serde_json::json!({
+                "total": total,
+                "limit": limit,
+                "offset": offset,
+                "listings": listings
+            }))
+        }
+        Err(e) => {
+            error!("Failed to list flagged listings: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list flagged listings"
+            }))
+        }
+    }
+}
@@ -0,0 +1,591 @@
+// JARVIS2026 - Natural-language search queries
+// Turns "3 bedroom villa in Bali under 5 miliar" into the structured filters
+// search already understands: rooms, property type, location, price and
+// area bounds, certificate. A rule-based parser handles English and
+// Indonesian phrasing offline; when `NL_QUERY_LLM_URL` is set, an
+// OpenAI-compatible chat model is asked first and the rules are the
+// fallback. Whatever isn't understood stays as free text, so mini-language
+// tokens such as `price<2b` keep working inside a sentence.
+
+use actix_web::{post, web, HttpResponse, Responder};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::filters::{self, Field, Filter, FilterSet, Op, Value};
+use crate::secrets;
+use crate::AppState;
+
+const LLM_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_QUERY_CHARS: usize = 300;
+
+/// Words that introduce a place, in English and Indonesian.
+const LOCATION_WORDS: &[&str] = &[
+    "in", "at", "near", "around", "di", "dekat", "daerah", "kawasan", "sekitar",
+];
+const STOPWORDS: &[&str] = &[
+    "a",
+    "an",
+    "the",
+    "with",
+    "and",
+    "or",
+    "for",
+    "from",
+    "sale",
+    "of",
+    "to",
+    "me",
+    "find",
+    "show",
+    "looking",
+    "want",
+    "need",
+    "i",
+    "property",
+    "properties",
+    "dijual",
+    "jual",
+    "yang",
+    "dengan",
+    "dan",
+    "untuk",
+    "cari",
+    "mau",
+    "ada",
+];
+/// (synonym, canonical `property_type`)
+const PROPERTY_TYPES: &[(&str, &str)] = &[
+    ("villa", "villa"),
+    ("villas", "villa"),
+    ("house", "house"),
+    ("houses", "house"),
+    ("home", "house"),
+    ("homes", "house"),
+    ("rumah", "house"),
+    ("apartment", "apartment"),
+    ("apartments", "apartment"),
+    ("apartemen", "apartment"),
+    ("condo", "apartment"),
+    ("flat", "apartment"),
+    ("townhouse", "townhouse"),
+    ("land", "land"),
+    ("tanah", "land"),
+    ("plot", "land"),
+    ("ruko", "shophouse"),
+    ("shophouse", "shophouse"),
+    ("office", "office"),
+    ("kantor", "office"),
+    ("warehouse", "warehouse"),
+    ("gudang", "warehouse"),
+];
+const CERTIFICATES: &[&str] = &["shm", "shgb", "hgb", "hgu", "hp", "girik", "ajb", "strata"];
+/// Phrases before an upper price bound
+const MAX_BOUNDS: &[&[&str]] = &[
+    &["under"],
+    &["below"],
+    &["max"],
+    &["maximum"],
+    &["maks"],
+    &["less", "than"],
+    &["up", "to"],
+    &["at", "most"],
+    &["dibawah"],
+    &["di", "bawah"],
+    &["kurang", "dari"],
+    &["<"],
+];
+/// Phrases before a lower price bound
+const MIN_BOUNDS: &[&[&str]] = &[
+    &["over"],
+    &["above"],
+    &["min"],
+    &["minimum"],
+    &["from"],
+    &["more", "than"],
+    &["at", "least"],
+    &["diatas"],
+    &["di", "atas"],
+    &["lebih", "dari"],
+    &["mulai"],
+    &[">"],
+];
+const RANGE_WORDS: &[&str] = &["between", "antara"];
+const RANGE_JOINERS: &[&str] = &["and", "to", "-", "dan", "sampai", "hingga"];
+/// Amount words that scale the number before them
+const MULTIPLIERS: &[(&str, f64)] = &[
+    ("ribu", 1e3),
+    ("rb", 1e3),
+    ("thousand", 1e3),
+    ("juta", 1e6),
+    ("million", 1e6),
+    ("mio", 1e6),
+    ("miliar", 1e9),
+    ("milyar", 1e9),
+    ("billion", 1e9),
+];
+
+const LLM_PROMPT: &str = r#"You turn property search requests into JSON filters. Reply with one JSON object with these keys, using null when the request doesn't say:
+"location" (place name, lowercase), "property_type" (one of villa, house, apartment, townhouse, land, shophouse, office, warehouse), "min_price" and "max_price" (numbers in rupiah; 1 juta = 1000000, 1 miliar = 1000000000), "bedrooms" and "bathrooms" (minimum counts), "min_area" (square metres), "certificate_type" (e.g. SHM, HGB), "keywords" (array of other words that must appear in the listing, usually empty)."#;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// What a parser understood. Room counts and area are minimums, matching the
+/// listing filter parameters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StructuredQuery {
+    pub location: Option<String>,
+    pub property_type: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub bedrooms: Option<i32>,
+    pub bathrooms: Option<i32>,
+    pub min_area: Option<f64>,
+    pub certificate_type: Option<String>,
+    /// Left as free text (or mini-language) for the regular search
+    pub keywords: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Interpretation {
+    pub parser: &'static str,
+    #[serde(flatten)]
+    pub query: StructuredQuery,
+}
+
+pub trait QueryParser: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn parse<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<StructuredQuery, String>>;
+}
+
+pub struct RuleParser;
+
+/// Any endpoint speaking the OpenAI `/v1/chat/completions` shape.
+pub struct LlmParser {
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+pub struct ParseRequest {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+// ============================================================================
+// RULE-BASED PARSING
+// ============================================================================
+
+enum Room {
+    Bedrooms,
+    Bathrooms,
+}
+
+/// A room noun at the start of `tokens`, with how many tokens it spans.
+fn room_word(tokens: &[String]) -> Option<(Room, usize)> {
+    match tokens {
+        [a, b, ..] if a == "kamar" && b == "mandi" => Some((Room::Bathrooms, 2)),
+        [a, b, ..] if a == "kamar" && b == "tidur" => Some((Room::Bedrooms, 2)),
+        [a, ..] => match a.as_str() {
+            "bedroom" | "bedrooms" | "bed" | "beds" | "br" | "bd" | "bdr" | "kamar" | "kt" => {
+                Some((Room::Bedrooms, 1))
+            }
+            "bathroom" | "bathrooms" | "bath" | "baths" | "ba" | "km" => Some((Room::Bathrooms, 1)),
+            _ => None,
+        },
+        [] => None,
+    }
+}
+
+fn is_area_unit(token: &str) -> bool {
+    matches!(token, "sqm" | "m2" | "m²" | "meter" | "meters")
+}
+
+/// `3br`, `2ba`, `200m2` written without a space.
+fn compact_count(token: &str) -> Option<(f64, &str)> {
+    let split = token.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (digits, unit) = token.split_at(split);
+    let count = digits.parse::<f64>().ok()?;
+    Some((count, unit))
+}
+
+/// A rupiah amount at the start of `tokens`: `2b`, `500jt`, `rp 2.500.000.000`,
+/// `2,5 miliar`. Returns the amount and how many tokens it spans.
+fn amount(tokens: &[String]) -> Option<(f64, usize)> {
+    let (skip, first) = match tokens.first()?.as_str() {
+        "rp" | "idr" => (1, tokens.get(1)?.as_str()),
+        token => (0, token),
+    };
+    let first = first
+        .strip_prefix("rp")
+        .or_else(|| first.strip_prefix("idr"))
+        .unwrap_or(first);
+    let value = filters::parse_number(first)?;
+    let multiplier = tokens
+        .get(skip + 1)
+        .and_then(|next| MULTIPLIERS.iter().find(|(word, _)| word == next))
+        .map(|(_, mult)| *mult);
+    match multiplier {
+        Some(mult) => Some((value * mult, skip + 2)),
+        None => Some((value, skip + 1)),
+    }
+}
+
+/// The longest phrase from `phrases` at the start of `tokens`.
+fn phrase_len(tokens: &[String], phrases: &[&[&str]]) -> Option<usize> {
+    phrases
+        .iter()
+        .filter(|phrase| {
+            phrase.len() <= tokens.len() && phrase.iter().zip(tokens).all(|(p, t)| p == t)
+        })
+        .map(|phrase| phrase.len())
+        .max()
+}
+
+fn property_type(token: &str) -> Option<&'static str> {
+    PROPERTY_TYPES
+        .iter()
+        .find(|(word, _)| *word == token)
+        .map(|(_, canonical)| *canonical)
+}
+
+/// Mini-language tokens (`price<2b`, `location:ubud`) pass through untouched.
+fn is_structured(token: &str) -> bool {
+    token.len() > 1 && token.contains([':', '<', '>', '='])
+}
+
+/// Words that end a place name.
+fn ends_location(tokens: &[String]) -> bool {
+    let token = tokens[0].as_str();
+    STOPWORDS.contains(&token)
+        || LOCATION_WORDS.contains(&token)
+        || RANGE_WORDS.contains(&token)
+        || property_type(token).is_some()
+        || CERTIFICATES.contains(&token)
+        || amount(tokens).is_some()
+        || phrase_len(tokens, MAX_BOUNDS).is_some()
+        || phrase_len(tokens, MIN_BOUNDS).is_some()
+        || is_structured(token)
+}
+
+impl StructuredQuery {
+    /// A count followed by a room noun or area unit, or written compactly.
+    fn take_count(&mut self, tokens: &[String]) -> Option<usize> {
+        let (count, unit, used) = match tokens[0].parse::<f64>() {
+            Ok(count) => (count, tokens.get(1).map(String::as_str)?, 2),
+            Err(_) => {
+                let (count, unit) = compact_count(&tokens[0])?;
+                (count, unit, 1)
+            }
+        };
+        if is_area_unit(unit) {
+            self.min_area = Some(count);
+            return Some(used);
+        }
+        let rest: Vec<String> = std::iter::once(unit.to_string())
+            .chain(tokens.iter().skip(used).cloned())
+            .collect();
+        let (room, room_len) = room_word(&rest)?;
+        let count = count as i32;
+        match room {
+            Room::Bedrooms => self.bedrooms = Some(count),
+            Room::Bathrooms => self.bathrooms = Some(count),
+        }
+        Some(used + room_len - 1)
+    }
+
+    fn take_price(&mut self, tokens: &[String]) -> Option<usize> {
+        if RANGE_WORDS.contains(&tokens[0].as_str()) {
+            let (low, low_len) = amount(&tokens[1..])?;
+            let joiner = tokens.get(1 + low_len)?;
+            if !RANGE_JOINERS.contains(&joiner.as_str()) {
+                return None;
+            }
+            let (high, high_len) = amount(&tokens[2 + low_len..])?;
+            self.min_price = Some(low.min(high));
+            self.max_price = Some(low.max(high));
+            return Some(2 + low_len + high_len);
+        }
+        let (is_max, bound_len) = match phrase_len(tokens, MAX_BOUNDS) {
+            Some(len) => (true, len),
+            None => (false, phrase_len(tokens, MIN_BOUNDS)?),
+        };
+        // "at least 3 bedrooms" bounds rooms, which are minimums anyway
+        if let Some(used) = self.take_count(&tokens[bound_len..]) {
+            return Some(bound_len + used);
+        }
+        let (value, amount_len) = amount(&tokens[bound_len..])?;
+        if is_max {
+            self.max_price = Some(value);
+        } else {
+            self.min_price = Some(value);
+        }
+        Some(bound_len + amount_len)
+    }
+
+    fn take_location(&mut self, tokens: &[String]) -> Option<usize> {
+        if !LOCATION_WORDS.contains(&tokens[0].as_str()) {
+            return None;
+        }
+        let words: Vec<&str> = (1..tokens.len())
+            .take_while(|i| !ends_location(&tokens[*i..]))
+            .map(|i| tokens[i].as_str())
+            .collect();
+        if words.is_empty() {
+            return Some(1);
+        }
+        self.location = Some(words.join(" "));
+        Some(1 + words.len())
+    }
+
+    /// Reads a query token by token, preferring the longest reading at each point.
+    pub fn from_rules(query: &str) -> Self {
+        let tokens: Vec<String> = query
+            .split_whitespace()
+            .map(|t| {
+                let t = t.to_lowercase();
+                if is_structured(&t) {
+                    t
+                } else {
+                    t.trim_matches(|c: char| matches!(c, ',' | '.' | '!' | '?' | ';'))
+                        .to_string()
+                }
+            })
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut parsed = StructuredQuery::default();
+        let mut i = 0;
+        while i < tokens.len() {
+            let rest = &tokens[i..];
+            let token = rest[0].as_str();
+            if is_structured(token) {
+                parsed.keywords.push(token.to_string());
+                i += 1;
+                continue;
+            }
+            let used = parsed
+                .take_price(rest)
+                .or_else(|| parsed.take_count(rest))
+                .or_else(|| parsed.take_location(rest));
+            if let Some(used) = used {
+                i += used;
+                continue;
+            }
+            if let Some(canonical) = property_type(token) {
+                parsed.property_type = Some(canonical.to_string());
+            } else if CERTIFICATES.contains(&token) {
+                parsed.certificate_type = Some(token.to_uppercase());
+            } else if !STOPWORDS.contains(&token) {
+                parsed.keywords.push(token.to_string());
+            }
+            i += 1;
+        }
+        parsed
+    }
+
+    /// Cleans up parser output: trims text, drops negative or empty values.
+    fn normalized(self) -> Self {
+        let text = |value: Option<String>| {
+            value
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty())
+        };
+        let non_negative = |value: Option<f64>| value.filter(|v| v.is_finite() && *v >= 0.0);
+        StructuredQuery {
+            location: text(self.location),
+            property_type: text(self.property_type),
+            min_price: non_negative(self.min_price),
+            max_price: non_negative(self.max_price),
+            bedrooms: self.bedrooms.filter(|n| *n >= 0),
+            bathrooms: self.bathrooms.filter(|n| *n >= 0),
+            min_area: non_negative(self.min_area),
+            certificate_type: text(self.certificate_type).map(|c| c.to_uppercase()),
+            keywords: self
+                .keywords
+                .into_iter()
+                .map(|k| k.trim().to_string())
+                .filter(|k| !k.is_empty())
+                .collect(),
+        }
+    }
+
+    /// The filters search applies; keywords go through the mini-language parser.
+    pub fn to_filter_set(&self) -> Result<FilterSet, String> {
+        let mut filter_set = FilterSet::parse_query(&self.keywords.join(" "))?;
+        let numeric = [
+            (Field::Price, Op::Gte, self.min_price),
+            (Field::Price, Op::Lte, self.max_price),
+            (Field::Bedrooms, Op::Gte, self.bedrooms.map(f64::from)),
+            (Field::Bathrooms, Op::Gte, self.bathrooms.map(f64::from)),
+            (Field::AreaSqm, Op::Gte, self.min_area),
+        ];
+        for (field, op, value) in numeric {
+            if let Some(value) = value {
+                filter_set.filters.push(Filter::Compare {
+                    field,
+                    op,
+                    value: Value::Number(value),
+                    negated: false,
+                });
+            }
+        }
+        let text = [
+            (Field::Location, Op::Contains, &self.location),
+            (Field::PropertyType, Op::Contains, &self.property_type),
+            (Field::CertificateType, Op::Eq, &self.certificate_type),
+        ];
+        for (field, op, value) in text {
+            if let Some(value) = value {
+                filter_set.filters.push(Filter::Compare {
+                    field,
+                    op,
+                    value: Value::Text(value.to_lowercase()),
+                    negated: false,
+                });
+            }
+        }
+        Ok(filter_set)
+    }
+}
+
+// ============================================================================
+// PARSERS
+// ============================================================================
+
+impl QueryParser for RuleParser {
+    fn name(&self) -> &'static str {
+        "rules"
+    }
+
+    fn parse<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<StructuredQuery, String>> {
+        Box::pin(async move { Ok(StructuredQuery::from_rules(query)) })
+    }
+}
+
+impl QueryParser for LlmParser {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn parse<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<StructuredQuery, String>> {
+        Box::pin(async move {
+            let mut request = self.client.post(&self.url).json(&serde_json::json!({
+                "model": self.model,
+                "temperature": 0,
+                "response_format": { "type": "json_object" },
+                "messages": [
+                    { "role": "system", "content": LLM_PROMPT },
+                    { "role": "user", "content": query }
+                ]
+            }));
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let response: ChatResponse = request
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Query parsing request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid query parsing response: {}", e))?;
+            let content = response
+                .choices
+                .into_iter()
+                .next()
+                .ok_or("Query parsing response has no choices")?
+                .message
+                .content;
+            serde_json::from_str::<StructuredQuery>(&content)
+                .map_err(|e| format!("Model returned unusable filters: {}", e))
+        })
+    }
+}
+
+/// Configured by `NL_QUERY_LLM_URL`, `NL_QUERY_LLM_MODEL` and optionally
+/// `NL_QUERY_LLM_API_KEY`; rules only otherwise.
+pub fn parser_from_env() -> Box<dyn QueryParser> {
+    let Ok(url) = std::env::var("NL_QUERY_LLM_URL") else {
+        return Box::new(RuleParser);
+    };
+    let Ok(model) = std::env::var("NL_QUERY_LLM_MODEL") else {
+        warn!("NL_QUERY_LLM_MODEL not set; parsing queries with rules only");
+        return Box::new(RuleParser);
+    };
+    let client = match reqwest::Client::builder().timeout(LLM_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(
+                "Failed to build query parsing client: {}; using rules only",
+                e
+            );
+            return Box::new(RuleParser);
+        }
+    };
+    info!("Parsing search queries with {} via {}", model, url);
+    Box::new(LlmParser {
+        url,
+        api_key: secrets::var("NL_QUERY_LLM_API_KEY").ok(),
+        model,
+        client,
+    })
+}
+
+/// Runs the configured parser, falling back to the rules when it fails.
+pub async fn interpret(state: &AppState, query: &str) -> Interpretation {
+    let query: String = query.trim().chars().take(MAX_QUERY_CHARS).collect();
+    match state.query_parser.parse(&query).await {
+        Ok(parsed) => Interpretation {
+            parser: state.query_parser.name(),
+            query: parsed.normalized(),
+        },
+        Err(e) => {
+            warn!("Falling back to rule-based query parsing: {}", e);
+            Interpretation {
+                parser: RuleParser.name(),
+                query: StructuredQuery::from_rules(&query).normalized(),
+            }
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Shows how a query would be understood, e.g. for filter chips in the UI.
+#[post("/api/search/parse")]
+pub async fn parse_query(
+    req: web::Json<ParseRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let interpretation = interpret(&state, &req.query).await;
+    match interpretation.query.to_filter_set() {
+        Ok(_) => HttpResponse::Ok().json(interpretation),
+        Err(message) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid search query: {}", message)
+        })),
+    }
+}
@@ -0,0 +1,155 @@
+// JARVIS2026 - In-app notifications
+// Subsystems drop events for a user into `notifications`; clients poll the
+// inbox and mark items read.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Notification {
+    id: Uuid,
+    kind: String,
+    payload: serde_json::Value,
+    created_at: chrono::DateTime<chrono::Utc>,
+    read_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct InboxQuery {
+    unread: Option<bool>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct MarkReadRequest {
+    /// Omit to mark everything read
+    ids: Option<Vec<Uuid>>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS notifications (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            payload JSONB NOT NULL DEFAULT '{}',
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            read_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_notifications_user ON notifications(user_id, created_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// DELIVERY
+// ============================================================================
+
+/// Queues a notification; pass a transaction to tie it to the triggering write.
+pub async fn notify<'c, E>(
+    executor: E,
+    user_id: Uuid,
+    kind: &str,
+    payload: serde_json::Value,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    sqlx::query("INSERT INTO notifications (user_id, kind, payload) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(kind)
+        .bind(payload)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/users/me/notifications")]
+pub async fn list_notifications(
+    user: CurrentUser,
+    query: web::Query<InboxQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    match sqlx::query_as::<_, Notification>(
+        r#"SELECT id, kind, payload, created_at, read_at FROM notifications
+        WHERE user_id = $1 AND ($2 = false OR read_at IS NULL)
+        ORDER BY created_at DESC
+        LIMIT $3"#,
+    )
+    .bind(user.id)
+    .bind(query.unread.unwrap_or(false))
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(notifications) => HttpResponse::Ok().json(notifications),
+        Err(e) => {
+            error!("Failed to load notifications for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load notifications"
+            }))
+        }
+    }
+}
+
+#[post("/api/users/me/notifications/read")]
+pub async fn mark_read(
+    user: CurrentUser,
+    req: web::Json<MarkReadRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query(
+        r#"UPDATE notifications SET read_at = NOW()
+        WHERE user_id = $1 AND read_at IS NULL
+          AND ($2::uuid[] IS NULL OR id = ANY($2))"#,
+    )
+    .bind(user.id)
+    .bind(req.ids.as_deref())
+    .execute(&state.db)
+    .await
+    {
+        Ok(result) => HttpResponse::Ok().json(serde_json::json!({
+            "marked": result.rows_affected()
+        })),
+        Err(e) => {
+            error!("Failed to mark notifications read for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update notifications"
+            }))
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// JARVIS2026 - Video playback analytics
+// Players report start, quartile and completion events per viewing session;
+// agents get a drop-off funnel for each walkthrough video they uploaded.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+const VISITOR_HEADER: &str = "X-Visitor-Id";
+const MAX_SESSION_ID_LEN: usize = 64;
+const DAILY_SERIES_DAYS: i32 = 30;
+
+/// Funnel stages in playback order.
+const EVENTS: &[&str] = &["start", "q25", "q50", "q75", "complete"];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct PlaybackEventRequest {
+    /// Client-generated id for one viewing of the video
+    session_id: String,
+    /// `start`, `q25`, `q50`, `q75` or `complete`
+    event: String,
+    position_seconds: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct EventCount {
+    event: String,
+    sessions: i64,
+}
+
+#[derive(Serialize)]
+struct FunnelStage {
+    event: &'static str,
+    sessions: i64,
+    /// Share of started sessions that reached this stage
+    rate: f64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct DailyStarts {
+    day: chrono::NaiveDate,
+    starts: i64,
+    completions: i64,
+}
+
+#[derive(Serialize)]
+struct PlaybackAnalytics {
+    media_id: Uuid,
+    unique_viewers: i64,
+    funnel: Vec<FunnelStage>,
+    daily: Vec<DailyStarts>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS playback_events (
+            id BIGSERIAL PRIMARY KEY,
+            media_id UUID NOT NULL REFERENCES media_uploads(id) ON DELETE CASCADE,
+            session_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            position_seconds DOUBLE PRECISION,
+            user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            visitor_id TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            UNIQUE (media_id, session_id, event)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// ANALYTICS
+// ============================================================================
+
+fn build_funnel(counts: &[EventCount]) -> Vec<FunnelStage> {
+    let sessions_for = |event: &str| {
+        counts
+            .iter()
+            .find(|c| c.event == event)
+            .map(|c| c.sessions)
+            .unwrap_or(0)
+    };
+    let started = sessions_for("start");
+
+    EVENTS
+        .iter()
+        .map(|event| {
+            let sessions = sessions_for(event);
+            FunnelStage {
+                event,
+                sessions,
+                rate: if started > 0 {
+                    sessions as f64 / started as f64
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect()
+}
+
+async fn media_owner(pool: &PgPool, media_id: Uuid) -> Result<Option<Option<Uuid>>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<Uuid>>(
+        "SELECT user_id FROM media_uploads WHERE id = $1 AND file_type IN ('video', 'drone_video')",
+    )
+    .bind(media_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Events are idempotent per session, so players can resend them safely.
+#[post("/api/media/{id}/playback")]
+pub async fn record_playback_event(
+    path: web::Path<Uuid>,
+    body: web::Json<PlaybackEventRequest>,
+    user: Option<CurrentUser>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let media_id = path.into_inner();
+
+    let Some(event) = EVENTS.iter().find(|e| **e == body.event) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("event must be one of {}", EVENTS.join(", "))
+        }));
+    };
+    let session_id = body.session_id.trim();
+    if session_id.is_empty() || session_id.len() > MAX_SESSION_ID_LEN {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("session_id must be 1-{} characters", MAX_SESSION_ID_LEN)
+        }));
+    }
+
+    match media_owner(&state.db, media_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Video not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up media {}: {}", media_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to record playback event"
+            }));
+        }
+    }
+
+    let visitor_id = req
+        .headers()
+        .get(VISITOR_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    match sqlx::query(
+        r#"INSERT INTO playback_events (media_id, session_id, event, position_seconds, user_id, visitor_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (media_id, session_id, event) DO NOTHING"#,
+    )
+    .bind(media_id)
+    .bind(session_id)
+    .bind(*event)
+    .bind(body.position_seconds.filter(|p| p.is_finite() && *p >= 0.0))
+    .bind(user.map(|u| u.id))
+    .bind(visitor_id)
+    .execute(&state.db)
+    .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            error!("Failed to record playback event for {}: {}", media_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to record playback event"
+            }))
+        }
+    }
+}
+
+/// Only the uploader (or an admin) can see how their video performs.
+#[get("/api/media/{id}/analytics")]
+pub async fn playback_analytics(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let media_id = path.into_inner();
+
+    match media_owner(&state.db, media_id).await {
+        Ok(Some(owner)) if owner == Some(user.id) || user.is_admin() => {}
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only the uploader can view these analytics"
+            }))
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Video not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up media {}: {}", media_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load analytics"
+            }));
+        }
+    }
+
+    let counts = sqlx::query_as::<_, EventCount>(
+        r#"SELECT event, COUNT(*) AS sessions FROM playback_events
+        WHERE media_id = $1 GROUP BY event"#,
+    )
+    .bind(media_id)
+    .fetch_all(&state.db);
+
+    let viewers = sqlx::query_scalar::<_, i64>(
+        r#"SELECT COUNT(DISTINCT COALESCE(user_id::text, visitor_id, session_id))
+        FROM playback_events WHERE media_id = $1 AND event = 'start'"#,
+    )
+    .bind(media_id)
+    .fetch_one(&state.db);
+
+    let daily = sqlx::query_as::<_, DailyStarts>(
+        r#"SELECT created_at::date AS day,
+            COUNT(*) FILTER (WHERE event = 'start') AS starts,
+            COUNT(*) FILTER (WHERE event = 'complete') AS completions
+        FROM playback_events
+        WHERE media_id = $1 AND created_at >= NOW() - make_interval(days => $2)
+        GROUP BY day ORDER BY day"#,
+    )
+    .bind(media_id)
+    .bind(DAILY_SERIES_DAYS)
+    .fetch_all(&state.db);
+
+    match tokio::try_join!(counts, viewers, daily) {
+        Ok((counts, unique_viewers, daily)) => HttpResponse::Ok().json(PlaybackAnalytics {
+            media_id,
+            unique_viewers,
+            funnel: build_funnel(&counts),
+            daily,
+        }),
+        Err(e) => {
+            error!("Failed to load playback analytics for {}: {}", media_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load analytics"
+            }))
+        }
+    }
+}
@@ -0,0 +1,418 @@
+// JARVIS2026 - Chat presence
+// `/ws/inquiries` is the live side of inquiry threads. While a user has it
+// open they count as online; closing the last socket stamps `last_seen_at`.
+// Over the socket a participant can say they're typing in a thread, which is
+// relayed to the other party, and new messages are pushed as they are sent.
+// Conversation payloads carry the other party's presence so a buyer can tell
+// whether to expect a quick reply. State is held in memory per instance, so
+// it's soft real-time: good enough to show, not to rely on.
+
+use actix_web::{
+    get,
+    http::header::{self, HeaderValue},
+    rt, web, Error, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::auth::{AuthError, CurrentUser};
+use crate::metrics::{protocol_token, BEARER_PROTOCOL};
+use crate::sessions;
+use crate::AppState;
+
+/// A typing notice lapses unless the client repeats it within this time.
+pub const TYPING_TTL: Duration = Duration::from_secs(6);
+/// Events buffered per socket before a slow one starts missing them
+const EVENT_BUFFER: usize = 256;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// Who is connected and typing where, with a channel for pushing events to
+/// a user's sockets.
+pub struct PresenceHub {
+    users: Mutex<HashMap<Uuid, Connected>>,
+    events: broadcast::Sender<Delivery>,
+}
+
+#[derive(Default)]
+struct Connected {
+    sockets: usize,
+    /// Inquiry id to when the user last said they were typing in it
+    typing: HashMap<Uuid, Instant>,
+}
+
+#[derive(Clone)]
+struct Delivery {
+    to: Uuid,
+    payload: Arc<str>,
+}
+
+/// The other party of a conversation, as shown alongside it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceStatus {
+    pub online: bool,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    /// Typing in this conversation right now
+    pub typing: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientEvent {
+    Typing {
+        inquiry_id: Uuid,
+        #[serde(default = "default_typing")]
+        typing: bool,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerEvent {
+    Presence {
+        user_id: Uuid,
+        online: bool,
+        last_seen_at: DateTime<Utc>,
+    },
+    Typing {
+        inquiry_id: Uuid,
+        user_id: Uuid,
+        typing: bool,
+        expires_in_secs: u64,
+    },
+    Message {
+        inquiry_id: Uuid,
+        message: serde_json::Value,
+    },
+}
+
+fn default_typing() -> bool {
+    true
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// HUB
+// ============================================================================
+
+impl PresenceHub {
+    pub fn new() -> Self {
+        PresenceHub {
+            users: Mutex::new(HashMap::new()),
+            events: broadcast::channel(EVENT_BUFFER).0,
+        }
+    }
+
+    /// True when this is the user's first socket.
+    fn connect(&self, user_id: Uuid) -> bool {
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let connected = users.entry(user_id).or_default();
+        connected.sockets += 1;
+        connected.sockets == 1
+    }
+
+    /// True when that was the user's last socket.
+    fn disconnect(&self, user_id: Uuid) -> bool {
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(connected) = users.get_mut(&user_id) else {
+            return false;
+        };
+        connected.sockets = connected.sockets.saturating_sub(1);
+        if connected.sockets == 0 {
+            users.remove(&user_id);
+            return true;
+        }
+        false
+    }
+
+    fn set_typing(&self, user_id: Uuid, inquiry_id: Uuid, typing: bool) {
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(connected) = users.get_mut(&user_id) else {
+            return;
+        };
+        if typing {
+            connected.typing.insert(inquiry_id, Instant::now());
+        } else {
+            connected.typing.remove(&inquiry_id);
+        }
+    }
+
+    /// Sending a message ends the sender's typing notice in that thread.
+    pub fn message_sent<M: Serialize>(
+        &self,
+        inquiry_id: Uuid,
+        sender: Uuid,
+        recipient: Uuid,
+        message: &M,
+    ) {
+        self.set_typing(sender, inquiry_id, false);
+        self.send(
+            recipient,
+            &ServerEvent::Typing {
+                inquiry_id,
+                user_id: sender,
+                typing: false,
+                expires_in_secs: 0,
+            },
+        );
+        match serde_json::to_value(message) {
+            Ok(message) => self.send(
+                recipient,
+                &ServerEvent::Message {
+                    inquiry_id,
+                    message,
+                },
+            ),
+            Err(e) => error!("Failed to encode inquiry message: {}", e),
+        }
+    }
+
+    /// `last_seen_at` comes from `last_seen`, which callers load in bulk.
+    pub fn status(
+        &self,
+        user_id: Uuid,
+        inquiry_id: Uuid,
+        last_seen: &HashMap<Uuid, DateTime<Utc>>,
+    ) -> PresenceStatus {
+        let users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let connected = users.get(&user_id);
+        PresenceStatus {
+            online: connected.is_some(),
+            last_seen_at: last_seen.get(&user_id).copied(),
+            typing: connected
+                .and_then(|c| c.typing.get(&inquiry_id))
+                .is_some_and(|since| since.elapsed() < TYPING_TTL),
+        }
+    }
+
+    /// Dropped silently when the user has no socket open.
+    pub fn send<T: Serialize>(&self, to: Uuid, event: &T) {
+        match serde_json::to_string(event) {
+            Ok(payload) => {
+                let _ = self.events.send(Delivery {
+                    to,
+                    payload: payload.into(),
+                });
+            }
+            Err(e) => error!("Failed to encode presence event: {}", e),
+        }
+    }
+}
+
+impl Default for PresenceHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub async fn last_seen(
+    pool: &PgPool,
+    user_ids: &[Uuid],
+) -> Result<HashMap<Uuid, DateTime<Utc>>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+        "SELECT id, last_seen_at FROM users WHERE id = ANY($1) AND last_seen_at IS NOT NULL",
+    )
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+async fn touch_last_seen(pool: &PgPool, user_id: Uuid) -> Result<DateTime<Utc>, sqlx::Error> {
+    sqlx::query_scalar::<_, DateTime<Utc>>(
+        "UPDATE users SET last_seen_at = NOW() WHERE id = $1 RETURNING last_seen_at",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Everyone the user has an inquiry with; they see the user's presence.
+async fn counterparts(pool: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"SELECT DISTINCT CASE WHEN owner_user_id = $1 THEN sender_user_id ELSE owner_user_id END
+        FROM inquiries WHERE owner_user_id = $1 OR sender_user_id = $1"#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// The other party of an inquiry the user takes part in.
+async fn counterpart_in(
+    pool: &PgPool,
+    user_id: Uuid,
+    inquiry_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"SELECT CASE WHEN owner_user_id = $2 THEN sender_user_id ELSE owner_user_id END
+        FROM inquiries WHERE id = $1 AND (owner_user_id = $2 OR sender_user_id = $2)"#,
+    )
+    .bind(inquiry_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+async fn announce(state: &AppState, user_id: Uuid, online: bool) {
+    let result = async {
+        let last_seen_at = touch_last_seen(&state.db, user_id).await?;
+        for other in counterparts(&state.db, user_id).await? {
+            state.presence.send(
+                other,
+                &ServerEvent::Presence {
+                    user_id,
+                    online,
+                    last_seen_at,
+                },
+            );
+        }
+        Ok::<_, sqlx::Error>(())
+    }
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to announce presence of {}: {}", user_id, e);
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Browsers may authenticate with the `bearer, <token>` subprotocol, as on
+/// the metrics socket. Clients send `{"type": "typing", "inquiry_id": ...}`
+/// every few seconds while typing and receive `presence`, `typing` and
+/// `message` events.
+#[get("/ws/inquiries")]
+pub async fn inquiry_socket(
+    req: HttpRequest,
+    body: web::Payload,
+    user: Result<CurrentUser, AuthError>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, Error> {
+    let protocol_token = protocol_token(&req);
+    let user_id = match (user, protocol_token.as_deref()) {
+        (Ok(user), _) => user.id,
+        (Err(auth_error), None) => return Err(auth_error.into()),
+        (Err(_), Some(token)) => {
+            let found = async {
+                let Some(user_id) = sessions::user_for_token(&state.db, token).await? else {
+                    return Ok(None);
+                };
+                sqlx::query_scalar::<_, Uuid>(
+                    "SELECT id FROM users WHERE id = $1 AND status <> 'banned'",
+                )
+                .bind(user_id)
+                .fetch_optional(&state.db)
+                .await
+            }
+            .await;
+            match found {
+                Ok(Some(user_id)) => user_id,
+                Ok(None) => return Err(AuthError::UnknownUser.into()),
+                Err(e) => {
+                    error!("Failed to authenticate inquiry socket: {}", e);
+                    return Err(AuthError::Internal.into());
+                }
+            }
+        }
+    };
+
+    let (mut response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    if protocol_token.is_some() {
+        response.headers_mut().insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(BEARER_PROTOCOL),
+        );
+    }
+    let mut events = state.presence.events.subscribe();
+    if state.presence.connect(user_id) {
+        announce(&state, user_id, true).await;
+    }
+
+    rt::spawn(async move {
+        // Threads this socket may send typing notices to, and to whom
+        let mut threads: HashMap<Uuid, Option<Uuid>> = HashMap::new();
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(delivery) if delivery.to == user_id => {
+                        if session.text(&*delivery.payload).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                message = messages.next() => match message {
+                    Some(Ok(actix_ws::Message::Text(text))) => {
+                        let Ok(ClientEvent::Typing { inquiry_id, typing }) =
+                            serde_json::from_str::<ClientEvent>(&text)
+                        else {
+                            continue;
+                        };
+                        let counterpart = match threads.get(&inquiry_id) {
+                            Some(counterpart) => *counterpart,
+                            None => {
+                                let counterpart = counterpart_in(&state.db, user_id, inquiry_id)
+                                    .await
+                                    .unwrap_or_else(|e| {
+                                        warn!("Failed to check inquiry {}: {}", inquiry_id, e);
+                                        None
+                                    });
+                                threads.insert(inquiry_id, counterpart);
+                                counterpart
+                            }
+                        };
+                        if let Some(counterpart) = counterpart {
+                            state.presence.set_typing(user_id, inquiry_id, typing);
+                            state.presence.send(
+                                counterpart,
+                                &ServerEvent::Typing {
+                                    inquiry_id,
+                                    user_id,
+                                    typing,
+                                    expires_in_secs: TYPING_TTL.as_secs(),
+                                },
+                            );
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        let _ = session.close(None).await;
+        if state.presence.disconnect(user_id) {
+            announce(&state, user_id, false).await;
+        }
+    });
+
+    Ok(response)
+}
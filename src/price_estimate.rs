@@ -0,0 +1,238 @@
+// JARVIS2026 - Price estimation
+// `POST /api/estimate-price` suggests a price range for a property described
+// by location, area and layout. Estimates come from a `PriceModel`; the one
+// in use today reads recent sales and active listings in the same location,
+// keeps those alike enough by `comparables::similarity`, and multiplies the
+// quartiles of their price per sqm by the property's area. The trait leaves
+// room for a learned model later without changing the endpoint.
+
+use actix_web::{post, web, HttpResponse, Responder};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, warn};
+
+use crate::comparables::{similarity, Features};
+use crate::formatting::{Locale, PriceDisplay};
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::AppState;
+
+/// Sales older than this say little about today's prices
+const SOLD_MONTHS: i32 = 24;
+/// Rows fetched per kind before scoring
+const CANDIDATE_POOL: i64 = 300;
+const MIN_SIMILARITY: f64 = 0.5;
+/// Fewer comparables than this give no estimate rather than a noisy one
+const MIN_COMPARABLES: usize = 3;
+/// Closest comparables the quartiles are taken over
+const MAX_COMPARABLES: usize = 50;
+const MAX_AREA_SQM: f64 = 100_000.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct EstimateRequest {
+    pub location: String,
+    pub area_sqm: f64,
+    pub bedrooms: Option<i32>,
+    pub bathrooms: Option<i32>,
+    pub property_type: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+/// Prices in rupiah, `low <= estimate <= high`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Estimate {
+    pub low: f64,
+    pub estimate: f64,
+    pub high: f64,
+    pub price_per_sqm: f64,
+    /// How many sales and listings the estimate rests on
+    pub sold_comparables: usize,
+    pub active_comparables: usize,
+}
+
+pub trait PriceModel: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// `None` when the model has too little to go on.
+    fn estimate<'a>(
+        &'a self,
+        pool: &'a PgPool,
+        request: &'a EstimateRequest,
+    ) -> BoxFuture<'a, Result<Option<Estimate>, String>>;
+}
+
+/// Quartiles of price per sqm over similar sales and listings nearby.
+pub struct ComparablesModel;
+
+#[derive(sqlx::FromRow)]
+struct Comparable {
+    sold: bool,
+    price: f64,
+    #[sqlx(flatten)]
+    features: Features,
+}
+
+#[derive(Serialize)]
+struct EstimateResponse {
+    model: String,
+    low: PriceDisplay,
+    estimate: PriceDisplay,
+    high: PriceDisplay,
+    price_per_sqm: f64,
+    sold_comparables: usize,
+    active_comparables: usize,
+}
+
+// ============================================================================
+// COMPARABLES MODEL
+// ============================================================================
+
+/// Linear interpolation between closest ranks; `values` must be sorted.
+fn quantile(values: &[f64], q: f64) -> f64 {
+    let position = q * (values.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    values[below] + (values[above] - values[below]) * (position - below as f64)
+}
+
+async fn load_comparables(
+    pool: &PgPool,
+    request: &EstimateRequest,
+) -> Result<Vec<Comparable>, sqlx::Error> {
+    sqlx::query_as::<_, Comparable>(&format!(
+        r#"(SELECT TRUE AS sold, sale_price AS price, location, property_type, bedrooms,
+                   bathrooms, area_sqm, latitude, longitude
+            FROM sold_records
+            WHERE LOWER(TRIM(location)) = LOWER(TRIM($1)) AND area_sqm > 0
+              AND sold_at >= NOW() - make_interval(months => $2)
+            ORDER BY sold_at DESC
+            LIMIT $3)
+        UNION ALL
+        (SELECT FALSE, price, location, property_type, bedrooms, bathrooms, area_sqm,
+                latitude, longitude
+            FROM properties
+            WHERE LOWER(TRIM(location)) = LOWER(TRIM($1)) AND area_sqm > 0 AND {} AND {}
+            ORDER BY created_at DESC NULLS LAST
+            LIMIT $3)"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(&request.location)
+    .bind(SOLD_MONTHS)
+    .bind(CANDIDATE_POOL)
+    .fetch_all(pool)
+    .await
+}
+
+impl PriceModel for ComparablesModel {
+    fn name(&self) -> &str {
+        "comparables"
+    }
+
+    fn estimate<'a>(
+        &'a self,
+        pool: &'a PgPool,
+        request: &'a EstimateRequest,
+    ) -> BoxFuture<'a, Result<Option<Estimate>, String>> {
+        Box::pin(async move {
+            let subject = Features {
+                location: request.location.clone(),
+                property_type: request.property_type.clone(),
+                bedrooms: request.bedrooms,
+                bathrooms: request.bathrooms,
+                area_sqm: Some(request.area_sqm),
+                latitude: request.latitude,
+                longitude: request.longitude,
+            };
+            let mut scored: Vec<(f64, Comparable)> = load_comparables(pool, request)
+                .await
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|c| (similarity(&subject, &c.features), c))
+                .filter(|(score, _)| *score >= MIN_SIMILARITY)
+                .collect();
+            if scored.len() < MIN_COMPARABLES {
+                return Ok(None);
+            }
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            scored.truncate(MAX_COMPARABLES);
+
+            let mut per_sqm: Vec<f64> = scored
+                .iter()
+                .filter_map(|(_, c)| c.features.area_sqm.map(|area| c.price / area))
+                .collect();
+            per_sqm.sort_by(f64::total_cmp);
+            let sold_comparables = scored.iter().filter(|(_, c)| c.sold).count();
+            let median = quantile(&per_sqm, 0.5);
+            Ok(Some(Estimate {
+                low: (quantile(&per_sqm, 0.25) * request.area_sqm).round(),
+                estimate: (median * request.area_sqm).round(),
+                high: (quantile(&per_sqm, 0.75) * request.area_sqm).round(),
+                price_per_sqm: median.round(),
+                sold_comparables,
+                active_comparables: scored.len() - sold_comparables,
+            }))
+        })
+    }
+}
+
+/// `PRICE_MODEL` picks the model; `comparables` is the only one so far.
+pub fn model_from_env() -> Box<dyn PriceModel> {
+    match std::env::var("PRICE_MODEL").as_deref() {
+        Err(_) | Ok("comparables") => {}
+        Ok(other) => warn!("Unknown PRICE_MODEL '{}'; using comparables", other),
+    }
+    Box::new(ComparablesModel)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/estimate-price")]
+pub async fn estimate_price(
+    req: web::Json<EstimateRequest>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if req.location.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "location is required"
+        }));
+    }
+    if !(req.area_sqm > 0.0 && req.area_sqm <= MAX_AREA_SQM) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("area_sqm must be between 0 and {}", MAX_AREA_SQM)
+        }));
+    }
+    if req.bedrooms.is_some_and(|n| n < 0) || req.bathrooms.is_some_and(|n| n < 0) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Room counts cannot be negative"
+        }));
+    }
+
+    match state.price_model.estimate(&state.db, &req).await {
+        Ok(Some(estimate)) => HttpResponse::Ok().json(EstimateResponse {
+            model: state.price_model.name().to_string(),
+            low: PriceDisplay::new(estimate.low, locale),
+            estimate: PriceDisplay::new(estimate.estimate, locale),
+            high: PriceDisplay::new(estimate.high, locale),
+            price_per_sqm: estimate.price_per_sqm,
+            sold_comparables: estimate.sold_comparables,
+            active_comparables: estimate.active_comparables,
+        }),
+        Ok(None) => HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "error": "Not enough comparable properties in this location to estimate a price"
+        })),
+        Err(e) => {
+            error!("Price estimation failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to estimate price"
+            }))
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// JARVIS2026 - In-process property cache
+// Hot listing detail pages and their media manifests are kept in a bounded,
+// TTL'd in-memory cache so single-node deployments get caching without
+// running Redis. Writers invalidate the listings they touch; the TTL bounds
+// how stale anything they miss (owner response stats) can get.
+
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::models3d::ModelAsset;
+use crate::responsiveness::ResponseStats;
+use crate::{MediaUpload, PropertyDetailRow};
+
+const DEFAULT_CAPACITY: u64 = 10_000;
+const DEFAULT_TTL_SECS: u64 = 60;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// A listing's detail page before viewer-specific shaping (privileged fields,
+/// locale formatting).
+pub struct CachedDetail {
+    pub row: PropertyDetailRow,
+    pub models: Vec<ModelAsset>,
+    pub response_stats: Option<ResponseStats>,
+}
+
+pub struct PropertyCache {
+    details: Cache<Uuid, Arc<CachedDetail>>,
+    /// Publicly visible media only; owners and admins bypass it
+    media: Cache<Uuid, Arc<Vec<MediaUpload>>>,
+}
+
+// ============================================================================
+// CACHE
+// ============================================================================
+
+impl PropertyCache {
+    pub fn new(capacity: u64, ttl: Duration) -> Self {
+        PropertyCache {
+            details: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+            media: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+        }
+    }
+
+    /// `PROPERTY_CACHE_CAPACITY` entries per kind (0 disables caching),
+    /// each kept for `PROPERTY_CACHE_TTL_SECS`.
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("PROPERTY_CACHE_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CAPACITY);
+        let ttl_secs = std::env::var("PROPERTY_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+        PropertyCache::new(capacity, Duration::from_secs(ttl_secs))
+    }
+
+    pub async fn detail(&self, property_id: Uuid) -> Option<Arc<CachedDetail>> {
+        self.details.get(&property_id).await
+    }
+
+    pub async fn store_detail(&self, property_id: Uuid, detail: CachedDetail) -> Arc<CachedDetail> {
+        let detail = Arc::new(detail);
+        self.details.insert(property_id, detail.clone()).await;
+        detail
+    }
+
+    pub async fn public_media(&self, property_id: Uuid) -> Option<Arc<Vec<MediaUpload>>> {
+        self.media.get(&property_id).await
+    }
+
+    pub async fn store_public_media(
+        &self,
+        property_id: Uuid,
+        media: Vec<MediaUpload>,
+    ) -> Arc<Vec<MediaUpload>> {
+        let media = Arc::new(media);
+        self.media.insert(property_id, media.clone()).await;
+        media
+    }
+
+    /// Drops a listing after it or its media changed.
+    pub async fn invalidate(&self, property_id: Uuid) {
+        self.details.invalidate(&property_id).await;
+        self.media.invalidate(&property_id).await;
+    }
+
+    /// For writes whose affected listings aren't known up front.
+    pub fn invalidate_all(&self) {
+        self.details.invalidate_all();
+        self.media.invalidate_all();
+    }
+}
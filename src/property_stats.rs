@@ -0,0 +1,198 @@
+// JARVIS2026 - Per-listing statistics for owners
+// Daily counts of what buyers did with a listing: detail views, saves,
+// inquiries, contact reveals and visits through shared links. Each comes from
+// the table its feature already logs to, bucketed by calendar day in the
+// listing's timezone over the last 7, 30 or 90 days; days without activity
+// are reported as zeros.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::timezones;
+use crate::AppState;
+
+const PERIODS: &[i32] = &[7, 30, 90];
+const DEFAULT_PERIOD: i32 = 30;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    days: Option<i32>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct DailyStats {
+    date: chrono::NaiveDate,
+    views: i64,
+    favorites: i64,
+    inquiries: i64,
+    contact_reveals: i64,
+    /// Visits through the listing's short links
+    shares: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct StatsTotals {
+    views: i64,
+    favorites: i64,
+    inquiries: i64,
+    contact_reveals: i64,
+    shares: i64,
+}
+
+#[derive(Serialize)]
+struct StatsResponse {
+    property_id: Uuid,
+    days: i32,
+    /// The listing's IANA timezone, which days are counted in
+    timezone: String,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    totals: StatsTotals,
+    daily: Vec<DailyStats>,
+}
+
+// ============================================================================
+// AGGREGATION
+// ============================================================================
+
+async fn daily_stats(
+    pool: &PgPool,
+    property_id: Uuid,
+    tz: chrono_tz::Tz,
+    from: chrono::NaiveDate,
+    days: i32,
+) -> Result<Vec<DailyStats>, sqlx::Error> {
+    let since = from
+        .and_time(chrono::NaiveTime::MIN)
+        .and_local_timezone(tz)
+        .earliest()
+        .map(|start| start.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|| from.and_time(chrono::NaiveTime::MIN).and_utc());
+    sqlx::query_as::<_, DailyStats>(
+        r#"WITH events AS (
+            SELECT 'view' AS kind, viewed_at AS at FROM property_views
+            WHERE property_id = $1 AND viewed_at >= $2
+            UNION ALL
+            SELECT 'favorite', created_at FROM favorites
+            WHERE property_id = $1 AND created_at >= $2
+            UNION ALL
+            SELECT 'inquiry', created_at FROM inquiries
+            WHERE property_id = $1 AND created_at >= $2
+            UNION ALL
+            SELECT 'contact_reveal', revealed_at FROM contact_reveals
+            WHERE property_id = $1 AND revealed_at >= $2
+            UNION ALL
+            SELECT 'share', c.clicked_at FROM short_link_clicks c
+            JOIN short_links l ON l.id = c.short_link_id
+            WHERE l.property_id = $1 AND c.clicked_at >= $2
+        )
+        SELECT d.day::date AS date,
+            COUNT(e.kind) FILTER (WHERE e.kind = 'view') AS views,
+            COUNT(e.kind) FILTER (WHERE e.kind = 'favorite') AS favorites,
+            COUNT(e.kind) FILTER (WHERE e.kind = 'inquiry') AS inquiries,
+            COUNT(e.kind) FILTER (WHERE e.kind = 'contact_reveal') AS contact_reveals,
+            COUNT(e.kind) FILTER (WHERE e.kind = 'share') AS shares
+        FROM generate_series($3::date, $3::date + ($4 - 1), INTERVAL '1 day') AS d(day)
+        LEFT JOIN events e ON (e.at AT TIME ZONE $5)::date = d.day::date
+        GROUP BY d.day
+        ORDER BY d.day"#,
+    )
+    .bind(property_id)
+    .bind(since)
+    .bind(from)
+    .bind(days)
+    .bind(tz.name())
+    .fetch_all(pool)
+    .await
+}
+
+impl StatsTotals {
+    fn sum(daily: &[DailyStats]) -> Self {
+        daily
+            .iter()
+            .fold(StatsTotals::default(), |mut totals, day| {
+                totals.views += day.views;
+                totals.favorites += day.favorites;
+                totals.inquiries += day.inquiries;
+                totals.contact_reveals += day.contact_reveals;
+                totals.shares += day.shares;
+                totals
+            })
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// The owner's (or an admin's) daily activity on a listing.
+#[get("/api/properties/{id}/stats")]
+pub async fn property_stats(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    query: web::Query<StatsQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let days = query.days.unwrap_or(DEFAULT_PERIOD);
+    if !PERIODS.contains(&days) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "days must be 7, 30 or 90"
+        }));
+    }
+
+    let listing = sqlx::query_as::<_, (Option<Uuid>, String)>(
+        "SELECT user_id, timezone FROM properties WHERE id = $1",
+    )
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await;
+    let timezone = match listing {
+        Ok(Some((owner, timezone))) if owner == Some(user.id) || user.is_admin() => timezone,
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only the owner can view listing statistics"
+            }))
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load owner of {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load listing statistics"
+            }));
+        }
+    };
+
+    let tz = timezones::parse(&timezone).unwrap_or(timezones::DEFAULT_TIMEZONE);
+    let to = chrono::Utc::now().with_timezone(&tz).date_naive();
+    let from = to - chrono::Duration::days(i64::from(days) - 1);
+    match daily_stats(&state.db, property_id, tz, from, days).await {
+        Ok(daily) => HttpResponse::Ok().json(StatsResponse {
+            property_id,
+            days,
+            timezone: tz.name().to_string(),
+            from,
+            to,
+            totals: StatsTotals::sum(&daily),
+            daily,
+        }),
+        Err(e) => {
+            error!("Failed to aggregate stats of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load listing statistics"
+            }))
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// JARVIS2026 - Per-request query budget
+// Every request counts the statements it runs and the time they take. sqlx
+// reports each statement as a `sqlx::query` tracing event from the task that
+// awaited it, so a tracing layer adds them to the usage of the request that
+// task is serving. The numbers are recorded on the request span, and
+// requests over budget are logged and kept for `/api/admin/query-budget`,
+// which is where N+1 patterns show up first.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    get,
+    middleware::Next,
+    web, Error, HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{warn, Event, Instrument, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::auth::AdminUser;
+use crate::AppState;
+
+/// Target sqlx logs executed statements under
+const QUERY_TARGET: &str = "sqlx::query";
+/// Over-budget requests kept for the admin endpoint
+const MAX_OFFENDERS: usize = 500;
+const DEFAULT_LIMIT: usize = 50;
+
+tokio::task_local! {
+    static USAGE: Arc<Usage>;
+}
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Default)]
+struct Usage {
+    queries: AtomicU64,
+    db_micros: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetConfig {
+    enabled: bool,
+    max_queries: u64,
+    max_db_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Offender {
+    method: String,
+    /// Route pattern, e.g. `/api/properties/{id}`
+    route: String,
+    path: String,
+    status: u16,
+    queries: u64,
+    db_ms: f64,
+    seen_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct RouteSummary {
+    route: String,
+    method: String,
+    requests: usize,
+    max_queries: u64,
+    max_db_ms: f64,
+}
+
+pub struct QueryBudget {
+    config: BudgetConfig,
+    offenders: Mutex<VecDeque<Offender>>,
+}
+
+#[derive(Deserialize)]
+pub struct OffendersQuery {
+    limit: Option<usize>,
+}
+
+/// Feeds `sqlx::query` events into the current request's usage.
+struct QueryCounter;
+
+#[derive(Default)]
+struct Elapsed(Option<f64>);
+
+// ============================================================================
+// CONFIGURATION
+// ============================================================================
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(default)
+}
+
+impl BudgetConfig {
+    pub fn from_env() -> Self {
+        let enabled = !matches!(
+            std::env::var("QUERY_BUDGET").as_deref(),
+            Ok("off" | "false" | "0")
+        );
+        BudgetConfig {
+            enabled,
+            max_queries: env_u64("QUERY_BUDGET_MAX_QUERIES", 25),
+            max_db_ms: env_u64("QUERY_BUDGET_MAX_DB_MS", 250),
+        }
+    }
+}
+
+impl QueryBudget {
+    pub fn new(config: BudgetConfig) -> Self {
+        if !config.enabled {
+            warn!("Query budget instrumentation disabled by QUERY_BUDGET");
+        }
+        QueryBudget {
+            config,
+            offenders: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn record_offender(&self, offender: Offender) {
+        let mut offenders = self.offenders.lock().unwrap();
+        if offenders.len() == MAX_OFFENDERS {
+            offenders.pop_front();
+        }
+        offenders.push_back(offender);
+    }
+}
+
+// ============================================================================
+// TRACING LAYER
+// ============================================================================
+
+impl Visit for Elapsed {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl<S: Subscriber> Layer<S> for QueryCounter {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != QUERY_TARGET {
+            return;
+        }
+        let mut elapsed = Elapsed::default();
+        event.record(&mut elapsed);
+        let _ = USAGE.try_with(|usage| {
+            usage.queries.fetch_add(1, Ordering::Relaxed);
+            let micros = (elapsed.0.unwrap_or(0.0) * 1e6) as u64;
+            usage.db_micros.fetch_add(micros, Ordering::Relaxed);
+        });
+    }
+}
+
+/// The counting layer, listening only to statement events so it doesn't
+/// change what the log output shows. `None` when the budget is disabled,
+/// which also spares sqlx from rendering events nobody reads.
+pub fn layer<S>(config: &BudgetConfig) -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    config.enabled.then(|| {
+        QueryCounter.with_filter(Targets::new().with_target(QUERY_TARGET, LevelFilter::TRACE))
+    })
+}
+
+// ============================================================================
+// MIDDLEWARE
+// ============================================================================
+
+/// Runs the request with its own usage counter inside a `request` span and
+/// checks the totals against the budget once the response is ready.
+pub async fn track(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await;
+    };
+    let budget = &state.query_budget;
+    if !budget.config.enabled {
+        return next.call(req).await;
+    }
+
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.path(),
+        db.queries = tracing::field::Empty,
+        db.time_ms = tracing::field::Empty,
+    );
+    let usage = Arc::new(Usage::default());
+    let result = USAGE
+        .scope(usage.clone(), next.call(req).instrument(span.clone()))
+        .await;
+
+    let queries = usage.queries.load(Ordering::Relaxed);
+    let db_ms = usage.db_micros.load(Ordering::Relaxed) as f64 / 1000.0;
+    span.record("db.queries", queries);
+    span.record("db.time_ms", db_ms);
+
+    if let Ok(res) = &result {
+        if queries > budget.config.max_queries || db_ms > budget.config.max_db_ms as f64 {
+            let request = res.request();
+            let route = request
+                .match_pattern()
+                .unwrap_or_else(|| request.path().to_string());
+            warn!(
+                parent: &span,
+                "Query budget exceeded: {} {} ran {} queries in {:.1}ms",
+                request.method(),
+                route,
+                queries,
+                db_ms
+            );
+            budget.record_offender(Offender {
+                method: request.method().to_string(),
+                route,
+                path: request.path().to_string(),
+                status: res.status().as_u16(),
+                queries,
+                db_ms,
+                seen_at: chrono::Utc::now(),
+            });
+        }
+    }
+    result
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Recent over-budget requests and the routes they came from, worst first.
+#[get("/api/admin/query-budget")]
+pub async fn list_offenders(
+    _admin: AdminUser,
+    query: web::Query<OffendersQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let budget = &state.query_budget;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_OFFENDERS);
+    let offenders: Vec<Offender> = budget.offenders.lock().unwrap().iter().cloned().collect();
+
+    let mut routes: HashMap<(String, String), RouteSummary> = HashMap::new();
+    for offender in &offenders {
+        let summary = routes
+            .entry((offender.method.clone(), offender.route.clone()))
+            .or_insert_with(|| RouteSummary {
+                route: offender.route.clone(),
+                method: offender.method.clone(),
+                ..Default::default()
+            });
+        summary.requests += 1;
+        summary.max_queries = summary.max_queries.max(offender.queries);
+        summary.max_db_ms = summary.max_db_ms.max(offender.db_ms);
+    }
+    let mut routes: Vec<RouteSummary> = routes.into_values().collect();
+    routes.sort_by(|a, b| {
+        b.max_queries
+            .cmp(&a.max_queries)
+            .then(b.max_db_ms.total_cmp(&a.max_db_ms))
+    });
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "enabled": budget.config.enabled,
+        "max_queries": budget.config.max_queries,
+        "max_db_ms": budget.config.max_db_ms,
+        "routes": routes,
+        "recent": offenders.iter().rev().take(limit).collect::<Vec<_>>()
+    }))
+}
@@ -0,0 +1,332 @@
+// JARVIS2026 - Search ranking
+// Search results are ordered by a weighted sum of per-listing factors. The
+// weights live in a single `ranking_weights` row that admins can tune without
+// a deploy; `?explain=true` on a search returns each factor's contribution.
+
+use actix_web::{get, post, put, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::geo::Proximity;
+use crate::{AppState, Property};
+
+/// Age at which the recency factor has dropped to one half.
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+/// A term found only in the description counts for this much of a title hit.
+const DESCRIPTION_MATCH_WEIGHT: f64 = 0.5;
+const MAX_WEIGHT: f64 = 100.0;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RankingWeights {
+    text_relevance: f64,
+    recency: f64,
+    completeness: f64,
+    verified: f64,
+    boost: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        RankingWeights {
+            text_relevance: 3.0,
+            recency: 1.0,
+            completeness: 1.0,
+            verified: 0.5,
+            boost: 2.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RankingParams {
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct FactorScore {
+    /// Factor value in 0..=1
+    value: f64,
+    weight: f64,
+    contribution: f64,
+}
+
+/// Per-factor breakdown of a listing's ranking score.
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    score: f64,
+    factors: BTreeMap<&'static str, FactorScore>,
+}
+
+#[derive(Deserialize)]
+pub struct BoostRequest {
+    /// Omit or null to remove the boost
+    until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS ranking_weights (
+            id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+            text_relevance DOUBLE PRECISION NOT NULL,
+            recency DOUBLE PRECISION NOT NULL,
+            completeness DOUBLE PRECISION NOT NULL,
+            verified DOUBLE PRECISION NOT NULL,
+            boost DOUBLE PRECISION NOT NULL,
+            updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    let defaults = RankingWeights::default();
+    sqlx::query(
+        r#"INSERT INTO ranking_weights (text_relevance, recency, completeness, verified, boost)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (id) DO NOTHING"#,
+    )
+    .bind(defaults.text_relevance)
+    .bind(defaults.recency)
+    .bind(defaults.completeness)
+    .bind(defaults.verified)
+    .bind(defaults.boost)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn load_weights(pool: &PgPool) -> Result<RankingWeights, sqlx::Error> {
+    sqlx::query_as::<_, RankingWeights>(
+        "SELECT text_relevance, recency, completeness, verified, boost FROM ranking_weights",
+    )
+    .fetch_optional(pool)
+    .await
+    .map(Option::unwrap_or_default)
+}
+
+// ============================================================================
+// SCORING
+// ============================================================================
+
+fn text_relevance(property: &Property, terms: &[String]) -> f64 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let title = property.title.to_lowercase();
+    let description = property.description.to_lowercase();
+    let total: f64 = terms
+        .iter()
+        .map(|term| {
+            let term = term.to_lowercase();
+            if title.contains(&term) {
+                1.0
+            } else if description.contains(&term) {
+                DESCRIPTION_MATCH_WEIGHT
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    total / terms.len() as f64
+}
+
+fn recency(property: &Property, now: chrono::DateTime<chrono::Utc>) -> f64 {
+    property.created_at.map_or(0.0, |created_at| {
+        let age_days = (now - created_at).num_seconds().max(0) as f64 / 86_400.0;
+        0.5_f64.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+    })
+}
+
+impl RankingWeights {
+    fn validate(&self) -> Result<(), String> {
+        let weights = [
+            ("text_relevance", self.text_relevance),
+            ("recency", self.recency),
+            ("completeness", self.completeness),
+            ("verified", self.verified),
+            ("boost", self.boost),
+        ];
+        for (name, weight) in weights {
+            if !(0.0..=MAX_WEIGHT).contains(&weight) {
+                return Err(format!("{} must be between 0 and {}", name, MAX_WEIGHT));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn explain(
+        &self,
+        property: &Property,
+        terms: &[String],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Explanation {
+        let boosted = property.boosted_until.is_some_and(|until| until > now);
+        let factors: BTreeMap<&'static str, FactorScore> = [
+            (
+                "text_relevance",
+                text_relevance(property, terms),
+                self.text_relevance,
+            ),
+            ("recency", recency(property, now), self.recency),
+            (
+                "completeness",
+                property.completeness_score.unwrap_or(0) as f64 / 100.0,
+                self.completeness,
+            ),
+            (
+                "verified",
+                if property.verified_aerial { 1.0 } else { 0.0 },
+                self.verified,
+            ),
+            ("boost", if boosted { 1.0 } else { 0.0 }, self.boost),
+        ]
+        .into_iter()
+        .map(|(name, value, weight)| {
+            (
+                name,
+                FactorScore {
+                    value,
+                    weight,
+                    contribution: value * weight,
+                },
+            )
+        })
+        .collect();
+
+        Explanation {
+            score: factors.values().map(|f| f.contribution).sum(),
+            factors,
+        }
+    }
+}
+
+/// Scores every result and, unless the caller already imposed an order
+/// (e.g. nearest first), sorts best first. Ties keep their incoming order.
+pub fn rank(
+    results: Vec<(Property, Option<Proximity>)>,
+    weights: &RankingWeights,
+    terms: &[String],
+    reorder: bool,
+) -> Vec<(Property, Option<Proximity>, Explanation)> {
+    let now = chrono::Utc::now();
+    let mut ranked: Vec<_> = results
+        .into_iter()
+        .map(|(property, proximity)| {
+            let explanation = weights.explain(&property, terms, now);
+            (property, proximity, explanation)
+        })
+        .collect();
+    if reorder {
+        ranked.sort_by(|(_, _, a), (_, _, b)| b.score.total_cmp(&a.score));
+    }
+    ranked
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/admin/ranking/weights")]
+pub async fn get_weights(_admin: AdminUser, state: web::Data<AppState>) -> impl Responder {
+    match load_weights(&state.db).await {
+        Ok(weights) => HttpResponse::Ok().json(weights),
+        Err(e) => {
+            error!("Failed to load ranking weights: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load ranking weights"
+            }))
+        }
+    }
+}
+
+#[put("/api/admin/ranking/weights")]
+pub async fn update_weights(
+    admin: AdminUser,
+    req: web::Json<RankingWeights>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let weights = req.into_inner();
+    if let Err(message) = weights.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }));
+    }
+
+    match sqlx::query(
+        r#"UPDATE ranking_weights
+        SET text_relevance = $1, recency = $2, completeness = $3, verified = $4, boost = $5,
+            updated_by = $6, updated_at = NOW()"#,
+    )
+    .bind(weights.text_relevance)
+    .bind(weights.recency)
+    .bind(weights.completeness)
+    .bind(weights.verified)
+    .bind(weights.boost)
+    .bind(admin.id)
+    .execute(&state.db)
+    .await
+    {
+        Ok(_) => {
+            info!("Ranking weights updated by {}: {:?}", admin.id, weights);
+            HttpResponse::Ok().json(weights)
+        }
+        Err(e) => {
+            error!("Failed to update ranking weights: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update ranking weights"
+            }))
+        }
+    }
+}
+
+#[post("/api/admin/properties/{id}/boost")]
+pub async fn boost_property(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    req: web::Json<BoostRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    match sqlx::query("UPDATE properties SET boosted_until = $1 WHERE id = $2")
+        .bind(req.until)
+        .bind(property_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Ok(_) => {
+            state.property_cache.invalidate(property_id).await;
+            info!(
+                "Property {} boosted until {:?} by {}",
+                property_id, req.until, admin.id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "boosted_until": req.until
+            }))
+        }
+        Err(e) => {
+            error!("Failed to boost property {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to boost property"
+            }))
+        }
+    }
+}
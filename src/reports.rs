@@ -0,0 +1,397 @@
+// JARVIS2026 - Monthly market trend reports
+// Compiled on a schedule into JSON + PDF and kept for agents to download.
+
+use actix_files::NamedFile;
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, PgPool};
+use tokio::fs as async_fs;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::AppState;
+
+const REPORTS_DIR: &str = "reports";
+const SCHEDULER_INTERVAL_SECS: u64 = 3600;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+struct AreaSummary {
+    location: String,
+    inventory: i64,
+    new_listings: i64,
+    median_price: Option<f64>,
+    median_price_per_sqm: Option<f64>,
+    median_days_on_market: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MarketSummary {
+    period: String,
+    period_start: NaiveDate,
+    period_end: NaiveDate,
+    inventory: i64,
+    new_listings: i64,
+    median_price: Option<f64>,
+    median_days_on_market: Option<f64>,
+    areas: Vec<AreaSummary>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct MarketReport {
+    id: Uuid,
+    period_start: NaiveDate,
+    summary: Json<MarketSummary>,
+    #[serde(skip_serializing)]
+    pdf_path: String,
+    generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct MarketReportListing {
+    id: Uuid,
+    period_start: NaiveDate,
+    generated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct ReportPeriod {
+    /// `YYYY-MM`
+    period: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS market_reports (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            period_start DATE UNIQUE NOT NULL,
+            summary JSONB NOT NULL,
+            pdf_path TEXT NOT NULL,
+            generated_at TIMESTAMPTZ DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// REPORT GENERATION
+// ============================================================================
+
+fn parse_period(raw: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", raw), "%Y-%m-%d").ok()
+}
+
+fn next_month(date: NaiveDate) -> NaiveDate {
+    if date.month() == 12 {
+        NaiveDate::from_ymd_opt(date.year() + 1, 1, 1).unwrap()
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1).unwrap()
+    }
+}
+
+fn previous_month(date: NaiveDate) -> NaiveDate {
+    if date.month() == 1 {
+        NaiveDate::from_ymd_opt(date.year() - 1, 12, 1).unwrap()
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() - 1, 1).unwrap()
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+async fn compile_summary(
+    pool: &PgPool,
+    period_start: NaiveDate,
+) -> Result<MarketSummary, sqlx::Error> {
+    let period_end = next_month(period_start);
+    let from = start_of_day(period_start);
+    let to = start_of_day(period_end);
+
+    // Inventory is everything listed before the end of the period;
+    // days on market is measured up to that same cut-off.
+    let areas = sqlx::query_as::<_, AreaSummary>(
+        r#"SELECT location,
+            COUNT(*) AS inventory,
+            COUNT(*) FILTER (WHERE created_at >= $1) AS new_listings,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY price) AS median_price,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY price / NULLIF(area_sqm, 0)) AS median_price_per_sqm,
+            percentile_cont(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM ($2 - created_at))::float8 / 86400
+            ) AS median_days_on_market
+        FROM properties
+        WHERE created_at < $2
+        GROUP BY location
+        ORDER BY inventory DESC, location"#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let (inventory, new_listings, median_price, median_days_on_market) =
+        sqlx::query_as::<_, (i64, i64, Option<f64>, Option<f64>)>(
+            r#"SELECT COUNT(*),
+                COUNT(*) FILTER (WHERE created_at >= $1),
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY price),
+                percentile_cont(0.5) WITHIN GROUP (
+                    ORDER BY EXTRACT(EPOCH FROM ($2 - created_at))::float8 / 86400
+                )
+            FROM properties
+            WHERE created_at < $2"#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(MarketSummary {
+        period: period_start.format("%Y-%m").to_string(),
+        period_start,
+        period_end,
+        inventory,
+        new_listings,
+        median_price,
+        median_days_on_market,
+        areas,
+    })
+}
+
+fn format_optional(value: Option<f64>, decimals: usize) -> String {
+    match value {
+        Some(v) => format!("{:.*}", decimals, v),
+        None => "-".to_string(),
+    }
+}
+
+fn render_pdf(summary: &MarketSummary) -> anyhow::Result<Vec<u8>> {
+    const PAGE_WIDTH: f32 = 210.0;
+    const PAGE_HEIGHT: f32 = 297.0;
+    const MARGIN: f32 = 15.0;
+    const LINE: f32 = 6.0;
+
+    let title = format!("JARVIS2026 Market Report {}", summary.period);
+    let (doc, page, layer) = PdfDocument::new(&title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    layer.use_text(&title, 18.0, Mm(MARGIN), Mm(y), &bold);
+    y -= LINE * 2.0;
+
+    let overview = [
+        format!("Period: {} to {}", summary.period_start, summary.period_end),
+        format!("Inventory: {} listings", summary.inventory),
+        format!("New listings: {}", summary.new_listings),
+        format!("Median price: {}", format_optional(summary.median_price, 0)),
+        format!(
+            "Median days on market: {}",
+            format_optional(summary.median_days_on_market, 1)
+        ),
+    ];
+    for line in &overview {
+        layer.use_text(line, 11.0, Mm(MARGIN), Mm(y), &regular);
+        y -= LINE;
+    }
+    y -= LINE;
+
+    let columns = [
+        ("Area", MARGIN),
+        ("Inventory", 80.0),
+        ("New", 105.0),
+        ("Median price", 122.0),
+        ("Price/sqm", 157.0),
+        ("DOM", 182.0),
+    ];
+
+    let header = |layer: &printpdf::PdfLayerReference, y: f32| {
+        for (label, x) in columns {
+            layer.use_text(label, 10.0, Mm(x), Mm(y), &bold);
+        }
+    };
+
+    header(&layer, y);
+    y -= LINE;
+
+    for area in &summary.areas {
+        if y < MARGIN {
+            let (page, layer_index) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            layer = doc.get_page(page).get_layer(layer_index);
+            y = PAGE_HEIGHT - MARGIN;
+            header(&layer, y);
+            y -= LINE;
+        }
+
+        let location: String = area.location.chars().take(32).collect();
+        let cells = [
+            location,
+            area.inventory.to_string(),
+            area.new_listings.to_string(),
+            format_optional(area.median_price, 0),
+            format_optional(area.median_price_per_sqm, 0),
+            format_optional(area.median_days_on_market, 1),
+        ];
+        for (text, (_, x)) in cells.iter().zip(columns) {
+            layer.use_text(text, 9.0, Mm(x), Mm(y), &regular);
+        }
+        y -= LINE;
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+async fn generate_report(pool: &PgPool, period_start: NaiveDate) -> anyhow::Result<()> {
+    let summary = compile_summary(pool, period_start).await?;
+    let pdf = render_pdf(&summary)?;
+
+    async_fs::create_dir_all(REPORTS_DIR).await?;
+    let pdf_path = format!("{}/market-{}.pdf", REPORTS_DIR, summary.period);
+    async_fs::write(&pdf_path, pdf).await?;
+
+    sqlx::query(
+        r#"INSERT INTO market_reports (period_start, summary, pdf_path)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (period_start) DO UPDATE
+        SET summary = EXCLUDED.summary, pdf_path = EXCLUDED.pdf_path, generated_at = NOW()"#,
+    )
+    .bind(period_start)
+    .bind(Json(&summary))
+    .bind(&pdf_path)
+    .execute(pool)
+    .await?;
+
+    info!(
+        "Market report generated for {} ({} areas)",
+        summary.period,
+        summary.areas.len()
+    );
+    Ok(())
+}
+
+/// Generates last month's report once the month has closed.
+pub fn spawn_scheduler(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let today = Utc::now().date_naive();
+            let period_start = previous_month(today.with_day(1).unwrap());
+
+            let exists = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM market_reports WHERE period_start = $1",
+            )
+            .bind(period_start)
+            .fetch_one(&pool)
+            .await
+            .map(|count| count > 0)
+            .unwrap_or(true);
+
+            if !exists {
+                if let Err(e) = generate_report(&pool, period_start).await {
+                    error!("Market report generation failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+async fn fetch_report(pool: &PgPool, period: &str) -> Result<MarketReport, HttpResponse> {
+    let Some(period_start) = parse_period(period) else {
+        return Err(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "period must be YYYY-MM"
+        })));
+    };
+
+    match sqlx::query_as::<_, MarketReport>("SELECT * FROM market_reports WHERE period_start = $1")
+        .bind(period_start)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(report)) => Ok(report),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Report not found"
+        }))),
+        Err(e) => {
+            error!("Failed to fetch market report: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch report"
+            })))
+        }
+    }
+}
+
+#[get("/api/reports/market")]
+pub async fn list_market_reports(state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, MarketReportListing>(
+        "SELECT id, period_start, generated_at FROM market_reports ORDER BY period_start DESC",
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(reports) => HttpResponse::Ok().json(reports),
+        Err(e) => {
+            error!("Failed to list market reports: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list reports"
+            }))
+        }
+    }
+}
+
+#[get("/api/reports/market/{period}")]
+pub async fn get_market_report(
+    path: web::Path<ReportPeriod>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match fetch_report(&state.db, &path.period).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(response) => response,
+    }
+}
+
+#[get("/api/reports/market/{period}/pdf")]
+pub async fn download_market_report(
+    req: HttpRequest,
+    path: web::Path<ReportPeriod>,
+    state: web::Data<AppState>,
+) -> HttpResponse {
+    let report = match fetch_report(&state.db, &path.period).await {
+        Ok(report) => report,
+        Err(response) => return response,
+    };
+
+    match NamedFile::open_async(&report.pdf_path).await {
+        Ok(file) => {
+            let filename = format!("market-report-{}.pdf", path.period);
+            file.set_content_disposition(header::ContentDisposition::attachment(filename))
+                .into_response(&req)
+        }
+        Err(e) => {
+            error!("Market report file missing for {}: {}", path.period, e);
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Report file not found"
+            }))
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// JARVIS2026 - Owner response times
+// An hourly job condenses each owner's recent inquiry replies into
+// `response_stats`; listings show a "usually responds within X hours" badge
+// derived from them.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::formatting::Locale;
+use crate::AppState;
+
+const RECOMPUTE_INTERVAL_SECS: u64 = 3600;
+const WINDOW_DAYS: i32 = 90;
+/// Unanswered inquiries only count against an owner once they are this old.
+const GRACE_HOURS: i32 = 24;
+const MIN_SAMPLE: i64 = 3;
+const MIN_RESPONSE_RATE: f64 = 0.6;
+/// Badge buckets, in hours.
+const BADGE_HOURS: &[i64] = &[1, 3, 12, 24];
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ResponseStats {
+    inquiries: i64,
+    answered: i64,
+    median_response_minutes: Option<f64>,
+    computed_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBadge {
+    within_hours: i64,
+    label: String,
+}
+
+#[derive(Serialize)]
+struct OwnerResponsiveness {
+    owner_user_id: Uuid,
+    badge: Option<ResponseBadge>,
+    stats: Option<ResponseStats>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS response_stats (
+            user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            inquiries BIGINT NOT NULL,
+            answered BIGINT NOT NULL,
+            median_response_minutes DOUBLE PRECISION,
+            computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// RECOMPUTATION
+// ============================================================================
+
+async fn recompute(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query(
+        r#"INSERT INTO response_stats (user_id, inquiries, answered, median_response_minutes, computed_at)
+        SELECT owner_user_id,
+            COUNT(*),
+            COUNT(first_response_at),
+            percentile_cont(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM first_response_at - created_at)::float8 / 60.0),
+            NOW()
+        FROM inquiries
+        WHERE created_at >= NOW() - make_interval(days => $1)
+          AND (first_response_at IS NOT NULL OR created_at < NOW() - make_interval(hours => $2))
+        GROUP BY owner_user_id
+        ON CONFLICT (user_id) DO UPDATE
+        SET inquiries = EXCLUDED.inquiries,
+            answered = EXCLUDED.answered,
+            median_response_minutes = EXCLUDED.median_response_minutes,
+            computed_at = EXCLUDED.computed_at"#,
+    )
+    .bind(WINDOW_DAYS)
+    .bind(GRACE_HOURS)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // NOW() is fixed for the transaction, so this only removes owners that
+    // had no recent inquiries and weren't refreshed above
+    sqlx::query("DELETE FROM response_stats WHERE computed_at < NOW()")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(updated)
+}
+
+pub fn spawn_scheduler(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(RECOMPUTE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match recompute(&pool).await {
+                Ok(owners) => info!("Response stats recomputed for {} owners", owners),
+                Err(e) => error!("Response stats recomputation failed: {}", e),
+            }
+        }
+    });
+}
+
+// ============================================================================
+// BADGES
+// ============================================================================
+
+impl ResponseStats {
+    pub fn badge(&self, locale: Locale) -> Option<ResponseBadge> {
+        if self.inquiries < MIN_SAMPLE
+            || (self.answered as f64 / self.inquiries as f64) < MIN_RESPONSE_RATE
+        {
+            return None;
+        }
+        let median_hours = self.median_response_minutes? / 60.0;
+        let within_hours = *BADGE_HOURS.iter().find(|h| median_hours <= **h as f64)?;
+        let label = match (locale, within_hours) {
+            (Locale::Indonesian, h) => format!("Biasanya membalas dalam {} jam", h),
+            (Locale::English, 1) => "Usually responds within an hour".to_string(),
+            (Locale::English, h) => format!("Usually responds within {} hours", h),
+        };
+        Some(ResponseBadge {
+            within_hours,
+            label,
+        })
+    }
+}
+
+pub async fn stats_for_owner(
+    pool: &PgPool,
+    owner_user_id: Uuid,
+) -> Result<Option<ResponseStats>, sqlx::Error> {
+    sqlx::query_as::<_, ResponseStats>(
+        r#"SELECT inquiries, answered, median_response_minutes, computed_at
+        FROM response_stats WHERE user_id = $1"#,
+    )
+    .bind(owner_user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/{id}/response-time")]
+pub async fn property_response_time(
+    path: web::Path<Uuid>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    let owner_user_id =
+        match sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM properties WHERE id = $1")
+            .bind(property_id)
+            .fetch_optional(&state.db)
+            .await
+        {
+            Ok(Some(Some(owner))) => owner,
+            Ok(_) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Property not found"
+                }))
+            }
+            Err(e) => {
+                error!("Failed to look up property {}: {}", property_id, e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to load response time"
+                }));
+            }
+        };
+
+    match stats_for_owner(&state.db, owner_user_id).await {
+        Ok(stats) => HttpResponse::Ok().json(OwnerResponsiveness {
+            owner_user_id,
+            badge: stats.as_ref().and_then(|s| s.badge(locale)),
+            stats,
+        }),
+        Err(e) => {
+            error!("Failed to load response stats for {}: {}", owner_user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load response time"
+            }))
+        }
+    }
+}
@@ -0,0 +1,372 @@
+// JARVIS2026 - Saved searches
+// A buyer saves a search as a filter, written the way the listing endpoint
+// takes it (`min_price`, `location`, `has`, ...) plus an optional `query` in
+// the search language. A background job runs every saved search against
+// listings that went live since it last looked and leaves one notification
+// per new match, so buyers don't have to keep searching.
+
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, PgPool, Postgres, QueryBuilder};
+use std::time::Duration;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::filters::{FilterSet, ListingFilterParams};
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::notifications::notify;
+use crate::AppState;
+
+const ALERT_INTERVAL: Duration = Duration::from_secs(600);
+const MAX_SEARCHES_PER_USER: i64 = 20;
+const MAX_NAME_LEN: usize = 64;
+/// Matches notified per search and run; the rest wait for the next run
+const MAX_MATCHES_PER_RUN: i64 = 20;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct SavedSearch {
+    id: Uuid,
+    name: String,
+    filters: Json<serde_json::Value>,
+    alerts_enabled: bool,
+    created_at: DateTime<Utc>,
+    last_checked_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct SaveSearchRequest {
+    name: String,
+    filters: serde_json::Value,
+    /// Defaults to on
+    alerts_enabled: Option<bool>,
+}
+
+/// The stored filter: listing query parameters plus search syntax.
+#[derive(Deserialize)]
+struct SavedFilter {
+    query: Option<String>,
+    #[serde(flatten)]
+    params: ListingFilterParams,
+}
+
+#[derive(sqlx::FromRow)]
+struct AlertSearch {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    filters: Json<serde_json::Value>,
+    last_checked_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Match {
+    id: Uuid,
+    title: String,
+    location: String,
+    price: f64,
+    slug: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS saved_searches (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            filters JSONB NOT NULL,
+            alerts_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            UNIQUE (user_id, name)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // One row per listing already notified, so a listing that is re-activated
+    // or edited doesn't alert twice
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS saved_search_matches (
+            saved_search_id UUID NOT NULL REFERENCES saved_searches(id) ON DELETE CASCADE,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            matched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (saved_search_id, property_id)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
+fn filter_set(filters: &serde_json::Value) -> Result<FilterSet, String> {
+    if !filters.is_object() {
+        return Err("filters must be a JSON object".to_string());
+    }
+    let saved: SavedFilter =
+        serde_json::from_value(filters.clone()).map_err(|e| format!("Invalid filters: {}", e))?;
+    let mut set = saved.params.into_filter_set()?;
+    if let Some(query) = saved.query.as_deref().filter(|q| !q.trim().is_empty()) {
+        set.filters.extend(FilterSet::parse_query(query)?.filters);
+    }
+    if set.filters.is_empty() {
+        return Err("A saved search needs at least one filter".to_string());
+    }
+    Ok(set)
+}
+
+// ============================================================================
+// ALERTS
+// ============================================================================
+
+/// Notifies the owner of listings that went live since the last check.
+async fn run_search(pool: &PgPool, search: &AlertSearch) -> Result<usize, sqlx::Error> {
+    let checked_at: DateTime<Utc> = sqlx::query_scalar("SELECT NOW()").fetch_one(pool).await?;
+
+    // Filters were valid when saved; one that no longer parses is skipped
+    // rather than matching everything
+    let matches = match filter_set(&search.filters) {
+        Ok(filters) => {
+            let mut sql = QueryBuilder::<Postgres>::new(
+                "SELECT id, title, location, price, slug FROM properties WHERE ",
+            );
+            sql.push(PUBLIC_LISTING_CONDITION);
+            sql.push(" AND ");
+            sql.push(ACTIVE_LISTING_CONDITION);
+            sql.push(
+                " AND GREATEST(created_at, COALESCE(status_changed_at, created_at), \
+                 COALESCE(moderated_at, created_at)) > ",
+            );
+            sql.push_bind(search.last_checked_at);
+            sql.push(" AND user_id IS DISTINCT FROM ");
+            sql.push_bind(search.user_id);
+            sql.push(
+                " AND NOT EXISTS (SELECT 1 FROM saved_search_matches m \
+                 WHERE m.property_id = properties.id AND m.saved_search_id = ",
+            );
+            sql.push_bind(search.id);
+            sql.push(")");
+            filters.push_and(&mut sql);
+            sql.push(" ORDER BY created_at LIMIT ");
+            sql.push_bind(MAX_MATCHES_PER_RUN);
+            sql.build_query_as::<Match>().fetch_all(pool).await?
+        }
+        Err(message) => {
+            error!(
+                "Saved search {} has invalid filters: {}",
+                search.id, message
+            );
+            Vec::new()
+        }
+    };
+
+    let mut tx = pool.begin().await?;
+    let mut notified = 0;
+    for listing in &matches {
+        let inserted = sqlx::query(
+            r#"INSERT INTO saved_search_matches (saved_search_id, property_id)
+            VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+        )
+        .bind(search.id)
+        .bind(listing.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            continue;
+        }
+        notify(
+            &mut *tx,
+            search.user_id,
+            "saved_search_match",
+            serde_json::json!({
+                "saved_search_id": search.id,
+                "saved_search_name": search.name,
+                "property_id": listing.id,
+                "title": listing.title,
+                "location": listing.location,
+                "price": listing.price,
+                "slug": listing.slug,
+            }),
+        )
+        .await?;
+        notified += 1;
+    }
+    // A full page may have left matches behind; keep the window open so the
+    // next run picks them up
+    if (matches.len() as i64) < MAX_MATCHES_PER_RUN {
+        sqlx::query("UPDATE saved_searches SET last_checked_at = $2 WHERE id = $1")
+            .bind(search.id)
+            .bind(checked_at)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(notified)
+}
+
+pub fn spawn_alerts(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ALERT_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let searches = match sqlx::query_as::<_, AlertSearch>(
+                r#"SELECT id, user_id, name, filters, last_checked_at FROM saved_searches
+                WHERE alerts_enabled ORDER BY last_checked_at"#,
+            )
+            .fetch_all(&pool)
+            .await
+            {
+                Ok(searches) => searches,
+                Err(e) => {
+                    error!("Failed to load saved searches: {}", e);
+                    continue;
+                }
+            };
+
+            let mut notified = 0;
+            for search in &searches {
+                match run_search(&pool, search).await {
+                    Ok(count) => notified += count,
+                    Err(e) => error!("Saved search {} failed: {}", search.id, e),
+                }
+            }
+            if notified > 0 {
+                info!(
+                    "Saved search alerts: {} matches across {} searches",
+                    notified,
+                    searches.len()
+                );
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/users/me/saved-searches")]
+pub async fn list_saved_searches(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, SavedSearch>(
+        r#"SELECT id, name, filters, alerts_enabled, created_at, last_checked_at
+        FROM saved_searches WHERE user_id = $1 ORDER BY created_at DESC"#,
+    )
+    .bind(user.id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(searches) => HttpResponse::Ok().json(searches),
+        Err(e) => {
+            error!("Failed to fetch saved searches for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch saved searches"
+            }))
+        }
+    }
+}
+
+/// Saves a search, or replaces the one with the same name. Alerts cover
+/// listings going live from now on.
+#[post("/api/users/me/saved-searches")]
+pub async fn save_search(
+    user: CurrentUser,
+    req: web::Json<SaveSearchRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("name must be 1-{} characters", MAX_NAME_LEN)
+        }));
+    }
+    if let Err(message) = filter_set(&req.filters) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": message,
+            "field": "filters"
+        }));
+    }
+
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM saved_searches WHERE user_id = $1 AND name <> $2",
+    )
+    .bind(user.id)
+    .bind(name)
+    .fetch_one(&state.db)
+    .await
+    .unwrap_or(0);
+    if count >= MAX_SEARCHES_PER_USER {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("A user can keep at most {} saved searches", MAX_SEARCHES_PER_USER)
+        }));
+    }
+
+    match sqlx::query_as::<_, SavedSearch>(
+        r#"INSERT INTO saved_searches (user_id, name, filters, alerts_enabled)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, name) DO UPDATE
+        SET filters = EXCLUDED.filters, alerts_enabled = EXCLUDED.alerts_enabled
+        RETURNING id, name, filters, alerts_enabled, created_at, last_checked_at"#,
+    )
+    .bind(user.id)
+    .bind(name)
+    .bind(Json(&req.filters))
+    .bind(req.alerts_enabled.unwrap_or(true))
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(search) => {
+            info!("Saved search '{}' stored for {}", search.name, user.id);
+            HttpResponse::Ok().json(search)
+        }
+        Err(e) => {
+            error!("Failed to save search for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to save search"
+            }))
+        }
+    }
+}
+
+#[delete("/api/users/me/saved-searches/{id}")]
+pub async fn delete_saved_search(
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query("DELETE FROM saved_searches WHERE id = $1 AND user_id = $2")
+        .bind(path.into_inner())
+        .bind(user.id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            HttpResponse::Ok().json(serde_json::json!({ "deleted": true }))
+        }
+        Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Saved search not found"
+        })),
+        Err(e) => {
+            error!("Failed to delete saved search for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete saved search"
+            }))
+        }
+    }
+}
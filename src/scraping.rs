@@ -0,0 +1,643 @@
+// JARVIS2026 - Anti-scraping
+// Public listing reads (browse, detail, search) are watched per client IP
+// for patterns people don't produce: walking listing ids in sorted order or
+// opening dozens of listings a minute, bursts of requests without a
+// Referer, and raw request rate. Each detection is a strike, and strikes
+// escalate the response from a short delay, to a longer one, to 429s for a
+// doubling period. Strikes fade after an hour of good behaviour. Suspects
+// are kept in `scraper_suspects` for admins to review and clear. API key
+// traffic is exempt; partners have their own quotas.
+//
+// With `SCRAPING_OPAQUE_CURSORS` set, paginated listing APIs stop taking raw
+// offsets and hand out encrypted, expiring cursors instead, so pages can't
+// be fetched in parallel or by guessing.
+
+use actix_web::{
+    body::MessageBody,
+    delete,
+    dev::{ServiceRequest, ServiceResponse},
+    error::ResponseError,
+    get,
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    web, Error, HttpResponse, Responder,
+};
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::api_keys;
+use crate::auth::AdminUser;
+use crate::geoip;
+use crate::secrets;
+use crate::AppState;
+
+/// Counting window for every signal
+const WINDOW: Duration = Duration::from_secs(60);
+/// Strikes are forgotten after this long without a new one
+const STRIKE_MEMORY: Duration = Duration::from_secs(3600);
+/// How long a delay or block from one strike lasts, before doubling
+const PENALTY_BASE: Duration = Duration::from_secs(60);
+const MAX_BLOCK: Duration = Duration::from_secs(3600);
+const FIRST_STRIKE_DELAY: Duration = Duration::from_millis(500);
+const SECOND_STRIKE_DELAY: Duration = Duration::from_secs(2);
+/// From this strike on, requests are refused rather than slowed
+const BLOCK_FROM_STRIKE: u32 = 3;
+const TRACKER_CAPACITY: usize = 10_000;
+const CURSOR_TTL: Duration = Duration::from_secs(600);
+const NONCE_LEN: usize = 12;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// Thresholds per `WINDOW`, from the environment.
+#[derive(Debug, Clone)]
+pub struct ScrapingConfig {
+    enabled: bool,
+    max_requests: usize,
+    max_no_referrer: usize,
+    max_listings: usize,
+    /// Consecutive detail fetches in ascending id order
+    walk_run: u32,
+    opaque_cursors: bool,
+}
+
+pub struct ScrapingGuard {
+    config: ScrapingConfig,
+    clients: Mutex<HashMap<IpAddr, Client>>,
+    cursor_cipher: Aes256Gcm,
+}
+
+#[derive(Default)]
+struct Client {
+    /// Request times, and whether each came without a Referer
+    requests: VecDeque<(Instant, bool)>,
+    /// Detail fetches of distinct listings
+    listings: VecDeque<(Instant, Uuid)>,
+    last_listing: Option<Uuid>,
+    ascending_run: u32,
+    strikes: u32,
+    last_strike: Option<Instant>,
+    penalty_until: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Signal {
+    IdWalk,
+    ListingBurst,
+    NoReferrerBurst,
+    RequestRate,
+}
+
+enum Verdict {
+    Allow,
+    Delay(Duration),
+    Block(Duration),
+}
+
+#[derive(Debug)]
+pub enum ScrapingError {
+    Throttled { retry_after: Duration },
+}
+
+#[derive(Deserialize)]
+pub struct SuspectsQuery {
+    /// Only IPs blocked right now
+    #[serde(default)]
+    active: bool,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Suspect {
+    ip: String,
+    strikes: i32,
+    signals: Vec<String>,
+    requests_per_min: i32,
+    user_agent: Option<String>,
+    last_path: Option<String>,
+    first_flagged_at: chrono::DateTime<chrono::Utc>,
+    last_flagged_at: chrono::DateTime<chrono::Utc>,
+    blocked_until: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip)]
+    total: i64,
+}
+
+/// A strike just added to a client.
+struct Strike {
+    strikes: u32,
+    signals: Vec<Signal>,
+    requests_per_min: usize,
+    blocked_for: Option<Duration>,
+}
+
+/// What a strike records about the client.
+struct Flagged {
+    ip: IpAddr,
+    strike: Strike,
+    user_agent: Option<String>,
+    path: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS scraper_suspects (
+            ip TEXT PRIMARY KEY,
+            strikes INTEGER NOT NULL,
+            signals TEXT[] NOT NULL,
+            requests_per_min INTEGER NOT NULL,
+            user_agent TEXT,
+            last_path TEXT,
+            first_flagged_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            last_flagged_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            blocked_until TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_scraper_suspects_recent ON scraper_suspects(last_flagged_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// CONFIGURATION
+// ============================================================================
+
+fn env_usize(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(default)
+}
+
+impl ScrapingConfig {
+    /// `SCRAPING_PROTECTION=off` disables detection. Per-minute limits:
+    /// `SCRAPING_MAX_REQUESTS` (240), `SCRAPING_MAX_NO_REFERRER` (60),
+    /// `SCRAPING_MAX_LISTINGS` (60); `SCRAPING_WALK_RUN` (10) ascending ids
+    /// count as a walk.
+    pub fn from_env() -> Self {
+        let enabled = !matches!(
+            std::env::var("SCRAPING_PROTECTION").as_deref(),
+            Ok("off" | "false" | "0")
+        );
+        let config = ScrapingConfig {
+            enabled,
+            max_requests: env_usize("SCRAPING_MAX_REQUESTS", 240),
+            max_no_referrer: env_usize("SCRAPING_MAX_NO_REFERRER", 60),
+            max_listings: env_usize("SCRAPING_MAX_LISTINGS", 60),
+            walk_run: env_usize("SCRAPING_WALK_RUN", 10) as u32,
+            opaque_cursors: matches!(
+                std::env::var("SCRAPING_OPAQUE_CURSORS").as_deref(),
+                Ok("on" | "true" | "1")
+            ),
+        };
+        if !config.enabled {
+            warn!("Scraping protection disabled by SCRAPING_PROTECTION");
+        }
+        config
+    }
+}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+impl ScrapingGuard {
+    /// The cursor key comes from `SCRAPING_CURSOR_KEY` (64 hex characters);
+    /// without it cursors don't survive a restart.
+    pub fn from_env() -> Self {
+        let key = secrets::var("SCRAPING_CURSOR_KEY")
+            .ok()
+            .and_then(|raw| hex::decode(raw.trim()).ok())
+            .filter(|bytes| bytes.len() == 32)
+            .map(|bytes| *Key::<Aes256Gcm>::from_slice(&bytes))
+            .unwrap_or_else(|| Aes256Gcm::generate_key(OsRng));
+        ScrapingGuard {
+            config: ScrapingConfig::from_env(),
+            clients: Mutex::new(HashMap::new()),
+            cursor_cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    /// Records the request and decides what to do with it. `listing` is set
+    /// for detail fetches.
+    fn check(
+        &self,
+        ip: IpAddr,
+        listing: Option<Uuid>,
+        has_referrer: bool,
+    ) -> (Verdict, Option<Strike>) {
+        let now = Instant::now();
+        let config = &self.config;
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients.len() >= TRACKER_CAPACITY {
+            clients.retain(|_, c| {
+                c.requests
+                    .back()
+                    .is_some_and(|(t, _)| now.duration_since(*t) < WINDOW)
+                    || c.penalty_until.is_some_and(|until| until > now)
+            });
+        }
+        let client = clients.entry(ip).or_default();
+
+        while client
+            .requests
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) >= WINDOW)
+        {
+            client.requests.pop_front();
+        }
+        while client
+            .listings
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) >= WINDOW)
+        {
+            client.listings.pop_front();
+        }
+        if client
+            .last_strike
+            .is_some_and(|t| now.duration_since(t) >= STRIKE_MEMORY)
+        {
+            client.strikes = 0;
+            client.last_strike = None;
+        }
+
+        client.requests.push_back((now, !has_referrer));
+        if let Some(id) = listing {
+            if !client.listings.iter().any(|(_, seen)| *seen == id) {
+                client.listings.push_back((now, id));
+            }
+            client.ascending_run = match client.last_listing {
+                Some(last) if id > last => client.ascending_run + 1,
+                _ => 0,
+            };
+            client.last_listing = Some(id);
+        }
+
+        let no_referrer = client.requests.iter().filter(|(_, bare)| *bare).count();
+        let mut signals = Vec::new();
+        if client.ascending_run >= config.walk_run {
+            signals.push(Signal::IdWalk);
+        }
+        if client.listings.len() > config.max_listings {
+            signals.push(Signal::ListingBurst);
+        }
+        if no_referrer > config.max_no_referrer {
+            signals.push(Signal::NoReferrerBurst);
+        }
+        if client.requests.len() > config.max_requests {
+            signals.push(Signal::RequestRate);
+        }
+
+        // A client already paying a penalty doesn't earn another strike until
+        // it runs out, or one burst would escalate straight to a block
+        let penalized = client.penalty_until.is_some_and(|until| until > now);
+        let mut flagged = None;
+        if !signals.is_empty() && !penalized {
+            client.strikes += 1;
+            client.last_strike = Some(now);
+            client.ascending_run = 0;
+            let doublings = client.strikes.saturating_sub(BLOCK_FROM_STRIKE).min(6);
+            let period = (PENALTY_BASE * 2u32.pow(doublings)).min(MAX_BLOCK);
+            client.penalty_until = Some(now + period);
+            let blocked_for = (client.strikes >= BLOCK_FROM_STRIKE).then_some(period);
+            flagged = Some(Strike {
+                strikes: client.strikes,
+                signals,
+                requests_per_min: client.requests.len(),
+                blocked_for,
+            });
+        }
+
+        let verdict = match client.penalty_until.filter(|until| *until > now) {
+            None => Verdict::Allow,
+            Some(until) => match client.strikes {
+                0 => Verdict::Allow,
+                1 => Verdict::Delay(FIRST_STRIKE_DELAY),
+                2 => Verdict::Delay(SECOND_STRIKE_DELAY),
+                _ => Verdict::Block(until - now),
+            },
+        };
+        (verdict, flagged)
+    }
+
+    /// Clears an IP's strikes and penalty.
+    fn forgive(&self, ip: IpAddr) {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.remove(&ip);
+    }
+}
+
+/// Anonymous and signed-in reads of public listing data.
+fn is_listing_read(method: &Method, path: &str) -> bool {
+    (method == Method::GET
+        && (path == "/api/properties"
+            || path.starts_with("/api/properties/")
+            || path.starts_with("/api/search/")))
+        || (method == Method::POST && path == "/api/search")
+}
+
+/// The listing a detail fetch is for: `/api/properties/{id}` and its subpaths.
+fn listing_in_path(path: &str) -> Option<Uuid> {
+    let rest = path.strip_prefix("/api/properties/")?;
+    Uuid::parse_str(rest.split('/').next()?).ok()
+}
+
+fn record_suspect(pool: &PgPool, flagged: Flagged) {
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let Flagged {
+            ip,
+            strike,
+            user_agent,
+            path,
+        } = flagged;
+        let signals: Vec<String> = strike
+            .signals
+            .iter()
+            .filter_map(|s| serde_json::to_value(s).ok())
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect();
+        let blocked_until = strike
+            .blocked_for
+            .and_then(|d| chrono::Duration::from_std(d).ok())
+            .map(|d| chrono::Utc::now() + d);
+        let result = sqlx::query(
+            r#"INSERT INTO scraper_suspects
+            (ip, strikes, signals, requests_per_min, user_agent, last_path, blocked_until)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (ip) DO UPDATE SET
+                strikes = EXCLUDED.strikes,
+                signals = ARRAY(SELECT DISTINCT unnest(scraper_suspects.signals || EXCLUDED.signals)),
+                requests_per_min = EXCLUDED.requests_per_min,
+                user_agent = EXCLUDED.user_agent,
+                last_path = EXCLUDED.last_path,
+                last_flagged_at = NOW(),
+                blocked_until = EXCLUDED.blocked_until"#,
+        )
+        .bind(ip.to_string())
+        .bind(strike.strikes as i32)
+        .bind(&signals)
+        .bind(strike.requests_per_min as i32)
+        .bind(&user_agent)
+        .bind(&path)
+        .bind(blocked_until)
+        .execute(&pool)
+        .await;
+        if let Err(e) = result {
+            warn!("Failed to record scraper suspect {}: {}", ip, e);
+        }
+    });
+}
+
+// ============================================================================
+// CURSORS
+// ============================================================================
+
+/// The offset of the next page, sealed together with an expiry and bound to
+/// `scope` (the query it pages through). `None` unless opaque cursors are on.
+pub fn next_cursor(state: &AppState, scope: &str, offset: i64) -> Option<String> {
+    let guard = &state.scraping;
+    if !guard.config.opaque_cursors {
+        return None;
+    }
+    let expires = chrono::Utc::now().timestamp() + CURSOR_TTL.as_secs() as i64;
+    let plaintext = [offset.to_be_bytes(), expires.to_be_bytes()].concat();
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let sealed = guard
+        .cursor_cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: scope.as_bytes(),
+            },
+        )
+        .ok()?;
+    Some(hex::encode([nonce.as_slice(), &sealed].concat()))
+}
+
+/// The page offset to serve. With opaque cursors on, only a valid cursor
+/// gets past the first page; otherwise the raw offset is used.
+pub fn resolve_offset(
+    state: &AppState,
+    scope: &str,
+    cursor: Option<&str>,
+    offset: Option<i64>,
+) -> Result<i64, &'static str> {
+    let guard = &state.scraping;
+    if !guard.config.opaque_cursors {
+        return Ok(offset.unwrap_or(0).max(0));
+    }
+    if offset.is_some_and(|o| o > 0) {
+        return Err("Use the cursor from the previous page instead of offset");
+    }
+    let Some(cursor) = cursor else {
+        return Ok(0);
+    };
+    let invalid = "Invalid or expired cursor";
+    let raw = hex::decode(cursor).map_err(|_| invalid)?;
+    if raw.len() <= NONCE_LEN {
+        return Err(invalid);
+    }
+    let (nonce, sealed) = raw.split_at(NONCE_LEN);
+    let plaintext = guard
+        .cursor_cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: sealed,
+                aad: scope.as_bytes(),
+            },
+        )
+        .map_err(|_| invalid)?;
+    let (offset, expires) = plaintext.split_at(8);
+    let offset = i64::from_be_bytes(offset.try_into().map_err(|_| invalid)?);
+    let expires = i64::from_be_bytes(expires.try_into().map_err(|_| invalid)?);
+    if expires < chrono::Utc::now().timestamp() {
+        return Err(invalid);
+    }
+    Ok(offset.max(0))
+}
+
+// ============================================================================
+// MIDDLEWARE
+// ============================================================================
+
+impl fmt::Display for ScrapingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScrapingError::Throttled { .. } => write!(f, "Too many requests; slow down"),
+        }
+    }
+}
+
+impl ResponseError for ScrapingError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::TOO_MANY_REQUESTS
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let ScrapingError::Throttled { retry_after } = self;
+        HttpResponse::build(self.status_code())
+            .insert_header((
+                header::RETRY_AFTER,
+                retry_after.as_secs().max(1).to_string(),
+            ))
+            .json(serde_json::json!({ "error": self.to_string() }))
+    }
+}
+
+pub async fn guard(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if !is_listing_read(req.method(), req.path()) || api_keys::context(req.request()).is_some() {
+        return next.call(req).await;
+    }
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return next.call(req).await;
+    };
+    if !state.scraping.config.enabled {
+        return next.call(req).await;
+    }
+    let Some(ip) = geoip::client_ip(req.request()) else {
+        return next.call(req).await;
+    };
+
+    let has_referrer = req.headers().contains_key(header::REFERER);
+    let (verdict, flagged) = state
+        .scraping
+        .check(ip, listing_in_path(req.path()), has_referrer);
+    if let Some(strike) = flagged {
+        info!(
+            "Suspected scraper {} (strike {}, {:?}) on {}",
+            ip,
+            strike.strikes,
+            strike.signals,
+            req.path()
+        );
+        record_suspect(
+            &state.db,
+            Flagged {
+                ip,
+                strike,
+                user_agent: req
+                    .headers()
+                    .get(header::USER_AGENT)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+                path: req.path().to_string(),
+            },
+        );
+    }
+
+    match verdict {
+        Verdict::Allow => {}
+        Verdict::Delay(delay) => tokio::time::sleep(delay).await,
+        Verdict::Block(retry_after) => return Err(ScrapingError::Throttled { retry_after }.into()),
+    }
+    next.call(req).await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/admin/scrapers")]
+pub async fn list_suspects(
+    _admin: AdminUser,
+    query: web::Query<SuspectsQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, Suspect>(
+        r#"SELECT ip, strikes, signals, requests_per_min, user_agent, last_path,
+                  first_flagged_at, last_flagged_at, blocked_until,
+                  COUNT(*) OVER () AS total
+        FROM scraper_suspects
+        WHERE NOT $1 OR blocked_until > NOW()
+        ORDER BY last_flagged_at DESC
+        LIMIT $2 OFFSET $3"#,
+    )
+    .bind(query.active)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(suspects) => HttpResponse::Ok().json(serde_json::json!({
+            "total": suspects.first().map_or(0, |s| s.total),
+            "limit": limit,
+            "offset": offset,
+            "suspects": suspects
+        })),
+        Err(e) => {
+            error!("Failed to list scraper suspects: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list scraper suspects"
+            }))
+        }
+    }
+}
+
+/// Lifts any penalty on the IP and drops it from the list.
+#[delete("/api/admin/scrapers/{ip}")]
+pub async fn clear_suspect(
+    admin: AdminUser,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let Ok(ip) = path.parse::<IpAddr>() else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid IP address"
+        }));
+    };
+    state.scraping.forgive(ip);
+
+    match sqlx::query("DELETE FROM scraper_suspects WHERE ip = $1")
+        .bind(ip.to_string())
+        .execute(&state.db)
+        .await
+    {
+        Ok(_) => {
+            info!("Admin {} cleared scraper suspect {}", admin.id, ip);
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => {
+            error!("Failed to clear scraper suspect {}: {}", ip, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to clear scraper suspect"
+            }))
+        }
+    }
+}
@@ -0,0 +1,666 @@
+// JARVIS2026 - Search query logging, autocomplete and search insights
+// Every search is recorded with its result count; the log powers popular
+// searches, autocomplete and zero-result reports for admins.
+
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::auth::AdminUser;
+use crate::formatting::{Locale, PriceDisplay};
+use crate::geo::Proximity;
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::ranking::Explanation;
+use crate::{AppState, Property};
+
+const DEFAULT_WINDOW_DAYS: i32 = 7;
+const MAX_WINDOW_DAYS: i32 = 90;
+const DEFAULT_LIMIT: i64 = 10;
+const MAX_LIMIT: i64 = 50;
+pub const MIN_LOCATION_QUERY_LEN: i32 = 3;
+
+/// Upper bounds (exclusive) of the rupiah price buckets; the last bucket is open-ended.
+const PRICE_BUCKETS: &[(f64, &str)] = &[
+    (1_000_000_000.0, "under_1b"),
+    (2_000_000_000.0, "1b_2b"),
+    (5_000_000_000.0, "2b_5b"),
+    (10_000_000_000.0, "5b_10b"),
+];
+const PRICE_BUCKET_TOP: &str = "10b_plus";
+const UNKNOWN_FACET: &str = "unknown";
+/// Locations beyond the most common ones are counted under `other`.
+const MAX_LOCATION_FACETS: usize = 20;
+const OTHER_FACET: &str = "other";
+
+const SNIPPET_CONTEXT_CHARS: usize = 60;
+const SNIPPET_MAX_CHARS: usize = 180;
+
+const SUGGEST_LIMIT: i64 = 8;
+const SUGGEST_MAX_QUERY_LEN: usize = 64;
+const SUGGEST_CACHE_TTL: Duration = Duration::from_secs(60);
+const SUGGEST_CACHE_CAPACITY: usize = 2048;
+const SUGGEST_REFRESH_INTERVAL_SECS: u64 = 600;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+struct InsightsQuery {
+    days: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct PopularQuery {
+    query: String,
+    searches: i64,
+    avg_results: f64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct PopularLocation {
+    location: String,
+    searches: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SearchFacets {
+    location: BTreeMap<String, usize>,
+    property_type: BTreeMap<String, usize>,
+    price_bucket: BTreeMap<String, usize>,
+    bedrooms: BTreeMap<String, usize>,
+    certificate_type: BTreeMap<String, usize>,
+}
+
+/// A search result with HTML-safe snippets in which matches are wrapped in `<mark>`.
+#[derive(Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    property: Property,
+    price_display: PriceDisplay,
+    highlights: Highlights,
+    #[serde(flatten)]
+    proximity: Option<Proximity>,
+    /// Present when the search was run with `explain=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ranking: Option<Explanation>,
+}
+
+#[derive(Serialize)]
+struct Highlights {
+    title: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SuggestQuery {
+    q: String,
+}
+
+#[derive(Clone, Serialize, sqlx::FromRow)]
+pub struct Suggestion {
+    kind: String,
+    value: String,
+}
+
+/// Short-lived cache of suggestion lists keyed by normalized prefix.
+pub struct SuggestionCache {
+    entries: Mutex<HashMap<String, (Instant, Vec<Suggestion>)>>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct ZeroResultQuery {
+    query: String,
+    searches: i64,
+    last_searched_at: chrono::DateTime<chrono::Utc>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS search_queries (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            query TEXT NOT NULL,
+            normalized TEXT NOT NULL,
+            result_count INTEGER NOT NULL,
+            searched_at TIMESTAMPTZ DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_search_queries_searched_at ON search_queries(searched_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_search_queries_normalized ON search_queries(normalized)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS search_suggestions (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            normalized TEXT NOT NULL,
+            weight BIGINT NOT NULL DEFAULT 0,
+            UNIQUE (kind, normalized)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_search_suggestions_prefix ON search_suggestions(normalized text_pattern_ops)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_search_suggestions_trgm ON search_suggestions USING GIN (normalized gin_trgm_ops)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// QUERY LOGGING
+// ============================================================================
+
+/// Lowercases and collapses whitespace so "Villa  Bali" and "villa bali" aggregate together.
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Records a search in the background so logging never adds latency to the response.
+pub fn log_search(pool: &PgPool, query: &str, result_count: usize) {
+    let normalized = normalize_query(query);
+    if normalized.is_empty() {
+        return;
+    }
+
+    let pool = pool.clone();
+    let query = query.trim().chars().take(256).collect::<String>();
+    tokio::spawn(async move {
+        let result = sqlx::query(
+            "INSERT INTO search_queries (query, normalized, result_count) VALUES ($1, $2, $3)",
+        )
+        .bind(&query)
+        .bind(&normalized)
+        .bind(result_count as i32)
+        .execute(&pool)
+        .await;
+
+        if let Err(e) = result {
+            warn!("Failed to log search '{}': {}", query, e);
+        }
+    });
+}
+
+// ============================================================================
+// FACETS
+// ============================================================================
+
+fn price_bucket(price: f64) -> &'static str {
+    PRICE_BUCKETS
+        .iter()
+        .find(|(upper, _)| price < *upper)
+        .map(|(_, label)| *label)
+        .unwrap_or(PRICE_BUCKET_TOP)
+}
+
+/// Counts the result set per filter dimension so the UI can label filter chips.
+pub fn compute_facets<'a>(results: impl IntoIterator<Item = &'a Property>) -> SearchFacets {
+    let mut facets = SearchFacets::default();
+    let mut locations: HashMap<String, usize> = HashMap::new();
+    for property in results {
+        let location = property
+            .location
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let location = if location.is_empty() {
+            UNKNOWN_FACET.to_string()
+        } else {
+            location
+        };
+        *locations.entry(location).or_default() += 1;
+
+        let property_type = property.property_type.as_deref().unwrap_or(UNKNOWN_FACET);
+        *facets
+            .property_type
+            .entry(property_type.to_string())
+            .or_default() += 1;
+
+        *facets
+            .price_bucket
+            .entry(price_bucket(property.price).to_string())
+            .or_default() += 1;
+
+        let bedrooms = match property.bedrooms {
+            Some(n) if n >= 5 => "5+".to_string(),
+            Some(n) => n.to_string(),
+            None => UNKNOWN_FACET.to_string(),
+        };
+        *facets.bedrooms.entry(bedrooms).or_default() += 1;
+
+        let certificate = property
+            .certificate_type
+            .as_deref()
+            .unwrap_or(UNKNOWN_FACET);
+        *facets
+            .certificate_type
+            .entry(certificate.to_string())
+            .or_default() += 1;
+    }
+
+    let mut locations: Vec<(String, usize)> = locations.into_iter().collect();
+    locations.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (i, (location, count)) in locations.into_iter().enumerate() {
+        let key = if i < MAX_LOCATION_FACETS {
+            location
+        } else {
+            OTHER_FACET.to_string()
+        };
+        *facets.location.entry(key).or_default() += count;
+    }
+    facets
+}
+
+// ============================================================================
+// HIGHLIGHTING
+// ============================================================================
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Case-insensitive, non-overlapping match positions of any needle, as char ranges.
+fn find_matches(chars: &[char], needles: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    for needle in needles {
+        if needle.is_empty() || needle.len() > chars.len() {
+            continue;
+        }
+        let mut i = 0;
+        while i + needle.len() <= chars.len() {
+            let hit = needle
+                .iter()
+                .enumerate()
+                .all(|(j, n)| chars[i + j].to_lowercase().eq(n.to_lowercase()));
+            if hit {
+                matches.push((i, i + needle.len()));
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    matches.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(matches.len());
+    for (start, end) in matches {
+        match merged.last_mut() {
+            Some(last) if start < last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn mark_range(chars: &[char], from: usize, to: usize, matches: &[(usize, usize)]) -> String {
+    let mut out = String::new();
+    let mut cursor = from;
+    for &(start, end) in matches.iter().filter(|(s, e)| *s >= from && *e <= to) {
+        out.push_str(&escape_html(
+            &chars[cursor..start].iter().collect::<String>(),
+        ));
+        out.push_str("<mark>");
+        out.push_str(&escape_html(&chars[start..end].iter().collect::<String>()));
+        out.push_str("</mark>");
+        cursor = end;
+    }
+    out.push_str(&escape_html(&chars[cursor..to].iter().collect::<String>()));
+    out
+}
+
+/// Returns `None` when the text has no match, so the UI can fall back to the raw field.
+fn highlight(text: &str, needles: &[Vec<char>], snippet: bool) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let matches = find_matches(&chars, needles);
+    let (first_start, _) = *matches.first()?;
+
+    if !snippet || chars.len() <= SNIPPET_MAX_CHARS {
+        return Some(mark_range(&chars, 0, chars.len(), &matches));
+    }
+
+    let from = first_start.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let to = (from + SNIPPET_MAX_CHARS).min(chars.len());
+    let mut out = String::new();
+    if from > 0 {
+        out.push('…');
+    }
+    out.push_str(&mark_range(&chars, from, to, &matches));
+    if to < chars.len() {
+        out.push('…');
+    }
+    Some(out)
+}
+
+pub fn highlight_results(
+    results: Vec<(Property, Option<Proximity>, Option<Explanation>)>,
+    terms: &[String],
+    locale: Locale,
+) -> Vec<SearchHit> {
+    let needles: Vec<Vec<char>> = terms.iter().map(|t| t.chars().collect()).collect();
+    results
+        .into_iter()
+        .map(|(property, proximity, ranking)| {
+            let highlights = Highlights {
+                title: highlight(&property.title, &needles, false),
+                description: highlight(&property.description, &needles, true),
+            };
+            SearchHit {
+                price_display: PriceDisplay::new(property.price, locale),
+                property,
+                highlights,
+                proximity,
+                ranking,
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// AUTOCOMPLETE
+// ============================================================================
+
+impl SuggestionCache {
+    pub fn new() -> Self {
+        SuggestionCache {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Vec<Suggestion>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < SUGGEST_CACHE_TTL)
+            .map(|(_, suggestions)| suggestions.clone())
+    }
+
+    fn insert(&self, key: String, suggestions: Vec<Suggestion>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= SUGGEST_CACHE_CAPACITY {
+            entries.retain(|_, (stored_at, _)| stored_at.elapsed() < SUGGEST_CACHE_TTL);
+            if entries.len() >= SUGGEST_CACHE_CAPACITY {
+                entries.clear();
+            }
+        }
+        entries.insert(key, (Instant::now(), suggestions));
+    }
+}
+
+/// Rebuilds the suggestions table from listings and successful searches.
+async fn refresh_suggestions(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM search_suggestions")
+        .execute(&mut *tx)
+        .await?;
+
+    // Only listings search can return; hidden ones mustn't leak through autocomplete
+    sqlx::query(&format!(
+        r#"INSERT INTO search_suggestions (kind, value, normalized, weight)
+        SELECT 'location', MIN(TRIM(location)), LOWER(TRIM(location)), COUNT(*)
+        FROM properties WHERE TRIM(location) <> '' AND {} AND {}
+        GROUP BY LOWER(TRIM(location))"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    // "Seminyak, Bali" contributes the neighborhood "Seminyak"
+    sqlx::query(&format!(
+        r#"INSERT INTO search_suggestions (kind, value, normalized, weight)
+        SELECT 'neighborhood', MIN(TRIM(split_part(location, ',', 1))),
+            LOWER(TRIM(split_part(location, ',', 1))), COUNT(*)
+        FROM properties
+        WHERE location LIKE '%,%' AND TRIM(split_part(location, ',', 1)) <> ''
+          AND {} AND {}
+        GROUP BY LOWER(TRIM(split_part(location, ',', 1)))"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(&format!(
+        r#"INSERT INTO search_suggestions (kind, value, normalized, weight)
+        SELECT 'title', MIN(TRIM(title)), LOWER(TRIM(title)), COUNT(*)
+        FROM properties WHERE TRIM(title) <> '' AND {} AND {}
+        GROUP BY LOWER(TRIM(title))"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"INSERT INTO search_suggestions (kind, value, normalized, weight)
+        SELECT 'query', normalized, normalized, COUNT(*)
+        FROM search_queries
+        WHERE result_count > 0 AND searched_at >= NOW() - INTERVAL '30 days'
+        GROUP BY normalized
+        HAVING COUNT(*) > 1"#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub fn spawn_suggestion_refresher(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(SUGGEST_REFRESH_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match refresh_suggestions(&pool).await {
+                Ok(()) => info!("Search suggestions refreshed"),
+                Err(e) => error!("Failed to refresh search suggestions: {}", e),
+            }
+        }
+    });
+}
+
+fn window(query: &InsightsQuery) -> (i32, i64) {
+    (
+        query
+            .days
+            .unwrap_or(DEFAULT_WINDOW_DAYS)
+            .clamp(1, MAX_WINDOW_DAYS),
+        query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+    )
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/search/popular")]
+pub async fn popular_searches(
+    query: web::Query<InsightsQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (days, limit) = window(&query);
+
+    let queries = sqlx::query_as::<_, PopularQuery>(
+        r#"SELECT normalized AS query, COUNT(*) AS searches,
+            AVG(result_count)::float8 AS avg_results
+        FROM search_queries
+        WHERE searched_at >= NOW() - make_interval(days => $1)
+        GROUP BY normalized
+        HAVING AVG(result_count) > 0
+        ORDER BY searches DESC, query
+        LIMIT $2"#,
+    )
+    .bind(days)
+    .bind(limit)
+    .fetch_all(&state.db);
+
+    // A search counts toward a location when the query names part of it
+    let locations_sql = format!(
+        r#"SELECT l.location, COUNT(*) AS searches
+        FROM search_queries s
+        JOIN (SELECT DISTINCT location FROM properties WHERE {} AND {}) l
+          ON LOWER(l.location) LIKE '%' || s.normalized || '%'
+        WHERE s.searched_at >= NOW() - make_interval(days => $1)
+          AND LENGTH(s.normalized) >= $3
+        GROUP BY l.location
+        ORDER BY searches DESC, l.location
+        LIMIT $2"#,
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    );
+    let locations = sqlx::query_as::<_, PopularLocation>(&locations_sql)
+        .bind(days)
+        .bind(limit)
+        .bind(MIN_LOCATION_QUERY_LEN)
+        .fetch_all(&state.db);
+
+    match tokio::try_join!(queries, locations) {
+        Ok((queries, locations)) => HttpResponse::Ok().json(serde_json::json!({
+            "days": days,
+            "queries": queries,
+            "locations": locations
+        })),
+        Err(e) => {
+            error!("Failed to fetch popular searches: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch popular searches"
+            }))
+        }
+    }
+}
+
+#[get("/api/search/suggest")]
+pub async fn suggest(
+    query: web::Query<SuggestQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let prefix: String = normalize_query(&query.q)
+        .chars()
+        .take(SUGGEST_MAX_QUERY_LEN)
+        .collect();
+    if prefix.is_empty() {
+        return HttpResponse::Ok().json(Vec::<Suggestion>::new());
+    }
+
+    if let Some(cached) = state.suggest_cache.get(&prefix) {
+        return HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "public, max-age=60"))
+            .json(cached);
+    }
+
+    // Prefix matches rank first; trigram similarity catches typos like "seminyk"
+    let like_prefix = format!(
+        "{}%",
+        prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    match sqlx::query_as::<_, Suggestion>(
+        r#"SELECT kind, value FROM search_suggestions
+        WHERE normalized LIKE $1 OR normalized % $2
+        ORDER BY (normalized LIKE $1) DESC, weight DESC, similarity(normalized, $2) DESC, value
+        LIMIT $3"#,
+    )
+    .bind(&like_prefix)
+    .bind(&prefix)
+    .bind(SUGGEST_LIMIT)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(suggestions) => {
+            state.suggest_cache.insert(prefix, suggestions.clone());
+            HttpResponse::Ok()
+                .insert_header((header::CACHE_CONTROL, "public, max-age=60"))
+                .json(suggestions)
+        }
+        Err(e) => {
+            error!("Suggestion lookup failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch suggestions"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/search/zero-results")]
+pub async fn zero_result_searches(
+    _admin: AdminUser,
+    query: web::Query<InsightsQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (days, limit) = window(&query);
+
+    match sqlx::query_as::<_, ZeroResultQuery>(
+        r#"SELECT normalized AS query, COUNT(*) AS searches, MAX(searched_at) AS last_searched_at
+        FROM search_queries
+        WHERE result_count = 0 AND searched_at >= NOW() - make_interval(days => $1)
+        GROUP BY normalized
+        ORDER BY searches DESC, last_searched_at DESC
+        LIMIT $2"#,
+    )
+    .bind(days)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(queries) => HttpResponse::Ok().json(serde_json::json!({
+            "days": days,
+            "queries": queries
+        })),
+        Err(e) => {
+            error!("Failed to fetch zero-result searches: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch zero-result searches"
+            }))
+        }
+    }
+}
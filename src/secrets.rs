@@ -0,0 +1,375 @@
+// JARVIS2026 - Secrets
+// Credentials (database URL, provider API keys, signing keys) are read
+// through a `SecretsProvider` chosen by `SECRETS_PROVIDER`: the process
+// environment (default), a directory of one-file-per-secret mounts such as
+// Docker or Kubernetes secrets, or a HashiCorp Vault KV v2 path. Everything
+// in `SECRET_NAMES` is loaded once at startup and served by `var`, which
+// falls back to the environment for anything the provider doesn't hold.
+// JWT signing keys are the exception: with `SECRETS_REFRESH_SECS` set they
+// are re-read on that interval, so a new `kid` takes over without a restart.
+
+use futures_util::future::BoxFuture;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env::VarError;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Every secret the application reads through `var`.
+const SECRET_NAMES: &[&str] = &[
+    "DATABASE_URL",
+    JWT_SIGNING_KEY,
+    "LIVE_API_KEY",
+    "LIVE_API_SECRET",
+    "LIVE_WEBHOOK_SECRET",
+    "CAPTCHA_SECRET",
+    "GOOGLE_CLIENT_SECRET",
+    "SMTP_URL",
+    "EMBEDDINGS_API_KEY",
+    "NL_QUERY_LLM_API_KEY",
+    "DESCRIPTION_LLM_API_KEY",
+    "VISION_API_KEY",
+    "CONTACT_RELAY_SECRET",
+    "IMAGE_SIGNING_KEY",
+    "DOCUMENTS_MASTER_KEY",
+    "DOCUMENT_URL_SIGNING_KEY",
+    "SCRAPING_CURSOR_KEY",
+];
+/// `<kid>:<secret>`
+const JWT_SIGNING_KEY: &str = "JWT_SIGNING_KEY";
+/// Older deployments sign with the live provider's key pair, the key name
+/// doubling as `kid`.
+const LEGACY_JWT_KEY_NAMES: [&str; 2] = ["LIVE_API_KEY", "LIVE_API_SECRET"];
+const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+const DEFAULT_VAULT_MOUNT: &str = "secret";
+const DEFAULT_VAULT_PATH: &str = "jarvis2026";
+const VAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static LOADED: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+pub trait SecretsProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The requested secrets the provider holds; absent ones are left out.
+    fn load<'a>(
+        &'a self,
+        names: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<HashMap<String, String>, String>>;
+}
+
+pub struct EnvSecrets;
+
+/// One file per secret, named after it, in `SECRETS_DIR`.
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+/// A KV v2 secret at `VAULT_ADDR`, read with `VAULT_TOKEN`; its keys are
+/// the secret names.
+pub struct VaultSecrets {
+    addr: String,
+    token: String,
+    mount: String,
+    path: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct VaultResponse {
+    data: VaultData,
+}
+
+#[derive(Deserialize)]
+struct VaultData {
+    data: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct SigningKey {
+    kid: String,
+    secret: String,
+}
+
+/// The current HMAC key for JWTs we issue, swappable at runtime.
+pub struct JwtKeys {
+    current: RwLock<Option<SigningKey>>,
+}
+
+// ============================================================================
+// PROVIDERS
+// ============================================================================
+
+impl SecretsProvider for EnvSecrets {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    fn load<'a>(
+        &'a self,
+        names: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<HashMap<String, String>, String>> {
+        Box::pin(async move {
+            Ok(names
+                .iter()
+                .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)))
+                .collect())
+        })
+    }
+}
+
+impl SecretsProvider for FileSecrets {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn load<'a>(
+        &'a self,
+        names: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<HashMap<String, String>, String>> {
+        Box::pin(async move {
+            let mut found = HashMap::new();
+            for name in names {
+                let path = self.dir.join(name);
+                match tokio::fs::read_to_string(&path).await {
+                    Ok(value) => {
+                        found.insert(name.to_string(), value.trim_end().to_string());
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+                }
+            }
+            Ok(found)
+        })
+    }
+}
+
+impl SecretsProvider for VaultSecrets {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    fn load<'a>(
+        &'a self,
+        names: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<HashMap<String, String>, String>> {
+        Box::pin(async move {
+            let url = format!("{}/v1/{}/data/{}", self.addr, self.mount, self.path);
+            let response = self
+                .client
+                .get(&url)
+                .header("X-Vault-Token", &self.token)
+                .send()
+                .await
+                .map_err(|e| format!("Vault request failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("Vault returned {} for {}", response.status(), url));
+            }
+            let body: VaultResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Invalid Vault response: {}", e))?;
+            Ok(names
+                .iter()
+                .filter_map(|name| {
+                    let value = match body.data.data.get(*name)? {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    Some((name.to_string(), value))
+                })
+                .collect())
+        })
+    }
+}
+
+/// `SECRETS_PROVIDER` = `env` (default), `file` (`SECRETS_DIR`, default
+/// `/run/secrets`) or `vault` (`VAULT_ADDR`, `VAULT_TOKEN`, optionally
+/// `VAULT_KV_MOUNT` and `VAULT_SECRET_PATH`). The provider's own settings
+/// always come from the environment.
+pub fn provider_from_env() -> Box<dyn SecretsProvider> {
+    match std::env::var("SECRETS_PROVIDER").as_deref() {
+        Err(_) | Ok("env") => Box::new(EnvSecrets),
+        Ok("file") => {
+            let dir = std::env::var("SECRETS_DIR").unwrap_or_else(|_| DEFAULT_SECRETS_DIR.into());
+            info!("Reading secrets from files in {}", dir);
+            Box::new(FileSecrets { dir: dir.into() })
+        }
+        Ok("vault") => {
+            let (Ok(addr), Ok(token)) = (std::env::var("VAULT_ADDR"), std::env::var("VAULT_TOKEN"))
+            else {
+                warn!("VAULT_ADDR/VAULT_TOKEN not set; reading secrets from the environment");
+                return Box::new(EnvSecrets);
+            };
+            let client = match reqwest::Client::builder().timeout(VAULT_TIMEOUT).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!(
+                        "Failed to build Vault client: {}; reading secrets from the environment",
+                        e
+                    );
+                    return Box::new(EnvSecrets);
+                }
+            };
+            let vault = VaultSecrets {
+                addr: addr.trim_end_matches('/').to_string(),
+                token,
+                mount: std::env::var("VAULT_KV_MOUNT")
+                    .unwrap_or_else(|_| DEFAULT_VAULT_MOUNT.into()),
+                path: std::env::var("VAULT_SECRET_PATH")
+                    .unwrap_or_else(|_| DEFAULT_VAULT_PATH.into()),
+                client,
+            };
+            info!(
+                "Reading secrets from Vault {} at {}/{}",
+                vault.addr, vault.mount, vault.path
+            );
+            Box::new(vault)
+        }
+        Ok(other) => {
+            warn!(
+                "Unknown SECRETS_PROVIDER '{}'; reading secrets from the environment",
+                other
+            );
+            Box::new(EnvSecrets)
+        }
+    }
+}
+
+// ============================================================================
+// LOOKUP
+// ============================================================================
+
+/// Reads `SECRET_NAMES` from the provider. Call once, before anything reads
+/// a secret.
+pub async fn load(provider: &dyn SecretsProvider) -> Result<(), String> {
+    let loaded = provider.load(SECRET_NAMES).await?;
+    info!(
+        "Loaded {} of {} secrets from {}",
+        loaded.len(),
+        SECRET_NAMES.len(),
+        provider.name()
+    );
+    LOADED
+        .set(loaded)
+        .map_err(|_| "Secrets were already loaded".to_string())
+}
+
+/// Drop-in for `std::env::var` on secrets: the provider's value, else the
+/// environment's.
+pub fn var(name: &str) -> Result<String, VarError> {
+    match LOADED.get().and_then(|loaded| loaded.get(name)) {
+        Some(value) => Ok(value.clone()),
+        None => std::env::var(name),
+    }
+}
+
+// ============================================================================
+// JWT SIGNING KEYS
+// ============================================================================
+
+impl SigningKey {
+    fn from_secrets(lookup: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        if let Some(raw) = lookup(JWT_SIGNING_KEY) {
+            let Some((kid, secret)) = raw
+                .split_once(':')
+                .filter(|(k, s)| !k.is_empty() && !s.is_empty())
+            else {
+                warn!(
+                    "{} must look like <kid>:<secret>; ignoring it",
+                    JWT_SIGNING_KEY
+                );
+                return None;
+            };
+            return Some(SigningKey {
+                kid: kid.to_string(),
+                secret: secret.to_string(),
+            });
+        }
+        let [kid_name, secret_name] = LEGACY_JWT_KEY_NAMES;
+        Some(SigningKey {
+            kid: lookup(kid_name)?,
+            secret: lookup(secret_name)?,
+        })
+    }
+}
+
+impl JwtKeys {
+    pub fn from_secrets() -> Self {
+        JwtKeys {
+            current: RwLock::new(SigningKey::from_secrets(|name| var(name).ok())),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.current.read().is_ok_and(|key| key.is_some())
+    }
+
+    /// HS256 with the current key, its id in the header; `claims` receives
+    /// the same id for issuers that expect it as `iss`.
+    pub fn sign<T: Serialize>(&self, claims: impl FnOnce(&str) -> T) -> Result<String, String> {
+        let key = self
+            .current
+            .read()
+            .map_err(|_| "JWT key lock poisoned".to_string())?
+            .clone()
+            .ok_or("No JWT signing key configured")?;
+        let header = Header {
+            kid: Some(key.kid.clone()),
+            ..Header::default()
+        };
+        encode(
+            &header,
+            &claims(&key.kid),
+            &EncodingKey::from_secret(key.secret.as_bytes()),
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    async fn reload(&self, provider: &dyn SecretsProvider) -> Result<(), String> {
+        let mut names = vec![JWT_SIGNING_KEY];
+        names.extend(LEGACY_JWT_KEY_NAMES);
+        let found = provider.load(&names).await?;
+        let Some(key) = SigningKey::from_secrets(|name| found.get(name).cloned()) else {
+            // Keep signing with what we have rather than stop issuing tokens
+            return Ok(());
+        };
+        let mut current = self
+            .current
+            .write()
+            .map_err(|_| "JWT key lock poisoned".to_string())?;
+        if current.as_ref() != Some(&key) {
+            info!("JWT signing key rotated to kid {}", key.kid);
+            *current = Some(key);
+        }
+        Ok(())
+    }
+}
+
+/// Re-reads the JWT signing key every `SECRETS_REFRESH_SECS`; off when unset.
+pub fn spawn_rotation(provider: Arc<dyn SecretsProvider>, keys: Arc<JwtKeys>) {
+    let Some(every) = std::env::var("SECRETS_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+    else {
+        return;
+    };
+    info!("Checking for JWT key rotation every {}s", every);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(every));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = keys.reload(provider.as_ref()).await {
+                warn!("Failed to refresh JWT signing key: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,428 @@
+// JARVIS2026 - Login sessions
+// A sign-in starts a session holding two opaque tokens: a short-lived access
+// token sent as `Authorization: Bearer <token>` (resolved by
+// `auth::CurrentUser` through `user_for_token`) and a longer-lived refresh
+// token that is exchanged for a fresh pair at `/api/auth/refresh`. Only
+// SHA-256 hashes of tokens are stored. Refresh tokens rotate on every use;
+// presenting one that was already rotated away revokes the whole session,
+// since it means the token was copied.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{PgExecutor, PgPool};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::{self, AdminUser, CurrentUser};
+use crate::AppState;
+
+const ACCESS_TTL_MINUTES: i64 = 60;
+const SESSION_TTL_DAYS: i64 = 30;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+pub struct IssuedSession {
+    pub session_id: Uuid,
+    /// Access token
+    pub token: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub refresh_token: String,
+    pub refresh_expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct SessionInfo {
+    id: Uuid,
+    method: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_refreshed_at: Option<chrono::DateTime<chrono::Utc>>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    current: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+/// How a presented refresh token relates to its session.
+#[derive(sqlx::FromRow)]
+struct RefreshMatch {
+    id: Uuid,
+    user_id: Uuid,
+    current: bool,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS sessions (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            token_hash TEXT UNIQUE NOT NULL,
+            method TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL,
+            revoked_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // `expires_at` bounds the whole session; the access token expires sooner.
+    // Sessions from before refresh tokens have neither column and simply
+    // run out at `expires_at`
+    for column in [
+        "access_expires_at TIMESTAMPTZ",
+        "refresh_hash TEXT UNIQUE",
+        "previous_refresh_hash TEXT",
+        "last_refreshed_at TIMESTAMPTZ",
+    ] {
+        sqlx::query(&format!(
+            "ALTER TABLE sessions ADD COLUMN IF NOT EXISTS {}",
+            column
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_sessions_previous_refresh ON sessions(previous_refresh_hash)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// TOKENS
+// ============================================================================
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn new_token() -> String {
+    // Two v4 UUIDs give 244 random bits
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Starts a session for `user_id`; `method` records how the user signed in.
+pub async fn issue(
+    pool: &PgPool,
+    user_id: Uuid,
+    method: &str,
+) -> Result<IssuedSession, sqlx::Error> {
+    let token = new_token();
+    let refresh_token = new_token();
+    let now = chrono::Utc::now();
+    let expires_at = now + chrono::Duration::minutes(ACCESS_TTL_MINUTES);
+    let refresh_expires_at = now + chrono::Duration::days(SESSION_TTL_DAYS);
+
+    let session_id = sqlx::query_scalar::<_, Uuid>(
+        r#"INSERT INTO sessions
+        (user_id, token_hash, method, access_expires_at, refresh_hash, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id"#,
+    )
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(method)
+    .bind(expires_at)
+    .bind(hash_token(&refresh_token))
+    .bind(refresh_expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(IssuedSession {
+        session_id,
+        token,
+        expires_at,
+        refresh_token,
+        refresh_expires_at,
+    })
+}
+
+/// The user behind a live (unexpired, unrevoked) access token.
+pub async fn user_for_token(pool: &PgPool, token: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    Ok(sign_in_for_token(pool, token)
+        .await?
+        .map(|(user_id, _)| user_id))
+}
+
+/// The user behind a live access token and when they signed in to start
+/// its session; refreshes rotate tokens but keep the session.
+pub async fn sign_in_for_token(
+    pool: &PgPool,
+    token: &str,
+) -> Result<Option<(Uuid, chrono::DateTime<chrono::Utc>)>, sqlx::Error> {
+    sqlx::query_as::<_, (Uuid, chrono::DateTime<chrono::Utc>)>(
+        r#"SELECT user_id, created_at FROM sessions
+        WHERE token_hash = $1 AND revoked_at IS NULL
+          AND COALESCE(access_expires_at, expires_at) > NOW()
+          AND expires_at > NOW()"#,
+    )
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Revokes every live session of a user. Returns how many were revoked.
+pub async fn revoke_all<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(executor)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+/// Swaps a refresh token for a new token pair. `Ok(None)` when the token is
+/// unknown, expired or revoked; replaying an already-rotated token also
+/// revokes its session.
+async fn rotate(pool: &PgPool, refresh_token: &str) -> Result<Option<IssuedSession>, sqlx::Error> {
+    let presented = hash_token(refresh_token);
+    let mut tx = pool.begin().await?;
+
+    let found = sqlx::query_as::<_, RefreshMatch>(
+        r#"SELECT id, user_id, refresh_hash = $1 AS current FROM sessions
+        WHERE (refresh_hash = $1 OR previous_refresh_hash = $1)
+          AND revoked_at IS NULL AND expires_at > NOW()
+        FOR UPDATE"#,
+    )
+    .bind(&presented)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(session) = found else {
+        return Ok(None);
+    };
+
+    if !session.current {
+        sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1")
+            .bind(session.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        warn!(
+            "Refresh token reuse on session {} (user {}); session revoked",
+            session.id, session.user_id
+        );
+        return Ok(None);
+    }
+
+    let token = new_token();
+    let refresh_token = new_token();
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(ACCESS_TTL_MINUTES);
+
+    let refresh_expires_at = sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
+        r#"UPDATE sessions
+        SET token_hash = $2, access_expires_at = LEAST($3, expires_at),
+            previous_refresh_hash = refresh_hash, refresh_hash = $4, last_refreshed_at = NOW()
+        WHERE id = $1
+        RETURNING expires_at"#,
+    )
+    .bind(session.id)
+    .bind(hash_token(&token))
+    .bind(expires_at)
+    .bind(hash_token(&refresh_token))
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(IssuedSession {
+        session_id: session.id,
+        token,
+        expires_at: expires_at.min(refresh_expires_at),
+        refresh_token,
+        refresh_expires_at,
+    }))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/auth/refresh")]
+pub async fn refresh_session(
+    req: web::Json<RefreshRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match rotate(&state.db, req.refresh_token.trim()).await {
+        Ok(Some(session)) => HttpResponse::Ok().json(session),
+        Ok(None) => HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Refresh token is invalid or expired"
+        })),
+        Err(e) => {
+            error!("Failed to refresh session: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to refresh session"
+            }))
+        }
+    }
+}
+
+/// Ends the session whose access token authenticates this request.
+#[post("/api/auth/logout")]
+pub async fn logout(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    let Some(token) = auth::bearer_token(&req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Authentication required"
+        }));
+    };
+
+    match sqlx::query(
+        "UPDATE sessions SET revoked_at = NOW() WHERE token_hash = $1 AND revoked_at IS NULL",
+    )
+    .bind(hash_token(&token))
+    .execute(&state.db)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Session not found"
+            }))
+        }
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({ "logged_out": true })),
+        Err(e) => {
+            error!("Failed to log out: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to log out"
+            }))
+        }
+    }
+}
+
+#[post("/api/auth/logout-all")]
+pub async fn logout_all(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    match revoke_all(&state.db, user.id).await {
+        Ok(revoked) => {
+            info!("User {} revoked {} sessions", user.id, revoked);
+            HttpResponse::Ok().json(serde_json::json!({ "revoked": revoked }))
+        }
+        Err(e) => {
+            error!("Failed to revoke sessions for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to revoke sessions"
+            }))
+        }
+    }
+}
+
+#[get("/api/users/me/sessions")]
+pub async fn my_sessions(
+    req: HttpRequest,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let current_hash = auth::bearer_token(&req).map(|token| hash_token(&token));
+
+    match sqlx::query_as::<_, SessionInfo>(
+        r#"SELECT id, method, created_at, last_refreshed_at, expires_at,
+            token_hash IS NOT DISTINCT FROM $2 AS current
+        FROM sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY COALESCE(last_refreshed_at, created_at) DESC"#,
+    )
+    .bind(user.id)
+    .bind(current_hash)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(e) => {
+            error!("Failed to list sessions for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load sessions"
+            }))
+        }
+    }
+}
+
+/// Signs a user out everywhere, e.g. after an account compromise.
+#[post("/api/admin/users/{id}/sessions/revoke")]
+pub async fn admin_revoke_sessions(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let user_id = path.into_inner();
+
+    match revoke_all(&state.db, user_id).await {
+        Ok(revoked) => {
+            info!(
+                "Admin {} revoked {} sessions of user {}",
+                admin.id, revoked, user_id
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "user_id": user_id,
+                "revoked": revoked
+            }))
+        }
+        Err(e) => {
+            error!("Failed to revoke sessions for {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to revoke sessions"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn revoked_and_logged_out_tokens_stop_authenticating() {
+        let Some(pool) = crate::test_pool().await else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        init_schema(&pool).await.unwrap();
+        let user_id: Uuid =
+            sqlx::query_scalar("INSERT INTO users (username) VALUES ($1) RETURNING id")
+                .bind(format!("session-{}", Uuid::new_v4()))
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let first = issue(&pool, user_id, "password").await.unwrap();
+        let second = issue(&pool, user_id, "password").await.unwrap();
+        assert_eq!(
+            user_for_token(&pool, &first.token).await.unwrap(),
+            Some(user_id)
+        );
+
+        sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE token_hash = $1")
+            .bind(hash_token(&first.token))
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(user_for_token(&pool, &first.token).await.unwrap(), None);
+        assert_eq!(
+            user_for_token(&pool, &second.token).await.unwrap(),
+            Some(user_id)
+        );
+
+        assert_eq!(revoke_all(&pool, user_id).await.unwrap(), 1);
+        assert_eq!(user_for_token(&pool, &second.token).await.unwrap(), None);
+        assert!(rotate(&pool, &second.refresh_token)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
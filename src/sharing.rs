@@ -0,0 +1,820 @@
+// JARVIS2026 - Listing sharing: short links with click tracking, QR codes for
+// brochures and yard signs, Open Graph preview images for WhatsApp/Facebook
+
+use ab_glyph::{FontVec, PxScale};
+use actix_web::{get, http::header, post, web, HttpRequest, HttpResponse, Responder};
+use image::{imageops, ImageFormat, Luma, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use qrcode::{EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json, PgPool};
+use std::io::Cursor;
+use tokio::fs as async_fs;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::agencies::{self, Branding};
+use crate::auth::CurrentUser;
+use crate::formatting::{format_price, Locale};
+use crate::listing_status::PUBLISHED_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::search::escape_html;
+use crate::AppState;
+
+const SHORT_CODE_LEN: usize = 7;
+const SHORT_CODE_ALPHABET: &[u8] = b"23456789abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+const QR_CHANNEL: &str = "qr";
+const DEFAULT_CHANNEL: &str = "direct";
+
+const DEFAULT_QR_SIZE: u32 = 512;
+const MIN_QR_SIZE: u32 = 128;
+const MAX_QR_SIZE: u32 = 2048;
+
+const OG_WIDTH: u32 = 1200;
+const OG_HEIGHT: u32 = 630;
+const OG_CACHE_DIR: &str = "og-cache";
+const OG_TITLE_MAX_CHARS: usize = 48;
+const OG_LOGO_MAX_WIDTH: u32 = 240;
+const OG_LOGO_MAX_HEIGHT: u32 = 96;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ShortLink {
+    id: Uuid,
+    code: String,
+    property_id: Uuid,
+    channel: String,
+    click_count: i64,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize)]
+struct ShortLinkResponse {
+    code: String,
+    url: String,
+    channel: String,
+    click_count: i64,
+}
+
+#[derive(Deserialize, Default)]
+struct CreateShortLinkRequest {
+    channel: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReferrerCount {
+    referrer: String,
+    clicks: i64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct ShortLinkStats {
+    code: String,
+    #[sqlx(skip)]
+    url: String,
+    channel: String,
+    click_count: i64,
+    last_clicked_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Top referrers, `direct` when the click had none
+    referrers: Json<Vec<ReferrerCount>>,
+}
+
+#[derive(Deserialize)]
+struct QrQuery {
+    size: Option<u32>,
+    #[serde(default)]
+    logo: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct OgListing {
+    title: String,
+    location: String,
+    price: f64,
+    cover_path: Option<String>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS short_links (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            code TEXT UNIQUE NOT NULL,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            channel TEXT NOT NULL,
+            click_count BIGINT NOT NULL DEFAULT 0,
+            created_at TIMESTAMPTZ DEFAULT NOW(),
+            UNIQUE (property_id, channel)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS short_link_clicks (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            short_link_id UUID NOT NULL REFERENCES short_links(id) ON DELETE CASCADE,
+            referrer TEXT,
+            user_agent TEXT,
+            clicked_at TIMESTAMPTZ DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_short_link_clicks_link ON short_link_clicks(short_link_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
+async fn property_exists(pool: &PgPool, property_id: Uuid) -> Result<bool, sqlx::Error> {
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM properties WHERE id = $1")
+        .bind(property_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// The web app opens the listing from `?property=`; it has no other routes.
+pub fn listing_url(base_url: &str, property_id: Uuid) -> String {
+    format!("{}/?property={}", base_url, property_id)
+}
+
+fn short_url(base_url: &str, code: &str) -> String {
+    format!("{}/p/{}", base_url, code)
+}
+
+pub fn generate_short_code() -> String {
+    Uuid::new_v4()
+        .as_bytes()
+        .iter()
+        .take(SHORT_CODE_LEN)
+        .map(|b| SHORT_CODE_ALPHABET[*b as usize % SHORT_CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+fn normalize_channel(raw: Option<&str>) -> Option<String> {
+    let channel = raw.unwrap_or(DEFAULT_CHANNEL).trim().to_lowercase();
+    let valid = !channel.is_empty()
+        && channel.len() <= 32
+        && channel
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(channel)
+}
+
+/// One link per (property, channel) so every share on a channel counts toward
+/// the same stats. Retries on the rare short-code collision.
+async fn get_or_create_short_link(
+    pool: &PgPool,
+    property_id: Uuid,
+    channel: &str,
+) -> Result<ShortLink, sqlx::Error> {
+    if let Some(link) = sqlx::query_as::<_, ShortLink>(
+        "SELECT * FROM short_links WHERE property_id = $1 AND channel = $2",
+    )
+    .bind(property_id)
+    .bind(channel)
+    .fetch_optional(pool)
+    .await?
+    {
+        return Ok(link);
+    }
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = sqlx::query_as::<_, ShortLink>(
+            r#"INSERT INTO short_links (code, property_id, channel) VALUES ($1, $2, $3)
+            ON CONFLICT (property_id, channel) DO UPDATE SET channel = EXCLUDED.channel
+            RETURNING *"#,
+        )
+        .bind(generate_short_code())
+        .bind(property_id)
+        .bind(channel)
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() && attempts < 5 => continue,
+            other => return other,
+        }
+    }
+}
+
+/// Pastes the logo over the center of the code on a white plate. The code is
+/// rendered with the highest error correction level so it still scans.
+fn overlay_logo(canvas: &mut RgbaImage, logo_path: &str) -> anyhow::Result<()> {
+    let logo = image::open(logo_path)?.to_rgba8();
+    let target = canvas.width() / 5;
+    let logo = imageops::resize(&logo, target, target, imageops::FilterType::Lanczos3);
+
+    let padding = target / 10;
+    let plate_size = target + padding * 2;
+    let plate = RgbaImage::from_pixel(plate_size, plate_size, Rgba([255, 255, 255, 255]));
+
+    let plate_offset = ((canvas.width() - plate_size) / 2) as i64;
+    imageops::overlay(canvas, &plate, plate_offset, plate_offset);
+    let logo_offset = ((canvas.width() - target) / 2) as i64;
+    imageops::overlay(canvas, &logo, logo_offset, logo_offset);
+    Ok(())
+}
+
+pub fn render_qr_png(data: &str, size: u32, logo_path: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    let level = if logo_path.is_some() {
+        EcLevel::H
+    } else {
+        EcLevel::M
+    };
+    let code = QrCode::with_error_correction_level(data.as_bytes(), level)?;
+    let rendered = code
+        .render::<Luma<u8>>()
+        .min_dimensions(size, size)
+        .quiet_zone(true)
+        .build();
+
+    let mut canvas = image::DynamicImage::ImageLuma8(rendered).to_rgba8();
+    if let Some(path) = logo_path {
+        if let Err(e) = overlay_logo(&mut canvas, path) {
+            warn!("QR logo overlay skipped ({}): {}", path, e);
+        }
+    }
+
+    let mut png = Vec::new();
+    canvas.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max - 1).collect();
+    out.push('…');
+    out
+}
+
+/// Cache key changes whenever anything drawn on the image changes, so edits
+/// to the listing never serve a stale preview.
+fn og_cache_path(property_id: Uuid, listing: &OgListing, branding: Option<&Branding>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(listing.title.as_bytes());
+    hasher.update(listing.location.as_bytes());
+    hasher.update(listing.price.to_le_bytes());
+    hasher.update(listing.cover_path.as_deref().unwrap_or("").as_bytes());
+    if let Some(updated_at) = branding.and_then(|b| b.updated_at) {
+        hasher.update(updated_at.timestamp_micros().to_le_bytes());
+    }
+    let digest = hex::encode(hasher.finalize());
+    format!("{}/{}-{}.png", OG_CACHE_DIR, property_id, &digest[..12])
+}
+
+/// Pastes the agency logo into the top right corner on a white plate.
+fn overlay_og_logo(canvas: &mut RgbaImage, logo_path: &str) -> anyhow::Result<()> {
+    let logo = image::open(logo_path)?;
+    let logo = logo
+        .resize(
+            OG_LOGO_MAX_WIDTH,
+            OG_LOGO_MAX_HEIGHT,
+            imageops::FilterType::Lanczos3,
+        )
+        .to_rgba8();
+
+    let padding = 12;
+    let plate = RgbaImage::from_pixel(
+        logo.width() + padding * 2,
+        logo.height() + padding * 2,
+        Rgba([255, 255, 255, 255]),
+    );
+    let margin = 32;
+    let plate_x = (OG_WIDTH - margin - plate.width()) as i64;
+    imageops::overlay(canvas, &plate, plate_x, margin as i64);
+    imageops::overlay(
+        canvas,
+        &logo,
+        plate_x + padding as i64,
+        (margin + padding) as i64,
+    );
+    Ok(())
+}
+
+fn render_og_png(
+    listing: &OgListing,
+    branding: Option<&Branding>,
+    font_path: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let color = |pick: fn(&Branding) -> Option<&String>, fallback: Rgba<u8>| {
+        branding
+            .and_then(pick)
+            .and_then(|c| agencies::parse_color(c))
+            .unwrap_or(fallback)
+    };
+    let background = color(|b| b.primary_color.as_ref(), Rgba([24, 32, 56, 255]));
+    let accent = color(|b| b.accent_color.as_ref(), Rgba([255, 196, 0, 255]));
+
+    let mut canvas = match listing.cover_path.as_deref().map(image::open).transpose() {
+        Ok(Some(cover)) => cover
+            .resize_to_fill(OG_WIDTH, OG_HEIGHT, imageops::FilterType::Lanczos3)
+            .to_rgba8(),
+        Ok(None) => RgbaImage::from_pixel(OG_WIDTH, OG_HEIGHT, background),
+        Err(e) => {
+            warn!("OG cover unreadable, using plain background: {}", e);
+            RgbaImage::from_pixel(OG_WIDTH, OG_HEIGHT, background)
+        }
+    };
+
+    // Darken the lower band so the overlay text stays legible on bright photos
+    let band_top = OG_HEIGHT * 3 / 5;
+    for y in band_top..OG_HEIGHT {
+        let strength = 0.35 + 0.45 * (y - band_top) as f32 / (OG_HEIGHT - band_top) as f32;
+        for x in 0..OG_WIDTH {
+            let pixel = canvas.get_pixel_mut(x, y);
+            for channel in 0..3 {
+                pixel[channel] = (pixel[channel] as f32 * (1.0 - strength)) as u8;
+            }
+        }
+    }
+
+    let font = FontVec::try_from_vec(std::fs::read(font_path)?)?;
+    let white = Rgba([255, 255, 255, 255]);
+    let margin = 48;
+
+    let price = format_price(listing.price, Locale::Indonesian);
+    let price_scale = PxScale::from(72.0);
+    let (_, price_height) = text_size(price_scale, &font, &price);
+    let price_y = OG_HEIGHT as i32 - margin - price_height as i32;
+    draw_text_mut(
+        &mut canvas,
+        accent,
+        margin,
+        price_y,
+        price_scale,
+        &font,
+        &price,
+    );
+
+    let title = truncate_chars(&listing.title, OG_TITLE_MAX_CHARS);
+    let title_scale = PxScale::from(48.0);
+    let (_, title_height) = text_size(title_scale, &font, &title);
+    let title_y = price_y - 24 - title_height as i32;
+    draw_text_mut(
+        &mut canvas,
+        white,
+        margin,
+        title_y,
+        title_scale,
+        &font,
+        &title,
+    );
+
+    let location = truncate_chars(&listing.location, OG_TITLE_MAX_CHARS + 16);
+    let location_scale = PxScale::from(30.0);
+    let (_, location_height) = text_size(location_scale, &font, &location);
+    let location_y = title_y - 16 - location_height as i32;
+    draw_text_mut(
+        &mut canvas,
+        white,
+        margin,
+        location_y,
+        location_scale,
+        &font,
+        &location,
+    );
+
+    if let Some(logo_path) = branding.and_then(|b| b.logo_path.as_deref()) {
+        if let Err(e) = overlay_og_logo(&mut canvas, logo_path) {
+            warn!("OG logo overlay skipped ({}): {}", logo_path, e);
+        }
+    }
+
+    let mut png = Vec::new();
+    canvas.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/{property_id}/qr.png")]
+pub async fn property_qr_code(
+    path: web::Path<Uuid>,
+    query: web::Query<QrQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    match property_exists(&state.db, property_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }));
+        }
+    }
+
+    let link = match get_or_create_short_link(&state.db, property_id, QR_CHANNEL).await {
+        Ok(link) => link,
+        Err(e) => {
+            error!("Failed to create QR short link for {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }));
+        }
+    };
+
+    let size = query
+        .size
+        .unwrap_or(DEFAULT_QR_SIZE)
+        .clamp(MIN_QR_SIZE, MAX_QR_SIZE);
+    // The listing agency's logo when it has one, otherwise the site logo
+    let logo_path = if query.logo {
+        let agency_logo = match agencies::for_property(&state.db, property_id).await {
+            Ok(branding) => branding.and_then(|b| b.logo_path),
+            Err(e) => {
+                warn!("Failed to load branding for {}: {}", property_id, e);
+                None
+            }
+        };
+        Some(agency_logo.unwrap_or_else(|| {
+            std::env::var("QR_LOGO_PATH").unwrap_or_else(|_| "static/logo.png".to_string())
+        }))
+    } else {
+        None
+    };
+    let url = short_url(&state.public_base_url, &link.code);
+
+    match web::block(move || render_qr_png(&url, size, logo_path.as_deref())).await {
+        Ok(Ok(png)) => HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header((header::CACHE_CONTROL, "public, max-age=86400"))
+            .body(png),
+        Ok(Err(e)) => {
+            error!("QR rendering failed for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }))
+        }
+        Err(e) => {
+            error!("QR rendering task failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }))
+        }
+    }
+}
+
+#[get("/api/properties/{property_id}/og.png")]
+pub async fn property_og_image(
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    // Hidden listings and hidden photos never reach a preview
+    let listing = match sqlx::query_as::<_, OgListing>(&format!(
+        r#"SELECT p.title, p.location, p.price,
+            (SELECT m.file_path FROM media_uploads m
+             WHERE m.property_id = p.id AND m.file_type = 'image' AND m.{public}
+             ORDER BY m.uploaded_at LIMIT 1) AS cover_path
+        FROM properties p WHERE p.id = $1 AND p.{public} AND p.{published}"#,
+        public = PUBLIC_LISTING_CONDITION,
+        published = PUBLISHED_LISTING_CONDITION
+    ))
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(listing)) => listing,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!(
+                "Failed to load property {} for OG image: {}",
+                property_id, e
+            );
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate preview image"
+            }));
+        }
+    };
+
+    let branding = match agencies::for_property(&state.db, property_id).await {
+        Ok(branding) => branding,
+        Err(e) => {
+            warn!("Failed to load branding for {}: {}", property_id, e);
+            None
+        }
+    };
+
+    let cache_path = og_cache_path(property_id, &listing, branding.as_ref());
+    if let Ok(png) = async_fs::read(&cache_path).await {
+        return HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header((header::CACHE_CONTROL, "public, max-age=3600"))
+            .body(png);
+    }
+
+    let font_path = std::env::var("OG_FONT_PATH")
+        .unwrap_or_else(|_| "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf".to_string());
+
+    let png = match web::block(move || render_og_png(&listing, branding.as_ref(), &font_path)).await
+    {
+        Ok(Ok(png)) => png,
+        Ok(Err(e)) => {
+            error!("OG rendering failed for {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate preview image"
+            }));
+        }
+        Err(e) => {
+            error!("OG rendering task failed: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate preview image"
+            }));
+        }
+    };
+
+    async_fs::create_dir_all(OG_CACHE_DIR).await.ok();
+    if let Err(e) = async_fs::write(&cache_path, &png).await {
+        warn!("Failed to cache OG image {}: {}", cache_path, e);
+    } else {
+        info!("OG image rendered for {}", property_id);
+    }
+
+    HttpResponse::Ok()
+        .content_type("image/png")
+        .insert_header((header::CACHE_CONTROL, "public, max-age=3600"))
+        .body(png)
+}
+
+#[post("/api/properties/{property_id}/short-link")]
+pub async fn create_short_link(
+    path: web::Path<Uuid>,
+    body: Option<web::Json<CreateShortLinkRequest>>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+
+    let Some(channel) = normalize_channel(body.channel.as_deref()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "channel must be 1-32 letters, digits, '-' or '_'"
+        }));
+    };
+
+    match property_exists(&state.db, property_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create short link"
+            }));
+        }
+    }
+
+    match get_or_create_short_link(&state.db, property_id, &channel).await {
+        Ok(link) => HttpResponse::Ok().json(ShortLinkResponse {
+            url: short_url(&state.public_base_url, &link.code),
+            code: link.code,
+            channel: link.channel,
+            click_count: link.click_count,
+        }),
+        Err(e) => {
+            error!("Failed to create short link for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create short link"
+            }))
+        }
+    }
+}
+
+#[get("/p/{code}")]
+pub async fn follow_short_link(
+    req: HttpRequest,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let code = path.into_inner();
+
+    let link = match sqlx::query_as::<_, (Uuid, Uuid)>(
+        "UPDATE short_links SET click_count = click_count + 1 WHERE code = $1 RETURNING id, property_id",
+    )
+    .bind(&code)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(link)) => link,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Link not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to resolve short link {}: {}", code, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to resolve link"
+            }));
+        }
+    };
+    let (link_id, property_id) = link;
+
+    let header_value = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(512).collect::<String>())
+    };
+
+    sqlx::query(
+        "INSERT INTO short_link_clicks (short_link_id, referrer, user_agent) VALUES ($1, $2, $3)",
+    )
+    .bind(link_id)
+    .bind(header_value(header::REFERER))
+    .bind(header_value(header::USER_AGENT))
+    .execute(&state.db)
+    .await
+    .ok();
+
+    let target = listing_url(&state.public_base_url, property_id);
+    let preview = sqlx::query_as::<_, (String, String, f64)>(&format!(
+        "SELECT title, location, price FROM properties WHERE id = $1 AND {} AND {}",
+        PUBLIC_LISTING_CONDITION, PUBLISHED_LISTING_CONDITION
+    ))
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await;
+
+    match preview {
+        Ok(Some((title, location, price))) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(share_page_html(
+                &state.public_base_url,
+                property_id,
+                &target,
+                &title,
+                &format!("{} · {}", format_price(price, Locale::Indonesian), location),
+            )),
+        // Nothing to preview; send the visitor straight on
+        Ok(None) => HttpResponse::Found()
+            .insert_header((header::LOCATION, target))
+            .finish(),
+        Err(e) => {
+            warn!("Failed to load preview for {}: {}", property_id, e);
+            HttpResponse::Found()
+                .insert_header((header::LOCATION, target))
+                .finish()
+        }
+    }
+}
+
+/// A landing page carrying the Open Graph tags link unfurlers read, which
+/// forwards browsers to the listing.
+fn share_page_html(
+    base_url: &str,
+    property_id: Uuid,
+    target: &str,
+    title: &str,
+    description: &str,
+) -> String {
+    let image = format!("{}/api/properties/{}/og.png", base_url, property_id);
+    let (title, description, image, target) = (
+        escape_html(title),
+        escape_html(description),
+        escape_html(&image),
+        escape_html(target),
+    );
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<meta property="og:type" content="website">
+<meta property="og:title" content="{title}">
+<meta property="og:description" content="{description}">
+<meta property="og:image" content="{image}">
+<meta property="og:image:width" content="{width}">
+<meta property="og:image:height" content="{height}">
+<meta property="og:url" content="{target}">
+<meta name="twitter:card" content="summary_large_image">
+<meta http-equiv="refresh" content="0; url={target}">
+</head>
+<body><a href="{target}">{title}</a></body>
+</html>
+"#,
+        width = OG_WIDTH,
+        height = OG_HEIGHT,
+    )
+}
+
+/// Click counts per link for the listing's owner (or an admin).
+#[get("/api/properties/{property_id}/short-links/stats")]
+pub async fn short_link_stats(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    let owner =
+        sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM properties WHERE id = $1")
+            .bind(property_id)
+            .fetch_optional(&state.db)
+            .await;
+    match owner {
+        Ok(Some(owner)) if owner == Some(user.id) || user.is_admin() => {}
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only the owner can view link statistics"
+            }))
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load owner of {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch link stats"
+            }));
+        }
+    }
+
+    // Clicks per link and referrer, then the top ten referrers of each link
+    match sqlx::query_as::<_, ShortLinkStats>(
+        r#"WITH referrers AS (
+            SELECT c.short_link_id, COALESCE(NULLIF(c.referrer, ''), 'direct') AS referrer,
+                   COUNT(*) AS clicks, MAX(c.clicked_at) AS last_clicked_at
+            FROM short_link_clicks c JOIN short_links l ON l.id = c.short_link_id
+            WHERE l.property_id = $1
+            GROUP BY 1, 2
+        ), ranked AS (
+            SELECT *, ROW_NUMBER() OVER (PARTITION BY short_link_id ORDER BY clicks DESC) AS rank
+            FROM referrers
+        )
+        SELECT l.code, l.channel, l.click_count, MAX(r.last_clicked_at) AS last_clicked_at,
+               COALESCE(
+                   JSONB_AGG(JSONB_BUILD_OBJECT('referrer', r.referrer, 'clicks', r.clicks)
+                             ORDER BY r.clicks DESC) FILTER (WHERE r.rank <= 10),
+                   '[]'
+               ) AS referrers
+        FROM short_links l LEFT JOIN ranked r ON r.short_link_id = l.id
+        WHERE l.property_id = $1
+        GROUP BY l.id
+        ORDER BY l.click_count DESC, l.created_at"#,
+    )
+    .bind(property_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(links) => HttpResponse::Ok().json(
+            links
+                .into_iter()
+                .map(|link| ShortLinkStats {
+                    url: short_url(&state.public_base_url, &link.code),
+                    ..link
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => {
+            error!(
+                "Failed to fetch short link stats for {}: {}",
+                property_id, e
+            );
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch link stats"
+            }))
+        }
+    }
+}
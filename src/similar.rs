@@ -0,0 +1,264 @@
+// JARVIS2026 - Similar properties
+// "You might also like" for a listing page. Candidates are active listings in
+// a price band around the subject and in the same area, plus its nearest
+// neighbours by embedding when it has one. Each is scored on the same
+// attribute likeness as comparable sales, on price closeness, and on meaning
+// (cosine similarity of the description vectors) when both sides are
+// embedded; the best few are returned.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::comparables::{similarity, Features};
+use crate::formatting::{Locale, PriceDisplay};
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::{AppState, Property, PropertyView};
+
+const DEFAULT_LIMIT: usize = 6;
+const MAX_LIMIT: usize = 24;
+/// Candidates priced within this factor of the subject, either way
+const PRICE_BAND: f64 = 2.0;
+/// Same-area listings within this distance count even under another name
+const NEARBY_KM: f64 = 5.0;
+/// Rows fetched per candidate query before scoring
+const CANDIDATE_POOL: i64 = 200;
+
+const ATTRIBUTES_WEIGHT: f64 = 3.0;
+const PRICE_WEIGHT: f64 = 1.5;
+const SEMANTIC_WEIGHT: f64 = 1.5;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct SimilarQuery {
+    limit: Option<usize>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Candidate {
+    #[sqlx(flatten)]
+    property: Property,
+    /// Cosine similarity to the subject; `None` unless both are embedded
+    semantic: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Subject {
+    #[sqlx(flatten)]
+    property: Property,
+    has_embedding: bool,
+}
+
+#[derive(Serialize)]
+struct SimilarListing {
+    #[serde(flatten)]
+    view: PropertyView,
+    score: f64,
+}
+
+#[derive(Serialize)]
+struct SimilarResponse {
+    property_id: Uuid,
+    results: Vec<SimilarListing>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    // Same-area lookups with a price range
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_properties_location_price ON properties(LOWER(TRIM(location)), price)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// SCORING
+// ============================================================================
+
+fn features(property: &Property) -> Features {
+    Features {
+        location: property.location.clone(),
+        property_type: property.property_type.clone(),
+        bedrooms: property.bedrooms,
+        bathrooms: property.bathrooms,
+        area_sqm: property.area_sqm,
+        latitude: property.latitude,
+        longitude: property.longitude,
+    }
+}
+
+/// 1 at the same price, falling to 0 at the edge of the band.
+fn price_closeness(a: f64, b: f64) -> f64 {
+    if a <= 0.0 || b <= 0.0 {
+        return 0.0;
+    }
+    (1.0 - (a / b).ln().abs() / PRICE_BAND.ln()).max(0.0)
+}
+
+/// 0-1 weighted blend; meaning only counts when both listings are embedded.
+fn score(subject: &Property, subject_features: &Features, candidate: &Candidate) -> f64 {
+    let components = [
+        (
+            ATTRIBUTES_WEIGHT,
+            Some(similarity(subject_features, &features(&candidate.property))),
+        ),
+        (
+            PRICE_WEIGHT,
+            Some(price_closeness(subject.price, candidate.property.price)),
+        ),
+        (SEMANTIC_WEIGHT, candidate.semantic.map(|s| s.max(0.0))),
+    ];
+    let (weighted, weights) = components
+        .iter()
+        .filter_map(|(weight, score)| score.map(|s| (weight * s, *weight)))
+        .fold((0.0, 0.0), |(sum, total), (s, w)| (sum + s, total + w));
+    weighted / weights
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+/// Cosine similarity to the subject (`$1`), read from its stored vector.
+const SEMANTIC_EXPR: &str =
+    "(1 - (embedding <=> (SELECT s.embedding FROM properties s WHERE s.id = $1)))::float8";
+
+async fn load_candidates(
+    pool: &PgPool,
+    subject: &Property,
+    has_embedding: bool,
+) -> Result<Vec<Candidate>, sqlx::Error> {
+    // Degrees of latitude spanning NEARBY_KM
+    let degrees = NEARBY_KM / 111.0;
+    let mut candidates = sqlx::query_as::<_, Candidate>(&format!(
+        r#"SELECT *, {} AS semantic
+        FROM properties
+        WHERE id <> $1 AND {} AND {}
+          AND price BETWEEN $2 AND $3
+          AND (LOWER(TRIM(location)) = LOWER(TRIM($4))
+               OR (latitude BETWEEN $5 - $7 AND $5 + $7
+                   AND longitude BETWEEN $6 - $7 / GREATEST(COS(RADIANS($5)), 0.01)
+                                     AND $6 + $7 / GREATEST(COS(RADIANS($5)), 0.01)))
+        ORDER BY created_at DESC NULLS LAST
+        LIMIT $8"#,
+        SEMANTIC_EXPR, PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(subject.id)
+    .bind(subject.price / PRICE_BAND)
+    .bind(subject.price * PRICE_BAND)
+    .bind(&subject.location)
+    .bind(subject.latitude)
+    .bind(subject.longitude)
+    .bind(degrees)
+    .bind(CANDIDATE_POOL)
+    .fetch_all(pool)
+    .await?;
+
+    if has_embedding {
+        // Alike in description wherever they are; walks the HNSW index
+        let neighbours = sqlx::query_as::<_, Candidate>(&format!(
+            r#"SELECT *, {0} AS semantic
+            FROM properties
+            WHERE id <> $1 AND embedding IS NOT NULL AND {1} AND {2}
+            ORDER BY embedding <=> (SELECT s.embedding FROM properties s WHERE s.id = $1)
+            LIMIT $2"#,
+            SEMANTIC_EXPR, PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+        ))
+        .bind(subject.id)
+        .bind(CANDIDATE_POOL)
+        .fetch_all(pool)
+        .await?;
+        candidates.extend(neighbours);
+        candidates.sort_by_key(|c| c.property.id);
+        candidates.dedup_by_key(|c| c.property.id);
+    }
+
+    Ok(candidates)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/properties/{id}/similar")]
+pub async fn similar_properties(
+    path: web::Path<Uuid>,
+    query: web::Query<SimilarQuery>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let result: Result<Option<Vec<(Candidate, f64)>>, sqlx::Error> = async {
+        let Some(subject) = sqlx::query_as::<_, Subject>(&format!(
+            r#"SELECT *, embedding IS NOT NULL AS has_embedding
+            FROM properties WHERE id = $1 AND {}"#,
+            PUBLIC_LISTING_CONDITION
+        ))
+        .bind(property_id)
+        .fetch_optional(&state.db)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let Subject {
+            property: subject,
+            has_embedding,
+        } = subject;
+        let subject_features = features(&subject);
+
+        let mut scored: Vec<(Candidate, f64)> = load_candidates(&state.db, &subject, has_embedding)
+            .await?
+            .into_iter()
+            .map(|candidate| {
+                let score = score(&subject, &subject_features, &candidate);
+                (candidate, (score * 1000.0).round() / 1000.0)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        Ok(Some(scored))
+    }
+    .await;
+
+    match result {
+        Ok(Some(scored)) => HttpResponse::Ok().json(SimilarResponse {
+            property_id,
+            results: scored
+                .into_iter()
+                .map(|(candidate, score)| SimilarListing {
+                    view: PropertyView {
+                        price_display: PriceDisplay::new(candidate.property.price, locale),
+                        property: candidate.property,
+                    },
+                    score,
+                })
+                .collect(),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Err(e) => {
+            error!(
+                "Failed to find similar properties for {}: {}",
+                property_id, e
+            );
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to find similar properties"
+            }))
+        }
+    }
+}
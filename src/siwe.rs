@@ -0,0 +1,407 @@
+// JARVIS2026 - Sign-In with Ethereum (EIP-4361)
+// The client fetches a one-time nonce, has the wallet sign a SIWE message
+// that embeds it, and posts message + signature back. We recover the signer
+// (EIP-191 personal_sign), match it to the wallet in the message, and start a
+// session for the user who verified that wallet, creating one on first
+// sign-in.
+
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::fraud;
+use crate::sessions::{self, IssuedSession};
+use crate::AppState;
+
+const NONCE_TTL_MINUTES: i64 = 10;
+const MESSAGE_HEADER_SUFFIX: &str = " wants you to sign in with your Ethereum account:";
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Serialize)]
+struct NonceResponse {
+    nonce: String,
+    domain: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    message: String,
+    /// 65-byte hex signature as returned by `personal_sign`
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct VerifyResponse {
+    user_id: Uuid,
+    wallet_address: String,
+    new_user: bool,
+    #[serde(flatten)]
+    session: IssuedSession,
+}
+
+/// The fields of an EIP-4361 message we act on.
+#[derive(Debug)]
+struct SiweMessage {
+    domain: String,
+    /// Lowercased `0x`-prefixed address
+    address: String,
+    version: String,
+    nonce: String,
+    expiration_time: Option<chrono::DateTime<chrono::Utc>>,
+    not_before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS siwe_nonces (
+            nonce TEXT PRIMARY KEY,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL,
+            used_at TIMESTAMPTZ
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Addresses typed into `create_user` are unproven; once a signature backs
+    // one it is stamped here and can belong to a single account only
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS wallet_verified_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS idx_users_verified_wallet
+        ON users (LOWER(wallet_address)) WHERE wallet_verified_at IS NOT NULL"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// MESSAGE PARSING AND VERIFICATION
+// ============================================================================
+
+/// A `0x`-prefixed, 20-byte hex Ethereum address, in any letter case.
+pub fn is_wallet_address(address: &str) -> bool {
+    address.len() == 42
+        && address.starts_with("0x")
+        && address[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn parse_timestamp(value: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .map_err(|_| format!("Invalid timestamp '{}'", value))
+}
+
+fn parse_message(message: &str) -> Result<SiweMessage, String> {
+    let mut lines = message.lines();
+
+    let domain = lines
+        .next()
+        .and_then(|line| line.strip_suffix(MESSAGE_HEADER_SUFFIX))
+        .filter(|d| !d.is_empty())
+        .ok_or("Not a Sign-In with Ethereum message")?
+        .to_string();
+
+    let address = lines.next().unwrap_or_default().trim();
+    if !is_wallet_address(address) {
+        return Err("Message does not contain a valid address".to_string());
+    }
+
+    let mut version = None;
+    let mut nonce = None;
+    let mut expiration_time = None;
+    let mut not_before = None;
+    for line in lines {
+        if let Some(v) = line.strip_prefix("Version: ") {
+            version = Some(v.trim().to_string());
+        } else if let Some(v) = line.strip_prefix("Nonce: ") {
+            nonce = Some(v.trim().to_string());
+        } else if let Some(v) = line.strip_prefix("Expiration Time: ") {
+            expiration_time = Some(parse_timestamp(v.trim())?);
+        } else if let Some(v) = line.strip_prefix("Not Before: ") {
+            not_before = Some(parse_timestamp(v.trim())?);
+        }
+    }
+
+    Ok(SiweMessage {
+        domain,
+        address: address.to_ascii_lowercase(),
+        version: version.ok_or("Message is missing a version")?,
+        nonce: nonce.ok_or("Message is missing a nonce")?,
+        expiration_time,
+        not_before,
+    })
+}
+
+/// Recovers the lowercased address that produced an EIP-191 `personal_sign`
+/// signature over `message`.
+fn recover_signer(message: &str, signature: &str) -> Option<String> {
+    let bytes = hex::decode(signature.trim().trim_start_matches("0x")).ok()?;
+    if bytes.len() != 65 {
+        return None;
+    }
+    let signature = Signature::from_slice(&bytes[..64]).ok()?;
+    // Wallets report v as 27/28; some libraries as 0/1
+    let v = bytes[64];
+    let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })?;
+
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    let hash = Keccak256::digest(prefixed.as_bytes());
+
+    let key = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id).ok()?;
+    let point = key.to_encoded_point(false);
+    let digest = Keccak256::digest(&point.as_bytes()[1..]);
+    Some(format!("0x{}", hex::encode(&digest[12..])))
+}
+
+/// The domain messages must be issued for: `SIWE_DOMAIN`, else the host of
+/// the public base URL.
+fn expected_domain(public_base_url: &str) -> String {
+    std::env::var("SIWE_DOMAIN").unwrap_or_else(|_| {
+        let without_scheme = public_base_url
+            .split_once("://")
+            .map_or(public_base_url, |(_, rest)| rest);
+        without_scheme
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    })
+}
+
+/// The account that already proved it holds the wallet, if any.
+async fn verified_owner(pool: &PgPool, address: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"SELECT id FROM users
+        WHERE LOWER(wallet_address) = $1 AND wallet_verified_at IS NOT NULL"#,
+    )
+    .bind(address)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Finds the account that verified a proven wallet, creating one when none
+/// has. An address typed in at registration proves nothing, so such claims
+/// never get the wallet's sign-in; they are cleared instead.
+async fn user_for_wallet(
+    pool: &PgPool,
+    address: &str,
+) -> Result<Option<(Uuid, bool)>, sqlx::Error> {
+    if let Some(user_id) = verified_owner(pool, address).await? {
+        return Ok(Some((user_id, false)));
+    }
+
+    sqlx::query(
+        r#"UPDATE users SET wallet_address = NULL
+        WHERE LOWER(wallet_address) = $1 AND wallet_verified_at IS NULL"#,
+    )
+    .bind(address)
+    .execute(pool)
+    .await?;
+
+    // The address doubles as the username unless another account took it. A
+    // concurrent first sign-in for the same wallet loses the race on the
+    // verified wallet index and picks up the winner's account
+    let suffixed = format!("{}-{}", address, &Uuid::new_v4().simple().to_string()[..8]);
+    for username in [address, suffixed.as_str()] {
+        let created = sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO users (username, wallet_address, wallet_verified_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT DO NOTHING
+            RETURNING id"#,
+        )
+        .bind(username)
+        .bind(address)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(user_id) = created {
+            return Ok(Some((user_id, true)));
+        }
+        if let Some(user_id) = verified_owner(pool, address).await? {
+            return Ok(Some((user_id, false)));
+        }
+    }
+    Ok(None)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/auth/nonce")]
+pub async fn issue_nonce(state: web::Data<AppState>) -> impl Responder {
+    let nonce = Uuid::new_v4().simple().to_string();
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(NONCE_TTL_MINUTES);
+
+    match sqlx::query("INSERT INTO siwe_nonces (nonce, expires_at) VALUES ($1, $2)")
+        .bind(&nonce)
+        .bind(expires_at)
+        .execute(&state.db)
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(NonceResponse {
+            nonce,
+            domain: expected_domain(&state.public_base_url),
+            expires_at,
+        }),
+        Err(e) => {
+            error!("Failed to issue sign-in nonce: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to issue nonce"
+            }))
+        }
+    }
+}
+
+#[post("/api/auth/verify")]
+pub async fn verify_signature(
+    http_req: HttpRequest,
+    req: web::Json<VerifyRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let message = match parse_message(&req.message) {
+        Ok(message) => message,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+    };
+
+    if message.version != "1" {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unsupported message version"
+        }));
+    }
+    if message.domain != expected_domain(&state.public_base_url) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Message was issued for a different domain"
+        }));
+    }
+    let now = chrono::Utc::now();
+    if message.expiration_time.is_some_and(|t| t <= now)
+        || message.not_before.is_some_and(|t| t > now)
+    {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Message is not valid at this time"
+        }));
+    }
+
+    if recover_signer(&req.message, &req.signature).as_deref() != Some(message.address.as_str()) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Signature does not match the wallet address"
+        }));
+    }
+
+    // Consume the nonce only after the signature checks out, so a bad
+    // attempt can't burn someone else's pending nonce
+    match sqlx::query(
+        r#"UPDATE siwe_nonces SET used_at = NOW()
+        WHERE nonce = $1 AND used_at IS NULL AND expires_at > NOW()"#,
+    )
+    .bind(&message.nonce)
+    .execute(&state.db)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 1 => {}
+        Ok(_) => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Nonce is unknown, expired or already used"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to consume sign-in nonce: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify sign-in"
+            }));
+        }
+    }
+
+    let (user_id, new_user) = match user_for_wallet(&state.db, &message.address).await {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Could not create an account for this wallet"
+            }))
+        }
+        Err(e) => {
+            error!(
+                "Failed to resolve user for wallet {}: {}",
+                message.address, e
+            );
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify sign-in"
+            }));
+        }
+    };
+
+    match sessions::issue(&state.db, user_id, "siwe").await {
+        Ok(session) => {
+            info!("Wallet sign-in: {} as user {}", message.address, user_id);
+            fraud::record_request(&state.db, user_id, &http_req).await;
+            HttpResponse::Ok().json(VerifyResponse {
+                user_id,
+                wallet_address: message.address,
+                new_user,
+                session,
+            })
+        }
+        Err(e) => {
+            error!("Failed to start session for {}: {}", user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to verify sign-in"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn a_typed_in_wallet_does_not_capture_the_owners_sign_in() {
+        let Some(pool) = crate::test_pool().await else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        init_schema(&pool).await.unwrap();
+        let address = format!("0x{:040x}", Uuid::new_v4().as_u128());
+
+        let squatter: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (username, wallet_address) VALUES ($1, $1) RETURNING id",
+        )
+        .bind(&address)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let (owner, created) = user_for_wallet(&pool, &address).await.unwrap().unwrap();
+        assert_ne!(owner, squatter);
+        assert!(created);
+
+        let claim: Option<String> =
+            sqlx::query_scalar("SELECT wallet_address FROM users WHERE id = $1")
+                .bind(squatter)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(claim, None);
+        assert_eq!(
+            user_for_wallet(&pool, &address).await.unwrap(),
+            Some((owner, false))
+        );
+    }
+}
@@ -0,0 +1,247 @@
+// JARVIS2026 - Sold listings
+// Marking a listing sold snapshots its final state and sale price into
+// `sold_records`, which outlives the listing and feeds comparables and price
+// estimates. Sellers may anonymize the record: the seller is dropped and the
+// price is rounded. Only active listings can be sold; selling moves them to
+// the `sold` status of the listing lifecycle (see `listing_status`).
+
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::CurrentUser;
+use crate::listing_status::ListingStatus;
+use crate::AppState;
+
+/// Significant digits kept in an anonymized sale price
+const ANONYMIZED_PRICE_DIGITS: i32 = 2;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct MarkSoldRequest {
+    sale_price: f64,
+    /// Defaults to now
+    sold_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    anonymize: bool,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SoldRecord {
+    id: Uuid,
+    property_id: Option<Uuid>,
+    location: String,
+    property_type: Option<String>,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    list_price: f64,
+    sale_price: f64,
+    price_per_sqm: Option<f64>,
+    anonymized: bool,
+    sold_at: chrono::DateTime<chrono::Utc>,
+}
+
+enum Outcome {
+    Sold(SoldRecord),
+    NotFound,
+    Forbidden,
+    NotActive(String),
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("ALTER TABLE properties ADD COLUMN IF NOT EXISTS sold_at TIMESTAMPTZ")
+        .execute(pool)
+        .await?;
+
+    // Listing fields are copied out so records survive the listing's deletion
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS sold_records (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            property_id UUID UNIQUE REFERENCES properties(id) ON DELETE SET NULL,
+            seller_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            location TEXT NOT NULL,
+            latitude DOUBLE PRECISION,
+            longitude DOUBLE PRECISION,
+            property_type TEXT,
+            bedrooms INTEGER,
+            bathrooms INTEGER,
+            area_sqm DOUBLE PRECISION,
+            list_price DOUBLE PRECISION NOT NULL,
+            sale_price DOUBLE PRECISION NOT NULL,
+            price_per_sqm DOUBLE PRECISION,
+            anonymized BOOLEAN NOT NULL DEFAULT FALSE,
+            snapshot JSONB NOT NULL,
+            sold_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE INDEX IF NOT EXISTS idx_sold_records_location
+        ON sold_records(LOWER(TRIM(location)), sold_at DESC)"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// SNAPSHOTS
+// ============================================================================
+
+/// Rounds to a few significant digits so the exact amount can't be read back.
+fn anonymize_price(price: f64) -> f64 {
+    let magnitude = 10f64.powi(price.log10().floor() as i32 - (ANONYMIZED_PRICE_DIGITS - 1));
+    (price / magnitude).round() * magnitude
+}
+
+async fn mark_sold(
+    pool: &PgPool,
+    user: CurrentUser,
+    property_id: Uuid,
+    req: &MarkSoldRequest,
+) -> Result<Outcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some((owner, status)) = sqlx::query_as::<_, (Option<Uuid>, String)>(
+        "SELECT user_id, status FROM properties WHERE id = $1 FOR UPDATE",
+    )
+    .bind(property_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(Outcome::NotFound);
+    };
+    if owner != Some(user.id) && !user.is_admin() {
+        return Ok(Outcome::Forbidden);
+    }
+    if status != ListingStatus::Active.as_str() {
+        return Ok(Outcome::NotActive(status));
+    }
+
+    let sale_price = if req.anonymize {
+        anonymize_price(req.sale_price)
+    } else {
+        req.sale_price
+    };
+    let sold_at = req.sold_at.unwrap_or_else(chrono::Utc::now);
+
+    // The snapshot keeps the listing as it was, with its media; anonymized
+    // records drop who sold it. The search vector is derived and left out
+    let record = sqlx::query_as::<_, SoldRecord>(
+        r#"INSERT INTO sold_records
+        (property_id, seller_user_id, location, latitude, longitude, property_type, bedrooms,
+         bathrooms, area_sqm, list_price, sale_price, price_per_sqm, anonymized, snapshot, sold_at)
+        SELECT p.id, CASE WHEN $3 THEN NULL ELSE p.user_id END, p.location, p.latitude,
+               p.longitude, p.property_type, p.bedrooms, p.bathrooms, p.area_sqm, p.price, $2,
+               CASE WHEN p.area_sqm > 0 THEN $2 / p.area_sqm END, $3,
+               (CASE WHEN $3 THEN to_jsonb(p) - 'user_id' ELSE to_jsonb(p) END) - 'embedding'
+                   || jsonb_build_object('media', (
+                       SELECT COALESCE(jsonb_agg(jsonb_build_object(
+                           'id', m.id, 'file_type', m.file_type, 'uploaded_at', m.uploaded_at
+                       ) ORDER BY m.uploaded_at), '[]'::jsonb)
+                       FROM media_uploads m WHERE m.property_id = p.id
+                   )),
+               $4
+        FROM properties p WHERE p.id = $1
+        RETURNING id, property_id, location, property_type, bedrooms, bathrooms, area_sqm,
+                  list_price, sale_price, price_per_sqm, anonymized, sold_at"#,
+    )
+    .bind(property_id)
+    .bind(sale_price)
+    .bind(req.anonymize)
+    .bind(sold_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE properties SET sold_at = $2, status = $3, status_changed_at = NOW() WHERE id = $1",
+    )
+    .bind(property_id)
+    .bind(sold_at)
+    .bind(ListingStatus::Sold.as_str())
+    .execute(&mut *tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        user.id,
+        "property.sold",
+        "property",
+        property_id,
+        serde_json::json!({
+            "sold_record_id": record.id,
+            "anonymized": req.anonymize
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Outcome::Sold(record))
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/properties/{id}/sold")]
+pub async fn mark_property_sold(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<MarkSoldRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    if !req.sale_price.is_finite() || req.sale_price <= 0.0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "sale_price must be positive"
+        }));
+    }
+    if req.sold_at.is_some_and(|at| at > chrono::Utc::now()) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "sold_at cannot be in the future"
+        }));
+    }
+
+    match mark_sold(&state.db, user, property_id, &req).await {
+        Ok(Outcome::Sold(record)) => {
+            state.property_cache.invalidate(property_id).await;
+            info!(
+                "Property {} marked sold by {} (record {})",
+                property_id, user.id, record.id
+            );
+            HttpResponse::Ok().json(record)
+        }
+        Ok(Outcome::NotFound) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property not found"
+        })),
+        Ok(Outcome::Forbidden) => HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the owner can mark a listing sold"
+        })),
+        Ok(Outcome::NotActive(status)) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Only active listings can be marked sold; this one is {}", status),
+            "status": status
+        })),
+        Err(e) => {
+            error!("Failed to mark property {} sold: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to mark property sold"
+            }))
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// JARVIS2026 - Token statements
+// Users download their token activity as CSV for record-keeping and tax
+// filing. Rows are streamed from Postgres straight into the response, so a
+// long history is never held in memory. Day boundaries and timestamps follow
+// the user's timezone.
+
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::timezones;
+use crate::AppState;
+
+const CSV_HEADER: &str = "date,transaction_id,type,amount,running_total,media_id,property_id\r\n";
+/// Rows buffered between the database reader and a slow client.
+const STREAM_BUFFER: usize = 64;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StatementFormat {
+    #[default]
+    Csv,
+}
+
+#[derive(Deserialize)]
+pub struct StatementQuery {
+    #[serde(default)]
+    format: StatementFormat,
+    /// First local day included
+    from: Option<NaiveDate>,
+    /// Last local day included
+    to: Option<NaiveDate>,
+}
+
+#[derive(sqlx::FromRow)]
+struct StatementRow {
+    id: Uuid,
+    created_at: chrono::DateTime<chrono::Utc>,
+    transaction_type: String,
+    amount: i64,
+    running_total: i64,
+    media_id: Option<Uuid>,
+    property_id: Option<Uuid>,
+}
+
+// ============================================================================
+// CSV
+// ============================================================================
+
+impl StatementFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            StatementFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            StatementFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Start of a local day as a UTC instant.
+fn local_midnight(tz: Tz, day: NaiveDate) -> Option<chrono::DateTime<chrono::Utc>> {
+    tz.from_local_datetime(&day.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+fn csv_row(row: &StatementRow, tz: Tz) -> String {
+    let optional = |id: Option<Uuid>| id.map(|id| id.to_string()).unwrap_or_default();
+    // Types are internal identifiers, but quote defensively all the same
+    let kind = if row.transaction_type.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", row.transaction_type.replace('"', "\"\""))
+    } else {
+        row.transaction_type.clone()
+    };
+    format!(
+        "{},{},{},{},{},{},{}\r\n",
+        row.created_at.with_timezone(&tz).to_rfc3339(),
+        row.id,
+        kind,
+        row.amount,
+        row.running_total,
+        optional(row.media_id),
+        optional(row.property_id)
+    )
+}
+
+/// Reads the statement row by row and feeds the response body until the
+/// rows run out or the client goes away.
+async fn write_statement(
+    pool: PgPool,
+    user_id: Uuid,
+    tz: Tz,
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+    tx: mpsc::Sender<Result<web::Bytes, std::io::Error>>,
+) {
+    if tx
+        .send(Ok(web::Bytes::from_static(CSV_HEADER.as_bytes())))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    // The running total covers the whole history, not just the window
+    let mut rows = sqlx::query_as::<_, StatementRow>(
+        r#"SELECT * FROM (
+            SELECT t.id, t.created_at, t.transaction_type, t.amount,
+                   SUM(t.amount) OVER (ORDER BY t.created_at, t.id)::BIGINT AS running_total,
+                   t.media_id, m.property_id
+            FROM token_transactions t
+            LEFT JOIN media_uploads m ON m.id = t.media_id
+            WHERE t.user_id = $1
+        ) s
+        WHERE ($2::timestamptz IS NULL OR created_at >= $2)
+          AND ($3::timestamptz IS NULL OR created_at < $3)
+        ORDER BY created_at, id"#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch(&pool);
+
+    while let Some(row) = rows.next().await {
+        let chunk = match row {
+            Ok(row) => Ok(web::Bytes::from(csv_row(&row, tz))),
+            Err(e) => {
+                error!("Token statement for {} failed mid-stream: {}", user_id, e);
+                Err(std::io::Error::other("statement export failed"))
+            }
+        };
+        let failed = chunk.is_err();
+        if tx.send(chunk).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/users/me/transactions/export")]
+pub async fn export_transactions(
+    user: CurrentUser,
+    query: web::Query<StatementQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "from must not be after to"
+            }));
+        }
+    }
+
+    let tz = match sqlx::query_scalar::<_, String>("SELECT timezone FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(name) => timezones::parse(&name).unwrap_or(timezones::DEFAULT_TIMEZONE),
+        Err(e) => {
+            error!("Failed to load timezone of {}: {}", user.id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export statement"
+            }));
+        }
+    };
+    let from = query.from.and_then(|day| local_midnight(tz, day));
+    let to = query
+        .to
+        .and_then(|day| day.succ_opt())
+        .and_then(|day| local_midnight(tz, day));
+
+    let range = match (query.from, query.to) {
+        (Some(from), Some(to)) => format!("-{}-to-{}", from, to),
+        (Some(from), None) => format!("-from-{}", from),
+        (None, Some(to)) => format!("-to-{}", to),
+        (None, None) => String::new(),
+    };
+    let filename = format!("token-statement{}.{}", range, query.format.extension());
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(write_statement(state.db.clone(), user.id, tz, from, to, tx));
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    HttpResponse::Ok()
+        .content_type(query.format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .streaming(body)
+}
@@ -0,0 +1,201 @@
+// JARVIS2026 - Per-user storage accounting
+// Media byte counts are kept in `storage_usage`, maintained by a trigger on
+// `media_uploads`, so usage reports never have to sum the uploads table.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(sqlx::FromRow)]
+struct UsageRow {
+    property_id: Uuid,
+    title: Option<String>,
+    file_type: String,
+    bytes: i64,
+    files: i64,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+struct UsageTotals {
+    bytes: i64,
+    files: i64,
+}
+
+#[derive(Serialize)]
+struct PropertyUsage {
+    property_id: Uuid,
+    title: Option<String>,
+    #[serde(flatten)]
+    totals: UsageTotals,
+    by_type: BTreeMap<String, UsageTotals>,
+}
+
+#[derive(Serialize)]
+struct StorageReport {
+    #[serde(flatten)]
+    totals: UsageTotals,
+    by_type: BTreeMap<String, UsageTotals>,
+    properties: Vec<PropertyUsage>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS storage_usage (
+            user_id UUID NOT NULL,
+            property_id UUID NOT NULL,
+            file_type TEXT NOT NULL,
+            bytes BIGINT NOT NULL DEFAULT 0,
+            files BIGINT NOT NULL DEFAULT 0,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (user_id, property_id, file_type)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE OR REPLACE FUNCTION track_storage_usage() RETURNS trigger AS $$
+        BEGIN
+            IF TG_OP IN ('DELETE', 'UPDATE')
+               AND OLD.user_id IS NOT NULL AND OLD.property_id IS NOT NULL THEN
+                UPDATE storage_usage
+                SET bytes = bytes - OLD.file_size, files = files - 1, updated_at = NOW()
+                WHERE user_id = OLD.user_id AND property_id = OLD.property_id
+                  AND file_type = OLD.file_type;
+                DELETE FROM storage_usage
+                WHERE user_id = OLD.user_id AND property_id = OLD.property_id
+                  AND file_type = OLD.file_type AND files <= 0;
+            END IF;
+            IF TG_OP IN ('INSERT', 'UPDATE')
+               AND NEW.user_id IS NOT NULL AND NEW.property_id IS NOT NULL THEN
+                INSERT INTO storage_usage (user_id, property_id, file_type, bytes, files)
+                VALUES (NEW.user_id, NEW.property_id, NEW.file_type, NEW.file_size, 1)
+                ON CONFLICT (user_id, property_id, file_type) DO UPDATE
+                SET bytes = storage_usage.bytes + EXCLUDED.bytes,
+                    files = storage_usage.files + 1,
+                    updated_at = NOW();
+            END IF;
+            RETURN NULL;
+        END
+        $$ LANGUAGE plpgsql"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("DROP TRIGGER IF EXISTS media_uploads_storage_usage ON media_uploads")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"CREATE TRIGGER media_uploads_storage_usage
+        AFTER INSERT OR DELETE OR UPDATE OF user_id, property_id, file_type, file_size
+        ON media_uploads
+        FOR EACH ROW EXECUTE FUNCTION track_storage_usage()"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // One-off backfill for media uploaded before the aggregate existed
+    sqlx::query(
+        r#"INSERT INTO storage_usage (user_id, property_id, file_type, bytes, files)
+        SELECT user_id, property_id, file_type, SUM(file_size), COUNT(*)
+        FROM media_uploads
+        WHERE user_id IS NOT NULL AND property_id IS NOT NULL
+          AND NOT EXISTS (SELECT 1 FROM storage_usage)
+        GROUP BY user_id, property_id, file_type"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// REPORTING
+// ============================================================================
+
+impl UsageTotals {
+    fn add(&mut self, bytes: i64, files: i64) {
+        self.bytes += bytes;
+        self.files += files;
+    }
+}
+
+fn build_report(rows: Vec<UsageRow>) -> StorageReport {
+    let mut totals = UsageTotals::default();
+    let mut by_type: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    let mut properties: Vec<PropertyUsage> = Vec::new();
+
+    for row in rows {
+        totals.add(row.bytes, row.files);
+        by_type
+            .entry(row.file_type.clone())
+            .or_default()
+            .add(row.bytes, row.files);
+
+        // Rows arrive grouped by property
+        if properties.last().map(|p| p.property_id) != Some(row.property_id) {
+            properties.push(PropertyUsage {
+                property_id: row.property_id,
+                title: row.title,
+                totals: UsageTotals::default(),
+                by_type: BTreeMap::new(),
+            });
+        }
+        let property = properties.last_mut().unwrap();
+        property.totals.add(row.bytes, row.files);
+        property
+            .by_type
+            .entry(row.file_type)
+            .or_default()
+            .add(row.bytes, row.files);
+    }
+
+    properties.sort_by_key(|p| std::cmp::Reverse(p.totals.bytes));
+    StorageReport {
+        totals,
+        by_type,
+        properties,
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/users/me/storage")]
+pub async fn my_storage(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, UsageRow>(
+        r#"SELECT su.property_id, p.title, su.file_type, su.bytes, su.files
+        FROM storage_usage su
+        LEFT JOIN properties p ON p.id = su.property_id
+        WHERE su.user_id = $1
+        ORDER BY su.property_id, su.file_type"#,
+    )
+    .bind(user.id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(rows) => HttpResponse::Ok().json(build_report(rows)),
+        Err(e) => {
+            error!("Failed to load storage usage for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load storage usage"
+            }))
+        }
+    }
+}
@@ -0,0 +1,498 @@
+// JARVIS2026 - Listing syndication to external portals
+// Approved listings are pushed to every enabled portal; listings that are
+// later rejected or unpublished are withdrawn again. Each (portal, listing)
+// pair tracks its remote id and sync state, and failed pushes are retried
+// with exponential backoff.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::sharing::listing_url;
+use crate::{AppState, Property};
+
+const WORKER_INTERVAL_SECS: u64 = 60;
+const PUSH_TIMEOUT: Duration = Duration::from_secs(30);
+const BATCH_SIZE: i64 = 100;
+const MAX_ATTEMPTS: i32 = 8;
+const MAX_BACKOFF_MINUTES: i32 = 360;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Portal {
+    id: Uuid,
+    name: String,
+    endpoint_url: String,
+    enabled: bool,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePortalRequest {
+    name: String,
+    /// Base URL of the portal's listing API; listings go to `{endpoint_url}/listings`.
+    endpoint_url: String,
+    api_key: String,
+}
+
+#[derive(Deserialize)]
+pub struct RetryQuery {
+    portal_id: Option<Uuid>,
+}
+
+/// A queued push or withdrawal for one listing on one portal.
+#[derive(sqlx::FromRow)]
+struct SyncJob {
+    portal_id: Uuid,
+    property_id: Uuid,
+    action: String,
+    remote_id: Option<String>,
+    attempts: i32,
+    endpoint_url: String,
+    api_key: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct SyncStatus {
+    portal: String,
+    remote_id: Option<String>,
+    action: String,
+    status: String,
+    attempts: i32,
+    last_error: Option<String>,
+    synced_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize)]
+struct ListingPayload<'a> {
+    external_id: Uuid,
+    url: String,
+    title: &'a str,
+    location: &'a str,
+    price: f64,
+    currency: &'static str,
+    description: &'a str,
+    bedrooms: Option<i32>,
+    bathrooms: Option<i32>,
+    area_sqm: Option<f64>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    property_type: Option<&'a str>,
+    certificate_type: Option<&'a str>,
+    timezone: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RemoteListing {
+    id: serde_json::Value,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS syndication_portals (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name TEXT UNIQUE NOT NULL,
+            endpoint_url TEXT NOT NULL,
+            api_key TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT true,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS syndication_listings (
+            portal_id UUID NOT NULL REFERENCES syndication_portals(id) ON DELETE CASCADE,
+            property_id UUID NOT NULL,
+            remote_id TEXT,
+            action TEXT NOT NULL DEFAULT 'upsert',
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            synced_at TIMESTAMPTZ,
+            PRIMARY KEY (portal_id, property_id)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE INDEX IF NOT EXISTS idx_syndication_due
+        ON syndication_listings(next_attempt_at) WHERE status IN ('pending', 'failed')"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// QUEUEING
+// ============================================================================
+
+/// Queues approved active listings that a portal doesn't have yet, and
+/// withdrawals for listings that are no longer approved or active, or were
+/// deleted.
+async fn enqueue_changes(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO syndication_listings (portal_id, property_id)
+        SELECT s.id, p.id FROM syndication_portals s
+        CROSS JOIN properties p
+        WHERE s.enabled AND p.moderation_status = 'approved' AND p.status = 'active'
+        ON CONFLICT (portal_id, property_id) DO UPDATE
+        SET action = 'upsert', status = 'pending', attempts = 0, next_attempt_at = NOW()
+        WHERE syndication_listings.action = 'remove'"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"UPDATE syndication_listings sl
+        SET action = 'remove', status = 'pending', attempts = 0, next_attempt_at = NOW()
+        WHERE sl.action = 'upsert'
+          AND NOT EXISTS (
+            SELECT 1 FROM properties p
+            WHERE p.id = sl.property_id AND p.moderation_status = 'approved'
+              AND p.status = 'active'
+          )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Re-pushes an edited listing to the portals that already carry it.
+pub async fn requeue<'e, E: PgExecutor<'e>>(
+    executor: E,
+    property_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE syndication_listings
+        SET status = 'pending', attempts = 0, last_error = NULL, next_attempt_at = NOW()
+        WHERE property_id = $1 AND action = 'upsert'"#,
+    )
+    .bind(property_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+fn backoff_minutes(attempts: i32) -> i32 {
+    2i32.saturating_pow(attempts.clamp(0, 16) as u32)
+        .min(MAX_BACKOFF_MINUTES)
+}
+
+// ============================================================================
+// PUSHING
+// ============================================================================
+
+fn listing_payload<'a>(property: &'a Property, base_url: &str) -> ListingPayload<'a> {
+    ListingPayload {
+        external_id: property.id,
+        url: listing_url(base_url, property.id),
+        title: &property.title,
+        location: &property.location,
+        price: property.price,
+        currency: "IDR",
+        description: &property.description,
+        bedrooms: property.bedrooms,
+        bathrooms: property.bathrooms,
+        area_sqm: property.area_sqm,
+        latitude: property.latitude,
+        longitude: property.longitude,
+        property_type: property.property_type.as_deref(),
+        certificate_type: property.certificate_type.as_deref(),
+        timezone: &property.timezone,
+    }
+}
+
+/// Returns the remote id after an upsert, `None` after a withdrawal.
+async fn push_job(
+    client: &reqwest::Client,
+    pool: &PgPool,
+    base_url: &str,
+    job: &SyncJob,
+) -> Result<Option<String>, String> {
+    let endpoint = job.endpoint_url.trim_end_matches('/');
+
+    if job.action == "remove" {
+        let Some(remote_id) = &job.remote_id else {
+            return Ok(None);
+        };
+        let response = client
+            .delete(format!("{}/listings/{}", endpoint, remote_id))
+            .bearer_auth(&job.api_key)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        // Already gone on their side counts as withdrawn
+        if response.status() != reqwest::StatusCode::NOT_FOUND {
+            response.error_for_status().map_err(|e| e.to_string())?;
+        }
+        return Ok(None);
+    }
+
+    let property = sqlx::query_as::<_, Property>("SELECT * FROM properties WHERE id = $1")
+        .bind(job.property_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Listing no longer exists".to_string())?;
+    let payload = listing_payload(&property, base_url);
+
+    let request = match &job.remote_id {
+        Some(remote_id) => client.put(format!("{}/listings/{}", endpoint, remote_id)),
+        None => client.post(format!("{}/listings", endpoint)),
+    };
+    let response = request
+        .bearer_auth(&job.api_key)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+
+    if let Some(remote_id) = &job.remote_id {
+        return Ok(Some(remote_id.clone()));
+    }
+    let remote: RemoteListing = response
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected portal response: {}", e))?;
+    let remote_id = match remote.id {
+        serde_json::Value::String(id) => id,
+        other => other.to_string(),
+    };
+    Ok(Some(remote_id))
+}
+
+async fn process_due(
+    client: &reqwest::Client,
+    pool: &PgPool,
+    base_url: &str,
+) -> Result<(), sqlx::Error> {
+    let jobs = sqlx::query_as::<_, SyncJob>(
+        r#"SELECT sl.portal_id, sl.property_id, sl.action, sl.remote_id, sl.attempts,
+                  s.endpoint_url, s.api_key
+        FROM syndication_listings sl
+        JOIN syndication_portals s ON s.id = sl.portal_id
+        WHERE s.enabled AND sl.status IN ('pending', 'failed')
+          AND sl.attempts < $1 AND sl.next_attempt_at <= NOW()
+        ORDER BY sl.next_attempt_at
+        LIMIT $2"#,
+    )
+    .bind(MAX_ATTEMPTS)
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    for job in jobs {
+        match push_job(client, pool, base_url, &job).await {
+            Ok(Some(remote_id)) => {
+                sqlx::query(
+                    r#"UPDATE syndication_listings
+                    SET remote_id = $3, status = 'synced', attempts = 0, last_error = NULL, synced_at = NOW()
+                    WHERE portal_id = $1 AND property_id = $2"#,
+                )
+                .bind(job.portal_id)
+                .bind(job.property_id)
+                .bind(&remote_id)
+                .execute(pool)
+                .await?;
+            }
+            Ok(None) => {
+                sqlx::query(
+                    r#"UPDATE syndication_listings
+                    SET remote_id = NULL, status = 'removed', attempts = 0, last_error = NULL, synced_at = NOW()
+                    WHERE portal_id = $1 AND property_id = $2"#,
+                )
+                .bind(job.portal_id)
+                .bind(job.property_id)
+                .execute(pool)
+                .await?;
+            }
+            Err(message) => {
+                warn!(
+                    "Syndication of {} to portal {} failed: {}",
+                    job.property_id, job.portal_id, message
+                );
+                sqlx::query(
+                    r#"UPDATE syndication_listings
+                    SET status = 'failed', attempts = attempts + 1, last_error = $3,
+                        next_attempt_at = NOW() + make_interval(mins => $4)
+                    WHERE portal_id = $1 AND property_id = $2"#,
+                )
+                .bind(job.portal_id)
+                .bind(job.property_id)
+                .bind(&message)
+                .bind(backoff_minutes(job.attempts))
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn spawn_worker(pool: PgPool, base_url: String) {
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(PUSH_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Syndication disabled, HTTP client failed to build: {}", e);
+                return;
+            }
+        };
+        let mut interval = tokio::time::interval(Duration::from_secs(WORKER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = enqueue_changes(&pool).await {
+                error!("Failed to queue syndication changes: {}", e);
+                continue;
+            }
+            if let Err(e) = process_due(&client, &pool, &base_url).await {
+                error!("Syndication run failed: {}", e);
+            }
+        }
+    });
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/admin/syndication/portals")]
+pub async fn create_portal(
+    _admin: AdminUser,
+    req: web::Json<CreatePortalRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if req.name.trim().is_empty() || req.api_key.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "name and api_key are required"
+        }));
+    }
+    if !req.endpoint_url.starts_with("https://") {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "endpoint_url must be https"
+        }));
+    }
+
+    match sqlx::query_as::<_, Portal>(
+        r#"INSERT INTO syndication_portals (name, endpoint_url, api_key)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, endpoint_url, enabled, created_at"#,
+    )
+    .bind(req.name.trim())
+    .bind(req.endpoint_url.trim_end_matches('/'))
+    .bind(req.api_key.trim())
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(portal) => {
+            info!("Syndication portal '{}' added", portal.name);
+            HttpResponse::Ok().json(portal)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": "A portal with that name already exists"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to create syndication portal: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create portal"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/syndication/portals")]
+pub async fn list_portals(_admin: AdminUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, Portal>(
+        "SELECT id, name, endpoint_url, enabled, created_at FROM syndication_portals ORDER BY name",
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(portals) => HttpResponse::Ok().json(portals),
+        Err(e) => {
+            error!("Failed to fetch syndication portals: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch portals"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/syndication/properties/{id}")]
+pub async fn property_syndication(
+    _admin: AdminUser,
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query_as::<_, SyncStatus>(
+        r#"SELECT s.name AS portal, sl.remote_id, sl.action, sl.status, sl.attempts,
+                  sl.last_error, sl.synced_at
+        FROM syndication_listings sl
+        JOIN syndication_portals s ON s.id = sl.portal_id
+        WHERE sl.property_id = $1
+        ORDER BY s.name"#,
+    )
+    .bind(path.into_inner())
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(statuses) => HttpResponse::Ok().json(statuses),
+        Err(e) => {
+            error!("Failed to fetch syndication status: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch syndication status"
+            }))
+        }
+    }
+}
+
+/// Re-queues failed pushes, including ones that exhausted their attempts.
+#[post("/api/admin/syndication/retry")]
+pub async fn retry_failed(
+    _admin: AdminUser,
+    query: web::Query<RetryQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query(
+        r#"UPDATE syndication_listings
+        SET status = 'pending', attempts = 0, next_attempt_at = NOW()
+        WHERE status = 'failed' AND ($1::uuid IS NULL OR portal_id = $1)"#,
+    )
+    .bind(query.portal_id)
+    .execute(&state.db)
+    .await
+    {
+        Ok(result) => HttpResponse::Ok().json(serde_json::json!({
+            "requeued": result.rows_affected()
+        })),
+        Err(e) => {
+            error!("Failed to requeue syndication jobs: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to requeue"
+            }))
+        }
+    }
+}
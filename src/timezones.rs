@@ -0,0 +1,34 @@
+// JARVIS2026 - Timezone handling
+// Users and listings carry an IANA timezone so anything tied to a local day or
+// time of day (viewing times, the daily check-in reward cap, listing
+// statistics) is evaluated where it happens, not in server time. Indonesia
+// spans WIB, WITA and WIT.
+
+use chrono_tz::Tz;
+
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Jakarta;
+
+/// Parses an IANA timezone name such as `Asia/Makassar`.
+pub fn parse(raw: &str) -> Result<Tz, String> {
+    raw.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone '{}'", raw.trim()))
+}
+
+/// Best guess for a listing without an explicit timezone. Indonesian zone
+/// borders roughly follow longitude: Bali, Nusa Tenggara, Sulawesi and
+/// eastern Kalimantan are WITA; Maluku and Papua are WIT.
+pub fn for_coordinates(latitude: Option<f64>, longitude: Option<f64>) -> Tz {
+    match latitude.zip(longitude) {
+        Some((lat, lng)) if (-11.5..=6.5).contains(&lat) && (94.0..=141.5).contains(&lng) => {
+            if lng >= 127.0 {
+                chrono_tz::Asia::Jayapura
+            } else if lng >= 114.5 {
+                chrono_tz::Asia::Makassar
+            } else {
+                chrono_tz::Asia::Jakarta
+            }
+        }
+        _ => DEFAULT_TIMEZONE,
+    }
+}
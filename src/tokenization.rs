@@ -0,0 +1,606 @@
+// JARVIS2026 - Fractional ownership
+// An owner can opt an approved listing into tokenization, splitting it into
+// shares held in the `property_shares` ledger. Shares are bought from and
+// sold back to the issuer, and rental income is paid out pro rata. Every
+// payment settles in platform tokens through `token_transactions`.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::account_status;
+use crate::auth::CurrentUser;
+use crate::AppState;
+
+const MAX_TOTAL_SHARES: i64 = 1_000_000;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct TokenizeRequest {
+    total_shares: i64,
+    /// Price in platform tokens
+    price_per_share: i64,
+}
+
+#[derive(Deserialize)]
+pub struct TradeRequest {
+    shares: i64,
+}
+
+#[derive(Deserialize)]
+pub struct DistributionRequest {
+    /// Rental income to distribute, in platform tokens
+    amount: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Offering {
+    property_id: Uuid,
+    issuer_user_id: Uuid,
+    total_shares: i64,
+    price_per_share: i64,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Holding {
+    user_id: Uuid,
+    shares: i64,
+}
+
+#[derive(Serialize)]
+struct CapTableEntry {
+    user_id: Uuid,
+    shares: i64,
+    ownership: f64,
+    is_issuer: bool,
+}
+
+#[derive(Serialize)]
+struct CapTable {
+    #[serde(flatten)]
+    offering: Offering,
+    /// Shares still held by the issuer and available to buy
+    available_shares: i64,
+    holders: Vec<CapTableEntry>,
+}
+
+#[derive(Serialize)]
+struct DistributionResult {
+    property_id: Uuid,
+    amount: i64,
+    distributed: i64,
+    recipients: usize,
+}
+
+/// Why a trade or payout was refused; mapped to a 4xx response.
+enum LedgerError {
+    NotFound(&'static str),
+    Conflict(String),
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for LedgerError {
+    fn from(e: sqlx::Error) -> Self {
+        LedgerError::Db(e)
+    }
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS property_offerings (
+            property_id UUID PRIMARY KEY REFERENCES properties(id) ON DELETE RESTRICT,
+            issuer_user_id UUID NOT NULL REFERENCES users(id),
+            total_shares BIGINT NOT NULL CHECK (total_shares > 0),
+            price_per_share BIGINT NOT NULL CHECK (price_per_share > 0),
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Append-only; a holder's position is the sum of their deltas
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS property_shares (
+            id BIGSERIAL PRIMARY KEY,
+            property_id UUID NOT NULL REFERENCES property_offerings(property_id) ON DELETE RESTRICT,
+            user_id UUID NOT NULL REFERENCES users(id),
+            delta BIGINT NOT NULL,
+            kind TEXT NOT NULL,
+            price_per_share BIGINT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_property_shares_holder ON property_shares(property_id, user_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS share_distributions (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            property_id UUID NOT NULL REFERENCES property_offerings(property_id) ON DELETE RESTRICT,
+            amount BIGINT NOT NULL,
+            distributed BIGINT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Shares are paid for: a listing with an offering can't be deleted out
+    // from under its holders. Tables created before this cascaded.
+    let cascading = sqlx::query_as::<_, (String, String, String)>(
+        r#"SELECT c.conname, c.conrelid::regclass::text, c.confrelid::regclass::text
+        FROM pg_constraint c
+        WHERE c.contype = 'f' AND c.confdeltype = 'c'
+          AND c.conrelid::regclass::text IN
+              ('property_offerings', 'property_shares', 'share_distributions')"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    for (constraint, table, referenced) in cascading {
+        let column = if referenced == "properties" {
+            "id"
+        } else {
+            "property_id"
+        };
+        sqlx::query(&format!(
+            "ALTER TABLE {table} DROP CONSTRAINT {constraint}, \
+             ADD CONSTRAINT {constraint} FOREIGN KEY (property_id) \
+             REFERENCES {referenced}({column}) ON DELETE RESTRICT"
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// LEDGER
+// ============================================================================
+
+/// The listings among `property_ids` that have a share offering. Their
+/// holders paid for their positions, so these listings can't be deleted.
+pub async fn offered_listings<'c, E>(
+    executor: E,
+    property_ids: &[Uuid],
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    sqlx::query_scalar::<_, Uuid>(
+        "SELECT property_id FROM property_offerings WHERE property_id = ANY($1)",
+    )
+    .bind(property_ids)
+    .fetch_all(executor)
+    .await
+}
+
+/// Locks the offering so concurrent trades on one property apply in order.
+async fn lock_offering(
+    tx: &mut Transaction<'_, Postgres>,
+    property_id: Uuid,
+) -> Result<Offering, LedgerError> {
+    sqlx::query_as::<_, Offering>(
+        r#"SELECT property_id, issuer_user_id, total_shares, price_per_share, created_at
+        FROM property_offerings WHERE property_id = $1 FOR UPDATE"#,
+    )
+    .bind(property_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(LedgerError::NotFound("Property is not tokenized"))
+}
+
+async fn holdings<'c, E>(executor: E, property_id: Uuid) -> Result<Vec<Holding>, sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    sqlx::query_as::<_, Holding>(
+        r#"SELECT user_id, SUM(delta)::BIGINT AS shares FROM property_shares
+        WHERE property_id = $1
+        GROUP BY user_id HAVING SUM(delta) > 0
+        ORDER BY shares DESC, user_id"#,
+    )
+    .bind(property_id)
+    .fetch_all(executor)
+    .await
+}
+
+fn shares_of(holdings: &[Holding], user_id: Uuid) -> i64 {
+    holdings
+        .iter()
+        .find(|h| h.user_id == user_id)
+        .map(|h| h.shares)
+        .unwrap_or(0)
+}
+
+async fn record_shares(
+    tx: &mut Transaction<'_, Postgres>,
+    offering: &Offering,
+    user_id: Uuid,
+    delta: i64,
+    kind: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO property_shares (property_id, user_id, delta, kind, price_per_share)
+        VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(offering.property_id)
+    .bind(user_id)
+    .bind(delta)
+    .bind(kind)
+    .bind(offering.price_per_share)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Moves tokens between users, failing if the payer can't cover it.
+async fn transfer_tokens(
+    tx: &mut Transaction<'_, Postgres>,
+    from: Uuid,
+    to: Uuid,
+    amount: i64,
+    transaction_type: &str,
+) -> Result<(), LedgerError> {
+    let debited = sqlx::query(
+        "UPDATE users SET token_balance = token_balance - $1 WHERE id = $2 AND token_balance >= $1",
+    )
+    .bind(amount)
+    .bind(from)
+    .execute(&mut **tx)
+    .await?;
+    if debited.rows_affected() == 0 {
+        return Err(LedgerError::Conflict(format!(
+            "Insufficient token balance ({} needed)",
+            amount
+        )));
+    }
+
+    sqlx::query("UPDATE users SET token_balance = token_balance + $1 WHERE id = $2")
+        .bind(amount)
+        .bind(to)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query(
+        r#"INSERT INTO token_transactions (user_id, amount, transaction_type)
+        VALUES ($1, $2, $4), ($3, -$2, $4)"#,
+    )
+    .bind(to)
+    .bind(amount)
+    .bind(from)
+    .bind(transaction_type)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Buys `shares` from the issuer (positive) or sells them back (negative).
+async fn trade(
+    pool: &PgPool,
+    property_id: Uuid,
+    user_id: Uuid,
+    shares: i64,
+) -> Result<(), LedgerError> {
+    let mut tx = pool.begin().await?;
+    let offering = lock_offering(&mut tx, property_id).await?;
+    if offering.issuer_user_id == user_id {
+        return Err(LedgerError::Conflict(
+            "The issuer can't trade their own shares".to_string(),
+        ));
+    }
+
+    let holdings = holdings(&mut *tx, property_id).await?;
+    let (seller, buyer) = if shares > 0 {
+        (offering.issuer_user_id, user_id)
+    } else {
+        (user_id, offering.issuer_user_id)
+    };
+    let quantity = shares.abs();
+    if shares_of(&holdings, seller) < quantity {
+        return Err(LedgerError::Conflict(if shares > 0 {
+            "Not enough shares available".to_string()
+        } else {
+            "You don't hold that many shares".to_string()
+        }));
+    }
+
+    let cost = quantity
+        .checked_mul(offering.price_per_share)
+        .ok_or_else(|| LedgerError::Conflict("Trade too large".to_string()))?;
+    transfer_tokens(&mut tx, buyer, seller, cost, "share_trade").await?;
+    record_shares(&mut tx, &offering, seller, -quantity, "sell").await?;
+    record_shares(&mut tx, &offering, buyer, quantity, "buy").await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Pays `amount` from the issuer to other holders in proportion to their shares.
+/// The issuer's own stake keeps its portion, and rounding remainders stay with
+/// the issuer.
+async fn distribute(
+    pool: &PgPool,
+    property_id: Uuid,
+    issuer: Uuid,
+    amount: i64,
+) -> Result<DistributionResult, LedgerError> {
+    let mut tx = pool.begin().await?;
+    let offering = lock_offering(&mut tx, property_id).await?;
+    if offering.issuer_user_id != issuer {
+        return Err(LedgerError::NotFound("Property is not tokenized by you"));
+    }
+
+    let mut distributed = 0i64;
+    let mut recipients = 0usize;
+    for holder in holdings(&mut *tx, property_id).await? {
+        if holder.user_id == issuer {
+            continue;
+        }
+        let payout =
+            (amount as i128 * holder.shares as i128 / offering.total_shares as i128) as i64;
+        if payout == 0 {
+            continue;
+        }
+        transfer_tokens(&mut tx, issuer, holder.user_id, payout, "rental_income").await?;
+        distributed += payout;
+        recipients += 1;
+    }
+
+    sqlx::query(
+        "INSERT INTO share_distributions (property_id, amount, distributed) VALUES ($1, $2, $3)",
+    )
+    .bind(property_id)
+    .bind(amount)
+    .bind(distributed)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(DistributionResult {
+        property_id,
+        amount,
+        distributed,
+        recipients,
+    })
+}
+
+fn ledger_error_response(e: LedgerError, action: &str) -> HttpResponse {
+    match e {
+        LedgerError::NotFound(message) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": message }))
+        }
+        LedgerError::Conflict(message) => {
+            HttpResponse::Conflict().json(serde_json::json!({ "error": message }))
+        }
+        LedgerError::Db(e) => {
+            error!("Failed to {}: {}", action, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to {}", action)
+            }))
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Opts an approved listing into fractional ownership; the owner starts
+/// holding every share.
+#[post("/api/properties/{id}/tokenize")]
+pub async fn tokenize_property(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<TokenizeRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+
+    if !(1..=MAX_TOTAL_SHARES).contains(&req.total_shares) || req.price_per_share <= 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!(
+                "total_shares must be 1-{} and price_per_share positive",
+                MAX_TOTAL_SHARES
+            )
+        }));
+    }
+
+    match sqlx::query_as::<_, (Option<Uuid>, String)>(
+        "SELECT user_id, moderation_status FROM properties WHERE id = $1",
+    )
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some((owner, _))) if owner != Some(user.id) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only the listing's owner can tokenize it"
+            }))
+        }
+        Ok(Some((_, status))) if status != "approved" => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Only approved listings can be tokenized"
+            }))
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to tokenize property"
+            }));
+        }
+    }
+
+    let result: Result<Option<Offering>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let Some(offering) = sqlx::query_as::<_, Offering>(
+            r#"INSERT INTO property_offerings (property_id, issuer_user_id, total_shares, price_per_share)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (property_id) DO NOTHING
+            RETURNING property_id, issuer_user_id, total_shares, price_per_share, created_at"#,
+        )
+        .bind(property_id)
+        .bind(user.id)
+        .bind(req.total_shares)
+        .bind(req.price_per_share)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        record_shares(&mut tx, &offering, user.id, offering.total_shares, "issue").await?;
+        tx.commit().await?;
+        Ok(Some(offering))
+    }
+    .await;
+
+    match result {
+        Ok(Some(offering)) => {
+            info!(
+                "Property {} tokenized into {} shares",
+                property_id, offering.total_shares
+            );
+            HttpResponse::Ok().json(offering)
+        }
+        Ok(None) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Property is already tokenized"
+        })),
+        Err(e) => {
+            error!("Failed to tokenize property {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to tokenize property"
+            }))
+        }
+    }
+}
+
+#[get("/api/properties/{id}/cap-table")]
+pub async fn cap_table(path: web::Path<Uuid>, state: web::Data<AppState>) -> impl Responder {
+    let property_id = path.into_inner();
+
+    let offering = sqlx::query_as::<_, Offering>(
+        r#"SELECT property_id, issuer_user_id, total_shares, price_per_share, created_at
+        FROM property_offerings WHERE property_id = $1"#,
+    )
+    .bind(property_id)
+    .fetch_optional(&state.db);
+
+    match tokio::try_join!(offering, holdings(&state.db, property_id)) {
+        Ok((Some(offering), holdings)) => {
+            let available_shares = shares_of(&holdings, offering.issuer_user_id);
+            let holders = holdings
+                .into_iter()
+                .map(|h| CapTableEntry {
+                    user_id: h.user_id,
+                    shares: h.shares,
+                    ownership: h.shares as f64 / offering.total_shares as f64,
+                    is_issuer: h.user_id == offering.issuer_user_id,
+                })
+                .collect();
+            HttpResponse::Ok().json(CapTable {
+                offering,
+                available_shares,
+                holders,
+            })
+        }
+        Ok((None, _)) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Property is not tokenized"
+        })),
+        Err(e) => {
+            error!("Failed to load cap table for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load cap table"
+            }))
+        }
+    }
+}
+
+#[post("/api/properties/{id}/shares/buy")]
+pub async fn buy_shares(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<TradeRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if req.shares <= 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "shares must be positive"
+        }));
+    }
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+    match trade(&state.db, path.into_inner(), user.id, req.shares).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => ledger_error_response(e, "buy shares"),
+    }
+}
+
+/// Sells shares back to the issuer at the offering price.
+#[post("/api/properties/{id}/shares/sell")]
+pub async fn sell_shares(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<TradeRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if req.shares <= 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "shares must be positive"
+        }));
+    }
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+    match trade(&state.db, path.into_inner(), user.id, -req.shares).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => ledger_error_response(e, "sell shares"),
+    }
+}
+
+#[post("/api/properties/{id}/distributions")]
+pub async fn distribute_income(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<DistributionRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    if req.amount <= 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "amount must be positive"
+        }));
+    }
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+    match distribute(&state.db, path.into_inner(), user.id, req.amount).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(e) => ledger_error_response(e, "distribute income"),
+    }
+}
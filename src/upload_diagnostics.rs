@@ -0,0 +1,440 @@
+// JARVIS2026 - Upload recording and replay
+// With `UPLOAD_DIAGNOSTICS` on, every completed upload leaves a recording:
+// the multipart fields with free text reduced to lengths, and the decision
+// taken for each file (content hash, duplicate verdict, reward, watermark).
+// Admins can replay a recording against the current pipeline, which works
+// the same decisions out again from today's code, policy and data without
+// changing anything, and reports where the two disagree.
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tokio::fs as async_fs;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::agencies;
+use crate::auth::AdminUser;
+use crate::upload_policy;
+use crate::AppState;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+/// What the upload handler decided for one file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaDecision {
+    pub media_id: Uuid,
+    pub filename: String,
+    pub is_drone: bool,
+    pub bytes: i64,
+    pub file_type: String,
+    /// Of the file as submitted, before any watermark
+    pub content_hash: String,
+    pub duplicate: bool,
+    pub over_cap: bool,
+    pub tokens: i64,
+    pub watermarked: bool,
+}
+
+/// Collected while an upload is processed and saved once it succeeds.
+pub struct Recording {
+    fields: serde_json::Value,
+    media: Vec<MediaDecision>,
+}
+
+/// Multipart fields worth keeping; titles, descriptions and coordinates are
+/// reduced to whether (and how much) was sent.
+pub struct SubmittedFields<'a> {
+    pub title: &'a str,
+    pub location: &'a str,
+    pub description: &'a str,
+    pub price: f64,
+    pub bedrooms: Option<i32>,
+    pub bathrooms: Option<i32>,
+    pub area_sqm: Option<f64>,
+    pub has_coordinates: bool,
+    pub property_type: Option<&'a str>,
+    pub certificate_type: Option<&'a str>,
+    pub timezone: Option<&'a str>,
+    pub confirm_price: bool,
+    pub draft: bool,
+    /// (filename, bytes, is_drone)
+    pub files: &'a [(&'a str, usize, bool)],
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct RecordingRow {
+    id: Uuid,
+    user_id: Uuid,
+    property_id: Option<Uuid>,
+    fields: serde_json::Value,
+    decisions: sqlx::types::Json<Vec<MediaDecision>>,
+    reward_cap: i64,
+    recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct RecordingsQuery {
+    user_id: Option<Uuid>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct MediaReplay {
+    recorded: MediaDecision,
+    replayed: MediaDecision,
+    /// Names of the decisions that came out differently
+    differences: Vec<&'static str>,
+    /// The stored file is gone, so its hash couldn't be checked
+    file_missing: bool,
+}
+
+#[derive(Serialize)]
+struct ReplayReport {
+    recording_id: Uuid,
+    property_id: Option<Uuid>,
+    recorded_at: chrono::DateTime<chrono::Utc>,
+    recorded_reward_cap: i64,
+    current_reward_cap: i64,
+    /// Why the current upload policy would turn the upload away, if it would
+    policy_rejection: Option<String>,
+    media: Vec<MediaReplay>,
+    identical: bool,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS upload_recordings (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            property_id UUID REFERENCES properties(id) ON DELETE SET NULL,
+            fields JSONB NOT NULL,
+            decisions JSONB NOT NULL,
+            reward_cap BIGINT NOT NULL,
+            recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_upload_recordings_user ON upload_recordings(user_id, recorded_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// RECORDING
+// ============================================================================
+
+/// Whether uploads are recorded, from `UPLOAD_DIAGNOSTICS`.
+pub fn enabled_from_env() -> bool {
+    std::env::var("UPLOAD_DIAGNOSTICS")
+        .map(|v| matches!(v.trim(), "1" | "true" | "on"))
+        .unwrap_or(false)
+}
+
+impl Recording {
+    pub fn new(submitted: &SubmittedFields) -> Self {
+        let files: Vec<serde_json::Value> = submitted
+            .files
+            .iter()
+            .map(|(filename, bytes, is_drone)| {
+                serde_json::json!({
+                    "field": if *is_drone { "drone_files" } else { "files" },
+                    "filename": filename,
+                    "bytes": bytes
+                })
+            })
+            .collect();
+        Recording {
+            fields: serde_json::json!({
+                "title_chars": submitted.title.chars().count(),
+                "location_chars": submitted.location.chars().count(),
+                "description_chars": submitted.description.chars().count(),
+                "price": submitted.price,
+                "bedrooms": submitted.bedrooms,
+                "bathrooms": submitted.bathrooms,
+                "area_sqm": submitted.area_sqm,
+                "has_coordinates": submitted.has_coordinates,
+                "property_type": submitted.property_type,
+                "certificate_type": submitted.certificate_type,
+                "timezone": submitted.timezone,
+                "confirm_price": submitted.confirm_price,
+                "draft": submitted.draft,
+                "files": files
+            }),
+            media: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, decision: MediaDecision) {
+        self.media.push(decision);
+    }
+
+    /// Failures are logged; a recording never fails the upload.
+    pub async fn save(self, pool: &PgPool, user_id: Uuid, property_id: Uuid, reward_cap: i64) {
+        let result = sqlx::query(
+            r#"INSERT INTO upload_recordings (user_id, property_id, fields, decisions, reward_cap)
+            VALUES ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(user_id)
+        .bind(property_id)
+        .bind(&self.fields)
+        .bind(sqlx::types::Json(&self.media))
+        .bind(reward_cap)
+        .execute(pool)
+        .await;
+        if let Err(e) = result {
+            error!("Failed to record upload of {}: {}", property_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// REPLAY
+// ============================================================================
+
+impl MediaDecision {
+    fn differences(&self, other: &MediaDecision) -> Vec<&'static str> {
+        [
+            ("file_type", self.file_type != other.file_type),
+            ("content_hash", self.content_hash != other.content_hash),
+            ("duplicate", self.duplicate != other.duplicate),
+            ("over_cap", self.over_cap != other.over_cap),
+            ("tokens", self.tokens != other.tokens),
+            ("watermarked", self.watermarked != other.watermarked),
+        ]
+        .into_iter()
+        .filter_map(|(name, differs)| differs.then_some(name))
+        .collect()
+    }
+}
+
+async fn hash_stored_file(pool: &PgPool, media_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    let Some(file_path) =
+        sqlx::query_scalar::<_, String>("SELECT file_path FROM media_uploads WHERE id = $1")
+            .bind(media_id)
+            .fetch_optional(pool)
+            .await?
+    else {
+        return Ok(None);
+    };
+    Ok(async_fs::read(&file_path)
+        .await
+        .ok()
+        .map(|data| hex::encode(Sha256::digest(&data))))
+}
+
+/// Works out each decision again with today's pipeline. Duplicates are
+/// judged against media that existed when the upload was recorded, counting
+/// the files before it in the same upload, as the handler does.
+async fn replay(state: &AppState, recording: RecordingRow) -> Result<ReplayReport, sqlx::Error> {
+    let decisions = recording.decisions.0;
+    let policy = upload_policy::for_user(&state.db, recording.user_id).await?;
+    let submitted: Vec<(&str, usize, bool)> = decisions
+        .iter()
+        .map(|d| (d.filename.as_str(), d.bytes as usize, d.is_drone))
+        .collect();
+    let policy_rejection = policy.check(submitted.into_iter()).err();
+    let watermark_logo = if policy.watermark {
+        agencies::for_user(&state.db, recording.user_id)
+            .await?
+            .and_then(|b| b.logo_path)
+    } else {
+        None
+    };
+
+    let mut rewarded_count = 0i64;
+    let mut media = Vec::with_capacity(decisions.len());
+    for (i, recorded) in decisions.iter().enumerate() {
+        let not_yet_uploaded: Vec<Uuid> = decisions[i..].iter().map(|d| d.media_id).collect();
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            r#"SELECT EXISTS(
+                SELECT 1 FROM media_uploads
+                WHERE content_hash = $1 AND uploaded_at <= $2 AND id <> ALL($3)
+            )"#,
+        )
+        .bind(&recorded.content_hash)
+        .bind(recording.recorded_at)
+        .bind(&not_yet_uploaded)
+        .fetch_one(&state.db)
+        .await?;
+
+        let file_type = upload_policy::file_type(&recorded.filename, recorded.is_drone);
+        let (over_cap, tokens) = crate::media_reward(
+            !duplicate,
+            rewarded_count,
+            state.reward_cap,
+            recorded.is_drone,
+        );
+        if tokens > 0 {
+            rewarded_count += 1;
+        }
+
+        // A watermarked file no longer matches the submitted hash
+        let (content_hash, file_missing) = if recorded.watermarked {
+            (recorded.content_hash.clone(), false)
+        } else {
+            match hash_stored_file(&state.db, recorded.media_id).await? {
+                Some(hash) => (hash, false),
+                None => (recorded.content_hash.clone(), true),
+            }
+        };
+
+        let replayed = MediaDecision {
+            media_id: recorded.media_id,
+            filename: recorded.filename.clone(),
+            is_drone: recorded.is_drone,
+            bytes: recorded.bytes,
+            file_type: file_type.to_string(),
+            content_hash,
+            duplicate,
+            over_cap,
+            tokens,
+            watermarked: watermark_logo.is_some() && file_type == "image",
+        };
+        media.push(MediaReplay {
+            differences: recorded.differences(&replayed),
+            recorded: recorded.clone(),
+            replayed,
+            file_missing,
+        });
+    }
+
+    Ok(ReplayReport {
+        identical: policy_rejection.is_none() && media.iter().all(|m| m.differences.is_empty()),
+        recording_id: recording.id,
+        property_id: recording.property_id,
+        recorded_at: recording.recorded_at,
+        recorded_reward_cap: recording.reward_cap,
+        current_reward_cap: state.reward_cap,
+        policy_rejection,
+        media,
+    })
+}
+
+async fn fetch_recording(
+    pool: &PgPool,
+    recording_id: Uuid,
+) -> Result<Option<RecordingRow>, sqlx::Error> {
+    sqlx::query_as::<_, RecordingRow>("SELECT * FROM upload_recordings WHERE id = $1")
+        .bind(recording_id)
+        .fetch_optional(pool)
+        .await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/admin/upload-recordings")]
+pub async fn list_recordings(
+    _admin: AdminUser,
+    query: web::Query<RecordingsQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, RecordingRow>(
+        r#"SELECT * FROM upload_recordings
+        WHERE $1::uuid IS NULL OR user_id = $1
+        ORDER BY recorded_at DESC
+        LIMIT $2 OFFSET $3"#,
+    )
+    .bind(query.user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(recordings) => HttpResponse::Ok().json(serde_json::json!({
+            "recording_enabled": state.record_uploads,
+            "limit": limit,
+            "offset": offset,
+            "recordings": recordings
+        })),
+        Err(e) => {
+            error!("Failed to list upload recordings: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to list upload recordings"
+            }))
+        }
+    }
+}
+
+#[get("/api/admin/upload-recordings/{id}")]
+pub async fn get_recording(
+    path: web::Path<Uuid>,
+    _admin: AdminUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let recording_id = path.into_inner();
+    match fetch_recording(&state.db, recording_id).await {
+        Ok(Some(recording)) => HttpResponse::Ok().json(recording),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Recording not found"
+        })),
+        Err(e) => {
+            error!("Failed to load upload recording {}: {}", recording_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load upload recording"
+            }))
+        }
+    }
+}
+
+/// Dry run: nothing is written, rewarded or moved.
+#[post("/api/admin/upload-recordings/{id}/replay")]
+pub async fn replay_recording(
+    path: web::Path<Uuid>,
+    admin: AdminUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let recording_id = path.into_inner();
+
+    let result = async {
+        match fetch_recording(&state.db, recording_id).await? {
+            Some(recording) => replay(&state, recording).await.map(Some),
+            None => Ok(None),
+        }
+    }
+    .await;
+
+    match result {
+        Ok(Some(report)) => {
+            info!(
+                "Admin {} replayed upload recording {} (identical: {})",
+                admin.id, recording_id, report.identical
+            );
+            HttpResponse::Ok().json(report)
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Recording not found"
+        })),
+        Err(e) => {
+            error!("Failed to replay upload recording {}: {}", recording_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to replay upload"
+            }))
+        }
+    }
+}
@@ -0,0 +1,396 @@
+// JARVIS2026 - Per-agency upload policies
+// Each agency (tenant) can have its own upload rules: the largest file and
+// number of files per upload, which media types are accepted, whether photos
+// are watermarked with the agency logo, and how strictly new listings are
+// moderated. Uploaders outside an agency, or in one without a policy, get the
+// defaults. Admins set policies; anyone may read them.
+
+use actix_web::{get, put, web, HttpResponse, Responder};
+use image::{imageops, DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::io::Cursor;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::aerial;
+use crate::audit;
+use crate::auth::AdminUser;
+use crate::models3d;
+use crate::AppState;
+
+/// Moderation status of listings held back by a strict policy.
+pub const HELD_STATUS: &str = "held";
+
+const DEFAULT_MAX_FILE_BYTES: i64 = 500 * 1024 * 1024;
+const DEFAULT_MAX_FILES: i32 = 100;
+const MEDIA_TYPES: &[&str] = &[
+    "image",
+    "video",
+    models3d::MODEL_FILE_TYPE,
+    aerial::DRONE_FILE_TYPE,
+];
+
+/// The watermark spans this fraction of the photo's width
+const WATERMARK_WIDTH_RATIO: f32 = 0.2;
+const WATERMARK_OPACITY: f32 = 0.5;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Moderation {
+    /// Listings go live at once and wait for review
+    #[default]
+    Standard,
+    /// Listings stay hidden until a moderator approves them
+    Strict,
+    /// Listings are approved on upload
+    Trusted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadPolicy {
+    pub max_file_bytes: i64,
+    pub max_files: i32,
+    /// Media types accepted, see `file_type`
+    pub allowed_types: Vec<String>,
+    /// Stamp photos with the agency logo
+    pub watermark: bool,
+    pub moderation: Moderation,
+}
+
+#[derive(sqlx::FromRow)]
+struct PolicyRow {
+    max_file_bytes: i64,
+    max_files: i32,
+    allowed_types: Vec<String>,
+    watermark: bool,
+    moderation: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS upload_policies (
+            agency_id UUID PRIMARY KEY REFERENCES agencies(id) ON DELETE CASCADE,
+            max_file_bytes BIGINT NOT NULL,
+            max_files INTEGER NOT NULL,
+            allowed_types TEXT[] NOT NULL,
+            watermark BOOLEAN NOT NULL DEFAULT FALSE,
+            moderation TEXT NOT NULL DEFAULT 'standard'
+                CHECK (moderation IN ('standard', 'strict', 'trusted')),
+            updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// POLICY RESOLUTION
+// ============================================================================
+
+impl Default for UploadPolicy {
+    fn default() -> Self {
+        UploadPolicy {
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+            allowed_types: MEDIA_TYPES.iter().map(|t| t.to_string()).collect(),
+            watermark: false,
+            moderation: Moderation::default(),
+        }
+    }
+}
+
+impl Moderation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Moderation::Standard => "standard",
+            Moderation::Strict => "strict",
+            Moderation::Trusted => "trusted",
+        }
+    }
+
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "strict" => Moderation::Strict,
+            "trusted" => Moderation::Trusted,
+            _ => Moderation::Standard,
+        }
+    }
+
+    /// `moderation_status` a new listing starts in.
+    pub fn initial_status(self) -> &'static str {
+        match self {
+            Moderation::Standard => "pending",
+            Moderation::Strict => HELD_STATUS,
+            Moderation::Trusted => "approved",
+        }
+    }
+}
+
+impl From<PolicyRow> for UploadPolicy {
+    fn from(row: PolicyRow) -> Self {
+        UploadPolicy {
+            max_file_bytes: row.max_file_bytes,
+            max_files: row.max_files,
+            allowed_types: row.allowed_types,
+            watermark: row.watermark,
+            moderation: Moderation::parse(&row.moderation),
+        }
+    }
+}
+
+const POLICY_COLUMNS: &str =
+    "p.max_file_bytes, p.max_files, p.allowed_types, p.watermark, p.moderation";
+
+async fn for_agency(pool: &PgPool, agency_id: Uuid) -> Result<UploadPolicy, sqlx::Error> {
+    let row = sqlx::query_as::<_, PolicyRow>(&format!(
+        "SELECT {} FROM upload_policies p WHERE p.agency_id = $1",
+        POLICY_COLUMNS
+    ))
+    .bind(agency_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(UploadPolicy::from).unwrap_or_default())
+}
+
+/// The policy of the uploader's agency, or the defaults.
+pub async fn for_user(pool: &PgPool, user_id: Uuid) -> Result<UploadPolicy, sqlx::Error> {
+    let row = sqlx::query_as::<_, PolicyRow>(&format!(
+        r#"SELECT {} FROM upload_policies p
+        JOIN users u ON u.agency_id = p.agency_id
+        WHERE u.id = $1"#,
+        POLICY_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(UploadPolicy::from).unwrap_or_default())
+}
+
+/// The media type an uploaded file is stored as.
+pub fn file_type(filename: &str, is_drone: bool) -> &'static str {
+    if is_drone {
+        aerial::DRONE_FILE_TYPE
+    } else if aerial::is_video_file(filename) {
+        "video"
+    } else if models3d::is_model_file(filename) {
+        models3d::MODEL_FILE_TYPE
+    } else {
+        "image"
+    }
+}
+
+impl UploadPolicy {
+    /// Checks an upload's files, given as (filename, size, is_drone).
+    pub fn check<'a>(
+        &self,
+        files: impl ExactSizeIterator<Item = (&'a str, usize, bool)>,
+    ) -> Result<(), String> {
+        if files.len() > self.max_files as usize {
+            return Err(format!(
+                "At most {} files can be uploaded at once",
+                self.max_files
+            ));
+        }
+        for (filename, size, is_drone) in files {
+            let file_type = file_type(filename, is_drone);
+            if !self.allowed_types.iter().any(|t| t == file_type) {
+                return Err(format!(
+                    "{}: {} uploads are not accepted for this account",
+                    filename, file_type
+                ));
+            }
+            if size as i64 > self.max_file_bytes {
+                return Err(format!(
+                    "{} is larger than the {} MB limit",
+                    filename,
+                    self.max_file_bytes / (1024 * 1024)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_file_bytes <= 0 || self.max_files <= 0 {
+            return Err("max_file_bytes and max_files must be positive".to_string());
+        }
+        if self.allowed_types.is_empty() {
+            return Err("allowed_types needs at least one media type".to_string());
+        }
+        if let Some(unknown) = self
+            .allowed_types
+            .iter()
+            .find(|t| !MEDIA_TYPES.contains(&t.as_str()))
+        {
+            return Err(format!(
+                "Unknown media type '{}'; use {}",
+                unknown,
+                MEDIA_TYPES.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WATERMARKING
+// ============================================================================
+
+/// Stamps the logo, faded, into the bottom right corner and re-encodes the
+/// photo in its original format.
+pub fn watermark(photo: &[u8], logo_path: &str) -> anyhow::Result<Vec<u8>> {
+    let format = image::guess_format(photo)?;
+    let mut canvas = image::load_from_memory_with_format(photo, format)?.to_rgba8();
+
+    let target_width = ((canvas.width() as f32 * WATERMARK_WIDTH_RATIO) as u32).max(1);
+    let mut logo = image::open(logo_path)?
+        .resize(
+            target_width,
+            canvas.height(),
+            imageops::FilterType::Lanczos3,
+        )
+        .to_rgba8();
+    for pixel in logo.pixels_mut() {
+        pixel[3] = (pixel[3] as f32 * WATERMARK_OPACITY) as u8;
+    }
+
+    let margin = canvas.width() / 40;
+    let x = canvas.width().saturating_sub(logo.width() + margin);
+    let y = canvas.height().saturating_sub(logo.height() + margin);
+    imageops::overlay(&mut canvas, &logo, x as i64, y as i64);
+
+    let mut out = Vec::new();
+    let stamped = match format {
+        // JPEG has no alpha channel
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8()),
+        _ => DynamicImage::ImageRgba8(canvas),
+    };
+    stamped.write_to(&mut Cursor::new(&mut out), format)?;
+    Ok(out)
+}
+
+/// Watermarks a photo off the async runtime, keeping the original if that
+/// fails.
+pub async fn watermark_photo(filename: &str, photo: Vec<u8>, logo_path: &str) -> Vec<u8> {
+    let logo_path = logo_path.to_string();
+    let original = photo.clone();
+    match web::block(move || watermark(&photo, &logo_path)).await {
+        Ok(Ok(stamped)) => stamped,
+        Ok(Err(e)) => {
+            warn!("Failed to watermark {}: {}", filename, e);
+            original
+        }
+        Err(e) => {
+            warn!("Watermarking {} was cancelled: {}", filename, e);
+            original
+        }
+    }
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[get("/api/agencies/{agency_id}/upload-policy")]
+pub async fn get_upload_policy(
+    path: web::Path<Uuid>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let agency_id = path.into_inner();
+    match for_agency(&state.db, agency_id).await {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(e) => {
+            error!("Failed to load upload policy of {}: {}", agency_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load upload policy"
+            }))
+        }
+    }
+}
+
+/// Replaces the agency's policy; omitted fields take the defaults.
+#[put("/api/admin/agencies/{agency_id}/upload-policy")]
+pub async fn set_upload_policy(
+    admin: AdminUser,
+    path: web::Path<Uuid>,
+    req: web::Json<UploadPolicy>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let agency_id = path.into_inner();
+    let mut policy = req.into_inner();
+    policy.allowed_types.sort();
+    policy.allowed_types.dedup();
+    if let Err(message) = policy.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": message }));
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query(
+            r#"INSERT INTO upload_policies
+            (agency_id, max_file_bytes, max_files, allowed_types, watermark, moderation, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (agency_id) DO UPDATE SET
+                max_file_bytes = EXCLUDED.max_file_bytes,
+                max_files = EXCLUDED.max_files,
+                allowed_types = EXCLUDED.allowed_types,
+                watermark = EXCLUDED.watermark,
+                moderation = EXCLUDED.moderation,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()"#,
+        )
+        .bind(agency_id)
+        .bind(policy.max_file_bytes)
+        .bind(policy.max_files)
+        .bind(&policy.allowed_types)
+        .bind(policy.watermark)
+        .bind(policy.moderation.as_str())
+        .bind(admin.id)
+        .execute(&mut *tx)
+        .await?;
+        audit::record(
+            &mut tx,
+            admin.id,
+            "agency.upload_policy",
+            "agency",
+            agency_id,
+            serde_json::json!(policy),
+        )
+        .await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            info!(
+                "Upload policy of agency {} updated by {}",
+                agency_id, admin.id
+            );
+            HttpResponse::Ok().json(policy)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => HttpResponse::NotFound()
+            .json(serde_json::json!({
+                "error": "Agency not found"
+            })),
+        Err(e) => {
+            error!("Failed to set upload policy of {}: {}", agency_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update upload policy"
+            }))
+        }
+    }
+}
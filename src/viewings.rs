@@ -0,0 +1,516 @@
+// JARVIS2026 - Property viewings
+// Visitors book a viewing; at the property the agent shows a per-viewing QR
+// code that the visitor scans to check in. A check-in with the right code,
+// on time and on site counts as verified attendance, which can earn the
+// visitor a token reward: once per listing and a few times a day at most.
+// Listings without coordinates can't verify attendance and never reward.
+
+use actix_web::{get, http::header, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::account_status;
+use crate::auth::CurrentUser;
+use crate::geo::haversine_km;
+use crate::listing_status::ACTIVE_LISTING_CONDITION;
+use crate::moderation::PUBLIC_LISTING_CONDITION;
+use crate::notifications::notify;
+use crate::sharing::render_qr_png;
+use crate::timezones;
+use crate::AppState;
+
+const QR_SIZE: u32 = 512;
+const VIEWING_COLUMNS: &str = r#"v.id, v.property_id, v.agent_user_id, v.visitor_user_id,
+    v.scheduled_at, v.status, v.checked_in_at, v.verified, v.tokens_awarded,
+    v.check_in_code, p.timezone"#;
+/// Check-ins are accepted from an hour before until three hours after the slot.
+const CHECK_IN_EARLY_MINUTES: i64 = 60;
+const CHECK_IN_LATE_MINUTES: i64 = 180;
+/// How close the visitor's reported position must be to count as on site.
+const ON_SITE_RADIUS_KM: f64 = 0.3;
+/// Upcoming viewings one visitor may have booked at once.
+const MAX_OPEN_VIEWINGS: i64 = 10;
+/// Rewarded check-ins per visitor per calendar day in their timezone.
+const MAX_REWARDED_PER_DAY: i64 = 3;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct BookViewingRequest {
+    scheduled_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct CheckInRequest {
+    code: String,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Viewing {
+    id: Uuid,
+    property_id: Uuid,
+    agent_user_id: Uuid,
+    visitor_user_id: Uuid,
+    scheduled_at: DateTime<Utc>,
+    status: String,
+    checked_in_at: Option<DateTime<Utc>>,
+    verified: bool,
+    tokens_awarded: i64,
+    #[serde(skip)]
+    check_in_code: String,
+    /// The listing's IANA timezone
+    timezone: String,
+    #[sqlx(skip)]
+    scheduled_local: String,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS viewings (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            agent_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            visitor_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            scheduled_at TIMESTAMPTZ NOT NULL,
+            status TEXT NOT NULL DEFAULT 'scheduled',
+            check_in_code TEXT NOT NULL,
+            checked_in_at TIMESTAMPTZ,
+            verified BOOLEAN NOT NULL DEFAULT false,
+            tokens_awarded BIGINT NOT NULL DEFAULT 0,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_viewings_agent ON viewings(agent_user_id, scheduled_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_viewings_visitor ON viewings(visitor_user_id, scheduled_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+impl Viewing {
+    /// Fills in the slot as wall-clock time at the property.
+    fn localized(mut self) -> Self {
+        let tz = timezones::parse(&self.timezone).unwrap_or(timezones::DEFAULT_TIMEZONE);
+        self.scheduled_local = self.scheduled_at.with_timezone(&tz).to_rfc3339();
+        self
+    }
+}
+
+async fn fetch_viewing(pool: &PgPool, viewing_id: Uuid) -> Result<Option<Viewing>, sqlx::Error> {
+    sqlx::query_as::<_, Viewing>(&format!(
+        "SELECT {} FROM viewings v JOIN properties p ON p.id = v.property_id WHERE v.id = $1",
+        VIEWING_COLUMNS
+    ))
+    .bind(viewing_id)
+    .fetch_optional(pool)
+    .await
+    .map(|v| v.map(Viewing::localized))
+}
+
+/// Token reward for verified attendance, from `VIEWING_CHECKIN_REWARD`; 0 disables it.
+fn check_in_reward() -> i64 {
+    std::env::var("VIEWING_CHECKIN_REWARD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v: &i64| *v > 0)
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+#[post("/api/properties/{id}/viewings")]
+pub async fn book_viewing(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<BookViewingRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+
+    if req.scheduled_at <= Utc::now() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "scheduled_at must be in the future"
+        }));
+    }
+
+    let agent_user_id = match sqlx::query_scalar::<_, Option<Uuid>>(&format!(
+        "SELECT user_id FROM properties WHERE id = $1 AND {} AND {}",
+        PUBLIC_LISTING_CONDITION, ACTIVE_LISTING_CONDITION
+    ))
+    .bind(property_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(Some(agent))) if agent == user.id => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "You can't book a viewing of your own listing"
+            }))
+        }
+        Ok(Some(Some(agent))) => agent,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Property not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to look up property {}: {}", property_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to book viewing"
+            }));
+        }
+    };
+
+    enum Booking {
+        Booked(Uuid),
+        AlreadyBooked,
+        TooMany,
+    }
+
+    let result: Result<Booking, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        // One booking at a time per visitor, so the limits below hold
+        sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+        let (open, open_here) = sqlx::query_as::<_, (i64, bool)>(
+            r#"SELECT COUNT(*), COALESCE(BOOL_OR(property_id = $2), false)
+            FROM viewings
+            WHERE visitor_user_id = $1 AND status = 'scheduled' AND scheduled_at > NOW()"#,
+        )
+        .bind(user.id)
+        .bind(property_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if open_here {
+            return Ok(Booking::AlreadyBooked);
+        }
+        if open >= MAX_OPEN_VIEWINGS {
+            return Ok(Booking::TooMany);
+        }
+
+        let viewing_id = sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO viewings (property_id, agent_user_id, visitor_user_id, scheduled_at, check_in_code)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id"#,
+        )
+        .bind(property_id)
+        .bind(agent_user_id)
+        .bind(user.id)
+        .bind(req.scheduled_at)
+        .bind(Uuid::new_v4().simple().to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+        notify(
+            &mut *tx,
+            agent_user_id,
+            "viewing_booked",
+            serde_json::json!({
+                "viewing_id": viewing_id,
+                "property_id": property_id,
+                "scheduled_at": req.scheduled_at,
+            }),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(Booking::Booked(viewing_id))
+    }
+    .await;
+
+    match result {
+        Ok(Booking::Booked(viewing_id)) => match fetch_viewing(&state.db, viewing_id).await {
+            Ok(Some(viewing)) => HttpResponse::Ok().json(viewing),
+            _ => HttpResponse::Ok().json(serde_json::json!({ "id": viewing_id })),
+        },
+        Ok(Booking::AlreadyBooked) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "You already have a viewing of this listing booked"
+        })),
+        Ok(Booking::TooMany) => HttpResponse::TooManyRequests().json(serde_json::json!({
+            "error": format!("You can have at most {} upcoming viewings", MAX_OPEN_VIEWINGS)
+        })),
+        Err(e) => {
+            error!("Failed to book viewing for {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to book viewing"
+            }))
+        }
+    }
+}
+
+/// Viewings the caller booked or hosts, soonest first.
+#[get("/api/users/me/viewings")]
+pub async fn my_viewings(user: CurrentUser, state: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as::<_, Viewing>(&format!(
+        r#"SELECT {} FROM viewings v JOIN properties p ON p.id = v.property_id
+        WHERE v.agent_user_id = $1 OR v.visitor_user_id = $1
+        ORDER BY v.scheduled_at"#,
+        VIEWING_COLUMNS
+    ))
+    .bind(user.id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(viewings) => HttpResponse::Ok().json(
+            viewings
+                .into_iter()
+                .map(Viewing::localized)
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => {
+            error!("Failed to load viewings for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load viewings"
+            }))
+        }
+    }
+}
+
+/// The QR the agent shows on site. It opens the web app, which posts the check-in.
+#[get("/api/viewings/{id}/qr.png")]
+pub async fn viewing_qr_code(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let viewing = match fetch_viewing(&state.db, path.into_inner()).await {
+        Ok(Some(viewing)) if viewing.agent_user_id == user.id => viewing,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Viewing not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load viewing: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }));
+        }
+    };
+
+    let url = format!(
+        "{}/?checkin={}&code={}",
+        state.public_base_url.trim_end_matches('/'),
+        viewing.id,
+        viewing.check_in_code
+    );
+
+    match web::block(move || render_qr_png(&url, QR_SIZE, None)).await {
+        Ok(Ok(png)) => HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header((header::CACHE_CONTROL, "private, no-store"))
+            .body(png),
+        Ok(Err(e)) => {
+            error!("Viewing QR rendering failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }))
+        }
+        Err(e) => {
+            error!("QR rendering task failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to generate QR code"
+            }))
+        }
+    }
+}
+
+#[post("/api/viewings/{id}/check-in")]
+pub async fn check_in(
+    path: web::Path<Uuid>,
+    user: CurrentUser,
+    req: web::Json<CheckInRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let viewing_id = path.into_inner();
+    if let Err(response) = account_status::ensure_active(&state.db, user.id).await {
+        return response;
+    }
+
+    let (viewing, property_location) = match sqlx::query_as::<
+        _,
+        (
+            Uuid,
+            Uuid,
+            DateTime<Utc>,
+            String,
+            String,
+            Option<f64>,
+            Option<f64>,
+        ),
+    >(
+        r#"SELECT v.visitor_user_id, v.property_id, v.scheduled_at, v.status, v.check_in_code,
+                  p.latitude, p.longitude
+        FROM viewings v JOIN properties p ON p.id = v.property_id
+        WHERE v.id = $1"#,
+    )
+    .bind(viewing_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some((visitor, property_id, scheduled_at, status, code, lat, lng)))
+            if visitor == user.id =>
+        {
+            ((property_id, scheduled_at, status, code), lat.zip(lng))
+        }
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Viewing not found"
+            }))
+        }
+        Err(e) => {
+            error!("Failed to load viewing {}: {}", viewing_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to check in"
+            }));
+        }
+    };
+    let (property_id, scheduled_at, status, code) = viewing;
+
+    if req.code.trim() != code {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Invalid check-in code"
+        }));
+    }
+    if status != "scheduled" {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Viewing is already {}", status)
+        }));
+    }
+    let now = Utc::now();
+    if now < scheduled_at - Duration::minutes(CHECK_IN_EARLY_MINUTES)
+        || now > scheduled_at + Duration::minutes(CHECK_IN_LATE_MINUTES)
+    {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Check-in is only open around the scheduled time"
+        }));
+    }
+
+    let on_site = match (property_location, req.latitude.zip(req.longitude)) {
+        (Some(property), Some(visitor)) => haversine_km(property, visitor) <= ON_SITE_RADIUS_KM,
+        (Some(_), None) => false,
+        // Without listing coordinates nothing shows the visitor was there
+        (None, _) => false,
+    };
+
+    let result: Result<Option<(Uuid, i64)>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        // Serializes the visitor's check-ins so the reward caps hold
+        sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+        // "Today" is the visitor's calendar day in their own timezone
+        let (rewarded_here, rewarded_today) = sqlx::query_as::<_, (bool, i64)>(
+            r#"SELECT COALESCE(BOOL_OR(v.property_id = $2), false),
+                      COUNT(v.id) FILTER (
+                          WHERE (v.checked_in_at AT TIME ZONE u.timezone)::date
+                              = (NOW() AT TIME ZONE u.timezone)::date)
+            FROM users u
+            LEFT JOIN viewings v ON v.visitor_user_id = u.id AND v.tokens_awarded > 0
+            WHERE u.id = $1"#,
+        )
+        .bind(user.id)
+        .bind(property_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let reward = if on_site && !rewarded_here && rewarded_today < MAX_REWARDED_PER_DAY {
+            check_in_reward()
+        } else {
+            0
+        };
+
+        let Some(agent_user_id) = sqlx::query_scalar::<_, Uuid>(
+            r#"UPDATE viewings
+            SET status = 'attended', checked_in_at = NOW(), verified = $2, tokens_awarded = $3
+            WHERE id = $1 AND status = 'scheduled'
+            RETURNING agent_user_id"#,
+        )
+        .bind(viewing_id)
+        .bind(on_site)
+        .bind(reward)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        if reward > 0 {
+            sqlx::query("UPDATE users SET token_balance = token_balance + $1 WHERE id = $2")
+                .bind(reward)
+                .bind(user.id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "INSERT INTO token_transactions (user_id, amount, transaction_type) VALUES ($1, $2, 'viewing_reward')",
+            )
+            .bind(user.id)
+            .bind(reward)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        notify(
+            &mut *tx,
+            agent_user_id,
+            "viewing_checked_in",
+            serde_json::json!({
+                "viewing_id": viewing_id,
+                "visitor_user_id": user.id,
+                "verified": on_site,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some((agent_user_id, reward)))
+    }
+    .await;
+
+    match result {
+        Ok(Some((_, reward))) => {
+            state.metrics.record_tokens(reward);
+            info!("Viewing {} checked in (verified: {})", viewing_id, on_site);
+            match fetch_viewing(&state.db, viewing_id).await {
+                Ok(Some(viewing)) => HttpResponse::Ok().json(viewing),
+                _ => HttpResponse::Ok().json(serde_json::json!({ "id": viewing_id })),
+            }
+        }
+        Ok(None) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Viewing is already checked in"
+        })),
+        Err(e) => {
+            error!("Failed to check in viewing {}: {}", viewing_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to check in"
+            }))
+        }
+    }
+}
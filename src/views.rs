@@ -0,0 +1,194 @@
+// JARVIS2026 - Listing view tracking
+// Every detail view is logged for analytics; logged-in users additionally get
+// a deduplicated, cross-device "recently viewed" list.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::CurrentUser;
+use crate::formatting::{Locale, PriceDisplay};
+use crate::{AppState, Property};
+
+const VISITOR_HEADER: &str = "X-Visitor-Id";
+/// Entries kept per user; older views fall off the list.
+const RECENTLY_VIEWED_LIMIT: i64 = 50;
+
+// ============================================================================
+// DATA STRUCTURES
+// ============================================================================
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct RecentlyViewed {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    property: Property,
+    last_viewed_at: chrono::DateTime<chrono::Utc>,
+    #[sqlx(skip)]
+    price_display: Option<PriceDisplay>,
+}
+
+#[derive(Deserialize)]
+pub struct RecentlyViewedQuery {
+    limit: Option<i64>,
+}
+
+// ============================================================================
+// DATABASE INITIALIZATION
+// ============================================================================
+
+pub async fn init_schema(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS property_views (
+            id BIGSERIAL PRIMARY KEY,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            visitor_id TEXT,
+            viewed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_property_views_property ON property_views(property_id, viewed_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS recently_viewed (
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            property_id UUID NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+            last_viewed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (user_id, property_id)
+        )"#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_recently_viewed_user ON recently_viewed(user_id, last_viewed_at DESC)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// VIEW TRACKING
+// ============================================================================
+
+async fn record_view(
+    pool: &PgPool,
+    property_id: Uuid,
+    user_id: Option<Uuid>,
+    visitor_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO property_views (property_id, user_id, visitor_id) VALUES ($1, $2, $3)",
+    )
+    .bind(property_id)
+    .bind(user_id)
+    .bind(visitor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if let Some(user_id) = user_id {
+        sqlx::query(
+            r#"INSERT INTO recently_viewed (user_id, property_id) VALUES ($1, $2)
+            ON CONFLICT (user_id, property_id) DO UPDATE SET last_viewed_at = NOW()"#,
+        )
+        .bind(user_id)
+        .bind(property_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"DELETE FROM recently_viewed WHERE user_id = $1 AND property_id NOT IN (
+                SELECT property_id FROM recently_viewed WHERE user_id = $1
+                ORDER BY last_viewed_at DESC LIMIT $2
+            )"#,
+        )
+        .bind(user_id)
+        .bind(RECENTLY_VIEWED_LIMIT)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+// ============================================================================
+// API HANDLERS
+// ============================================================================
+
+/// Called by the client when a listing detail is opened. Anonymous views are
+/// counted but only logged-in users get a recently-viewed entry.
+#[post("/api/properties/{id}/views")]
+pub async fn track_view(
+    path: web::Path<Uuid>,
+    user: Option<CurrentUser>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let property_id = path.into_inner();
+    let visitor_id = req
+        .headers()
+        .get(VISITOR_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    match record_view(&state.db, property_id, user.map(|u| u.id), visitor_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => HttpResponse::NotFound()
+            .json(serde_json::json!({
+                "error": "Property not found"
+            })),
+        Err(e) => {
+            error!("Failed to record view of {}: {}", property_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to record view"
+            }))
+        }
+    }
+}
+
+#[get("/api/users/me/recently-viewed")]
+pub async fn recently_viewed(
+    user: CurrentUser,
+    query: web::Query<RecentlyViewedQuery>,
+    locale: Locale,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(20).clamp(1, RECENTLY_VIEWED_LIMIT);
+
+    match sqlx::query_as::<_, RecentlyViewed>(
+        r#"SELECT p.*, rv.last_viewed_at FROM recently_viewed rv
+        JOIN properties p ON p.id = rv.property_id
+        WHERE rv.user_id = $1
+        ORDER BY rv.last_viewed_at DESC LIMIT $2"#,
+    )
+    .bind(user.id)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(mut items) => {
+            for item in &mut items {
+                item.price_display = Some(PriceDisplay::new(item.property.price, locale));
+            }
+            HttpResponse::Ok().json(items)
+        }
+        Err(e) => {
+            error!("Failed to fetch recently viewed for {}: {}", user.id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch recently viewed listings"
+            }))
+        }
+    }
+}
@@ -13,16 +13,28 @@ const uploadForm = document.getElementById('uploadForm');
 const propertiesGrid = document.getElementById('propertiesGrid');
 const fileInput = document.getElementById('fileInput');
 const fileList = document.getElementById('fileList');
+const loadMoreBtn = document.getElementById('loadMoreBtn');
+
+// Where the next page of listings starts: the server's cursor when it hands
+// them out, otherwise a plain offset
+let nextPage = null;
 
 // Initialize
 document.addEventListener('DOMContentLoaded', async () => {
     initNavigation();
+    handleOAuthRedirect();
     await initUser();
-    loadProperties();
+    await handleViewingCheckIn();
+    await handleEmailVerification();
+    await handlePasswordReset();
+    if (!(await handleSharedListing())) {
+        loadProperties();
+    }
     updateBalance();
     
     // File input change handler for preview
     fileInput.addEventListener('change', handleFileSelect);
+    loadMoreBtn.addEventListener('click', () => loadProperties(true));
 });
 
 // Navigation Logic
@@ -44,6 +56,22 @@ function initNavigation() {
     });
 }
 
+// OAuth sign-in lands on /#user_id=...&access_token=...&refresh_token=...
+function handleOAuthRedirect() {
+    const params = new URLSearchParams(window.location.hash.slice(1));
+    if (params.get('oauth_error')) {
+        alert('Sign-in failed: ' + params.get('oauth_error'));
+    } else if (params.get('access_token')) {
+        appState.userId = params.get('user_id');
+        localStorage.setItem('jarvis_user_id', appState.userId);
+        localStorage.setItem('jarvis_access_token', params.get('access_token'));
+        localStorage.setItem('jarvis_refresh_token', params.get('refresh_token'));
+    } else {
+        return;
+    }
+    window.history.replaceState({}, '', window.location.pathname + window.location.search);
+}
+
 // User Logic
 async function initUser() {
     if (!appState.userId) {
@@ -75,6 +103,86 @@ async function initUser() {
     }
 }
 
+// Viewing check-in: the agent's QR opens /?checkin=<viewing id>&code=<code>
+async function handleViewingCheckIn() {
+    const params = new URLSearchParams(window.location.search);
+    const viewingId = params.get('checkin');
+    if (!viewingId || !localStorage.getItem('jarvis_access_token')) return;
+
+    const position = await new Promise(resolve => {
+        if (!navigator.geolocation) return resolve(null);
+        navigator.geolocation.getCurrentPosition(p => resolve(p.coords), () => resolve(null), { timeout: 10000 });
+    });
+
+    try {
+        const res = await fetch(`${API_BASE}/viewings/${viewingId}/check-in`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+                'Authorization': 'Bearer ' + localStorage.getItem('jarvis_access_token')
+            },
+            body: JSON.stringify({
+                code: params.get('code'),
+                latitude: position ? position.latitude : null,
+                longitude: position ? position.longitude : null
+            })
+        });
+        const result = await res.json();
+        if (res.ok) {
+            const reward = result.tokens_awarded > 0 ? ` You earned ${result.tokens_awarded} tokens.` : '';
+            alert(`Checked in to your viewing!${reward}`);
+        } else {
+            alert('Check-in failed: ' + result.error);
+        }
+    } catch (e) {
+        console.error('Check-in error', e);
+    }
+    window.history.replaceState({}, '', window.location.pathname);
+}
+
+// Email verification: the confirmation mail links to /?verify_email=<token>
+async function handleEmailVerification() {
+    const params = new URLSearchParams(window.location.search);
+    const token = params.get('verify_email');
+    if (!token) return;
+
+    try {
+        const res = await fetch(`${API_BASE}/auth/verify-email`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ token })
+        });
+        const result = await res.json();
+        alert(res.ok ? 'Your email address is verified.' : 'Verification failed: ' + result.error);
+    } catch (e) {
+        console.error('Email verification error', e);
+    }
+    window.history.replaceState({}, '', window.location.pathname);
+}
+
+// Password reset: the reset mail links to /?reset_password=<token>
+async function handlePasswordReset() {
+    const params = new URLSearchParams(window.location.search);
+    const token = params.get('reset_password');
+    if (!token) return;
+
+    const password = prompt('Choose a new password (at least 8 characters)');
+    if (password) {
+        try {
+            const res = await fetch(`${API_BASE}/auth/reset-password`, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ token, password })
+            });
+            const result = await res.json();
+            alert(res.ok ? 'Your password was changed. Please sign in again.' : 'Reset failed: ' + result.error);
+        } catch (e) {
+            console.error('Password reset error', e);
+        }
+    }
+    window.history.replaceState({}, '', window.location.pathname);
+}
+
 async function updateBalance() {
     if (!appState.userId) return;
     try {
@@ -91,21 +199,84 @@ async function updateBalance() {
     }
 }
 
+// Shared links and QR codes open /?property=<id>
+async function handleSharedListing() {
+    const propertyId = new URLSearchParams(window.location.search).get('property');
+    if (!propertyId) return false;
+
+    navLinks.forEach(l => l.classList.remove('active'));
+    pages.forEach(page => page.classList.remove('active'));
+    document.getElementById('propertyPage').classList.add('active');
+
+    const detail = document.getElementById('propertyDetail');
+    detail.innerHTML = '<div class="loading-spinner"><i class="fa-solid fa-circle-notch fa-spin"></i></div>';
+    try {
+        const res = await fetch(`${API_BASE}/properties/${encodeURIComponent(propertyId)}`);
+        if (!res.ok) {
+            detail.innerHTML = '<div class="empty-state">This listing is no longer available.</div>';
+            return true;
+        }
+        const prop = await res.json();
+        detail.innerHTML = `
+            <h2 class="title"></h2>
+            <div class="location"><i class="fa-solid fa-map-marker-alt"></i> <span></span></div>
+            <div class="price-tag"></div>
+            <div class="specs">
+                <div class="spec-item"><i class="fa-solid fa-bed"></i> ${prop.bedrooms || '-'}</div>
+                <div class="spec-item"><i class="fa-solid fa-bath"></i> ${prop.bathrooms || '-'}</div>
+                <div class="spec-item"><i class="fa-solid fa-ruler-combined"></i> ${prop.area_sqm || '-'}m²</div>
+            </div>
+            <p class="description"></p>
+        `;
+        detail.querySelector('.title').textContent = prop.title;
+        detail.querySelector('.location span').textContent = prop.location;
+        detail.querySelector('.price-tag').textContent = prop.price_display.formatted;
+        detail.querySelector('.description').textContent = prop.description || '';
+    } catch (e) {
+        console.error('Failed to load shared listing', e);
+        detail.innerHTML = '<div class="empty-state">Failed to load the listing. Check server connection.</div>';
+    }
+    return true;
+}
+
 // Property Logic
-async function loadProperties() {
-    propertiesGrid.innerHTML = '<div class="loading-spinner"><i class="fa-solid fa-circle-notch fa-spin"></i></div>';
+async function loadProperties(more = false) {
+    if (!more) {
+        nextPage = null;
+        propertiesGrid.innerHTML = '<div class="loading-spinner"><i class="fa-solid fa-circle-notch fa-spin"></i></div>';
+    }
+    loadMoreBtn.hidden = true;
     
     try {
-        const res = await fetch(`${API_BASE}/properties`);
-        const properties = await res.json();
+        const query = new URLSearchParams();
+        if (more && nextPage) {
+            query.set(nextPage.cursor ? 'cursor' : 'offset', nextPage.cursor || nextPage.offset);
+        }
+        const res = await fetch(`${API_BASE}/properties?${query}`);
+        const data = await res.json();
+        if (!res.ok) {
+            throw new Error(data.error || res.statusText);
+        }
+        const properties = data.properties;
         
-        propertiesGrid.innerHTML = '';
+        if (!more) {
+            propertiesGrid.innerHTML = '';
+        }
         
-        if (properties.length === 0) {
+        if (properties.length === 0 && !more) {
             propertiesGrid.innerHTML = '<div class="empty-state">No properties found. Be the first to upload!</div>';
             return;
         }
 
+        if (data.next_cursor) {
+            nextPage = { cursor: data.next_cursor };
+        } else if (properties.length === data.limit) {
+            nextPage = { offset: data.offset + data.limit };
+        } else {
+            nextPage = null;
+        }
+        loadMoreBtn.hidden = !nextPage;
+
         properties.forEach(prop => {
             const card = document.createElement('div');
             card.className = 'property-card';
@@ -124,7 +295,8 @@ async function loadProperties() {
             card.innerHTML = `
                 <div class="card-image">
                     ${mediaHtml}
-                    <div class="price-tag">$${prop.price.toLocaleString()}</div>
+                    <div class="price-tag">${prop.price_display.compact}</div>
+                    ${prop.verified_aerial ? '<div class="aerial-badge"><i class="fa-solid fa-helicopter"></i> Verified aerial</div>' : ''}
                 </div>
                 <div class="card-info">
                     <h3>${prop.title}</h3>
@@ -162,17 +334,23 @@ function handleFileSelect(event) {
 uploadForm.addEventListener('submit', async (e) => {
     e.preventDefault();
     
+    const accessToken = localStorage.getItem('jarvis_access_token');
+    if (!accessToken) {
+        alert('Sign in to upload a property.');
+        return;
+    }
+
     const submitBtn = uploadForm.querySelector('.submit-btn');
     const originalText = submitBtn.innerHTML;
     submitBtn.innerHTML = '<i class="fa-solid fa-circle-notch fa-spin"></i> Uploading...';
     submitBtn.disabled = true;
     
     const formData = new FormData(uploadForm);
-    formData.append('user_id', appState.userId);
     
     try {
         const res = await fetch(`${API_BASE}/upload-property`, {
             method: 'POST',
+            headers: { 'Authorization': 'Bearer ' + accessToken },
             body: formData
         });
         
@@ -186,7 +364,7 @@ uploadForm.addEventListener('submit', async (e) => {
             // Go to home
             navLinks[0].click(); 
         } else {
-            alert('Upload failed: ' + result.message);
+            alert('Upload failed: ' + (result.error || result.message));
         }
     } catch (e) {
         console.error('Upload error', e);